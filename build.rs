@@ -0,0 +1,27 @@
+use std::process::Command;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Commit reported by `/health`; builds outside a git checkout (e.g. Docker) can pass it in
+    let git_hash = std::env::var("GIT_HASH").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|hash| hash.trim().to_string())
+    });
+    println!(
+        "cargo:rustc-env=GIT_HASH={}",
+        git_hash.as_deref().unwrap_or("unknown")
+    );
+    println!("cargo:rerun-if-env-changed=GIT_HASH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    // Protobuf code is only generated for the gRPC server (requires `protoc`)
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/markets.proto")?;
+
+    Ok(())
+}
@@ -0,0 +1,5 @@
+ALTER TABLE markets ADD COLUMN clob_token_id TEXT;
+ALTER TABLE markets ADD COLUMN best_bid REAL;
+ALTER TABLE markets ADD COLUMN best_ask REAL;
+ALTER TABLE markets ADD COLUMN midpoint REAL;
+ALTER TABLE markets ADD COLUMN spread REAL;
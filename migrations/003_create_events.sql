@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    slug TEXT,
+    description TEXT,
+    volume REAL,
+    end_date TEXT,
+    discovered_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE markets ADD COLUMN event_id TEXT REFERENCES events(id);
+
+CREATE INDEX IF NOT EXISTS idx_markets_event_id ON markets(event_id);
+CREATE INDEX IF NOT EXISTS idx_events_discovered_at ON events(discovered_at);
@@ -0,0 +1,5 @@
+ALTER TABLE markets ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+ALTER TABLE markets ADD COLUMN winning_outcome TEXT;
+ALTER TABLE markets ADD COLUMN resolved_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_markets_status ON markets(status);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    price REAL,
+    volume REAL,
+    scraped_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_market_scraped_at ON price_history(market_id, scraped_at);
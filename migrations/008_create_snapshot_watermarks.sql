@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS snapshot_watermarks (
+    name TEXT PRIMARY KEY,
+    exported_until TIMESTAMP NOT NULL
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS raw_payloads (
+    market_id TEXT NOT NULL,
+    source TEXT NOT NULL,
+    payload BLOB NOT NULL,
+    payload_hash TEXT NOT NULL,
+    scraped_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (market_id, scraped_at)
+);
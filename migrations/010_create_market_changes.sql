@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS market_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    field TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    changed_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_changes_market_changed_at ON market_changes(market_id, changed_at);
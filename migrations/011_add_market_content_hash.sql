@@ -0,0 +1 @@
+ALTER TABLE markets ADD COLUMN content_hash TEXT;
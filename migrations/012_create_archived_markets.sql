@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS archived_markets (
+    id TEXT PRIMARY KEY,
+    data TEXT NOT NULL,
+    resolved_at TIMESTAMP,
+    archived_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE markets ADD COLUMN last_seen_at TIMESTAMP;
+ALTER TABLE markets ADD COLUMN missed_scrapes INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS outcomes (
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    token_id TEXT,
+    price REAL,
+    best_bid REAL,
+    best_ask REAL,
+    updated_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (market_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_outcomes_token_id ON outcomes(token_id);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS tags (
+    slug TEXT PRIMARY KEY,
+    label TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS market_tags (
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    tag_slug TEXT NOT NULL REFERENCES tags(slug),
+    PRIMARY KEY (market_id, tag_slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_market_tags_tag_slug ON market_tags(tag_slug);
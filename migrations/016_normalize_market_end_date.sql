@@ -0,0 +1,14 @@
+-- Store market end dates as RFC 3339 UTC (`2024-11-05T12:00:00Z`), the format written at ingest,
+-- so they decode as timestamps and compare correctly as text
+
+-- Epoch milliseconds first: SQLite would read a bare number as a Julian day
+UPDATE markets
+SET end_date = strftime('%Y-%m-%dT%H:%M:%SZ', CAST(end_date AS INTEGER) / 1000, 'unixepoch')
+WHERE end_date GLOB '[0-9]*' AND end_date NOT GLOB '*[^0-9]*';
+
+-- Everything else; values SQLite can't read become NULL and are filled in by the next scrape
+UPDATE markets
+SET end_date = strftime('%Y-%m-%dT%H:%M:%SZ', end_date)
+WHERE end_date GLOB '*[^0-9]*';
+
+CREATE INDEX IF NOT EXISTS idx_markets_end_date ON markets(end_date);
@@ -0,0 +1,3 @@
+ALTER TABLE markets ADD COLUMN volume_24hr REAL;
+ALTER TABLE markets ADD COLUMN liquidity REAL;
+ALTER TABLE markets ADD COLUMN open_interest REAL;
@@ -0,0 +1,8 @@
+ALTER TABLE markets ADD COLUMN slug TEXT;
+ALTER TABLE markets ADD COLUMN condition_id TEXT;
+ALTER TABLE markets ADD COLUMN question_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_markets_slug ON markets(slug);
+CREATE INDEX IF NOT EXISTS idx_markets_condition_id ON markets(condition_id);
+CREATE INDEX IF NOT EXISTS idx_markets_question_id ON markets(question_id);
+CREATE INDEX IF NOT EXISTS idx_markets_clob_token_id ON markets(clob_token_id);
@@ -0,0 +1,2 @@
+ALTER TABLE markets ADD COLUMN image TEXT;
+ALTER TABLE markets ADD COLUMN icon TEXT;
@@ -0,0 +1,3 @@
+ALTER TABLE markets ADD COLUMN source TEXT NOT NULL DEFAULT 'polymarket';
+
+CREATE INDEX IF NOT EXISTS idx_markets_source ON markets(source);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS scraper_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS metrics_snapshot (
+    name TEXT PRIMARY KEY,
+    value INTEGER NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS candles (
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    interval TEXT NOT NULL,
+    start_at TIMESTAMP NOT NULL,
+    open REAL,
+    high REAL,
+    low REAL,
+    close REAL,
+    volume REAL,
+    points INTEGER NOT NULL,
+    PRIMARY KEY (market_id, interval, start_at)
+);
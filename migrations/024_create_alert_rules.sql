@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    condition TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS alerts_fired (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id INTEGER NOT NULL,
+    market_id TEXT NOT NULL,
+    message TEXT NOT NULL,
+    fired_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_alerts_fired_rule_market ON alerts_fired(rule_id, market_id, fired_at);
+CREATE INDEX IF NOT EXISTS idx_alerts_fired_fired_at ON alerts_fired(fired_at);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS watchlists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS watchlist_markets (
+    watchlist_id INTEGER NOT NULL REFERENCES watchlists(id),
+    market_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (watchlist_id, market_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_watchlist_markets_market ON watchlist_markets(market_id);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS volume_spikes (
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    detected_at TIMESTAMP NOT NULL,
+    interval_volume REAL NOT NULL,
+    baseline_volume REAL NOT NULL,
+    ratio REAL NOT NULL,
+    PRIMARY KEY (market_id, detected_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_volume_spikes_detected_at ON volume_spikes(detected_at);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS daily_reports (
+    date TEXT PRIMARY KEY,
+    report TEXT NOT NULL,
+    generated_at TIMESTAMP NOT NULL
+);
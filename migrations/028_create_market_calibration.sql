@@ -0,0 +1,11 @@
+-- No foreign key: scores are kept after their market is archived
+CREATE TABLE IF NOT EXISTS market_calibration (
+    market_id TEXT PRIMARY KEY,
+    outcome REAL NOT NULL,
+    resolved_at TIMESTAMP NOT NULL,
+    scored_at TIMESTAMP NOT NULL,
+    forecast_1h REAL,
+    forecast_1d REAL,
+    forecast_7d REAL,
+    forecast_30d REAL
+);
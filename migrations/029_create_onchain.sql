@@ -0,0 +1,16 @@
+-- USDC traded per CLOB token, summed from the CTF Exchange's OrderFilled events
+CREATE TABLE IF NOT EXISTS onchain_volumes (
+    token_id TEXT PRIMARY KEY,
+    volume REAL NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+-- Latest comparison of each market with its condition on Polygon
+CREATE TABLE IF NOT EXISTS onchain_verifications (
+    market_id TEXT PRIMARY KEY REFERENCES markets(id),
+    resolved BOOLEAN NOT NULL,
+    winning_outcome TEXT,
+    volume REAL,
+    mismatch TEXT,
+    checked_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Recent trades of hot markets from the Polymarket data API
+CREATE TABLE IF NOT EXISTS trades (
+    -- Hash of the trade's fields, so trades returned by overlapping fetches are stored once
+    id TEXT PRIMARY KEY,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    token_id TEXT,
+    outcome TEXT,
+    side TEXT NOT NULL,
+    size REAL NOT NULL,
+    price REAL NOT NULL,
+    traded_at TIMESTAMP NOT NULL,
+    maker TEXT,
+    taker TEXT,
+    transaction_hash TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_trades_market_traded_at ON trades(market_id, traded_at);
@@ -0,0 +1,17 @@
+-- Top levels of hot markets' CLOB orderbooks, captured after every scrape
+CREATE TABLE IF NOT EXISTS orderbook_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    token_id TEXT NOT NULL,
+    -- JSON arrays of {price, size}, best level first
+    bids TEXT NOT NULL,
+    asks TEXT NOT NULL,
+    midpoint REAL,
+    spread REAL,
+    bid_depth REAL NOT NULL,
+    ask_depth REAL NOT NULL,
+    imbalance REAL,
+    captured_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_orderbook_snapshots_market_captured_at ON orderbook_snapshots(market_id, captured_at);
@@ -0,0 +1,13 @@
+-- Top holders of hot markets from the Polymarket data API, one set per capture
+CREATE TABLE IF NOT EXISTS positions (
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    token_id TEXT NOT NULL,
+    outcome_index INTEGER,
+    wallet TEXT NOT NULL,
+    name TEXT,
+    amount REAL NOT NULL,
+    captured_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (market_id, token_id, wallet, captured_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_positions_market_captured_at ON positions(market_id, captured_at);
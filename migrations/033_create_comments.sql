@@ -0,0 +1,15 @@
+ALTER TABLE events ADD COLUMN comment_count INTEGER;
+
+-- Recent comments on the events of hot markets from the Gamma API
+CREATE TABLE IF NOT EXISTS comments (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    parent_comment_id TEXT,
+    author TEXT,
+    author_name TEXT,
+    body TEXT NOT NULL,
+    reaction_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_comments_event_created_at ON comments(event_id, created_at);
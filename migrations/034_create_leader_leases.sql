@@ -0,0 +1,7 @@
+-- Leases replicas sharing the database compete for; only the holder of `scraper` scrapes
+CREATE TABLE IF NOT EXISTS leader_leases (
+    name TEXT PRIMARY KEY,
+    holder TEXT NOT NULL,
+    acquired_at TIMESTAMP NOT NULL,
+    expires_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Last successful scrape of each shard of the market universe, reported by its holder
+CREATE TABLE IF NOT EXISTS shard_progress (
+    shard_count INTEGER NOT NULL,
+    shard_index INTEGER NOT NULL,
+    instance_id TEXT NOT NULL,
+    last_success_at TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (shard_count, shard_index)
+);
@@ -0,0 +1,20 @@
+-- Notifications written in the same transaction as the market upsert that produced them
+CREATE TABLE IF NOT EXISTS outbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    dedup_key TEXT NOT NULL UNIQUE,
+    market_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbox_created_at ON outbox(created_at);
+
+-- Delivery state of each outbox event per notification sink
+CREATE TABLE IF NOT EXISTS outbox_deliveries (
+    outbox_id INTEGER NOT NULL,
+    sink TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    delivered_at TIMESTAMP,
+    PRIMARY KEY (outbox_id, sink)
+);
@@ -0,0 +1,2 @@
+-- Deliveries given up on for good; everything else is retried until acknowledged
+ALTER TABLE outbox_deliveries ADD COLUMN dead_lettered_at TIMESTAMP;
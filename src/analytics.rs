@@ -0,0 +1,181 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+use crate::models::{MarketCorrelation, PriceHistoryRow};
+
+/// Most markets compared in one correlation request
+pub const MAX_CORRELATION_MARKETS: usize = 20;
+/// Buckets a window is split into, so long windows don't cost more than short ones
+const TARGET_BUCKETS: i64 = 240;
+const MIN_BUCKET_SECS: i64 = 60;
+/// Fewest bucket-to-bucket price changes two markets must share for their correlation to be
+/// reported
+const MIN_SAMPLES: usize = 3;
+
+/// Width of the buckets prices are sampled into over `window`
+pub fn bucket_size(window: Duration) -> Duration {
+    Duration::seconds((window.num_seconds() / TARGET_BUCKETS).max(MIN_BUCKET_SECS))
+}
+
+/// Pairwise correlations of the price changes of `ids` from their price history in
+/// `[from, to)`
+/// Each market's last price per bucket is carried forward over buckets without
+/// observations, so markets scraped at different times still line up. Changes from one
+/// bucket to the next are correlated rather than price levels, which any two trending
+/// markets would appear to share
+pub fn correlations(
+    ids: &[String],
+    rows: &[PriceHistoryRow],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    bucket: Duration,
+) -> Vec<MarketCorrelation> {
+    let buckets = ((to - from).num_seconds() + bucket.num_seconds() - 1) / bucket.num_seconds();
+    let buckets = buckets.max(0) as usize;
+
+    let mut series: HashMap<&str, Vec<Option<f64>>> = HashMap::new();
+    for row in rows {
+        let Some(price) = row.price.and_then(|price| price.to_f64()) else {
+            continue;
+        };
+        if row.scraped_at < from || row.scraped_at >= to {
+            continue;
+        }
+        let index = ((row.scraped_at - from).num_seconds() / bucket.num_seconds()) as usize;
+        let values = series
+            .entry(row.market_id.as_str())
+            .or_insert_with(|| vec![None; buckets]);
+        // Rows are in time order, so the last price of a bucket wins
+        if let Some(value) = values.get_mut(index) {
+            *value = Some(price);
+        }
+    }
+    let mut returns: HashMap<&str, Vec<Option<f64>>> = HashMap::new();
+    for (id, values) in series.iter_mut() {
+        let mut carried = None;
+        for value in values.iter_mut() {
+            match value {
+                Some(price) => carried = Some(*price),
+                None => *value = carried,
+            }
+        }
+        let changes = values
+            .windows(2)
+            .map(|pair| Some(pair[1]? - pair[0]?))
+            .collect();
+        returns.insert(id, changes);
+    }
+
+    let mut pairs = Vec::new();
+    for (i, a) in ids.iter().enumerate() {
+        for b in &ids[i + 1..] {
+            let (xs, ys): (Vec<f64>, Vec<f64>) =
+                match (returns.get(a.as_str()), returns.get(b.as_str())) {
+                    (Some(xs), Some(ys)) => xs
+                        .iter()
+                        .zip(ys)
+                        .filter_map(|(x, y)| Some(((*x)?, (*y)?)))
+                        .unzip(),
+                    _ => (Vec::new(), Vec::new()),
+                };
+            pairs.push(MarketCorrelation {
+                market_a: a.clone(),
+                market_b: b.clone(),
+                correlation: (xs.len() >= MIN_SAMPLES)
+                    .then(|| pearson(&xs, &ys))
+                    .flatten(),
+                samples: xs.len(),
+            });
+        }
+    }
+    pairs
+}
+
+/// Pearson correlation coefficient; None when either series is constant or too short
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() < 2 || xs.len() != ys.len() {
+        return None;
+    }
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some((cov / (var_x * var_y).sqrt()).clamp(-1.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::prelude::FromPrimitive;
+    use rust_decimal::Decimal;
+
+    fn row(market_id: &str, price: f64, from: DateTime<Utc>, minutes: i64) -> PriceHistoryRow {
+        PriceHistoryRow {
+            market_id: market_id.to_string(),
+            price: Decimal::from_f64(price),
+            volume: None,
+            scraped_at: from + Duration::minutes(minutes),
+        }
+    }
+
+    #[test]
+    fn test_pearson() {
+        assert_eq!(pearson(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), Some(1.0));
+        assert_eq!(pearson(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]), Some(-1.0));
+        assert_eq!(pearson(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0]), None);
+        assert_eq!(pearson(&[1.0], &[1.0]), None);
+    }
+
+    #[test]
+    fn test_correlations_compare_price_changes() {
+        let from = Utc::now();
+        let to = from + Duration::minutes(5);
+        let ids = vec!["a".to_string(), "b".to_string()];
+        // Both trend upwards, but `b` moves most when `a` moves least
+        let prices = [(0.10, 0.50), (0.20, 0.53), (0.25, 0.58), (0.35, 0.61), (0.40, 0.66)];
+        let rows: Vec<_> = (0..)
+            .zip(prices)
+            .flat_map(|(minute, (a, b))| [row("a", a, from, minute), row("b", b, from, minute)])
+            .collect();
+
+        let pairs = correlations(&ids, &rows, from, to, Duration::minutes(1));
+        assert_eq!(pairs[0].samples, 4);
+        assert!(pairs[0].correlation.unwrap() < -0.99);
+    }
+
+    #[test]
+    fn test_correlations_carry_prices_forward() {
+        let from = Utc::now();
+        let to = from + Duration::minutes(5);
+        let ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        // `b` is scraped less often but moves with `a`; `c` has no history
+        let rows = vec![
+            row("a", 0.1, from, 0),
+            row("a", 0.2, from, 1),
+            row("a", 0.3, from, 2),
+            row("a", 0.4, from, 3),
+            row("b", 0.5, from, 0),
+            row("b", 0.7, from, 2),
+            row("b", 0.8, from, 3),
+        ];
+
+        let pairs = correlations(&ids, &rows, from, to, Duration::minutes(1));
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(
+            (pairs[0].market_a.as_str(), pairs[0].market_b.as_str()),
+            ("a", "b")
+        );
+        assert_eq!(pairs[0].samples, 4);
+        assert!(pairs[0].correlation.unwrap() > 0.5);
+        assert_eq!((pairs[1].correlation, pairs[1].samples), (None, 0));
+    }
+}
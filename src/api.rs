@@ -1,28 +1,113 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::get,
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        MatchedPath, Path, Query, RawQuery, Request, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        Html, IntoResponse, Json, Response,
+    },
+    routing::{delete, get, post, put},
     Router,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Deserialize;
-use sqlx::Pool;
-use sqlx::Sqlite;
+use std::collections::HashSet;
+use std::convert::Infallible;
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Instant;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tracing::{error, info, info_span, warn, Instrument};
+use async_graphql::http::GraphiQLSource;
+use async_graphql_axum::GraphQL;
+use utoipa::{IntoParams, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
 
-use crate::db;
-use crate::metrics::Metrics;
-use crate::models::{HealthResponse, Market, MarketsResponse, MetricsResponse};
+use crate::analytics;
+use crate::auth;
+use crate::cache::ResponseCache;
+use crate::circuit_breaker::CircuitState;
+use crate::export;
+use crate::graphql::{self, MarketSchema};
+use crate::scraper::{self, ScrapeSummary, ScraperControl, ScraperHealth, ScraperState, ScraperStatus};
+use crate::shards::Shard;
+use crate::metrics::{self, Metrics};
+use crate::rate_limit::{self, RateLimiter};
+use crate::rules;
+use crate::storage::Storage;
+use crate::updates::{MarketUpdate, UpdateFilter, UpdateSender};
+use crate::models::{
+    AlertCondition, AlertFired, AlertRule, BatchMarketsResponse, BookLevel, CalibrationBucket, CalibrationHorizon, CalibrationResponse, Candle, CandleInterval, Comment, CommentsResponse, CorrelationResponse, CreateAlertRuleRequest, CreateWatchlistRequest, CreateWebhookRequest, DailyReport, Event, EventsResponse, HealthResponse, HoldersResponse, HorizonCalibration, Market, MarketFilter,
+    MarketChange, MarketCorrelation, MarketMover, MarketSortField, MarketVolume, MarketStatus, MarketsResponse, MoverSort, OnchainVerification, OrderbookSnapshot, Outcome, Position, PricePoint, MetricsResponse, ReadinessCheck, ReadinessResponse,
+    ScrapeIntervalRequest, ShardProgress, SortOrder, StatsResponse, Tag, TagCount, Trade, TradeSide, UpdateWatchlistRequest, VolumeSpike, Watchlist, Webhook,
+    WebhookDeadLetter,
+};
+
+/// Scheduled scrapes that may pass without a success before the service reports not ready
+const MAX_MOVERS_LIMIT: u32 = 100;
+const MAX_SPIKES_LIMIT: u32 = 100;
+const MAX_ENDING_LIMIT: u32 = 500;
+const MAX_BATCH_IDS: usize = 500; // IDs accepted by a single `/markets/batch` request
+/// Distinct market IDs across all watchlists; each is refreshed with the hot tier
+const MAX_WATCHED_MARKETS: usize = 1000;
+const MAX_HISTORY_LIMIT: u32 = 1000;
+/// Reported by `/health`; the git hash is embedded by the build script
+const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("GIT_HASH"));
 
 /// Query parameters for pagination
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaginationParams {
     #[serde(default = "default_limit")]
+    #[param(default = 20)]
     pub limit: u32,
     #[serde(default = "default_offset")]
+    #[param(default = 0)]
     pub offset: u32,
+    /// Optional status filter
+    #[param(inline)]
+    pub status: Option<MarketStatus>,
+    #[serde(default)]
+    #[param(inline)]
+    pub sort_by: MarketSortField,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+    pub min_volume: Option<f64>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub ending_before: Option<DateTime<Utc>>,
+    pub ending_after: Option<DateTime<Utc>>,
+    /// Include markets no longer returned by Polymarket (`status=delisted`); ignored when `status` is set
+    #[serde(default)]
+    pub include_delisted: bool,
+    /// Only markets carrying this tag slug (see `/tags`)
+    pub tag: Option<String>,
+    /// Only markets listed on this venue (`polymarket`, `manifold`)
+    pub source: Option<String>,
+}
+
+impl PaginationParams {
+    /// Market filters and ordering from the query string (events ignore these)
+    fn market_filter(&self) -> MarketFilter {
+        MarketFilter {
+            status: self.status,
+            min_volume: self.min_volume,
+            min_price: self.min_price,
+            max_price: self.max_price,
+            ending_before: self.ending_before,
+            ending_after: self.ending_after,
+            include_delisted: self.include_delisted,
+            tag: self.tag.clone(),
+            source: self.source.clone(),
+            sort_by: self.sort_by,
+            order: self.order,
+        }
+    }
 }
 
 fn default_limit() -> u32 {
@@ -34,94 +119,884 @@ fn default_offset() -> u32 {
 }
 
 /// Query parameters for filtering markets by discovery date
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SinceParams {
     pub since: DateTime<Utc>,
 }
 
-/// API state containing both database pool and metrics
+/// Response format override; without it, `Accept: text/csv` selects CSV
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FormatParams {
+    /// `json` or `csv`
+    pub format: Option<String>,
+}
+
+/// Query parameters for a market's price history
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryParams {
+    #[serde(default = "default_history_limit")]
+    #[param(default = 100, maximum = 1000)]
+    pub limit: u32,
+}
+
+fn default_history_limit() -> u32 {
+    100
+}
+
+/// Query parameters for a market's trades
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TradesParams {
+    /// Only trades after this time
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default = "default_history_limit")]
+    #[param(default = 100, maximum = 1000)]
+    pub limit: u32,
+}
+
+/// Query parameters for a market's holders
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HoldersParams {
+    /// Return the latest capture at or before this time instead of the latest one
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for a market's candles
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CandleParams {
+    /// Candle period, `1h` or `1d`
+    #[serde(default)]
+    #[param(inline)]
+    pub interval: CandleInterval,
+    #[serde(default = "default_history_limit")]
+    #[param(default = 100, maximum = 1000)]
+    pub limit: u32,
+}
+
+/// Query parameters for top movers
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MoversParams {
+    /// Lookback window such as `30m`, `24h` or `7d`
+    #[serde(default = "default_movers_window")]
+    #[param(default = "24h")]
+    pub window: String,
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: MoverSort,
+    #[serde(default = "default_limit")]
+    #[param(default = 20, maximum = 100)]
+    pub limit: u32,
+}
+
+fn default_movers_window() -> String {
+    "24h".to_string()
+}
+
+/// Query parameters for price correlations
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CorrelationParams {
+    /// Comma-separated IDs of 2 to 20 markets
+    pub ids: String,
+    /// Lookback window such as `24h`, `7d` or `30d`
+    #[serde(default = "default_correlation_window")]
+    #[param(default = "30d")]
+    pub window: String,
+}
+
+fn default_correlation_window() -> String {
+    "30d".to_string()
+}
+
+/// Query parameters for volume spikes
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SpikesParams {
+    /// How far back to list detected spikes, such as `1h`, `24h` or `7d`
+    #[serde(default = "default_movers_window")]
+    #[param(default = "24h")]
+    pub window: String,
+    #[serde(default = "default_limit")]
+    #[param(default = 20, maximum = 100)]
+    pub limit: u32,
+}
+
+/// Query parameters for markets ending soon
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EndingParams {
+    /// Window from now such as `12h` or `2d`
+    #[serde(default = "default_ending_within")]
+    #[param(default = "48h")]
+    pub within: String,
+    #[serde(default = "default_limit")]
+    #[param(default = 20, maximum = 500)]
+    pub limit: u32,
+}
+
+fn default_ending_within() -> String {
+    "48h".to_string()
+}
+
+/// Parse a window like `30m`, `24h` or `7d` into a duration
+/// None for windows too long to represent
+pub(crate) fn parse_window(window: &str) -> Option<chrono::Duration> {
+    let unit = window.chars().last()?;
+    let amount: i64 = window[..window.len() - unit.len_utf8()].parse().ok()?;
+    if amount <= 0 {
+        return None;
+    }
+    match unit {
+        'm' => chrono::TimeDelta::try_minutes(amount),
+        'h' => chrono::TimeDelta::try_hours(amount),
+        'd' => chrono::TimeDelta::try_days(amount),
+        _ => None,
+    }
+}
+
+/// Start of a window like `24h` that ends at `end`
+/// None for invalid windows and ones reaching back before the earliest representable time
+pub(crate) fn window_start(window: &str, end: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    end.checked_sub_signed(parse_window(window)?)
+}
+
+/// API state containing the storage backend, metrics, the market update channel and response cache
 #[derive(Clone)]
 pub struct AppState {
-    pub pool: Arc<Pool<Sqlite>>,
+    pub storage: Arc<dyn Storage>,
     pub metrics: Arc<Metrics>,
+    pub updates: UpdateSender,
+    pub cache: ResponseCache,
+    pub scraper: ScraperControl,
 }
 
 /// Create the API router
-pub fn create_router(pool: Arc<Pool<Sqlite>>, metrics: Arc<Metrics>) -> Router {
-    let state = AppState { pool, metrics };
-    
-    Router::new()
+/// REST routes are served under `/v1`, with unprefixed legacy aliases;
+/// `/admin` routes are only mounted when an admin token is configured,
+/// and requests are only rate limited when a limiter is supplied
+pub fn create_router(
+    storage: Arc<dyn Storage>,
+    metrics: Arc<Metrics>,
+    updates: UpdateSender,
+    cache: ResponseCache,
+    scraper: ScraperControl,
+    admin_token: Option<String>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+) -> Router {
+    let schema: MarketSchema = graphql::build_schema(Arc::clone(&storage));
+    let state = AppState {
+        storage,
+        metrics,
+        updates,
+        cache,
+        scraper,
+    };
+
+    let v1 = v1_routes(admin_token);
+
+    // New API versions get their own `vN_routes` nested next to `/v1`
+    let mut router = Router::new()
+        .nest("/v1", v1.clone())
+        .merge(v1)
+        .route("/metrics/prometheus", get(prometheus_metrics_handler))
+        .route("/graphql", get(graphiql_handler).post_service(GraphQL::new(schema)))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()));
+
+    // Added before the metrics layer so rejected requests are still counted there
+    if let Some(limiter) = rate_limiter {
+        router = router.route_layer(middleware::from_fn_with_state(limiter, rate_limit::rate_limit));
+    }
+
+    router
+        .route_layer(middleware::from_fn(track_http_metrics))
+        .with_state(state)
+}
+
+/// Version 1 of the REST API; response shapes here must stay backwards compatible
+fn v1_routes(admin_token: Option<String>) -> Router<AppState> {
+    let router = Router::new()
         .route("/health", get(health_handler))
+        .route("/health/live", get(liveness_handler))
+        .route("/health/ready", get(readiness_handler))
         .route("/metrics", get(metrics_handler))
+        .route("/stats", get(stats_handler))
         .route("/markets", get(markets_handler))
         .route("/markets/new", get(new_markets_handler))
+        .route("/markets/movers", get(movers_handler))
+        .route("/markets/spikes", get(spikes_handler))
+        .route("/markets/ending", get(ending_markets_handler))
+        .route("/markets/batch", post(batch_markets_handler))
+        .route("/markets/stream", get(market_stream_handler))
+        .route("/ws", get(ws_handler))
         .route("/markets/:id", get(market_by_id_handler))
-        .with_state(state)
+        .route("/markets/:id/history", get(price_history_handler))
+        .route("/markets/:id/changes", get(market_changes_handler))
+        .route("/markets/:id/candles", get(candles_handler))
+        .route("/markets/:id/trades", get(trades_handler))
+        .route("/markets/:id/orderbook", get(orderbook_handler))
+        .route("/markets/:id/holders", get(holders_handler))
+        .route("/markets/:id/comments", get(comments_handler))
+        .route("/markets/:id/onchain", get(onchain_verification_handler))
+        .route("/export/markets.jsonl", get(export_jsonl_handler))
+        .route("/tags", get(tags_handler))
+        .route("/events", get(events_handler))
+        .route("/events/:id", get(event_by_id_handler))
+        .route("/events/:id/markets", get(event_markets_handler))
+        .route("/alerts/fired", get(alerts_fired_handler))
+        .route("/watchlists", get(list_watchlists_handler))
+        .route("/watchlists/:name", get(get_watchlist_handler))
+        .route("/watchlists/:name/markets", get(watchlist_markets_handler))
+        .route("/watchlists/:name/stream", get(watchlist_stream_handler))
+        .route("/watchlists/:name/ws", get(watchlist_ws_handler))
+        .route("/reports/daily/:date", get(daily_report_handler))
+        .route("/analytics/correlation", get(correlation_handler))
+        .route("/analytics/calibration", get(calibration_handler));
+
+    match admin_token {
+        Some(token) => {
+            let admin = Router::new()
+                .route("/admin/scraper", get(scraper_status_handler))
+                .route("/admin/scraper/pause", post(pause_scraper_handler))
+                .route("/admin/scraper/resume", post(resume_scraper_handler))
+                .route("/admin/scrape", post(trigger_scrape_handler))
+                .route("/markets/:id/refresh", post(refresh_market_handler))
+                .route("/webhooks", get(list_webhooks_handler).post(create_webhook_handler))
+                .route("/webhooks/:id", delete(delete_webhook_handler))
+                .route("/webhooks/dead-letters", get(webhook_dead_letters_handler))
+                .route("/watchlists", post(create_watchlist_handler))
+                .route(
+                    "/watchlists/:name",
+                    put(update_watchlist_handler).delete(delete_watchlist_handler),
+                )
+                .route("/alerts", get(list_alert_rules_handler).post(create_alert_rule_handler))
+                .route("/alerts/:id", delete(delete_alert_rule_handler))
+                .route(
+                    "/admin/scrape-interval",
+                    get(get_scrape_interval_handler).post(set_scrape_interval_handler),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    Arc::<str>::from(token),
+                    auth::require_admin_token,
+                ));
+            router.merge(admin)
+        }
+        None => {
+            info!("No admin token configured, admin endpoints are disabled");
+            router
+        }
+    }
+}
+
+/// OpenAPI description of the public REST endpoints, served at `/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Polymarket Scraper API", version = "1"),
+    servers((url = "/v1")),
+    paths(
+        health_handler,
+        liveness_handler,
+        readiness_handler,
+        metrics_handler,
+        stats_handler,
+        markets_handler,
+        new_markets_handler,
+        movers_handler,
+        spikes_handler,
+        ending_markets_handler,
+        batch_markets_handler,
+        market_by_id_handler,
+        price_history_handler,
+        market_changes_handler,
+        candles_handler,
+        trades_handler,
+        orderbook_handler,
+        holders_handler,
+        comments_handler,
+        onchain_verification_handler,
+        export_jsonl_handler,
+        tags_handler,
+        events_handler,
+        event_by_id_handler,
+        event_markets_handler,
+        create_webhook_handler,
+        list_webhooks_handler,
+        delete_webhook_handler,
+        webhook_dead_letters_handler,
+        create_alert_rule_handler,
+        list_alert_rules_handler,
+        delete_alert_rule_handler,
+        alerts_fired_handler,
+        create_watchlist_handler,
+        list_watchlists_handler,
+        get_watchlist_handler,
+        update_watchlist_handler,
+        delete_watchlist_handler,
+        watchlist_markets_handler,
+        daily_report_handler,
+        correlation_handler,
+        calibration_handler,
+    ),
+    components(schemas(
+        Market,
+        Outcome,
+        Tag,
+        TagCount,
+        MarketsResponse,
+        BatchMarketsResponse,
+        Event,
+        EventsResponse,
+        HealthResponse,
+        CircuitState,
+        ScraperHealth,
+        Shard,
+        ReadinessCheck,
+        ReadinessResponse,
+        MetricsResponse,
+        ShardProgress,
+        StatsResponse,
+        MarketSortField,
+        MarketStatus,
+        SortOrder,
+        MarketMover,
+        MoverSort,
+        VolumeSpike,
+        PricePoint,
+        MarketChange,
+        Candle,
+        CandleInterval,
+        Trade,
+        TradeSide,
+        BookLevel,
+        OrderbookSnapshot,
+        Position,
+        HoldersResponse,
+        Comment,
+        CommentsResponse,
+        OnchainVerification,
+        Webhook,
+        CreateWebhookRequest,
+        WebhookDeadLetter,
+        AlertCondition,
+        AlertRule,
+        CreateAlertRuleRequest,
+        AlertFired,
+        Watchlist,
+        CreateWatchlistRequest,
+        UpdateWatchlistRequest,
+        DailyReport,
+        MarketVolume,
+        CorrelationResponse,
+        MarketCorrelation,
+        CalibrationResponse,
+        HorizonCalibration,
+        CalibrationHorizon,
+        CalibrationBucket,
+    ))
+)]
+pub struct ApiDoc;
+
+/// GraphiQL explorer for the `/graphql` endpoint
+async fn graphiql_handler() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+/// Record request count and latency per route, and run the request in a span for tracing
+/// Uses the matched route template (e.g. `/markets/:id`) to keep label cardinality bounded
+async fn track_http_metrics(req: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let span = info_span!(
+        "http_request",
+        method = %method,
+        path = %path,
+        status = tracing::field::Empty
+    );
+    let response = next.run(req).instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+
+    let status = response.status().as_u16().to_string();
+    ::metrics::counter!(
+        metrics::HTTP_REQUESTS_TOTAL,
+        "method" => method.clone(),
+        "path" => path.clone(),
+        "status" => status
+    )
+    .increment(1);
+    ::metrics::histogram!(
+        metrics::HTTP_REQUEST_DURATION_SECONDS,
+        "method" => method,
+        "path" => path
+    )
+    .record(started.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Health check endpoint; `status` is `degraded` while the upstream circuit is open, the
+/// scraper task is restarting or dead, or scrapes have stalled
+#[utoipa::path(get, path = "/health", tag = "health", responses((status = 200, body = HealthResponse)))]
+async fn health_handler(State(state): State<AppState>) -> Json<HealthResponse> {
+    let circuit = state.scraper.circuit_state();
+    let scraper = state.scraper.health();
+    let stalled = state.scraper.stall().is_some();
+    let status = if circuit == CircuitState::Open || scraper != ScraperHealth::Running || stalled {
+        "degraded"
+    } else {
+        "ok"
+    };
+    let started_at = state.metrics.started_at();
+    let scraper_status = state.scraper.status();
+    Json(HealthResponse {
+        status: status.to_string(),
+        upstream_circuit: Some(circuit),
+        scraper: Some(scraper),
+        leader: Some(scraper_status.leader),
+        shard: Some(scraper_status.shard).filter(|shard| scraper_status.leader && !shard.is_all()),
+        version: Some(VERSION.to_string()),
+        started_at: Some(started_at),
+        uptime_secs: Some((Utc::now() - started_at).num_seconds().max(0) as u64),
+        scrape_interval_secs: Some(state.scraper.interval_secs()),
+        source: Some(state.scraper.source().to_string()),
+        build_id: scraper_status.build_id,
+    })
 }
 
-/// Health check endpoint
-async fn health_handler() -> Json<HealthResponse> {
+/// Liveness probe: the process is up and serving requests
+#[utoipa::path(get, path = "/health/live", tag = "health", responses((status = 200, body = HealthResponse)))]
+async fn liveness_handler() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok".to_string(),
+        ..Default::default()
     })
 }
 
+/// Readiness probe: database reachable, scrapes succeeding and build ID usable
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All checks passed", body = ReadinessResponse),
+        (status = 503, description = "At least one check failed", body = ReadinessResponse)
+    )
+)]
+async fn readiness_handler(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let database = match state.storage.ping().await {
+        Ok(()) => ReadinessCheck {
+            name: "database".to_string(),
+            ok: true,
+            detail: None,
+        },
+        Err(e) => {
+            warn!("Readiness check could not reach the database: {}", e);
+            ReadinessCheck {
+                name: "database".to_string(),
+                ok: false,
+                detail: Some(format!("database unreachable: {}", e)),
+            }
+        }
+    };
+
+    let scraper_status = state.scraper.status();
+    let stall = state.scraper.stall();
+    let skipped = if scraper_status.state == ScraperState::Paused {
+        Some("scraper paused, check skipped".to_string())
+    } else if !scraper_status.leader {
+        Some("another instance is the scraper leader, check skipped".to_string())
+    } else {
+        None
+    };
+    let last_scrape = ReadinessCheck {
+        name: "last_scrape".to_string(),
+        ok: stall.is_none(),
+        detail: stall.or(skipped),
+    };
+    let scrapes_ok = last_scrape.ok;
+
+    let build_id_valid = state.scraper.build_id_valid();
+    let build_id = ReadinessCheck {
+        name: "build_id".to_string(),
+        ok: build_id_valid,
+        detail: (!build_id_valid).then(|| "no Next.js build ID discovered".to_string()),
+    };
+
+    let checks = vec![database, last_scrape, build_id];
+    let ready = checks.iter().all(|check| check.ok);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    // Stalled scrapes alone leave stale data served, rather than none
+    let summary = if ready {
+        "ready"
+    } else if !scrapes_ok && checks.iter().filter(|check| !check.ok).count() == 1 {
+        "degraded"
+    } else {
+        "not_ready"
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            status: summary.to_string(),
+            checks,
+        }),
+    )
+}
+
 /// Metrics endpoint
+#[utoipa::path(get, path = "/metrics", tag = "health", responses((status = 200, body = MetricsResponse)))]
 async fn metrics_handler(
     State(state): State<AppState>,
 ) -> Result<Json<MetricsResponse>, StatusCode> {
-    let total_markets = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM markets")
-        .fetch_one(&*state.pool)
-        .await
-        .map_err(|e| {
-            error!("Database error in metrics_handler: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    const CACHE_KEY: &str = "api:metrics";
+    if let Some(mut cached) = state.cache.get::<MetricsResponse>(CACHE_KEY).await {
+        // Cached counts may lag a little, the circuit and scraper state shouldn't
+        cached.upstream_circuit = state.scraper.circuit_state();
+        cached.scraper = state.scraper.health();
+        return Ok(Json(cached));
+    }
+
+    let total_markets = state.storage.count_markets().await.map_err(|e| {
+        error!("Database error in metrics_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
     let last_scrape_time = state.metrics.get_last_scrape_time();
 
-    Ok(Json(MetricsResponse {
+    let mut shards = state.storage.get_shard_progress().await.map_err(|e| {
+        error!("Database error in metrics_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let now = Utc::now();
+    for shard in &mut shards {
+        shard.lag_secs = shard
+            .last_success_at
+            .map(|at| (now - at).num_seconds().max(0));
+    }
+
+    let response = MetricsResponse {
         total_markets,
         total_scrapes: state.metrics.get_total_scrapes(),
         successful_scrapes: state.metrics.get_successful_scrapes(),
         failed_scrapes: state.metrics.get_failed_scrapes(),
+        markets_discovered: state.metrics.get_markets_discovered(),
         last_scrape_time,
-    }))
+        rejected_markets: state
+            .metrics
+            .get_rejections()
+            .into_iter()
+            .map(|(reason, count)| (reason.to_string(), count))
+            .collect(),
+        errors: state
+            .metrics
+            .get_errors()
+            .into_iter()
+            .map(|(category, count)| (category.as_str().to_string(), count))
+            .collect(),
+        upstream_circuit: state.scraper.circuit_state(),
+        scraper: state.scraper.health(),
+        shards,
+    };
+    state.cache.set(CACHE_KEY, &response).await;
+
+    Ok(Json(response))
+}
+
+/// Aggregate market statistics
+#[utoipa::path(get, path = "/stats", tag = "markets", responses((status = 200, body = StatsResponse)))]
+async fn stats_handler(State(state): State<AppState>) -> Result<Json<StatsResponse>, StatusCode> {
+    const CACHE_KEY: &str = "api:stats";
+    if let Some(cached) = state.cache.get::<StatsResponse>(CACHE_KEY).await {
+        return Ok(Json(cached));
+    }
+
+    let stats = state.storage.get_market_stats().await.map_err(|e| {
+        error!("Database error in stats_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    state.cache.set(CACHE_KEY, &stats).await;
+
+    Ok(Json(stats))
+}
+
+/// All tags with the number of markets carrying each, most used first
+#[utoipa::path(get, path = "/tags", tag = "markets", responses((status = 200, body = Vec<TagCount>)))]
+async fn tags_handler(State(state): State<AppState>) -> Result<Json<Vec<TagCount>>, StatusCode> {
+    let tags = state.storage.get_tag_counts().await.map_err(|e| {
+        error!("Database error in tags_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(tags))
+}
+
+/// Prometheus text-format metrics endpoint
+async fn prometheus_metrics_handler(State(state): State<AppState>) -> Result<Response, StatusCode> {
+    let counts = state.storage.count_markets_by_status()
+        .await
+        .map_err(|e| {
+            error!("Database error in prometheus_metrics_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    for (status, count) in counts {
+        ::metrics::gauge!(metrics::MARKETS, "status" => status).set(count as f64);
+    }
+    // A task that died can't report it, so refresh the gauge on every scrape of this endpoint
+    scraper::set_health_gauge(state.scraper.health());
+
+    let body = state.metrics.render_prometheus().ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+        .into_response())
+}
+
+/// Scraper state and interval
+async fn scraper_status_handler(State(state): State<AppState>) -> Json<ScraperStatus> {
+    Json(state.scraper.status())
+}
+
+/// Stop scheduled scrapes until resumed
+async fn pause_scraper_handler(State(state): State<AppState>) -> Json<ScraperStatus> {
+    info!("Scraper paused via admin API");
+    state.scraper.set_state(ScraperState::Paused);
+    Json(state.scraper.status())
+}
+
+/// Restart scheduled scrapes
+async fn resume_scraper_handler(State(state): State<AppState>) -> Json<ScraperStatus> {
+    info!("Scraper resumed via admin API");
+    state.scraper.set_state(ScraperState::Running);
+    Json(state.scraper.status())
+}
+
+/// Run a scrape cycle now and return its summary
+async fn trigger_scrape_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ScrapeSummary>, StatusCode> {
+    info!("Immediate scrape triggered via admin API");
+
+    let summary = state.scraper.trigger_scrape().await.map_err(|e| {
+        error!("Admin-triggered scrape failed: {}", e);
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    Ok(Json(summary))
+}
+
+/// Fetch one market from upstream now, store it and return the fresh record
+async fn refresh_market_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Market>, StatusCode> {
+    info!("Refresh of market {} triggered via admin API", id);
+
+    let mut market = state.scraper.refresh_market(&id)
+        .await
+        .map_err(|e| {
+            error!("Admin-triggered refresh of market {} failed: {}", id, e);
+            StatusCode::BAD_GATEWAY
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    market.outcomes = state.storage.get_outcomes(&market.id)
+        .await
+        .map_err(|e| {
+            error!("Database error in refresh_market_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    market.tags = state.storage.get_market_tags(&market.id)
+        .await
+        .map_err(|e| {
+            error!("Database error in refresh_market_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(market))
+}
+
+/// Current scrape interval
+async fn get_scrape_interval_handler(State(state): State<AppState>) -> Json<ScrapeIntervalRequest> {
+    Json(ScrapeIntervalRequest {
+        interval_secs: state.scraper.interval_secs(),
+    })
+}
+
+/// Change the scrape interval without restarting the scraper
+async fn set_scrape_interval_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ScrapeIntervalRequest>,
+) -> Result<Json<ScrapeIntervalRequest>, StatusCode> {
+    state
+        .scraper
+        .set_interval_secs(request.interval_secs)
+        .map_err(|e| {
+            warn!("Rejected scrape interval change: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    info!("Scrape interval set to {} seconds via admin API", request.interval_secs);
+
+    Ok(Json(request))
 }
 
 /// Get all markets with pagination
+/// First-page responses are cached, keyed by the raw query string
+#[utoipa::path(
+    get,
+    path = "/markets",
+    tag = "markets",
+    params(PaginationParams, FormatParams),
+    responses((
+        status = 200,
+        description = "Markets as JSON, or CSV rows for `?format=csv` or `Accept: text/csv`",
+        body = MarketsResponse
+    ))
+)]
 async fn markets_handler(
     State(state): State<AppState>,
     Query(params): Query<PaginationParams>,
-) -> Result<Json<MarketsResponse>, StatusCode> {
-    info!(
-        "Fetching markets with limit={}, offset={}",
-        params.limit, params.offset
-    );
+    Query(format): Query<FormatParams>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    info!("Fetching markets with {:?}", params);
 
-    let (markets, total) = db::get_markets(&state.pool, params.limit, params.offset)
+    let filter = params.market_filter();
+    if wants_csv(&headers, format.format.as_deref()) {
+        let rows = state.storage.stream_markets(params.limit, params.offset, &filter);
+        return Ok(csv_response(export::CSV_HEADER, rows, export::market_csv_row));
+    }
+
+    let cache_key = (params.offset == 0)
+        .then(|| format!("api:markets:{}", query.unwrap_or_default()));
+    if let Some(key) = &cache_key {
+        if let Some(cached) = state.cache.get::<MarketsResponse>(key).await {
+            return Ok(Json(cached).into_response());
+        }
+    }
+
+    let (mut markets, total) = state.storage.get_markets(params.limit, params.offset, &filter)
+        .await
+        .map_err(|e| {
+            error!("Database error in markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    state.storage.attach_outcomes(&mut markets)
         .await
         .map_err(|e| {
             error!("Database error in markets_handler: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    Ok(Json(MarketsResponse {
+    let response = MarketsResponse {
         markets,
         total,
         limit: params.limit,
         offset: params.offset,
-    }))
+    };
+    if let Some(key) = &cache_key {
+        state.cache.set(key, &response).await;
+    }
+
+    Ok(Json(response).into_response())
+}
+
+/// True if the client asked for CSV via `?format=csv` or, absent that, the `Accept` header
+fn wants_csv(headers: &HeaderMap, format: Option<&str>) -> bool {
+    match format {
+        Some(format) => format.eq_ignore_ascii_case("csv"),
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/csv")),
+    }
+}
+
+/// Stream rows from the database as a CSV body without buffering them
+fn csv_response<T, S>(csv_header: &'static str, rows: S, to_row: fn(&T) -> String) -> Response
+where
+    T: 'static,
+    S: Stream<Item = crate::error::Result<T>> + Send + 'static,
+{
+    let lines = tokio_stream::once(Ok(format!("{}\n", csv_header))).chain(rows.map(move |row| {
+        row.map(|r| format!("{}\n", to_row(&r)))
+            .map_err(anyhow::Error::from)
+    }));
+
+    streaming_response("text/csv; charset=utf-8", lines)
+}
+
+/// Response whose body is sent line by line as the stream produces it
+/// A database error mid-stream is logged and cuts the response short
+fn streaming_response<S>(content_type: &'static str, lines: S) -> Response
+where
+    S: Stream<Item = anyhow::Result<String>> + Send + 'static,
+{
+    let lines = lines.map(|line| {
+        line.map_err(|e| {
+            error!("Database error while streaming response: {}", e);
+            e
+        })
+    });
+
+    ([(header::CONTENT_TYPE, content_type)], Body::from_stream(lines)).into_response()
+}
+
+/// Export every stored market as newline-delimited JSON, streamed row by row
+#[utoipa::path(
+    get,
+    path = "/export/markets.jsonl",
+    tag = "markets",
+    responses((status = 200, description = "One JSON-encoded `Market` per line", content_type = "application/x-ndjson"))
+)]
+async fn export_jsonl_handler(State(state): State<AppState>) -> Response {
+    info!("Streaming JSON Lines export of all markets");
+
+    let filter = MarketFilter {
+        include_delisted: true,
+        ..Default::default()
+    };
+    let lines = state.storage.stream_markets(u32::MAX, 0, &filter).map(
+        |row| -> anyhow::Result<String> {
+            let market = row?;
+            Ok(serde_json::to_string(&market)? + "\n")
+        },
+    );
+
+    streaming_response("application/x-ndjson", lines)
 }
 
 /// Get markets discovered since a given timestamp
+#[utoipa::path(
+    get,
+    path = "/markets/new",
+    tag = "markets",
+    params(SinceParams),
+    responses((status = 200, body = Vec<Market>))
+)]
 async fn new_markets_handler(
     State(state): State<AppState>,
     Query(params): Query<SinceParams>,
 ) -> Result<Json<Vec<Market>>, StatusCode> {
     info!("Fetching markets discovered since: {}", params.since);
 
-    let markets = db::get_markets_since(&state.pool, params.since)
+    let markets = state.storage.get_markets_since(params.since)
         .await
         .map_err(|e| {
             error!("Database error in new_markets_handler: {}", e);
@@ -131,23 +1006,1530 @@ async fn new_markets_handler(
     Ok(Json(markets))
 }
 
-/// Get a single market by ID
+/// Markets with the largest price change over a window
+#[utoipa::path(
+    get,
+    path = "/markets/movers",
+    tag = "markets",
+    params(MoversParams),
+    responses(
+        (status = 200, body = Vec<MarketMover>),
+        (status = 400, description = "Invalid window")
+    )
+)]
+async fn movers_handler(
+    State(state): State<AppState>,
+    Query(params): Query<MoversParams>,
+) -> Result<Json<Vec<MarketMover>>, StatusCode> {
+    let since = window_start(&params.window, Utc::now()).ok_or(StatusCode::BAD_REQUEST)?;
+    let limit = params.limit.min(MAX_MOVERS_LIMIT);
+
+    let movers = state.storage.get_top_movers(since, params.sort, limit)
+        .await
+        .map_err(|e| {
+            error!("Database error in movers_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(movers))
+}
+
+/// Markets flagged for trading far more volume than their 24h average, newest first
+#[utoipa::path(
+    get,
+    path = "/markets/spikes",
+    tag = "markets",
+    params(SpikesParams),
+    responses(
+        (status = 200, body = Vec<VolumeSpike>),
+        (status = 400, description = "Invalid window")
+    )
+)]
+async fn spikes_handler(
+    State(state): State<AppState>,
+    Query(params): Query<SpikesParams>,
+) -> Result<Json<Vec<VolumeSpike>>, StatusCode> {
+    let since = window_start(&params.window, Utc::now()).ok_or(StatusCode::BAD_REQUEST)?;
+    let limit = params.limit.min(MAX_SPIKES_LIMIT);
+
+    let spikes = state.storage.get_volume_spikes(since, limit)
+        .await
+        .map_err(|e| {
+            error!("Database error in spikes_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(spikes))
+}
+
+/// Active markets whose end date falls within a window from now, soonest first
+#[utoipa::path(
+    get,
+    path = "/markets/ending",
+    tag = "markets",
+    params(EndingParams),
+    responses(
+        (status = 200, body = Vec<Market>),
+        (status = 400, description = "Invalid window")
+    )
+)]
+async fn ending_markets_handler(
+    State(state): State<AppState>,
+    Query(params): Query<EndingParams>,
+) -> Result<Json<Vec<Market>>, StatusCode> {
+    let within = parse_window(&params.within).ok_or(StatusCode::BAD_REQUEST)?;
+    let now = Utc::now();
+    let until = now.checked_add_signed(within).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let markets = state.storage.get_markets_ending_between(
+        now,
+        until,
+        params.limit.min(MAX_ENDING_LIMIT),
+    )
+    .await
+    .map_err(|e| {
+        error!("Database error in ending_markets_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(markets))
+}
+
+/// Look up many markets at once, reporting IDs that weren't found
+#[utoipa::path(
+    post,
+    path = "/markets/batch",
+    tag = "markets",
+    request_body = Vec<String>,
+    responses(
+        (status = 200, body = BatchMarketsResponse),
+        (status = 400, description = "More than 500 IDs requested")
+    )
+)]
+async fn batch_markets_handler(
+    State(state): State<AppState>,
+    Json(ids): Json<Vec<String>>,
+) -> Result<Json<BatchMarketsResponse>, StatusCode> {
+    // Duplicates are dropped so they don't count against the cap twice
+    let ids = dedup_ids(ids);
+    if ids.len() > MAX_BATCH_IDS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut found = state.storage.get_markets_by_ids(&ids)
+        .await
+        .map_err(|e| {
+            error!("Database error in batch_markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    state.storage.attach_outcomes(&mut found)
+        .await
+        .map_err(|e| {
+            error!("Database error in batch_markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(order_batch(&ids, found)))
+}
+
+/// Drop repeated IDs, keeping the first occurrence of each
+fn dedup_ids(ids: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(id.clone())).collect()
+}
+
+/// Order found markets as requested and collect the IDs with no match
+fn order_batch(ids: &[String], found: Vec<Market>) -> BatchMarketsResponse {
+    let mut by_id: std::collections::HashMap<String, Market> =
+        found.into_iter().map(|m| (m.id.clone(), m)).collect();
+
+    let mut markets = Vec::with_capacity(by_id.len());
+    let mut missing = Vec::new();
+    for id in ids {
+        match by_id.remove(id) {
+            Some(market) => markets.push(market),
+            None => missing.push(id.clone()),
+        }
+    }
+
+    BatchMarketsResponse { markets, missing }
+}
+
+/// Server-Sent Events stream of newly discovered and changed markets
+/// Each message's event name is the update type (`new_market` or `market_changed`)
+async fn market_stream_handler(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    info!("New SSE subscriber for market updates");
+    update_stream(state.updates.subscribe(), None)
+}
+
+/// SSE stream of updates, limited to the `watched` markets if given
+fn update_stream(
+    updates: broadcast::Receiver<MarketUpdate>,
+    watched: Option<HashSet<String>>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let stream = BroadcastStream::new(updates).filter_map(move |msg| match msg {
+        Ok(update) => {
+            if !is_watched(&watched, &update) {
+                return None;
+            }
+            SseEvent::default()
+                .event(update.kind())
+                .json_data(&update)
+                .ok()
+                .map(Ok)
+        }
+        Err(e) => {
+            warn!("SSE subscriber lagging behind: {}", e);
+            None
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Whether an update concerns one of the `watched` markets; everything is watched without a list
+fn is_watched(watched: &Option<HashSet<String>>, update: &MarketUpdate) -> bool {
+    watched
+        .as_ref()
+        .is_none_or(|ids| ids.contains(&update.market().id))
+}
+
+/// WebSocket push API for market updates
+/// Initial filters come from the query string (`min_volume`, `keyword`, `prices_only`);
+/// clients can replace them at any time by sending a filter as a JSON text message
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(filter): Query<UpdateFilter>,
+) -> Response {
+    info!("New WebSocket subscriber with filter: {:?}", filter);
+    let updates = state.updates.subscribe();
+    ws.on_upgrade(move |socket| handle_ws_connection(socket, updates, filter, None))
+}
+
+/// Forward matching updates to a WebSocket client until either side closes
+/// With `watched` set, only updates of those markets are forwarded whatever the filter
+async fn handle_ws_connection(
+    mut socket: WebSocket,
+    mut updates: broadcast::Receiver<MarketUpdate>,
+    mut filter: UpdateFilter,
+    watched: Option<HashSet<String>>,
+) {
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    if !is_watched(&watched, &update) || !filter.matches(&update) {
+                        continue;
+                    }
+                    let Ok(text) = serde_json::to_string(&update) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket subscriber lagging behind, skipped {} updates", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    let reply = match serde_json::from_str::<UpdateFilter>(&text) {
+                        Ok(new_filter) => {
+                            filter = new_filter;
+                            serde_json::json!({ "type": "subscribed", "filter": filter })
+                        }
+                        Err(e) => serde_json::json!({ "type": "error", "message": e.to_string() }),
+                    };
+                    if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    info!("WebSocket subscriber disconnected");
+}
+
+/// Get a single market by ID, slug, condition ID, question ID or CLOB token ID
+#[utoipa::path(
+    get,
+    path = "/markets/{id}",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID, slug, condition ID, question ID or CLOB token ID")),
+    responses((status = 200, body = Market), (status = 404, description = "Market not found"))
+)]
 async fn market_by_id_handler(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> Result<Json<Market>, StatusCode> {
     info!("Fetching market with ID: {}", id);
 
-    let market = db::get_market_by_id(&state.pool, &id)
+    let mut market = state.storage.find_market(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in market_by_id_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    market.outcomes = state.storage.get_outcomes(&market.id)
+        .await
+        .map_err(|e| {
+            error!("Database error in market_by_id_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    market.tags = state.storage.get_market_tags(&market.id)
         .await
         .map_err(|e| {
             error!("Database error in market_by_id_handler: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    match market {
-        Some(m) => Ok(Json(m)),
-        None => Err(StatusCode::NOT_FOUND),
-    }
+    Ok(Json(market))
 }
 
+/// Price history of a market, newest first; CSV via `?format=csv` or `Accept: text/csv`
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/history",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID"), HistoryParams, FormatParams),
+    responses(
+        (status = 200, body = Vec<PricePoint>),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn price_history_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryParams>,
+    Query(format): Query<FormatParams>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let market = state.storage.get_market_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in price_history_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if market.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let limit = params.limit.min(MAX_HISTORY_LIMIT);
+    if wants_csv(&headers, format.format.as_deref()) {
+        let rows = state.storage.stream_price_history(&id, limit);
+        return Ok(csv_response(
+            export::PRICE_HISTORY_CSV_HEADER,
+            rows,
+            export::price_point_csv_row,
+        ));
+    }
+
+    let history = state.storage.get_price_history(&id, limit)
+        .await
+        .map_err(|e| {
+            error!("Database error in price_history_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(history).into_response())
+}
+
+/// Field-level change log of a market (title edits, price moves, end date extensions), newest first
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/changes",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID"), HistoryParams),
+    responses(
+        (status = 200, body = Vec<MarketChange>),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn market_changes_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<Vec<MarketChange>>, StatusCode> {
+    let market = state.storage.get_market_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in market_changes_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if market.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let changes = state.storage.get_market_changes(&id, params.limit.min(MAX_HISTORY_LIMIT))
+        .await
+        .map_err(|e| {
+            error!("Database error in market_changes_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(changes))
+}
+
+/// Recent trades of a market, newest first; collected for hot-tier markets only
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/trades",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID"), TradesParams),
+    responses(
+        (status = 200, body = Vec<Trade>),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn trades_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<TradesParams>,
+) -> Result<Json<Vec<Trade>>, StatusCode> {
+    let market = state.storage.get_market_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in trades_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if market.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let trades = state.storage.get_trades(&id, params.since, params.limit.min(MAX_HISTORY_LIMIT))
+        .await
+        .map_err(|e| {
+            error!("Database error in trades_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(trades))
+}
+
+/// Latest orderbook snapshot of a market with its depth and imbalance; captured for
+/// hot-tier markets only
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/orderbook",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID")),
+    responses(
+        (status = 200, body = OrderbookSnapshot),
+        (status = 404, description = "No orderbook captured for the market")
+    )
+)]
+async fn orderbook_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<OrderbookSnapshot>, StatusCode> {
+    let snapshot = state.storage.get_latest_orderbook_snapshot(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in orderbook_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(snapshot))
+}
+
+/// Top holders of each outcome of a market from one capture; captured for hot-tier
+/// markets only
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/holders",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID"), HoldersParams),
+    responses(
+        (status = 200, body = HoldersResponse),
+        (status = 404, description = "No holders captured for the market")
+    )
+)]
+async fn holders_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<HoldersParams>,
+) -> Result<Json<HoldersResponse>, StatusCode> {
+    let holders = state.storage.get_holders(&id, params.at)
+        .await
+        .map_err(|e| {
+            error!("Database error in holders_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let captured_at = holders.first().ok_or(StatusCode::NOT_FOUND)?.captured_at;
+
+    Ok(Json(HoldersResponse {
+        market_id: id,
+        captured_at,
+        holders,
+    }))
+}
+
+/// Recent comments on a market's event with its comment counts; collected for hot-tier
+/// markets only
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/comments",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID"), HistoryParams),
+    responses(
+        (status = 200, body = CommentsResponse),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn comments_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<CommentsResponse>, StatusCode> {
+    let market = state.storage.get_market_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in comments_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let Some(event_id) = market.event_id else {
+        return Ok(Json(CommentsResponse {
+            market_id: id,
+            event_id: None,
+            comment_count: None,
+            comments_last_hour: 0,
+            comments: Vec::new(),
+        }));
+    };
+
+    let event = state.storage.get_event_by_id(&event_id)
+        .await
+        .map_err(|e| {
+            error!("Database error in comments_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let comments_last_hour = state.storage
+        .count_comments_since(Some(&event_id), Utc::now() - chrono::Duration::hours(1))
+        .await
+        .map_err(|e| {
+            error!("Database error in comments_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let comments = state.storage.get_comments(&event_id, params.limit.min(MAX_HISTORY_LIMIT))
+        .await
+        .map_err(|e| {
+            error!("Database error in comments_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(CommentsResponse {
+        market_id: id,
+        event_id: Some(event_id),
+        comment_count: event.and_then(|event| event.comment_count),
+        comments_last_hour,
+        comments,
+    }))
+}
+
+/// Latest comparison of a market with its CTF condition on Polygon
+/// Only recorded when the service runs with the `onchain` feature and an RPC URL
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/onchain",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID")),
+    responses(
+        (status = 200, body = OnchainVerification),
+        (status = 404, description = "Market not checked on chain yet")
+    )
+)]
+async fn onchain_verification_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<OnchainVerification>, StatusCode> {
+    let verification = state.storage.get_onchain_verification(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in onchain_verification_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(verification))
+}
+
+/// Hourly or daily open/high/low/close candles of a market, newest first
+#[utoipa::path(
+    get,
+    path = "/markets/{id}/candles",
+    tag = "markets",
+    params(("id" = String, Path, description = "Market ID"), CandleParams),
+    responses(
+        (status = 200, body = Vec<Candle>),
+        (status = 404, description = "Market not found")
+    )
+)]
+async fn candles_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<CandleParams>,
+) -> Result<Json<Vec<Candle>>, StatusCode> {
+    let market = state.storage.get_market_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in candles_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if market.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let candles = state.storage.get_candles(&id, params.interval, params.limit.min(MAX_HISTORY_LIMIT))
+        .await
+        .map_err(|e| {
+            error!("Database error in candles_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(candles))
+}
+
+/// Get all events with pagination
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    params(PaginationParams),
+    responses((status = 200, body = EventsResponse))
+)]
+async fn events_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<EventsResponse>, StatusCode> {
+    info!(
+        "Fetching events with limit={}, offset={}",
+        params.limit, params.offset
+    );
+
+    let (events, total) = state.storage.get_events(params.limit, params.offset)
+        .await
+        .map_err(|e| {
+            error!("Database error in events_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(EventsResponse {
+        events,
+        total,
+        limit: params.limit,
+        offset: params.offset,
+    }))
+}
+
+/// Get a single event by ID
+#[utoipa::path(
+    get,
+    path = "/events/{id}",
+    tag = "events",
+    params(("id" = String, Path, description = "Event ID")),
+    responses((status = 200, body = Event), (status = 404, description = "Event not found"))
+)]
+async fn event_by_id_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Event>, StatusCode> {
+    info!("Fetching event with ID: {}", id);
+
+    let event = state.storage.get_event_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in event_by_id_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    match event {
+        Some(ev) => Ok(Json(ev)),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Get all markets belonging to an event
+#[utoipa::path(
+    get,
+    path = "/events/{id}/markets",
+    tag = "events",
+    params(("id" = String, Path, description = "Event ID")),
+    responses((status = 200, body = Vec<Market>), (status = 404, description = "Event not found"))
+)]
+async fn event_markets_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Market>>, StatusCode> {
+    info!("Fetching markets for event: {}", id);
+
+    let event = state.storage.get_event_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in event_markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if event.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let markets = state.storage.get_markets_by_event(&id)
+        .await
+        .map_err(|e| {
+            error!("Database error in event_markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(markets))
+}
+
+/// Register a webhook
+/// Requires the admin token
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, body = Webhook),
+        (status = 400, description = "Invalid URL"),
+        (status = 401, description = "Missing or invalid admin token")
+    )
+)]
+async fn create_webhook_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<Webhook>), StatusCode> {
+    if reqwest::Url::parse(&request.url).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    info!("Registering webhook: {}", request.url);
+
+    let webhook = state.storage.insert_webhook(&request.url, request.secret.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Database error in create_webhook_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// List registered webhooks (secrets are omitted)
+/// Requires the admin token
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, body = Vec<Webhook>),
+        (status = 401, description = "Missing or invalid admin token")
+    )
+)]
+async fn list_webhooks_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Webhook>>, StatusCode> {
+    let webhooks = state.storage.get_webhooks().await.map_err(|e| {
+        error!("Database error in list_webhooks_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(webhooks))
+}
+
+/// Delete a webhook
+/// Requires the admin token
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = i64, Path, description = "Webhook ID")),
+    responses(
+        (status = 204),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+async fn delete_webhook_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> StatusCode {
+    info!("Deleting webhook: {}", id);
+
+    match state.storage.delete_webhook(id).await {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Database error in delete_webhook_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// List the most recent failed webhook deliveries
+/// Requires the admin token
+#[utoipa::path(
+    get,
+    path = "/webhooks/dead-letters",
+    tag = "webhooks",
+    params(PaginationParams),
+    responses(
+        (status = 200, body = Vec<WebhookDeadLetter>),
+        (status = 401, description = "Missing or invalid admin token")
+    )
+)]
+async fn webhook_dead_letters_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<Vec<WebhookDeadLetter>>, StatusCode> {
+    let dead_letters = state.storage.get_webhook_dead_letters(params.limit)
+        .await
+        .map_err(|e| {
+            error!("Database error in webhook_dead_letters_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(dead_letters))
+}
+
+/// Create an alert rule, evaluated against the updates of every scrape
+/// Requires the admin token
+#[utoipa::path(
+    post,
+    path = "/alerts",
+    tag = "alerts",
+    request_body = CreateAlertRuleRequest,
+    responses(
+        (status = 201, body = AlertRule),
+        (status = 400, description = "Invalid condition"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 409, description = "A rule with this name already exists")
+    )
+)]
+async fn create_alert_rule_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CreateAlertRuleRequest>,
+) -> Result<(StatusCode, Json<AlertRule>), StatusCode> {
+    if let Err(e) = rules::validate(&request.condition) {
+        warn!("Rejected alert rule {}: {}", request.name, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    info!("Creating alert rule: {}", request.name);
+
+    let rule = state.storage.insert_alert_rule(&request.name, &request.condition)
+        .await
+        .map_err(|e| {
+            error!("Database error in create_alert_rule_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+/// List alert rules, including those loaded from the configuration
+/// Requires the admin token
+#[utoipa::path(
+    get,
+    path = "/alerts",
+    tag = "alerts",
+    responses(
+        (status = 200, body = Vec<AlertRule>),
+        (status = 401, description = "Missing or invalid admin token")
+    )
+)]
+async fn list_alert_rules_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<AlertRule>>, StatusCode> {
+    let rules = state.storage.get_alert_rules().await.map_err(|e| {
+        error!("Database error in list_alert_rules_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rules))
+}
+
+/// Delete an alert rule; alerts it already fired are kept
+/// Requires the admin token
+#[utoipa::path(
+    delete,
+    path = "/alerts/{id}",
+    tag = "alerts",
+    params(("id" = i64, Path, description = "Alert rule ID")),
+    responses(
+        (status = 204),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 404, description = "Alert rule not found")
+    )
+)]
+async fn delete_alert_rule_handler(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> StatusCode {
+    info!("Deleting alert rule: {}", id);
+
+    match state.storage.delete_alert_rule(id).await {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Database error in delete_alert_rule_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// List the most recently fired alerts, newest first
+#[utoipa::path(
+    get,
+    path = "/alerts/fired",
+    tag = "alerts",
+    params(HistoryParams),
+    responses((status = 200, body = Vec<AlertFired>))
+)]
+async fn alerts_fired_handler(
+    State(state): State<AppState>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<Vec<AlertFired>>, StatusCode> {
+    let alerts = state.storage.get_alerts_fired(params.limit.min(MAX_HISTORY_LIMIT))
+        .await
+        .map_err(|e| {
+            error!("Database error in alerts_fired_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(alerts))
+}
+
+/// Whether an ID has the shape of a Gamma market ID
+fn is_gamma_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 20 && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Market IDs for the watchlist `name`, without duplicates
+/// Rejected with 400 when there are more than `MAX_BATCH_IDS`, when an ID is neither a
+/// Gamma ID nor a stored market, or when the watchlists would watch more than
+/// `MAX_WATCHED_MARKETS` markets between them
+async fn watchlist_ids(
+    state: &AppState,
+    name: &str,
+    market_ids: Vec<String>,
+    handler: &str,
+) -> Result<Vec<String>, StatusCode> {
+    let db_error = |e: crate::error::Error| {
+        error!("Database error in {}: {}", handler, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let ids = dedup_ids(market_ids);
+    if ids.len() > MAX_BATCH_IDS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let other: Vec<String> = ids.iter().filter(|id| !is_gamma_id(id)).cloned().collect();
+    if !other.is_empty() {
+        let stored = state.storage.get_markets_by_ids(&other).await.map_err(db_error)?;
+        if stored.len() < other.len() {
+            warn!("Rejected watchlist {} with unknown market IDs", name);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let mut watched: HashSet<String> = state.storage.get_watchlists()
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .filter(|watchlist| watchlist.name != name)
+        .flat_map(|watchlist| watchlist.market_ids)
+        .collect();
+    watched.extend(ids.iter().cloned());
+    if watched.len() > MAX_WATCHED_MARKETS {
+        warn!(
+            "Rejected watchlist {}: more than {} markets would be watched",
+            name, MAX_WATCHED_MARKETS
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(ids)
+}
+
+/// Load a watchlist, mapping a missing one to 404
+async fn load_watchlist(state: &AppState, name: &str, handler: &str) -> Result<Watchlist, StatusCode> {
+    state.storage.get_watchlist(name)
+        .await
+        .map_err(|e| {
+            error!("Database error in {}: {}", handler, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Create a watchlist of market IDs
+/// Requires the admin token
+#[utoipa::path(
+    post,
+    path = "/watchlists",
+    tag = "watchlists",
+    request_body = CreateWatchlistRequest,
+    responses(
+        (status = 201, body = Watchlist),
+        (status = 400, description = "Empty name, more than 500 markets, unknown market IDs or more than 1000 markets watched in total"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 409, description = "A watchlist with this name already exists")
+    )
+)]
+async fn create_watchlist_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CreateWatchlistRequest>,
+) -> Result<(StatusCode, Json<Watchlist>), StatusCode> {
+    if request.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let ids = watchlist_ids(&state, &request.name, request.market_ids, "create_watchlist_handler")
+        .await?;
+    info!("Creating watchlist {} with {} markets", request.name, ids.len());
+
+    let watchlist = state.storage.create_watchlist(&request.name, &ids)
+        .await
+        .map_err(|e| {
+            error!("Database error in create_watchlist_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    Ok((StatusCode::CREATED, Json(watchlist)))
+}
+
+/// List watchlists by name
+#[utoipa::path(get, path = "/watchlists", tag = "watchlists", responses((status = 200, body = Vec<Watchlist>)))]
+async fn list_watchlists_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Watchlist>>, StatusCode> {
+    let watchlists = state.storage.get_watchlists().await.map_err(|e| {
+        error!("Database error in list_watchlists_handler: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(watchlists))
+}
+
+/// Get a watchlist
+#[utoipa::path(
+    get,
+    path = "/watchlists/{name}",
+    tag = "watchlists",
+    params(("name" = String, Path, description = "Watchlist name")),
+    responses((status = 200, body = Watchlist), (status = 404, description = "Watchlist not found"))
+)]
+async fn get_watchlist_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Watchlist>, StatusCode> {
+    Ok(Json(load_watchlist(&state, &name, "get_watchlist_handler").await?))
+}
+
+/// Replace the markets of a watchlist
+/// Requires the admin token
+#[utoipa::path(
+    put,
+    path = "/watchlists/{name}",
+    tag = "watchlists",
+    params(("name" = String, Path, description = "Watchlist name")),
+    request_body = UpdateWatchlistRequest,
+    responses(
+        (status = 200, body = Watchlist),
+        (status = 400, description = "More than 500 markets, unknown market IDs or more than 1000 markets watched in total"),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 404, description = "Watchlist not found")
+    )
+)]
+async fn update_watchlist_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<UpdateWatchlistRequest>,
+) -> Result<Json<Watchlist>, StatusCode> {
+    let ids = watchlist_ids(&state, &name, request.market_ids, "update_watchlist_handler").await?;
+    info!("Updating watchlist {} with {} markets", name, ids.len());
+
+    let watchlist = state.storage.set_watchlist_markets(&name, &ids)
+        .await
+        .map_err(|e| {
+            error!("Database error in update_watchlist_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(watchlist))
+}
+
+/// Delete a watchlist
+/// Requires the admin token
+#[utoipa::path(
+    delete,
+    path = "/watchlists/{name}",
+    tag = "watchlists",
+    params(("name" = String, Path, description = "Watchlist name")),
+    responses(
+        (status = 204),
+        (status = 401, description = "Missing or invalid admin token"),
+        (status = 404, description = "Watchlist not found")
+    )
+)]
+async fn delete_watchlist_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    info!("Deleting watchlist: {}", name);
+
+    match state.storage.delete_watchlist(&name).await {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Database error in delete_watchlist_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Get the stored markets of a watchlist, in watchlist order
+/// IDs that haven't been scraped yet are left out
+#[utoipa::path(
+    get,
+    path = "/watchlists/{name}/markets",
+    tag = "watchlists",
+    params(("name" = String, Path, description = "Watchlist name")),
+    responses((status = 200, body = Vec<Market>), (status = 404, description = "Watchlist not found"))
+)]
+async fn watchlist_markets_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<Market>>, StatusCode> {
+    let watchlist = load_watchlist(&state, &name, "watchlist_markets_handler").await?;
+
+    let mut found = state.storage.get_markets_by_ids(&watchlist.market_ids)
+        .await
+        .map_err(|e| {
+            error!("Database error in watchlist_markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    state.storage.attach_outcomes(&mut found)
+        .await
+        .map_err(|e| {
+            error!("Database error in watchlist_markets_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(order_batch(&watchlist.market_ids, found).markets))
+}
+
+/// Server-Sent Events stream of updates to a watchlist's markets
+/// Uses the watchlist's markets at the time of connecting
+async fn watchlist_stream_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, StatusCode> {
+    let watchlist = load_watchlist(&state, &name, "watchlist_stream_handler").await?;
+    info!("New SSE subscriber for watchlist {}", name);
+
+    let watched = watchlist.market_ids.into_iter().collect();
+    Ok(update_stream(state.updates.subscribe(), Some(watched)))
+}
+
+/// WebSocket push API for updates to a watchlist's markets
+/// Accepts the same filters as `/ws`, applied on top of the watchlist's markets at the time
+/// of connecting
+async fn watchlist_ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(filter): Query<UpdateFilter>,
+) -> Result<Response, StatusCode> {
+    let watchlist = load_watchlist(&state, &name, "watchlist_ws_handler").await?;
+    info!("New WebSocket subscriber for watchlist {} with filter: {:?}", name, filter);
+
+    let watched = watchlist.market_ids.into_iter().collect();
+    let updates = state.updates.subscribe();
+    Ok(ws.on_upgrade(move |socket| handle_ws_connection(socket, updates, filter, Some(watched))))
+}
+
+/// Summary of a UTC day: new markets, biggest movers, volume leaders and resolved markets
+#[utoipa::path(
+    get,
+    path = "/reports/daily/{date}",
+    tag = "reports",
+    params(("date" = String, Path, description = "UTC day as YYYY-MM-DD")),
+    responses(
+        (status = 200, body = DailyReport),
+        (status = 400, description = "Invalid date"),
+        (status = 404, description = "No report for this day yet")
+    )
+)]
+async fn daily_report_handler(
+    State(state): State<AppState>,
+    Path(date): Path<String>,
+) -> Result<Json<DailyReport>, StatusCode> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let report = state.storage.get_daily_report(date)
+        .await
+        .map_err(|e| {
+            error!("Database error in daily_report_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(report))
+}
+
+/// Pairwise price correlation of markets over a window, to find markets that move together
+#[utoipa::path(
+    get,
+    path = "/analytics/correlation",
+    tag = "analytics",
+    params(CorrelationParams),
+    responses(
+        (status = 200, body = CorrelationResponse),
+        (status = 400, description = "Invalid window, or fewer than 2 or more than 20 IDs")
+    )
+)]
+async fn correlation_handler(
+    State(state): State<AppState>,
+    Query(params): Query<CorrelationParams>,
+) -> Result<Json<CorrelationResponse>, StatusCode> {
+    let window = parse_window(&params.window).ok_or(StatusCode::BAD_REQUEST)?;
+    let ids = dedup_ids(
+        params.ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect(),
+    );
+    if ids.len() < 2 || ids.len() > analytics::MAX_CORRELATION_MARKETS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let to = Utc::now();
+    let from = to.checked_sub_signed(window).ok_or(StatusCode::BAD_REQUEST)?;
+    let rows = state.storage.get_price_history_since(&ids, from)
+        .await
+        .map_err(|e| {
+            error!("Database error in correlation_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let bucket = analytics::bucket_size(window);
+    Ok(Json(CorrelationResponse {
+        window: params.window,
+        bucket_secs: bucket.num_seconds(),
+        correlations: analytics::correlations(&ids, &rows, from, to, bucket),
+    }))
+}
+
+/// How well market prices forecast resolved outcomes: Brier scores and reliability buckets
+/// of the last price 1 hour, 1 day, 7 days and 30 days before resolution
+#[utoipa::path(
+    get,
+    path = "/analytics/calibration",
+    tag = "analytics",
+    responses((status = 200, body = CalibrationResponse))
+)]
+async fn calibration_handler(
+    State(state): State<AppState>,
+) -> Result<Json<CalibrationResponse>, StatusCode> {
+    let calibration = state.storage.get_calibration()
+        .await
+        .map_err(|e| {
+            error!("Database error in calibration_handler: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(calibration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_breaker::BreakerConfig;
+    use crate::db;
+    use crate::scraper::ScrapeSource;
+    use crate::storage::SqliteStorage;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::Service;
+
+    /// Router over an empty in-memory database
+    async fn test_router(admin_token: Option<&str>) -> Router {
+        let (scraper, _signals) = crate::scraper::control(
+            3600,
+            ScrapeSource::Gamma,
+            BreakerConfig {
+                failure_threshold: 5,
+                open_for: std::time::Duration::from_secs(60),
+            },
+        );
+        test_router_with(admin_token, scraper).await
+    }
+
+    /// Router over an empty in-memory database, reporting the state of `scraper`
+    async fn test_router_with(admin_token: Option<&str>, scraper: ScraperControl) -> Router {
+        create_router(
+            Arc::new(SqliteStorage::new(db::test_pools().await)),
+            Arc::new(Metrics::new()),
+            crate::updates::channel(),
+            ResponseCache::default(),
+            scraper,
+            admin_token.map(str::to_string),
+            None,
+        )
+    }
+
+    async fn status_of(router: &Router, request: Request<Body>) -> StatusCode {
+        let mut router = router.clone();
+        std::future::poll_fn(|cx| Service::<Request<Body>>::poll_ready(&mut router, cx))
+            .await
+            .unwrap();
+        router.call(request).await.unwrap().status()
+    }
+
+    fn get_request(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_readiness_follows_the_watchdog() {
+        let (scraper, _signals) = crate::scraper::control(
+            60,
+            ScrapeSource::Gamma,
+            BreakerConfig {
+                failure_threshold: 5,
+                open_for: std::time::Duration::from_secs(60),
+            },
+        );
+        let router = test_router_with(None, scraper.clone()).await;
+        let readiness = || async {
+            let mut router = router.clone();
+            let response = router.call(get_request("/health/ready")).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let last_scrape = body["checks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|check| check["name"] == "last_scrape")
+                .cloned()
+                .unwrap();
+            (status, body["status"].as_str().unwrap().to_string(), last_scrape)
+        };
+
+        scraper.set_leader(true);
+        let (status, summary, last_scrape) = readiness().await;
+        assert_eq!((status, summary.as_str()), (StatusCode::OK, "ready"));
+        assert_eq!(last_scrape["ok"], true);
+
+        // Stalled scrapes alone leave the instance degraded rather than not ready
+        scraper.set_stall(Some("no successful scrape since startup".to_string()));
+        let (status, summary, last_scrape) = readiness().await;
+        assert_eq!((status, summary.as_str()), (StatusCode::SERVICE_UNAVAILABLE, "degraded"));
+        assert_eq!(last_scrape["ok"], false);
+        assert_eq!(last_scrape["detail"], "no successful scrape since startup");
+
+        // Once scrapes recover, and on paused or following instances, the check passes
+        scraper.set_stall(None);
+        scraper.set_state(ScraperState::Paused);
+        let (status, _, last_scrape) = readiness().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(last_scrape["detail"], "scraper paused, check skipped");
+        scraper.set_state(ScraperState::Running);
+        scraper.set_leader(false);
+        let (status, _, last_scrape) = readiness().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            last_scrape["detail"],
+            "another instance is the scraper leader, check skipped"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alert_rules_require_the_admin_token() {
+        let create = |token: Option<&str>| {
+            let mut request =
+                Request::post("/v1/alerts").header("Content-Type", "application/json");
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {}", token));
+            }
+            let body = serde_json::json!({
+                "name": "moves",
+                "condition": { "type": "price_move", "threshold": 0.1, "window": "106751991d" },
+            });
+            request.body(Body::from(body.to_string())).unwrap()
+        };
+
+        // Without an admin token the endpoints aren't mounted at all
+        let router = test_router(None).await;
+        assert_eq!(status_of(&router, create(None)).await, StatusCode::NOT_FOUND);
+
+        let router = test_router(Some("s3cret")).await;
+        assert_eq!(status_of(&router, get_request("/v1/alerts")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_of(&router, create(Some("wrong"))).await, StatusCode::UNAUTHORIZED);
+        // A window that overflows is rejected before it reaches the rules engine
+        assert_eq!(status_of(&router, create(Some("s3cret"))).await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_require_the_admin_token() {
+        let router = test_router(None).await;
+        assert_eq!(status_of(&router, get_request("/v1/webhooks")).await, StatusCode::NOT_FOUND);
+
+        let router = test_router(Some("s3cret")).await;
+        for uri in ["/v1/webhooks", "/v1/webhooks/dead-letters"] {
+            assert_eq!(status_of(&router, get_request(uri)).await, StatusCode::UNAUTHORIZED);
+        }
+        let authorized = Request::get("/v1/webhooks/dead-letters")
+            .header("Authorization", "Bearer s3cret")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(status_of(&router, authorized).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_watchlists_are_validated_and_require_the_admin_token() {
+        let save = |method: &str, uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", "Bearer s3cret")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let ids = |range: std::ops::Range<u32>| range.map(|id| id.to_string()).collect::<Vec<_>>();
+
+        let router = test_router(Some("s3cret")).await;
+        let unauthorized = Request::post("/v1/watchlists")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::json!({ "name": "a", "market_ids": ["1"] }).to_string()))
+            .unwrap();
+        assert_eq!(status_of(&router, unauthorized).await, StatusCode::UNAUTHORIZED);
+
+        // IDs must look like Gamma IDs or name a stored market
+        let unknown = serde_json::json!({ "name": "a", "market_ids": ["1", "../events"] });
+        let status = status_of(&router, save("POST", "/v1/watchlists", unknown)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let first = serde_json::json!({ "name": "a", "market_ids": ids(0..500) });
+        let status = status_of(&router, save("POST", "/v1/watchlists", first)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let second = serde_json::json!({ "name": "b", "market_ids": ids(500..1000) });
+        let status = status_of(&router, save("POST", "/v1/watchlists", second)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        // The watchlists together watch at most 1000 markets
+        let third = serde_json::json!({ "name": "c", "market_ids": ids(1000..1001) });
+        let status = status_of(&router, save("POST", "/v1/watchlists", third)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        // A watchlist replacing its own markets doesn't count them twice
+        let replaced = serde_json::json!({ "market_ids": ids(1000..1500) });
+        let status = status_of(&router, save("PUT", "/v1/watchlists/b", replaced)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Reading stays public
+        assert_eq!(status_of(&router, get_request("/v1/watchlists/a")).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_huge_windows_are_rejected() {
+        let router = test_router(None).await;
+        for uri in [
+            "/v1/markets/movers?window=106751991d",
+            "/v1/markets/spikes?window=106751991d",
+            "/v1/markets/ending?within=106751991d",
+            "/v1/markets/movers?window=9223372036854775807m",
+        ] {
+            let status = status_of(&router, get_request(uri)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        }
+        assert_eq!(
+            status_of(&router, get_request("/v1/markets/movers?window=24h")).await,
+            StatusCode::OK
+        );
+    }
+
+    #[test]
+    fn test_openapi_spec_documents_markets() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        let markets = &spec["paths"]["/markets"]["get"];
+        let params: Vec<&str> = markets["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|p| p["name"].as_str())
+            .collect();
+        assert!(params.contains(&"limit"));
+        assert!(params.contains(&"sort_by"));
+        assert!(params.contains(&"ending_before"));
+
+        assert!(spec["components"]["schemas"]["Market"]["properties"]["current_price"].is_object());
+        assert!(spec["paths"]["/markets/{id}"]["get"].is_object());
+    }
+
+    #[test]
+    fn test_wants_csv() {
+        let mut headers = HeaderMap::new();
+        assert!(!wants_csv(&headers, None));
+        assert!(wants_csv(&headers, Some("CSV")));
+
+        headers.insert(header::ACCEPT, "text/csv, */*;q=0.1".parse().unwrap());
+        assert!(wants_csv(&headers, None));
+        assert!(!wants_csv(&headers, Some("json")));
+    }
+
+    #[test]
+    fn test_order_batch() {
+        let ids = vec!["b".to_string(), "x".to_string(), "a".to_string()];
+        let found = vec![
+            Market {
+                id: "a".to_string(),
+                ..Default::default()
+            },
+            Market {
+                id: "b".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let response = order_batch(&ids, found);
+        let found_ids: Vec<&str> = response.markets.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(found_ids, vec!["b", "a"]);
+        assert_eq!(response.missing, vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("24h"), Some(chrono::Duration::hours(24)));
+        assert_eq!(parse_window("30m"), Some(chrono::Duration::minutes(30)));
+        assert_eq!(parse_window("7d"), Some(chrono::Duration::days(7)));
+        assert_eq!(parse_window("0h"), None);
+        assert_eq!(parse_window("h"), None);
+        assert_eq!(parse_window("24x"), None);
+        assert_eq!(parse_window(""), None);
+        // Windows too long to represent or to subtract from now are rejected, not a panic
+        assert_eq!(parse_window("9223372036854775807d"), None);
+        assert_eq!(parse_window("106751991168d"), None);
+        assert!(parse_window("106751991d").is_some());
+        assert_eq!(window_start("106751991d", Utc::now()), None);
+        assert!(window_start("24h", Utc::now()).is_some());
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use tracing::warn;
+
+use crate::db;
+use crate::models::Precision;
+use crate::scraper::{self, ScrapeSource};
+use crate::validation;
+
+/// Archived payloads loaded per page while reprocessing
+const REPROCESS_PAGE_SIZE: i64 = 256;
+
+/// Gzip-compressed raw payload and the SHA-256 of its uncompressed JSON
+pub struct EncodedPayload {
+    pub compressed: Vec<u8>,
+    pub hash: String,
+}
+
+/// Outcome of a `reprocess` run
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ReprocessSummary {
+    pub reprocessed: u64,
+    pub failed: u64,
+}
+
+/// Compress a raw market payload for storage
+pub fn encode(raw: &serde_json::Value) -> Result<EncodedPayload> {
+    let json = serde_json::to_vec(raw)?;
+    let hash = hex::encode(Sha256::digest(&json));
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json)?;
+    Ok(EncodedPayload {
+        compressed: encoder.finish()?,
+        hash,
+    })
+}
+
+/// Decompress a stored payload back into JSON
+pub fn decode(compressed: &[u8]) -> Result<serde_json::Value> {
+    let mut json = Vec::new();
+    GzDecoder::new(compressed)
+        .read_to_end(&mut json)
+        .context("Failed to decompress raw payload")?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Re-parse the latest archived payload of each market (or a single market) with the
+/// current parsers and write the result back to the markets table, rounded to `precision`
+/// Payloads are read a page at a time from the read pool, so the single writer connection
+/// is free for the updates
+pub async fn reprocess(
+    pools: &db::DbPools,
+    market_id: Option<&str>,
+    precision: Precision,
+) -> Result<ReprocessSummary> {
+    let mut summary = ReprocessSummary::default();
+    let mut after: Option<String> = None;
+
+    loop {
+        let rows = db::get_latest_raw_payloads(
+            &pools.read,
+            market_id,
+            after.as_deref(),
+            REPROCESS_PAGE_SIZE,
+        )
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after = Some(last.market_id.clone());
+
+        for row in rows {
+            let parsed = row
+                .source
+                .parse::<ScrapeSource>()
+                .and_then(|source| {
+                    let raw = decode(&row.payload)?;
+                    let mut market = scraper::parse_raw_market(source, &raw)?;
+                    validation::validate_market(&market)
+                        .map_err(|rejection| anyhow::anyhow!("{}: {}", rejection.reason, rejection.detail))?;
+                    market.round_to(precision);
+                    Ok(market)
+                });
+            let market = match parsed {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!("Failed to reprocess market {}: {}", row.market_id, e);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+
+            let pool = &pools.write;
+            db::upsert_market(pool, &market).await?;
+            db::upsert_outcomes(pool, &market.id, &market.outcomes).await?;
+            db::set_market_tags(pool, &market.id, &market.tags).await?;
+            // Only sources reporting a lifecycle status carry resolution fields
+            if market.status.is_some() {
+                db::update_market_resolution(pool, &market).await?;
+            }
+            summary.reprocessed += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let raw = serde_json::json!({ "id": "1", "question": "Will it rain?", "outcomePrices": "[\"0.4\"]" });
+        let encoded = encode(&raw).unwrap();
+
+        assert_eq!(encoded.hash.len(), 64);
+        assert_eq!(encode(&raw).unwrap().hash, encoded.hash);
+        assert_eq!(decode(&encoded.compressed).unwrap(), raw);
+    }
+}
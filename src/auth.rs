@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Reject requests that don't carry `Authorization: Bearer <admin token>`
+pub async fn require_admin_token(
+    State(token): State<Arc<str>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(provided) if tokens_match(provided, &token) => Ok(next.run(req).await),
+        _ => {
+            warn!("Rejected unauthorized admin request to {}", req.uri().path());
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+/// Compare tokens in constant time so response timing doesn't leak the token
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("s3cret", "s3cret"));
+        assert!(!tokens_match("s3creT", "s3cret"));
+        assert!(!tokens_match("s3cret-longer", "s3cret"));
+        assert!(!tokens_match("", "s3cret"));
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(feature = "redis")]
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "redis")]
+use tracing::warn;
+
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 5;
+
+/// Short-lived cache for hot API responses
+/// Backed by Redis when the `redis` feature is enabled and configured, otherwise a no-op;
+/// cache errors are logged and treated as misses so they never fail a request
+#[derive(Clone, Default)]
+pub struct ResponseCache {
+    #[cfg(feature = "redis")]
+    redis: Option<(ConnectionManager, u64)>,
+}
+
+impl ResponseCache {
+    /// Cache responses in Redis for `ttl_secs` seconds
+    #[cfg(feature = "redis")]
+    pub fn redis(conn: ConnectionManager, ttl_secs: u64) -> Self {
+        Self {
+            redis: Some((conn, ttl_secs)),
+        }
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        #[cfg(feature = "redis")]
+        if let Some((conn, _)) = &self.redis {
+            let cached: Option<String> = match conn.clone().get(key).await {
+                Ok(cached) => cached,
+                Err(e) => {
+                    warn!("Redis cache read failed for {}: {}", key, e);
+                    None
+                }
+            };
+            return cached.and_then(|json| serde_json::from_str(&json).ok());
+        }
+
+        let _ = key;
+        None
+    }
+
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) {
+        #[cfg(feature = "redis")]
+        if let Some((conn, ttl_secs)) = &self.redis {
+            let Ok(json) = serde_json::to_string(value) else {
+                return;
+            };
+            if let Err(e) = conn
+                .clone()
+                .set_ex::<_, _, ()>(key, json, *ttl_secs)
+                .await
+            {
+                warn!("Redis cache write failed for {}: {}", key, e);
+            }
+            return;
+        }
+
+        let _ = (key, value);
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info};
+
+use crate::models::{CalibrationHorizon, Market, MarketCalibration, Outcome};
+use crate::scraper::ScraperControl;
+use crate::storage::Storage;
+
+/// Markets scored per batch
+const SCORING_BATCH_SIZE: u32 = 100;
+const SCORING_INTERVAL: Duration = Duration::from_secs(600);
+
+/// 1.0 if the first outcome won, 0.0 if another did; None if the winner isn't known
+/// Without stored outcomes the market is taken to be a `Yes`/`No` market
+fn realized_outcome(market: &Market, outcomes: &[Outcome]) -> Option<f64> {
+    let winner = market.winning_outcome.as_deref()?;
+    let first = outcomes
+        .first()
+        .map_or("Yes", |outcome| outcome.name.as_str());
+    Some(if winner.eq_ignore_ascii_case(first) {
+        1.0
+    } else {
+        0.0
+    })
+}
+
+/// Forecasts of a resolved market at every horizon, from its price history
+async fn score_market(storage: &dyn Storage, market: &Market) -> Result<Option<MarketCalibration>> {
+    let Some(resolved_at) = market.resolved_at else {
+        return Ok(None);
+    };
+    let outcomes = storage.get_outcomes(&market.id).await?;
+    let Some(outcome) = realized_outcome(market, &outcomes) else {
+        return Ok(None);
+    };
+
+    let mut forecasts = [None; CalibrationHorizon::ALL.len()];
+    for (forecast, horizon) in forecasts.iter_mut().zip(CalibrationHorizon::ALL) {
+        *forecast = storage
+            .get_last_price_at(&market.id, resolved_at - horizon.duration())
+            .await?;
+    }
+    Ok(Some(MarketCalibration {
+        market_id: market.id.clone(),
+        outcome,
+        resolved_at,
+        forecasts,
+    }))
+}
+
+/// Score every resolved market that hasn't been scored yet; returns how many were scored
+/// Markets without any price at a horizon are stored too, so they aren't looked at again
+pub async fn score_pending(storage: &dyn Storage) -> Result<usize> {
+    let mut scored = 0;
+    loop {
+        let pending = storage
+            .get_markets_pending_calibration(SCORING_BATCH_SIZE)
+            .await?;
+        for market in &pending {
+            if let Some(calibration) = score_market(storage, market).await? {
+                storage.save_market_calibration(&calibration).await?;
+                scored += 1;
+            }
+        }
+        if pending.len() < SCORING_BATCH_SIZE as usize {
+            return Ok(scored);
+        }
+    }
+}
+
+/// Run `score_pending` every ten minutes on the primary instance
+pub async fn run_scoring(storage: Arc<dyn Storage>, scraper: ScraperControl) {
+    info!(
+        "Scoring resolved markets for calibration every {} seconds",
+        SCORING_INTERVAL.as_secs()
+    );
+
+    let mut interval = tokio::time::interval(SCORING_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        match score_pending(storage.as_ref()).await {
+            Ok(scored) => debug!("Scored {} resolved markets for calibration", scored),
+            Err(e) => error!("Calibration scoring failed: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_realized_outcome() {
+        let market = |winner: Option<&str>| Market {
+            winning_outcome: winner.map(str::to_string),
+            ..Default::default()
+        };
+        let outcomes = |names: &[&str]| -> Vec<Outcome> {
+            names
+                .iter()
+                .map(|name| Outcome {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect()
+        };
+
+        assert_eq!(realized_outcome(&market(Some("Yes")), &[]), Some(1.0));
+        assert_eq!(realized_outcome(&market(Some("No")), &[]), Some(0.0));
+        assert_eq!(realized_outcome(&market(None), &[]), None);
+        let candidates = outcomes(&["Trump", "Harris"]);
+        assert_eq!(
+            realized_outcome(&market(Some("Trump")), &candidates),
+            Some(1.0)
+        );
+        assert_eq!(
+            realized_outcome(&market(Some("Harris")), &candidates),
+            Some(0.0)
+        );
+    }
+}
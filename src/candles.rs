@@ -0,0 +1,48 @@
+use anyhow::Result;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info};
+
+use crate::db;
+use crate::models::CandleInterval;
+use crate::scraper::ScraperControl;
+
+pub const DEFAULT_AGGREGATION_INTERVAL_SECS: u64 = 300;
+
+/// Roll new price history into hourly and daily candles
+/// Returns the number of candles written
+pub async fn aggregate(pool: &Pool<Sqlite>) -> Result<u64> {
+    let mut written = 0;
+    for interval in CandleInterval::ALL {
+        written += db::aggregate_candles(pool, interval).await?;
+    }
+    Ok(written)
+}
+
+/// Run `aggregate` at a fixed interval on the primary instance
+/// Candles outlive the raw points they were built from, which the retention policy prunes
+pub async fn run_aggregation(
+    pool: Arc<Pool<Sqlite>>,
+    interval_secs: u64,
+    scraper: ScraperControl,
+) -> Result<()> {
+    info!(
+        "Aggregating price history into candles every {} seconds",
+        interval_secs
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        match aggregate(&pool).await {
+            Ok(written) => debug!("Wrote {} candles", written),
+            Err(e) => error!("Candle aggregation failed: {:#}", e),
+        }
+    }
+}
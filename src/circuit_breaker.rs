@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::metrics;
+
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+pub const DEFAULT_OPEN_SECS: u64 = 300;
+
+/// State of the circuit around the upstream API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Upstream healthy, scheduled scrapes run
+    #[default]
+    Closed,
+    /// Too many consecutive failures, scheduled scrapes are skipped
+    Open,
+    /// Cool-down elapsed, the next scrape probes whether upstream has recovered
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub const ALL: [CircuitState; 3] = [
+        CircuitState::Closed,
+        CircuitState::Open,
+        CircuitState::HalfOpen,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// When the circuit opens and how long it stays open
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerConfig {
+    /// Consecutive failed scrapes that open the circuit
+    pub failure_threshold: u32,
+    /// Time the circuit stays open before a probe scrape is let through
+    pub open_for: Duration,
+}
+
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Circuit breaker for scrapes against the upstream API
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    inner: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        set_state_gauge(CircuitState::Closed);
+        Self {
+            config,
+            inner: Mutex::new(Breaker {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).state
+    }
+
+    /// Whether a scheduled scrape may run; moves an open circuit to half-open once
+    /// its cool-down has elapsed
+    pub fn allow(&self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    fn allow_at(&self, now: Instant) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match inner.state {
+            CircuitState::Closed | CircuitState::HalfOpen => true,
+            CircuitState::Open => {
+                let cooled_down = !inner.opened_at.is_some_and(|opened| {
+                    now.saturating_duration_since(opened) < self.config.open_for
+                });
+                if cooled_down {
+                    info!("Upstream circuit half-open, probing with the next scrape");
+                    transition(&mut inner, CircuitState::HalfOpen);
+                }
+                cooled_down
+            }
+        }
+    }
+
+    /// Close the circuit after a successful scrape
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        if inner.state != CircuitState::Closed {
+            info!("Upstream recovered, circuit closed");
+            transition(&mut inner, CircuitState::Closed);
+        }
+    }
+
+    /// Count a failed scrape; opens the circuit at the threshold or when a probe fails
+    pub fn record_failure(&self) {
+        self.record_failure_at(Instant::now())
+    }
+
+    fn record_failure_at(&self, now: Instant) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures += 1;
+        let open = match inner.state {
+            CircuitState::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+            CircuitState::HalfOpen | CircuitState::Open => true,
+        };
+        if !open {
+            return;
+        }
+
+        // A failure while already open (an on-demand scrape) restarts the cool-down
+        inner.opened_at = Some(now);
+        if inner.state != CircuitState::Open {
+            warn!(
+                "Upstream circuit open after {} consecutive failed scrapes, skipping scheduled scrapes for {}s",
+                inner.consecutive_failures,
+                self.config.open_for.as_secs()
+            );
+            transition(&mut inner, CircuitState::Open);
+        }
+    }
+}
+
+fn transition(inner: &mut Breaker, to: CircuitState) {
+    inner.state = to;
+    set_state_gauge(to);
+}
+
+/// Set the state gauge to 1 for the current state and 0 for the others
+fn set_state_gauge(current: CircuitState) {
+    for state in CircuitState::ALL {
+        ::metrics::gauge!(metrics::UPSTREAM_CIRCUIT_STATE, "state" => state.as_str())
+            .set(if state == current { 1.0 } else { 0.0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_probes_and_closes() {
+        let breaker = CircuitBreaker::new(BreakerConfig {
+            failure_threshold: 3,
+            open_for: Duration::from_secs(60),
+        });
+        let start = Instant::now();
+
+        breaker.record_failure_at(start);
+        breaker.record_failure_at(start);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure_at(start);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert!(!breaker.allow_at(start + Duration::from_secs(30)));
+        assert!(breaker.allow_at(start + Duration::from_secs(60)));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // A failed probe reopens the circuit for another full cool-down
+        breaker.record_failure_at(start + Duration::from_secs(61));
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow_at(start + Duration::from_secs(100)));
+        assert!(breaker.allow_at(start + Duration::from_secs(121)));
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure_at(start + Duration::from_secs(130));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
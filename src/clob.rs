@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::models::{BookLevel, OrderbookSnapshot};
+use crate::throttle::ThrottledClient;
+
+// Polymarket CLOB REST API
+const CLOB_BASE_URL: &str = "https://clob.polymarket.com";
+/// Levels per side kept in orderbook snapshots
+pub const DEFAULT_ORDERBOOK_DEPTH: usize = 10;
+
+/// Best bid/ask, midpoint and spread for a single CLOB token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClobQuote {
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+    pub midpoint: Option<Decimal>,
+    pub spread: Option<Decimal>,
+}
+
+/// Orderbook response from `GET /book` (also the payload of WebSocket `book` events)
+#[derive(Debug, Deserialize)]
+pub(crate) struct OrderBook {
+    #[serde(default)]
+    bids: Vec<OrderLevel>,
+    #[serde(default)]
+    asks: Vec<OrderLevel>,
+}
+
+/// A single price level; the CLOB encodes numbers as strings
+#[derive(Debug, Deserialize)]
+struct OrderLevel {
+    price: String,
+    #[serde(default)]
+    size: String,
+}
+
+/// Fetch the orderbook for a token and derive its quote
+pub async fn fetch_quote(client: &ThrottledClient, token_id: &str) -> Result<ClobQuote> {
+    Ok(quote_from_book(&fetch_book(client, token_id).await?))
+}
+
+/// Fetch the orderbook for a token and keep its best `depth` levels per side
+pub async fn fetch_snapshot(
+    client: &ThrottledClient,
+    market_id: &str,
+    token_id: &str,
+    depth: usize,
+) -> Result<OrderbookSnapshot> {
+    let book = fetch_book(client, token_id).await?;
+    Ok(snapshot_from_book(market_id, token_id, &book, depth, Utc::now()))
+}
+
+/// Fetch the orderbook of a token
+async fn fetch_book(client: &ThrottledClient, token_id: &str) -> Result<OrderBook> {
+    let request = client
+        .get(format!("{}/book", CLOB_BASE_URL))
+        .query(&[("token_id", token_id)])
+        .header("Accept", "application/json");
+    let book: OrderBook = client
+        .send(request)
+        .await
+        .context("Failed to fetch CLOB orderbook")?
+        .error_for_status()
+        .context("CLOB API returned an error status")?
+        .json()
+        .await
+        .context("Failed to parse CLOB orderbook")?;
+
+    Ok(book)
+}
+
+/// Compute best bid/ask, midpoint and spread from an orderbook
+/// Levels are scanned rather than trusting the API's sort order
+pub(crate) fn quote_from_book(book: &OrderBook) -> ClobQuote {
+    let best_bid = book
+        .bids
+        .iter()
+        .filter_map(|level| level.price.parse::<Decimal>().ok())
+        .max();
+    let best_ask = book
+        .asks
+        .iter()
+        .filter_map(|level| level.price.parse::<Decimal>().ok())
+        .min();
+
+    let (midpoint, spread) = match (best_bid, best_ask) {
+        (Some(bid), Some(ask)) => (Some((bid + ask) / Decimal::TWO), Some(ask - bid)),
+        _ => (None, None),
+    };
+
+    ClobQuote {
+        best_bid,
+        best_ask,
+        midpoint,
+        spread,
+    }
+}
+
+/// Levels of one side of a book that parse, best first; bids are best when highest
+fn top_levels(levels: &[OrderLevel], bids: bool, depth: usize) -> Vec<BookLevel> {
+    let mut parsed: Vec<BookLevel> = levels
+        .iter()
+        .filter_map(|level| {
+            Some(BookLevel {
+                price: level.price.parse().ok()?,
+                size: level.size.parse().ok()?,
+            })
+        })
+        .collect();
+    parsed.sort_by(|a, b| {
+        let order = a.price.cmp(&b.price);
+        if bids {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+    parsed.truncate(depth);
+    parsed
+}
+
+/// Snapshot the best `depth` levels of each side of an orderbook with their depth and
+/// imbalance
+pub(crate) fn snapshot_from_book(
+    market_id: &str,
+    token_id: &str,
+    book: &OrderBook,
+    depth: usize,
+    captured_at: DateTime<Utc>,
+) -> OrderbookSnapshot {
+    let bids = top_levels(&book.bids, true, depth);
+    let asks = top_levels(&book.asks, false, depth);
+    let bid_depth: Decimal = bids.iter().map(|level| level.size).sum();
+    let ask_depth: Decimal = asks.iter().map(|level| level.size).sum();
+    let total_depth = bid_depth + ask_depth;
+    let quote = quote_from_book(book);
+
+    OrderbookSnapshot {
+        market_id: market_id.to_string(),
+        token_id: token_id.to_string(),
+        bids,
+        asks,
+        midpoint: quote.midpoint,
+        spread: quote.spread,
+        bid_depth,
+        ask_depth,
+        imbalance: (total_depth > Decimal::ZERO)
+            .then(|| ((bid_depth - ask_depth) / total_depth).to_f64())
+            .flatten(),
+        captured_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_quote_from_book() {
+        let book: OrderBook = serde_json::from_value(serde_json::json!({
+            "bids": [{"price": "0.40", "size": "10"}, {"price": "0.45", "size": "5"}],
+            "asks": [{"price": "0.55", "size": "10"}, {"price": "0.50", "size": "5"}]
+        }))
+        .unwrap();
+
+        let quote = quote_from_book(&book);
+        assert_eq!(quote.best_bid, Some(dec!(0.45)));
+        assert_eq!(quote.best_ask, Some(dec!(0.50)));
+        // Exact, where floats would give 0.04999999999999999
+        assert_eq!(quote.midpoint, Some(dec!(0.475)));
+        assert_eq!(quote.spread, Some(dec!(0.05)));
+    }
+
+    #[test]
+    fn test_snapshot_from_book() {
+        let book: OrderBook = serde_json::from_value(serde_json::json!({
+            "bids": [
+                {"price": "0.40", "size": "10"},
+                {"price": "0.45", "size": "5"},
+                {"price": "0.30", "size": "100"}
+            ],
+            "asks": [{"price": "0.55", "size": "10"}, {"price": "0.50", "size": "5"}]
+        }))
+        .unwrap();
+
+        let snapshot = snapshot_from_book("101", "111", &book, 2, Utc::now());
+        let bid_prices: Vec<Decimal> = snapshot.bids.iter().map(|level| level.price).collect();
+        let ask_prices: Vec<Decimal> = snapshot.asks.iter().map(|level| level.price).collect();
+        assert_eq!(bid_prices, vec![dec!(0.45), dec!(0.40)]);
+        assert_eq!(ask_prices, vec![dec!(0.50), dec!(0.55)]);
+        assert_eq!(snapshot.bid_depth, dec!(15));
+        assert_eq!(snapshot.ask_depth, dec!(15));
+        assert_eq!(snapshot.imbalance, Some(0.0));
+
+        let empty: OrderBook = serde_json::from_value(serde_json::json!({})).unwrap();
+        let snapshot = snapshot_from_book("101", "111", &empty, 10, Utc::now());
+        assert!(snapshot.bids.is_empty());
+        assert_eq!(snapshot.imbalance, None);
+    }
+
+    #[test]
+    fn test_quote_from_one_sided_book() {
+        let book: OrderBook = serde_json::from_value(serde_json::json!({
+            "bids": [{"price": "0.40", "size": "10"}],
+            "asks": []
+        }))
+        .unwrap();
+
+        let quote = quote_from_book(&book);
+        assert_eq!(quote.best_bid, Some(dec!(0.40)));
+        assert_eq!(quote.best_ask, None);
+        assert_eq!(quote.midpoint, None);
+        assert_eq!(quote.spread, None);
+    }
+}
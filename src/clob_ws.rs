@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{info, warn};
+
+use crate::clob::{quote_from_book, OrderBook};
+use crate::db;
+use crate::scraper::ScraperControl;
+
+// Polymarket CLOB market channel
+const CLOB_WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
+const INITIAL_RECONNECT_DELAY_SECS: u64 = 1;
+const MAX_RECONNECT_DELAY_SECS: u64 = 60;
+const RESUBSCRIBE_INTERVAL_SECS: u64 = 300; // Reconnect periodically to pick up newly tracked markets
+const PING_INTERVAL_SECS: u64 = 10;
+
+/// Orderbook snapshot pushed on the market channel
+#[derive(Debug, Deserialize)]
+struct BookEvent {
+    event_type: String,
+    asset_id: String,
+    #[serde(flatten)]
+    book: OrderBook,
+}
+
+/// Run the CLOB price stream forever on the primary instance, reconnecting with exponential
+/// backoff
+pub async fn run_price_stream(pool: Arc<Pool<Sqlite>>, scraper: ScraperControl) -> Result<()> {
+    let mut delay = INITIAL_RECONNECT_DELAY_SECS;
+
+    loop {
+        // One subscription per deployment: other replicas wait until they become primary
+        if !scraper.is_primary() {
+            sleep(Duration::from_secs(PING_INTERVAL_SECS)).await;
+            continue;
+        }
+        match stream_prices(&pool, &scraper).await {
+            Ok(()) => {
+                delay = INITIAL_RECONNECT_DELAY_SECS;
+            }
+            Err(e) => {
+                warn!(
+                    "CLOB price stream error: {}, reconnecting in {} seconds...",
+                    e, delay
+                );
+                sleep(Duration::from_secs(delay)).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY_SECS);
+            }
+        }
+    }
+}
+
+/// Subscribe to all tracked markets and write quotes until the resubscribe interval elapses
+/// or the instance stops being primary
+async fn stream_prices(pool: &Pool<Sqlite>, scraper: &ScraperControl) -> Result<()> {
+    let token_to_market: HashMap<String, String> = db::get_clob_token_ids(pool)
+        .await?
+        .into_iter()
+        .map(|(market_id, token_id)| (token_id, market_id))
+        .collect();
+
+    if token_to_market.is_empty() {
+        info!("No markets with CLOB token IDs yet, waiting before subscribing");
+        sleep(Duration::from_secs(RESUBSCRIBE_INTERVAL_SECS)).await;
+        return Ok(());
+    }
+
+    let (mut ws, _) = connect_async(CLOB_WS_URL)
+        .await
+        .context("Failed to connect to CLOB WebSocket")?;
+
+    let subscribe = serde_json::json!({
+        "type": "market",
+        "assets_ids": token_to_market.keys().collect::<Vec<_>>(),
+    });
+    ws.send(Message::Text(subscribe.to_string()))
+        .await
+        .context("Failed to send CLOB subscription")?;
+    info!("Subscribed to {} CLOB market channels", token_to_market.len());
+
+    let resubscribe_at = Instant::now() + Duration::from_secs(RESUBSCRIBE_INTERVAL_SECS);
+    let mut ping = tokio::time::interval(Duration::from_secs(PING_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(resubscribe_at) => {
+                let _ = ws.close(None).await;
+                return Ok(());
+            }
+            _ = ping.tick() => {
+                if !scraper.is_primary() {
+                    let _ = ws.close(None).await;
+                    return Ok(());
+                }
+                ws.send(Message::Text("PING".to_string()))
+                    .await
+                    .context("Failed to send CLOB ping")?;
+            }
+            msg = ws.next() => match msg {
+                Some(Ok(Message::Text(text))) => handle_message(pool, &token_to_market, &text).await,
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(anyhow::anyhow!("CLOB WebSocket closed by server"));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+        }
+    }
+}
+
+/// Apply orderbook events from a WebSocket message to the database
+/// Messages may carry a single event or an array of events; other event types are ignored
+async fn handle_message(pool: &Pool<Sqlite>, token_to_market: &HashMap<String, String>, text: &str) {
+    let events = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(serde_json::Value::Array(events)) => events,
+        Ok(event) => vec![event],
+        Err(_) => return, // e.g. PONG
+    };
+
+    for event in events {
+        let Ok(book_event) = serde_json::from_value::<BookEvent>(event) else {
+            continue;
+        };
+        if book_event.event_type != "book" {
+            continue;
+        }
+        let Some(market_id) = token_to_market.get(&book_event.asset_id) else {
+            continue;
+        };
+
+        let quote = quote_from_book(&book_event.book);
+        if let Err(e) = db::update_outcome_quote(pool, &book_event.asset_id, &quote).await {
+            warn!("Failed to store streamed outcome quote for market {}: {}", market_id, e);
+        }
+        if let Err(e) = db::update_market_quote(pool, market_id, &quote).await {
+            warn!("Failed to store streamed quote for market {}: {}", market_id, e);
+        }
+    }
+}
@@ -0,0 +1,624 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use sqlx::{Pool, Sqlite};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::cli::{
+    Cli, Command, ExportArgs, ExportFormat, ExportTable, ReprocessArgs, RetentionArgs, ScrapeArgs,
+    ServeArgs,
+};
+#[cfg(feature = "clob-ws")]
+use crate::clob_ws;
+#[cfg(feature = "grpc")]
+use crate::grpc;
+#[cfg(feature = "onchain")]
+use crate::onchain;
+#[cfg(feature = "s3")]
+use crate::snapshot;
+use crate::storage::{SqliteStorage, Storage};
+use crate::{
+    api, archive, cache, calibration, candles, db, export, leader, metrics, models, notify, rate_limit,
+    reports, retention, rules, scraper, spikes, updates, watchdog,
+};
+
+/// How long the scraper gets to store the scrape in progress when the service shuts down
+const SCRAPER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run the command selected on the command line
+/// Without a subcommand the service is started as if `serve` had been given
+pub async fn run(cli: Cli) -> Result<()> {
+    let command = cli
+        .command
+        .unwrap_or_else(|| Command::Serve(Box::new(ServeArgs::parse_from(["serve"]))));
+
+    // Initialize database (applies pending migrations)
+    let pools = db::init_db(&cli.database_url, &cli.sqlite.db_options()).await?;
+
+    match command {
+        Command::Serve(args) => serve(pools, *args).await,
+        Command::ScrapeOnce(args) => scrape_once(Arc::new(SqliteStorage::new(pools)), *args).await,
+        Command::Export(args) => export_table(&pools.read, args).await,
+        Command::Migrate => {
+            info!("Database migrations applied to {}", cli.database_url);
+            Ok(())
+        }
+        Command::Stats => print_stats(&pools.read).await,
+        Command::Reprocess(args) => reprocess(&pools, args).await,
+        Command::Prune(args) => prune(&pools.write, args).await,
+    }
+}
+
+fn build_scraper_config(args: &ScrapeArgs) -> scraper::ScraperConfig {
+    scraper::ScraperConfig {
+        source: args.source,
+        additional_sources: args.additional_sources.clone(),
+        page_size: args.page_size,
+        max_pages: args.max_pages,
+        delist_after_missed_scrapes: args.delist_after_missed_scrapes,
+        precision: args.precision.precision(),
+        filter: args.filter.filter(),
+        enrich_concurrency: args.enrich_concurrency,
+        tiers: args.tiers(),
+        orderbook_depth: args.orderbook_depth,
+        throttle: args.throttle(),
+        proxies: args.proxies.clone(),
+        headers: args.headers.clone(),
+        user_agents: args.user_agents.clone(),
+        fixtures: args.fixtures(),
+        upstream_base_url: args.upstream_base_url.clone(),
+        subgraph_url: args.subgraph_url.clone(),
+        positions_subgraph_url: args.positions_subgraph_url.clone(),
+    }
+}
+
+/// Run the scraper, notifiers and API server until shutdown
+async fn serve(pools: db::DbPools, args: ServeArgs) -> Result<()> {
+    info!("Starting Polymarket Scraper Service");
+
+    // Market data for the scraper and the APIs; background tasks below use the pools directly
+    let storage: Arc<dyn Storage> = Arc::new(SqliteStorage::new(pools.clone()));
+
+    let notification_filter = notify::NotificationFilter {
+        keywords: args
+            .notify_keywords
+            .iter()
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect(),
+        min_volume: args.notify_min_volume,
+    };
+
+    // Initialize metrics and the Prometheus recorder
+    let prometheus_handle = metrics::install_prometheus_recorder()?;
+    let metrics = Arc::new(metrics::Metrics::new().with_prometheus(prometheus_handle));
+    // Counters continue from the last snapshot so dashboards survive restarts
+    metrics::restore_snapshot(storage.as_ref(), &metrics).await?;
+
+    // Alert rules from the configuration are stored next to those created through the API
+    if let Some(path) = &args.alert_rules {
+        let config_rules = rules::load_rules_file(path)?;
+        rules::sync_config_rules(storage.as_ref(), &config_rules).await?;
+    }
+
+    // Connect to Redis, shared by the response cache and the pub/sub notifier
+    #[cfg(feature = "redis")]
+    let redis_conn = match &args.redis_url {
+        Some(url) => {
+            let conn = redis::Client::open(url.as_str())?
+                .get_connection_manager()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to connect to Redis at {}: {}", url, e))?;
+            info!("Connected to Redis at {}", url);
+            Some(conn)
+        }
+        None => None,
+    };
+
+    let response_cache = cache::ResponseCache::default();
+    #[cfg(feature = "redis")]
+    let response_cache = match &redis_conn {
+        Some(conn) => cache::ResponseCache::redis(conn.clone(), args.redis_cache_ttl),
+        None => response_cache,
+    };
+
+    // Channel carrying market updates from the scraper to streaming API clients
+    let updates = updates::channel();
+
+    // Clone storage and metrics for scraper
+    let scraper_config = build_scraper_config(&args.scrape);
+    let spike_config = args.spikes();
+    let (scraper_control, scraper_signals) = scraper::control(
+        args.scrape_interval,
+        args.scrape.source,
+        args.circuit_breaker(),
+    );
+    // With leader election or sharding, replicas only scrape once they hold a lease
+    let leader_config = args.leader();
+    if leader_config.is_some() {
+        scraper_control.set_leader(false);
+    }
+    let scraper_storage = Arc::clone(&storage);
+    let scraper_metrics = Arc::clone(&metrics);
+    let scraper_updates = updates.clone();
+    // Cancelled on shutdown so the scraper can finish its writes instead of being aborted
+    let scraper_shutdown = CancellationToken::new();
+    let scraper_token = scraper_shutdown.clone();
+
+    // Spawn scraper task
+    let mut scraper_handle = tokio::spawn(async move {
+        if let Err(e) = scraper::run_scraper(
+            scraper_storage,
+            scraper_config,
+            scraper_metrics,
+            scraper_updates,
+            scraper_signals,
+            scraper_token,
+        )
+        .await
+        {
+            error!("Scraper task failed: {}", e);
+        }
+    });
+
+    // Spawn the election for a scraper lease
+    let leader_control = scraper_control.clone();
+    let election_handle = leader_config.clone().map(|config| {
+        tokio::spawn(leader::run_election(
+            Arc::clone(&storage),
+            config,
+            scraper_control.clone(),
+            Arc::clone(&metrics),
+        ))
+    });
+
+    // Spawn real-time CLOB price stream
+    #[cfg(feature = "clob-ws")]
+    let price_stream_handle = {
+        let stream_pool = Arc::clone(&pools.write);
+        let stream_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) = clob_ws::run_price_stream(stream_pool, stream_control).await {
+                error!("CLOB price stream task failed: {}", e);
+            }
+        })
+    };
+
+    // Register notification sinks and spawn the dispatcher
+    let mut dispatcher = notify::Dispatcher::new();
+    dispatcher.add_sink(Arc::new(notify::webhook::WebhookNotifier::new(Arc::clone(&pools.write))?));
+    if let Some(webhook_url) = args.discord_webhook_url {
+        dispatcher.add_sink(Arc::new(notify::discord::DiscordNotifier::new(
+            webhook_url,
+            notification_filter.clone(),
+        )?));
+    }
+    if let (Some(bot_token), Some(chat_id)) = (args.telegram_bot_token, args.telegram_chat_id) {
+        dispatcher.add_sink(Arc::new(notify::telegram::TelegramNotifier::new(
+            bot_token,
+            chat_id,
+            notification_filter.clone(),
+            args.price_move_threshold,
+        )?));
+    }
+    #[cfg(feature = "kafka")]
+    let kafka_relay_handle = args.kafka_brokers.map(|brokers| {
+        let config = notify::kafka::KafkaConfig {
+            brokers,
+            new_market_topic: args.kafka_new_market_topic,
+            price_update_topic: args.kafka_price_topic,
+        };
+        dispatcher.add_sink(Arc::new(notify::kafka::KafkaNotifier::new(
+            Arc::clone(&pools.write),
+            config.clone(),
+        )));
+        let relay_pool = Arc::clone(&pools.write);
+        let relay_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) = notify::kafka::run_outbox_relay(relay_pool, config, relay_control).await
+            {
+                error!("Kafka outbox relay failed: {}", e);
+            }
+        })
+    });
+    #[cfg(feature = "nats")]
+    if let Some(url) = args.nats_url {
+        dispatcher.add_sink(Arc::new(
+            notify::nats::NatsNotifier::connect(&url, args.nats_subject_prefix).await?,
+        ));
+    }
+    #[cfg(feature = "redis")]
+    if let Some(conn) = redis_conn {
+        dispatcher.add_sink(Arc::new(notify::redis::RedisNotifier::new(conn, args.redis_channel)));
+    }
+    let alerts = dispatcher.alerts();
+    let sink_names = dispatcher.sink_names();
+    let dispatcher_storage = Arc::clone(&storage);
+    let dispatcher_control = scraper_control.clone();
+    let dispatcher_handle = tokio::spawn(async move {
+        if let Err(e) = dispatcher.run(dispatcher_storage, dispatcher_control).await {
+            error!("Notification dispatcher failed: {}", e);
+        }
+    });
+
+    // Spawn the alert rules engine
+    let rules_handle = tokio::spawn(rules::run_supervised(
+        Arc::clone(&storage),
+        alerts.clone(),
+        updates.clone(),
+        scraper_control.clone(),
+    ));
+
+    // Spawn volume spike detection over the price history
+    let spikes_handle = {
+        let spikes_storage = Arc::clone(&storage);
+        let spikes_alerts = alerts.clone();
+        let spikes_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) = spikes::run_spike_detection(
+                spikes_storage,
+                spikes_alerts,
+                spike_config,
+                spikes_control,
+            )
+            .await
+            {
+                error!("Volume spike detection task failed: {}", e);
+            }
+        })
+    };
+
+    // Spawn the daily report job
+    let reports_handle = tokio::spawn(reports::run_daily_reports(
+        Arc::clone(&storage),
+        alerts.clone(),
+        args.daily_report_notify,
+        scraper_control.clone(),
+    ));
+
+    // Spawn the watchdog for scrapes that stopped succeeding
+    let watchdog_handle = tokio::spawn(watchdog::run_watchdog(
+        Arc::clone(&metrics),
+        scraper_control.clone(),
+        args.stall_intervals,
+        alerts,
+    ));
+
+    // Spawn periodic metrics snapshots
+    let metrics_snapshot_handle = tokio::spawn(metrics::run_snapshots(
+        Arc::clone(&storage),
+        Arc::clone(&metrics),
+        args.metrics_snapshot_interval,
+    ));
+
+    // Spawn scheduled retention maintenance
+    let maintenance_handle = {
+        let maintenance_pool = Arc::clone(&pools.write);
+        let policy = args.retention.policy();
+        let interval_secs = args.maintenance_interval;
+        let maintenance_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) = retention::run_maintenance(
+                maintenance_pool,
+                policy,
+                interval_secs,
+                maintenance_control,
+                sink_names,
+            )
+            .await
+            {
+                error!("Maintenance task failed: {}", e);
+            }
+        })
+    };
+
+    // Spawn candle aggregation over the price history
+    let candles_handle = {
+        let candles_pool = Arc::clone(&pools.write);
+        let interval_secs = args.candle_interval;
+        let candles_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                candles::run_aggregation(candles_pool, interval_secs, candles_control).await
+            {
+                error!("Candle aggregation task failed: {}", e);
+            }
+        })
+    };
+
+    // Spawn calibration scoring of resolved markets
+    let calibration_handle = tokio::spawn(calibration::run_scoring(
+        Arc::clone(&storage),
+        scraper_control.clone(),
+    ));
+
+    // Spawn on-chain verification against Polygon
+    #[cfg(feature = "onchain")]
+    let onchain_handle = args.polygon_rpc_url.map(|rpc_url| {
+        let config = onchain::OnchainConfig {
+            rpc_url,
+            start_block: args.onchain_start_block,
+            interval_secs: args.onchain_interval,
+        };
+        let onchain_storage = Arc::clone(&storage);
+        let onchain_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                onchain::run_verification(onchain_storage, config, onchain_control).await
+            {
+                error!("On-chain verification task failed: {}", e);
+            }
+        })
+    });
+
+    // Spawn periodic object storage snapshots
+    #[cfg(feature = "s3")]
+    let snapshot_handle = args.snapshot_bucket.map(|bucket| {
+        let config = snapshot::SnapshotConfig {
+            bucket,
+            prefix: args.snapshot_prefix,
+            endpoint: args.snapshot_endpoint,
+            interval_secs: args.snapshot_interval,
+            format: args.snapshot_format,
+        };
+        let snapshot_pool = Arc::clone(&pools.write);
+        let snapshot_control = scraper_control.clone();
+        tokio::spawn(async move {
+            if let Err(e) = snapshot::run_snapshots(snapshot_pool, config, snapshot_control).await {
+                error!("Snapshot task failed: {}", e);
+            }
+        })
+    });
+
+    // Spawn gRPC server on its own port
+    #[cfg(feature = "grpc")]
+    let grpc_handle = args.grpc_port.map(|port| {
+        let grpc_pool = Arc::clone(&pools.read);
+        let grpc_updates = updates.clone();
+        tokio::spawn(async move {
+            if let Err(e) = grpc::serve(port, grpc_pool, grpc_updates).await {
+                error!("gRPC server failed: {}", e);
+            }
+        })
+    });
+
+    // Clone metrics for API
+    let api_metrics = Arc::clone(&metrics);
+
+    // Create API router
+    let app = api::create_router(
+        Arc::clone(&storage),
+        api_metrics,
+        updates,
+        response_cache,
+        scraper_control,
+        args.admin_token,
+        args.rate_limit_per_minute
+            .map(|limit| Arc::new(rate_limit::RateLimiter::new(limit, args.api_keys))),
+    );
+
+    // Create server with graceful shutdown
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", args.port))
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to bind to port {}: {}", args.port, e))?;
+
+    info!("API server listening on http://0.0.0.0:{}", args.port);
+    info!("Health check available at http://0.0.0.0:{}/health", args.port);
+
+    // Run server with graceful shutdown
+    // Peer addresses are needed to rate limit by client IP
+    // The scraper starts winding down as soon as the signal arrives, alongside the server
+    let server_shutdown = scraper_shutdown.clone();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            server_shutdown.cancel();
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Server error: {}", e))?;
+
+    // Let the scraper finish storing the scrape in progress, then cancel the other tasks
+    scraper_shutdown.cancel();
+    if tokio::time::timeout(SCRAPER_SHUTDOWN_TIMEOUT, &mut scraper_handle).await.is_err() {
+        warn!(
+            "Scraper did not stop within {} seconds, aborting it",
+            SCRAPER_SHUTDOWN_TIMEOUT.as_secs()
+        );
+        scraper_handle.abort();
+    }
+    if let Some(handle) = election_handle {
+        handle.abort();
+    }
+    if let Some(config) = &leader_config {
+        leader::release(storage.as_ref(), config, &leader_control).await;
+    }
+    metrics_snapshot_handle.abort();
+    metrics::save_snapshot(storage.as_ref(), &metrics).await;
+    dispatcher_handle.abort();
+    rules_handle.abort();
+    spikes_handle.abort();
+    reports_handle.abort();
+    watchdog_handle.abort();
+    maintenance_handle.abort();
+    candles_handle.abort();
+    calibration_handle.abort();
+    #[cfg(feature = "clob-ws")]
+    price_stream_handle.abort();
+    #[cfg(feature = "onchain")]
+    if let Some(handle) = onchain_handle {
+        handle.abort();
+    }
+    #[cfg(feature = "s3")]
+    if let Some(handle) = snapshot_handle {
+        handle.abort();
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(handle) = grpc_handle {
+        handle.abort();
+    }
+    #[cfg(feature = "kafka")]
+    if let Some(handle) = kafka_relay_handle {
+        handle.abort();
+    }
+    info!("Service shutdown complete");
+
+    Ok(())
+}
+
+/// Run a single scrape cycle and report how many new markets were found
+async fn scrape_once(storage: Arc<dyn Storage>, args: ScrapeArgs) -> Result<()> {
+    let metrics = Arc::new(metrics::Metrics::new());
+    let config = build_scraper_config(&args);
+
+    let summary = scraper::scrape_once(storage, config, metrics, updates::channel()).await?;
+    info!(
+        "Scrape completed in {} ms: {} markets fetched, {} new",
+        summary.duration_ms, summary.markets_fetched, summary.new_markets
+    );
+
+    Ok(())
+}
+
+/// Write stored markets or price history as JSON, JSON Lines, CSV or Parquet
+async fn export_table(pool: &Pool<Sqlite>, args: ExportArgs) -> Result<()> {
+    if args.table == ExportTable::PriceHistory && args.status.is_some() {
+        anyhow::bail!("--status only applies to the markets table");
+    }
+
+    let mut out: Box<dyn Write + Send> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).with_context(|| {
+            format!("Failed to create export file {}", path.display())
+        })?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    let exported = match args.table {
+        ExportTable::Markets => {
+            let filter = models::MarketFilter {
+                status: args.status,
+                include_delisted: true,
+                ..Default::default()
+            };
+            let (markets, _) = db::get_markets(pool, u32::MAX, 0, &filter).await?;
+            match args.format {
+                ExportFormat::Json => export::write_json(&markets, &mut out)?,
+                ExportFormat::Jsonl => export::write_jsonl(&markets, &mut out)?,
+                ExportFormat::Csv => export::write_csv(&markets, &mut out)?,
+                #[cfg(feature = "parquet")]
+                ExportFormat::Parquet => export::parquet::write_markets(&markets, &mut out)?,
+                #[cfg(not(feature = "parquet"))]
+                ExportFormat::Parquet => anyhow::bail!(PARQUET_DISABLED),
+            }
+            markets.len()
+        }
+        ExportTable::PriceHistory => {
+            let rows = db::get_all_price_history(pool).await?;
+            match args.format {
+                ExportFormat::Json => export::write_json(&rows, &mut out)?,
+                ExportFormat::Jsonl => export::write_jsonl(&rows, &mut out)?,
+                ExportFormat::Csv => export::write_csv(&rows, &mut out)?,
+                #[cfg(feature = "parquet")]
+                ExportFormat::Parquet => export::parquet::write_price_history(&rows, &mut out)?,
+                #[cfg(not(feature = "parquet"))]
+                ExportFormat::Parquet => anyhow::bail!(PARQUET_DISABLED),
+            }
+            rows.len()
+        }
+    };
+    out.flush()?;
+
+    info!("Exported {} rows from {:?}", exported, args.table);
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+const PARQUET_DISABLED: &str = "Parquet export requires building with `--features parquet`";
+
+/// Print market counts per status as JSON
+async fn print_stats(pool: &Pool<Sqlite>) -> Result<()> {
+    let counts = db::count_markets_by_status(pool).await?;
+    let total: i64 = counts.iter().map(|(_, count)| count).sum();
+    let by_status: serde_json::Map<String, serde_json::Value> = counts
+        .into_iter()
+        .map(|(status, count)| (status, count.into()))
+        .collect();
+
+    let stats = serde_json::json!({
+        "total_markets": total,
+        "markets_by_status": by_status,
+    });
+    println!("{}", serde_json::to_string_pretty(&stats)?);
+
+    Ok(())
+}
+
+async fn reprocess(pools: &db::DbPools, args: ReprocessArgs) -> Result<()> {
+    let summary = archive::reprocess(pools, args.market_id.as_deref(), args.precision.precision()).await?;
+    info!(
+        "Reprocessed {} archived markets ({} failed)",
+        summary.reprocessed, summary.failed
+    );
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+
+    Ok(())
+}
+
+async fn prune(pool: &Pool<Sqlite>, args: RetentionArgs) -> Result<()> {
+    // Without the serve configuration every sink's pending deliveries are kept
+    let summary = retention::prune(pool, args.policy(), None).await?;
+    info!(
+        "Pruned {} price history rows, {} trades, {} comments, {} positions, {} orderbook snapshots and {} outbox events and archived {} markets",
+        summary.price_history_deleted,
+        summary.trades_deleted,
+        summary.comments_deleted,
+        summary.positions_deleted,
+        summary.orderbook_snapshots_deleted,
+        summary.outbox_deleted,
+        summary.markets_archived
+    );
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+
+    Ok(())
+}
+
+/// Handle graceful shutdown signal (Ctrl+C)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        match signal::ctrl_c().await {
+            Ok(()) => {
+                info!("Received shutdown signal (Ctrl+C)");
+            }
+            Err(e) => {
+                error!("Failed to install Ctrl+C handler: {}", e);
+            }
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+                info!("Received terminate signal");
+            }
+            Err(e) => {
+                error!("Failed to install terminate signal handler: {}", e);
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
@@ -0,0 +1,131 @@
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{RequestBuilder, Response};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// `ETag` and `Last-Modified` of a response, sent back as `If-None-Match` and
+/// `If-Modified-Since` so upstream can answer 304 when nothing changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+impl Validators {
+    /// Validators of a response, if it has any
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let etag = headers.get(ETAG).cloned();
+        let last_modified = headers.get(LAST_MODIFIED).cloned();
+        (etag.is_some() || last_modified.is_some()).then_some(Self {
+            etag,
+            last_modified,
+        })
+    }
+
+    /// Make the request conditional on the resource having changed
+    pub fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        request
+    }
+}
+
+/// Listing response whose markets were stored, to be remembered for the next conditional
+/// request to the same URL
+#[derive(Debug, Clone)]
+pub struct ListingPage {
+    pub url: String,
+    pub validators: Validators,
+    /// Markets the response listed, which still count as seen when it comes back unchanged
+    pub ids: Vec<String>,
+}
+
+/// Outcome of a conditional request
+pub enum Conditional {
+    /// Sent without validators or answered with a new body
+    Modified { response: Response, url: String },
+    /// 304: unchanged since the remembered response, which listed these markets
+    NotModified { ids: Vec<String> },
+}
+
+#[derive(Debug, Clone)]
+struct CachedListing {
+    validators: Validators,
+    ids: Vec<String>,
+}
+
+/// Validators and market IDs of listing responses stored by earlier scrapes, by URL
+#[derive(Default)]
+pub struct ListingCache {
+    pages: Mutex<HashMap<String, CachedListing>>,
+}
+
+impl ListingCache {
+    /// Validators remembered for the URL and the IDs listed by that response
+    pub fn get(&self, url: &str) -> Option<(Validators, Vec<String>)> {
+        let pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        pages
+            .get(url)
+            .map(|cached| (cached.validators.clone(), cached.ids.clone()))
+    }
+
+    pub fn remember(&self, listings: Vec<ListingPage>) {
+        let mut pages = self.pages.lock().unwrap_or_else(|e| e.into_inner());
+        for listing in listings {
+            pages.insert(
+                listing.url,
+                CachedListing {
+                    validators: listing.validators,
+                    ids: listing.ids,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validators_round_trip() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Validators::from_headers(&headers), None);
+
+        headers.insert(ETAG, HeaderValue::from_static("W/\"abc\""));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Mon, 01 Jan 2024 00:00:00 GMT"),
+        );
+        let validators = Validators::from_headers(&headers).unwrap();
+
+        let request = validators
+            .apply(reqwest::Client::new().get("https://gamma-api.polymarket.com/markets"))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[IF_NONE_MATCH], "W/\"abc\"");
+        assert_eq!(
+            request.headers()[IF_MODIFIED_SINCE],
+            "Mon, 01 Jan 2024 00:00:00 GMT"
+        );
+
+        let cache = ListingCache::default();
+        let url = "https://gamma-api.polymarket.com/markets?offset=0";
+        assert!(cache.get(url).is_none());
+        cache.remember(vec![ListingPage {
+            url: url.to_string(),
+            validators: validators.clone(),
+            ids: vec!["1".to_string(), "2".to_string()],
+        }]);
+        assert_eq!(
+            cache.get(url),
+            Some((validators, vec!["1".to_string(), "2".to_string()]))
+        );
+    }
+}
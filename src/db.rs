@@ -1,17 +1,77 @@
-use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use sqlx::{
-    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
-    Pool, Sqlite,
+    sqlite::{
+        SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow, SqliteSynchronous,
+    },
+    FromRow, Pool, QueryBuilder, Row, Sqlite, Transaction,
 };
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 use tracing::info;
 
-use crate::models::Market;
+use crate::clob::ClobQuote;
+use crate::error::{Error, Result};
+use crate::metrics::MetricsSnapshot;
+use crate::source;
+use crate::updates::{FieldChange, MarketUpdate};
+use crate::models::{
+    AlertCondition, AlertFired, AlertRule, CalibrationBucket, CalibrationHorizon, CalibrationResponse, Candle, CandleInterval, Comment, DailyReport, DecimalColumn, Event, HorizonCalibration, Market, MarketCalibration, MarketChange, MarketFilter, MarketMover, MarketStatus, MarketVolume, OnchainVerification, OrderbookSnapshot, OutboxEvent, Outcome, Position, Tag, TagCount, MoverSort, PriceHistoryRow, PricePoint,
+    RawPayloadRow, ShardProgress, SortOrder, StatsResponse, Trade, VolumeSpike, Watchlist, Webhook, WebhookDeadLetter,
+};
+#[cfg(feature = "kafka")]
+use crate::models::KafkaOutboxMessage;
+
+/// Columns selected when loading a `Market`
+const MARKET_COLUMNS: &str = "id, source, slug, condition_id, question_id, title, description, image, icon, current_price, volume, volume_24hr, \
+    liquidity, open_interest, end_date, clob_token_id, best_bid, best_ask, midpoint, spread, event_id, status, winning_outcome, \
+    resolved_at, discovered_at, updated_at, last_seen_at";
+
+/// Max markets re-checked for resolution per scrape cycle
+const RESOLUTION_CHECK_BATCH_SIZE: i64 = 50;
+
+/// Rows buffered between a streaming query and its consumer
+const STREAM_BUFFER_ROWS: usize = 256;
+
+/// Market IDs bound per `mark_markets_seen` statement, well under SQLite's variable limit
+const SEEN_UPDATE_CHUNK_SIZE: usize = 500;
+
+const WRITE_POOL_SIZE: u32 = 1; // SQLite allows a single writer at a time
+const READ_POOL_SIZE: u32 = 8;
+
+pub const DEFAULT_BUSY_TIMEOUT_MS: u64 = 5000;
+pub const DEFAULT_CACHE_SIZE_KIB: u32 = 65_536; // 64 MiB per connection
+
+/// SQLite connection pragmas applied to every pooled connection
+#[derive(Debug, Clone)]
+pub struct DbOptions {
+    pub journal_mode: SqliteJournalMode,
+    /// How long a connection waits on a locked database before failing
+    pub busy_timeout: Duration,
+    pub synchronous: SqliteSynchronous,
+    /// Page cache size per connection in KiB
+    pub cache_size_kib: u32,
+}
+
+/// Separate connection pools for writers (scraper, notifiers, maintenance) and API readers
+#[derive(Debug, Clone)]
+pub struct DbPools {
+    /// Read-only connections
+    pub read: Arc<Pool<Sqlite>>,
+    /// Single connection so writers queue in the pool instead of failing on a locked database
+    pub write: Arc<Pool<Sqlite>>,
+}
 
-/// Initialize database connection pool
-pub async fn init_db(database_url: &str) -> Result<Pool<Sqlite>> {
+/// Initialize the read and write connection pools
+pub async fn init_db(database_url: &str, db_options: &DbOptions) -> Result<DbPools> {
     info!("Connecting to database at: {}", database_url);
+    let in_memory = is_in_memory(database_url);
     
     // For SQLite, ensure the database file can be created
     // Extract file path from connection string (format: sqlite:path or sqlite://path)
@@ -21,140 +81,3031 @@ pub async fn init_db(database_url: &str) -> Result<Pool<Sqlite>> {
         .unwrap_or(database_url);
     
     // Ensure parent directory exists if path contains directories
-    if let Some(parent) = std::path::Path::new(db_path).parent() {
+    if let Some(parent) = std::path::Path::new(db_path).parent().filter(|_| !in_memory) {
         if !parent.exists() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| anyhow::anyhow!("Failed to create database directory: {}", e))?;
+            std::fs::create_dir_all(parent).map_err(|e| {
+                Error::Config(anyhow::anyhow!("Failed to create database directory: {}", e))
+            })?;
         }
     }
     
-    // Use SqliteConnectOptions to enable create_if_missing
-    let options = SqliteConnectOptions::from_str(database_url)?
-        .create_if_missing(true);
-    
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect_with(options)
+    let options = SqliteConnectOptions::from_str(database_url)
+        .map_err(|e| Error::Config(e.into()))?
+        // Connections of the pool must all see the same in-memory database
+        .shared_cache(in_memory)
+        .busy_timeout(db_options.busy_timeout)
+        .synchronous(db_options.synchronous)
+        // A negative cache_size is interpreted as KiB rather than pages
+        .pragma("cache_size", format!("-{}", db_options.cache_size_kib));
+
+    // An in-memory database is dropped with its last connection, so that one is never closed
+    let write_pool = if in_memory {
+        SqlitePoolOptions::new()
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        SqlitePoolOptions::new()
+    };
+
+    // Only the writer creates the file and sets the journal mode
+    let write = write_pool
+        .max_connections(WRITE_POOL_SIZE)
+        .connect_with(
+            options
+                .clone()
+                .create_if_missing(true)
+                .journal_mode(db_options.journal_mode),
+        )
         .await?;
 
     // Run migrations
-    sqlx::migrate!("./migrations").run(&pool).await?;
-    
+    sqlx::migrate!("./migrations").run(&write).await?;
+
+    // An in-memory database has no file to open read-only, so readers share the writer
+    let read = if in_memory {
+        write.clone()
+    } else {
+        SqlitePoolOptions::new()
+            .max_connections(READ_POOL_SIZE)
+            .connect_with(options.read_only(true))
+            .await?
+    };
+
     info!("Database initialized successfully");
-    Ok(pool)
+    Ok(DbPools {
+        read: Arc::new(read),
+        write: Arc::new(write),
+    })
+}
+
+/// Empty in-memory database for tests
+#[cfg(test)]
+pub(crate) async fn test_pools() -> DbPools {
+    let options = DbOptions {
+        journal_mode: SqliteJournalMode::Memory,
+        busy_timeout: Duration::from_secs(5),
+        synchronous: SqliteSynchronous::Normal,
+        cache_size_kib: 2048,
+    };
+    init_db("sqlite::memory:", &options).await.unwrap()
+}
+
+/// Whether a connection string names an in-memory database (`sqlite::memory:` or a
+/// `mode=memory` URI) rather than a file
+fn is_in_memory(database_url: &str) -> bool {
+    database_url.contains(":memory:") || database_url.contains("mode=memory")
+}
+
+/// Stored form of a market end date: RFC 3339 in UTC with a `Z` suffix, so range filters can
+/// compare it as text against timestamps formatted the same way
+fn end_date_column(end_date: DateTime<Utc>) -> String {
+    end_date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Stored form of a decimal price or volume; SQLite has no decimal type so it is kept as `REAL`
+fn decimal_column(value: Option<Decimal>) -> Option<f64> {
+    value.and_then(|d| d.to_f64())
+}
+
+/// SHA-256 over the scraped fields written by `upsert_market`
+fn market_content_hash(market: &Market) -> Result<String> {
+    let fields = serde_json::to_vec(&(
+        &market.source,
+        (&market.slug, &market.condition_id, &market.question_id),
+        &market.title,
+        &market.description,
+        (&market.image, &market.icon),
+        market.current_price,
+        market.volume,
+        market.volume_24hr,
+        market.liquidity,
+        market.open_interest,
+        &market.end_date,
+        &market.clob_token_id,
+        (market.best_bid, market.best_ask, market.midpoint, market.spread),
+    ))
+    .map_err(Error::storage)?;
+    Ok(hex::encode(Sha256::digest(fields)))
 }
 
 /// Upsert a market into the database
-/// Returns true if the market was newly discovered, false if it was updated
+/// Returns true if the market was newly discovered, false if it was updated or unchanged
+///
+/// Uses a single `INSERT ... ON CONFLICT` statement so concurrent writers can't race.
+/// On insert `discovered_at` and `updated_at` receive the same value; on update only
+/// `updated_at` changes, so comparing them tells the two cases apart. Rows whose
+/// content hash is unchanged are left untouched and return no row. Bid/ask quotes only
+/// replace the stored CLOB quotes when the payload carries them.
 pub async fn upsert_market(pool: &Pool<Sqlite>, market: &Market) -> Result<bool> {
-    let is_new = sqlx::query_scalar::<_, bool>(
-        "SELECT NOT EXISTS(SELECT 1 FROM markets WHERE id = ?)"
+    let (is_new, _) = upsert_market_returning(pool, market, Utc::now()).await?;
+    Ok(is_new)
+}
+
+/// Upsert a market and queue its notification in the outbox within one transaction, so
+/// the notification exists exactly when the upsert committed
+/// A new market queues a `NewMarket` event; a changed one queues `change`, if given.
+/// Unchanged rows queue nothing.
+pub async fn upsert_market_with_outbox(
+    pool: &Pool<Sqlite>,
+    market: &Market,
+    change: Option<&MarketUpdate>,
+) -> Result<bool> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let (is_new, stored) = upsert_market_returning(&mut *tx, market, now).await?;
+    let update = match (stored, is_new) {
+        (false, _) => None,
+        (true, true) => Some(MarketUpdate::NewMarket {
+            market: market.clone(),
+        }),
+        (true, false) => change.cloned(),
+    };
+    if let Some(update) = update {
+        insert_outbox(&mut tx, &update, now).await?;
+    }
+
+    tx.commit().await?;
+    Ok(is_new)
+}
+
+/// Run the market upsert, returning whether the market was new and whether a row was written
+async fn upsert_market_returning<'e, E>(
+    executor: E,
+    market: &Market,
+    now: DateTime<Utc>,
+) -> Result<(bool, bool)>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query_scalar::<_, bool>(
+        r#"
+        INSERT INTO markets (id, source, slug, condition_id, question_id, title, description, image, icon, current_price, volume, volume_24hr, liquidity, open_interest, end_date, clob_token_id, best_bid, best_ask, midpoint, spread, content_hash, discovered_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            source = excluded.source,
+            slug = COALESCE(excluded.slug, markets.slug),
+            condition_id = COALESCE(excluded.condition_id, markets.condition_id),
+            question_id = COALESCE(excluded.question_id, markets.question_id),
+            title = excluded.title,
+            description = excluded.description,
+            image = COALESCE(excluded.image, markets.image),
+            icon = COALESCE(excluded.icon, markets.icon),
+            current_price = excluded.current_price,
+            volume = excluded.volume,
+            volume_24hr = excluded.volume_24hr,
+            liquidity = excluded.liquidity,
+            open_interest = excluded.open_interest,
+            end_date = excluded.end_date,
+            clob_token_id = COALESCE(excluded.clob_token_id, markets.clob_token_id),
+            best_bid = COALESCE(excluded.best_bid, markets.best_bid),
+            best_ask = COALESCE(excluded.best_ask, markets.best_ask),
+            midpoint = COALESCE(excluded.midpoint, markets.midpoint),
+            spread = COALESCE(excluded.spread, markets.spread),
+            content_hash = excluded.content_hash,
+            updated_at = excluded.updated_at
+        WHERE markets.content_hash IS NOT excluded.content_hash
+        RETURNING discovered_at = updated_at
+        "#,
     )
     .bind(&market.id)
-    .fetch_one(pool)
+    .bind(&market.source)
+    .bind(&market.slug)
+    .bind(&market.condition_id)
+    .bind(&market.question_id)
+    .bind(&market.title)
+    .bind(&market.description)
+    .bind(&market.image)
+    .bind(&market.icon)
+    .bind(decimal_column(market.current_price))
+    .bind(decimal_column(market.volume))
+    .bind(decimal_column(market.volume_24hr))
+    .bind(decimal_column(market.liquidity))
+    .bind(decimal_column(market.open_interest))
+    .bind(market.end_date.map(end_date_column))
+    .bind(&market.clob_token_id)
+    .bind(decimal_column(market.best_bid))
+    .bind(decimal_column(market.best_ask))
+    .bind(decimal_column(market.midpoint))
+    .bind(decimal_column(market.spread))
+    .bind(market_content_hash(market)?)
+    .bind(now)
+    .bind(now)
+    .fetch_optional(executor)
     .await?;
 
+    Ok((row.unwrap_or(false), row.is_some()))
+}
+
+/// Insert or update the outcomes of a market; quotes are left to `update_outcome_quote`
+pub async fn upsert_outcomes(pool: &Pool<Sqlite>, market_id: &str, outcomes: &[Outcome]) -> Result<()> {
+    if outcomes.is_empty() {
+        return Ok(());
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "INSERT INTO outcomes (market_id, name, position, token_id, price, updated_at) ",
+    );
     let now = Utc::now();
-    
-    if is_new {
-        // Insert new market
-        sqlx::query(
-            r#"
-            INSERT INTO markets (id, title, description, current_price, volume, end_date, discovered_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&market.id)
-        .bind(&market.title)
-        .bind(&market.description)
-        .bind(market.current_price)
-        .bind(market.volume)
-        .bind(&market.end_date)
-        .bind(now)
-        .bind(now)
+    qb.push_values(outcomes.iter().enumerate(), |mut row, (position, outcome)| {
+        row.push_bind(market_id)
+            .push_bind(&outcome.name)
+            .push_bind(position as i64)
+            .push_bind(&outcome.token_id)
+            .push_bind(decimal_column(outcome.price))
+            .push_bind(now);
+    });
+    qb.push(
+        " ON CONFLICT(market_id, name) DO UPDATE SET \
+         position = excluded.position, \
+         token_id = COALESCE(excluded.token_id, outcomes.token_id), \
+         price = excluded.price, \
+         updated_at = excluded.updated_at",
+    );
+    qb.build().execute(pool).await?;
+
+    Ok(())
+}
+
+/// Store the CLOB quote of the outcome with the given token
+pub async fn update_outcome_quote(pool: &Pool<Sqlite>, token_id: &str, quote: &ClobQuote) -> Result<()> {
+    sqlx::query("UPDATE outcomes SET best_bid = ?, best_ask = ?, updated_at = ? WHERE token_id = ?")
+        .bind(decimal_column(quote.best_bid))
+        .bind(decimal_column(quote.best_ask))
+        .bind(Utc::now())
+        .bind(token_id)
         .execute(pool)
         .await?;
-    } else {
-        // Update existing market (preserve discovered_at)
+
+    Ok(())
+}
+
+/// Get the outcomes of a market in listing order
+pub async fn get_outcomes(pool: &Pool<Sqlite>, market_id: &str) -> Result<Vec<Outcome>> {
+    let outcomes = sqlx::query_as::<_, Outcome>(
+        "SELECT name, token_id, price, best_bid, best_ask FROM outcomes
+         WHERE market_id = ?
+         ORDER BY position"
+    )
+    .bind(market_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(outcomes)
+}
+
+/// Load and nest the outcomes of each market with a single query
+pub async fn attach_outcomes(pool: &Pool<Sqlite>, markets: &mut [Market]) -> Result<()> {
+    let market_ids: Vec<String> = markets.iter().map(|market| market.id.clone()).collect();
+    let mut by_market = get_outcomes_by_market(pool, &market_ids).await?;
+    for market in markets.iter_mut() {
+        market.outcomes = by_market.remove(&market.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Outcomes of each of `market_ids` in listing order, in one query; markets without any
+/// are left out
+pub async fn get_outcomes_by_market(
+    pool: &Pool<Sqlite>,
+    market_ids: &[String],
+) -> Result<HashMap<String, Vec<Outcome>>> {
+    let mut by_market: HashMap<String, Vec<Outcome>> = HashMap::new();
+    if market_ids.is_empty() {
+        return Ok(by_market);
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT market_id, name, token_id, price, best_bid, best_ask FROM outcomes WHERE market_id IN (",
+    );
+    let mut separated = qb.separated(", ");
+    for market_id in market_ids {
+        separated.push_bind(market_id);
+    }
+    separated.push_unseparated(") ORDER BY market_id, position");
+
+    let rows = qb.build().fetch_all(pool).await?;
+    for row in &rows {
+        by_market
+            .entry(row.try_get("market_id")?)
+            .or_default()
+            .push(Outcome::from_row(row)?);
+    }
+
+    Ok(by_market)
+}
+
+/// Replace the tags of a market, creating tags seen for the first time
+/// Links that are unchanged are left alone so repeated scrapes don't rewrite them
+pub async fn set_market_tags(pool: &Pool<Sqlite>, market_id: &str, tags: &[Tag]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let mut unlink = QueryBuilder::<Sqlite>::new("DELETE FROM market_tags WHERE market_id = ");
+    unlink.push_bind(market_id);
+    if !tags.is_empty() {
+        unlink.push(" AND tag_slug NOT IN (");
+        let mut separated = unlink.separated(", ");
+        for tag in tags {
+            separated.push_bind(&tag.slug);
+        }
+        separated.push_unseparated(")");
+    }
+    unlink.build().execute(&mut *tx).await?;
+
+    if !tags.is_empty() {
+        let mut upsert = QueryBuilder::<Sqlite>::new("INSERT INTO tags (slug, label) ");
+        upsert.push_values(tags, |mut row, tag| {
+            row.push_bind(&tag.slug).push_bind(&tag.label);
+        });
+        upsert.push(" ON CONFLICT(slug) DO UPDATE SET label = excluded.label WHERE label != excluded.label");
+        upsert.build().execute(&mut *tx).await?;
+
+        let mut link = QueryBuilder::<Sqlite>::new("INSERT OR IGNORE INTO market_tags (market_id, tag_slug) ");
+        link.push_values(tags, |mut row, tag| {
+            row.push_bind(market_id).push_bind(&tag.slug);
+        });
+        link.build().execute(&mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Get the tags of a market, alphabetically
+pub async fn get_market_tags(pool: &Pool<Sqlite>, market_id: &str) -> Result<Vec<Tag>> {
+    let tags = sqlx::query_as::<_, Tag>(
+        "SELECT t.slug, t.label FROM tags t
+         JOIN market_tags mt ON mt.tag_slug = t.slug
+         WHERE mt.market_id = ?
+         ORDER BY t.slug"
+    )
+    .bind(market_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+/// Tags of each of `market_ids` alphabetically by slug, in one query; markets without any
+/// are left out
+pub async fn get_tags_by_market(
+    pool: &Pool<Sqlite>,
+    market_ids: &[String],
+) -> Result<HashMap<String, Vec<Tag>>> {
+    let mut by_market: HashMap<String, Vec<Tag>> = HashMap::new();
+    if market_ids.is_empty() {
+        return Ok(by_market);
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT mt.market_id, t.slug, t.label FROM tags t
+         JOIN market_tags mt ON mt.tag_slug = t.slug
+         WHERE mt.market_id IN (",
+    );
+    let mut separated = qb.separated(", ");
+    for market_id in market_ids {
+        separated.push_bind(market_id);
+    }
+    separated.push_unseparated(") ORDER BY mt.market_id, t.slug");
+
+    let rows = qb.build().fetch_all(pool).await?;
+    for row in &rows {
+        by_market
+            .entry(row.try_get("market_id")?)
+            .or_default()
+            .push(Tag::from_row(row)?);
+    }
+
+    Ok(by_market)
+}
+
+/// All tags with the number of listed (not delisted) markets carrying each, most used first
+pub async fn get_tag_counts(pool: &Pool<Sqlite>) -> Result<Vec<TagCount>> {
+    let counts = sqlx::query_as::<_, TagCount>(
+        "SELECT t.slug, t.label, COUNT(m.id) AS market_count FROM tags t
+         LEFT JOIN market_tags mt ON mt.tag_slug = t.slug
+         LEFT JOIN markets m ON m.id = mt.market_id AND m.status != 'delisted'
+         GROUP BY t.slug, t.label
+         ORDER BY market_count DESC, t.slug"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(counts)
+}
+
+/// Record that markets were returned by a scrape, restoring any that had been delisted
+/// Restorations are logged as `status` transitions in the change log; `last_seen_at` never
+/// moves backwards, as a shard whose walk started earlier may finish later
+pub async fn mark_markets_seen(pool: &Pool<Sqlite>, ids: &[String], seen_at: DateTime<Utc>) -> Result<()> {
+    for chunk in ids.chunks(SEEN_UPDATE_CHUNK_SIZE) {
+        let mut tx = pool.begin().await?;
+
+        let mut log = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO market_changes (market_id, field, old_value, new_value, changed_at) \
+             SELECT id, 'status', status, 'active', ",
+        );
+        log.push_bind(seen_at)
+            .push(" FROM markets WHERE status = 'delisted' AND id IN (");
+        let mut separated = log.separated(", ");
+        for id in chunk {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        log.build().execute(&mut *tx).await?;
+
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            "UPDATE markets SET last_seen_at = MAX(COALESCE(last_seen_at, ",
+        );
+        qb.push_bind(seen_at).push("), ").push_bind(seen_at).push(
+            "), missed_scrapes = 0, \
+             status = CASE WHEN status = 'delisted' THEN 'active' ELSE status END \
+             WHERE id IN (",
+        );
+        let mut separated = qb.separated(", ");
+        for id in chunk {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        qb.build().execute(&mut *tx).await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Count a missed scrape for every active, not yet ended market of `source` that a complete
+/// scrape at `seen_at` didn't return, then delist those missing for `max_missed` consecutive
+/// scrapes and log the transitions. Returns how many markets were delisted
+pub async fn delist_unseen_markets(
+    pool: &Pool<Sqlite>,
+    source: &str,
+    seen_at: DateTime<Utc>,
+    max_missed: u32,
+) -> Result<u64> {
+    // Markets past their end date drop out of the active listing and are left to resolution tracking
+    sqlx::query(
+        "UPDATE markets SET missed_scrapes = missed_scrapes + 1
+         WHERE status = 'active' AND source = ?
+           AND (last_seen_at IS NULL OR last_seen_at < ?)
+           AND (end_date IS NULL OR end_date > ?)"
+    )
+    .bind(source)
+    .bind(seen_at)
+    .bind(end_date_column(seen_at))
+    .execute(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO market_changes (market_id, field, old_value, new_value, changed_at)
+         SELECT id, 'status', status, 'delisted', ? FROM markets
+         WHERE status = 'active' AND source = ? AND missed_scrapes >= ?"
+    )
+    .bind(seen_at)
+    .bind(source)
+    .bind(max_missed as i64)
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query(
+        "UPDATE markets SET status = 'delisted', updated_at = ?
+         WHERE status = 'active' AND source = ? AND missed_scrapes >= ?"
+    )
+    .bind(seen_at)
+    .bind(source)
+    .bind(max_missed as i64)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Append a price observation for a market to the price history
+pub async fn insert_price_point(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    price: Option<Decimal>,
+    volume: Option<Decimal>,
+    scraped_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO price_history (market_id, price, volume, scraped_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(market_id)
+    .bind(decimal_column(price))
+    .bind(decimal_column(volume))
+    .bind(scraped_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Delete price history rows scraped before a cutoff, returning how many were removed
+pub async fn delete_price_history_before(pool: &Pool<Sqlite>, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM price_history WHERE scraped_at < ?")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete trades made before a cutoff; returns how many were deleted
+pub async fn delete_trades_before(pool: &Pool<Sqlite>, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM trades WHERE traded_at < ?")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete comments posted before a cutoff; returns how many were deleted
+pub async fn delete_comments_before(pool: &Pool<Sqlite>, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM comments WHERE created_at < ?")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete holder captures taken before a cutoff, keeping each market's latest one as it is
+/// only replaced when the holders change; returns how many positions were deleted
+pub async fn delete_positions_before(pool: &Pool<Sqlite>, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM positions
+         WHERE captured_at < ? AND captured_at < (
+             SELECT MAX(latest.captured_at) FROM positions latest
+             WHERE latest.market_id = positions.market_id
+         )"
+    )
+    .bind(before)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete orderbook snapshots captured before a cutoff; returns how many were deleted
+pub async fn delete_orderbook_snapshots_before(
+    pool: &Pool<Sqlite>,
+    before: DateTime<Utc>,
+) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM orderbook_snapshots WHERE captured_at < ?")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Move markets resolved before a cutoff to `archived_markets` as JSON, dropping their rows in
+/// every per-market table; returns how many markets were archived
+pub async fn archive_markets_resolved_before(
+    pool: &Pool<Sqlite>,
+    before: DateTime<Utc>,
+    archived_at: DateTime<Utc>,
+) -> Result<u64> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets WHERE status IN ('resolved', 'archived') AND resolved_at < ?",
+        MARKET_COLUMNS
+    ))
+    .bind(before)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    for market in &markets {
         sqlx::query(
-            r#"
-            UPDATE markets SET
-                title = ?,
-                description = ?,
-                current_price = ?,
-                volume = ?,
-                end_date = ?,
-                updated_at = ?
-            WHERE id = ?
-            "#,
+            "INSERT OR REPLACE INTO archived_markets (id, data, resolved_at, archived_at) VALUES (?, ?, ?, ?)"
         )
-        .bind(&market.title)
-        .bind(&market.description)
-        .bind(market.current_price)
-        .bind(market.volume)
-        .bind(&market.end_date)
-        .bind(now)
         .bind(&market.id)
-        .execute(pool)
+        .bind(serde_json::to_string(market).map_err(Error::storage)?)
+        .bind(market.resolved_at)
+        .bind(archived_at)
+        .execute(&mut *tx)
         .await?;
+
+        for table in [
+            "price_history",
+            "candles",
+            "market_changes",
+            "raw_payloads",
+            "volume_spikes",
+            "trades",
+            "orderbook_snapshots",
+            "positions",
+            "onchain_verifications",
+            "outcomes",
+            "market_tags",
+        ] {
+            sqlx::query(&format!("DELETE FROM {} WHERE market_id = ?", table))
+                .bind(&market.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM markets WHERE id = ?")
+            .bind(&market.id)
+            .execute(&mut *tx)
+            .await?;
     }
+    tx.commit().await?;
 
-    Ok(is_new)
+    Ok(markets.len() as u64)
 }
 
-/// Get all markets with pagination
-pub async fn get_markets(
+/// Record field transitions detected for a market in the audit log
+pub async fn insert_market_changes(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    changes: &[FieldChange],
+    changed_at: DateTime<Utc>,
+) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "INSERT INTO market_changes (market_id, field, old_value, new_value, changed_at) ",
+    );
+    qb.push_values(changes, |mut row, change| {
+        row.push_bind(market_id.to_string())
+            .push_bind(change.field)
+            .push_bind(change.old_value.clone())
+            .push_bind(change.new_value.clone())
+            .push_bind(changed_at);
+    });
+    qb.build().execute(pool).await?;
+
+    Ok(())
+}
+
+/// Get the most recent field transitions for a market, newest first
+pub async fn get_market_changes(
     pool: &Pool<Sqlite>,
+    market_id: &str,
     limit: u32,
-    offset: u32,
-) -> Result<(Vec<Market>, i64)> {
-    let markets = sqlx::query_as::<_, Market>(
-        "SELECT id, title, description, current_price, volume, end_date, discovered_at, updated_at 
-         FROM markets 
-         ORDER BY discovered_at DESC 
-         LIMIT ? OFFSET ?"
+) -> Result<Vec<MarketChange>> {
+    let changes = sqlx::query_as::<_, MarketChange>(
+        "SELECT field, old_value, new_value, changed_at FROM market_changes
+         WHERE market_id = ?
+         ORDER BY changed_at DESC, id DESC
+         LIMIT ?"
     )
+    .bind(market_id)
     .bind(limit as i64)
-    .bind(offset as i64)
     .fetch_all(pool)
     .await?;
 
-    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM markets")
-        .fetch_one(pool)
-        .await?;
+    Ok(changes)
+}
 
-    Ok((markets, total))
+/// Store trades, skipping ones already stored; returns how many were new
+pub async fn insert_trades(pool: &Pool<Sqlite>, trades: &[Trade]) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut inserted = 0;
+    for trade in trades {
+        inserted += sqlx::query(
+            "INSERT OR IGNORE INTO trades
+                (id, market_id, token_id, outcome, side, size, price, traded_at, maker, taker, transaction_hash)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&trade.id)
+        .bind(&trade.market_id)
+        .bind(&trade.token_id)
+        .bind(&trade.outcome)
+        .bind(trade.side)
+        .bind(decimal_column(Some(trade.size)))
+        .bind(decimal_column(Some(trade.price)))
+        .bind(trade.traded_at)
+        .bind(&trade.maker)
+        .bind(&trade.taker)
+        .bind(&trade.transaction_hash)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(inserted)
 }
 
-/// Get markets discovered since a given timestamp
-pub async fn get_markets_since(
+/// Get a market's trades, newest first, optionally only those after `since`
+pub async fn get_trades(
     pool: &Pool<Sqlite>,
-    since: chrono::DateTime<Utc>,
-) -> Result<Vec<Market>> {
-    let markets = sqlx::query_as::<_, Market>(
-        "SELECT id, title, description, current_price, volume, end_date, discovered_at, updated_at 
-         FROM markets 
-         WHERE discovered_at >= ? 
-         ORDER BY discovered_at DESC"
+    market_id: &str,
+    since: Option<DateTime<Utc>>,
+    limit: u32,
+) -> Result<Vec<Trade>> {
+    let trades = sqlx::query_as::<_, Trade>(
+        "SELECT id, market_id, token_id, outcome, side, size, price, traded_at, maker, taker, transaction_hash
+         FROM trades
+         WHERE market_id = ? AND (? IS NULL OR traded_at > ?)
+         ORDER BY traded_at DESC
+         LIMIT ?"
     )
+    .bind(market_id)
+    .bind(since)
     .bind(since)
+    .bind(limit as i64)
     .fetch_all(pool)
     .await?;
 
-    Ok(markets)
+    Ok(trades)
 }
 
-/// Get a single market by ID
-pub async fn get_market_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<Market>> {
-    let market = sqlx::query_as::<_, Market>(
-        "SELECT id, title, description, current_price, volume, end_date, discovered_at, updated_at 
-         FROM markets 
-         WHERE id = ?"
+/// Store an orderbook snapshot
+pub async fn insert_orderbook_snapshot(pool: &Pool<Sqlite>, snapshot: &OrderbookSnapshot) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO orderbook_snapshots
+            (market_id, token_id, bids, asks, midpoint, spread, bid_depth, ask_depth, imbalance, captured_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
-    .bind(id)
+    .bind(&snapshot.market_id)
+    .bind(&snapshot.token_id)
+    .bind(serde_json::to_string(&snapshot.bids).map_err(Error::storage)?)
+    .bind(serde_json::to_string(&snapshot.asks).map_err(Error::storage)?)
+    .bind(decimal_column(snapshot.midpoint))
+    .bind(decimal_column(snapshot.spread))
+    .bind(decimal_column(Some(snapshot.bid_depth)))
+    .bind(decimal_column(Some(snapshot.ask_depth)))
+    .bind(snapshot.imbalance)
+    .bind(snapshot.captured_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the latest orderbook snapshot of a market, if one was captured
+pub async fn get_latest_orderbook_snapshot(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+) -> Result<Option<OrderbookSnapshot>> {
+    let row = sqlx::query(
+        "SELECT market_id, token_id, bids, asks, midpoint, spread, bid_depth, ask_depth, imbalance, captured_at
+         FROM orderbook_snapshots
+         WHERE market_id = ?
+         ORDER BY captured_at DESC, id DESC
+         LIMIT 1"
+    )
+    .bind(market_id)
     .fetch_optional(pool)
     .await?;
 
-    Ok(market)
+    row.map(orderbook_snapshot_from_row).transpose()
+}
+
+/// Decode an `orderbook_snapshots` row, whose levels are stored as JSON
+fn orderbook_snapshot_from_row(row: SqliteRow) -> Result<OrderbookSnapshot> {
+    let bids: String = row.try_get("bids")?;
+    let asks: String = row.try_get("asks")?;
+    Ok(OrderbookSnapshot {
+        market_id: row.try_get("market_id")?,
+        token_id: row.try_get("token_id")?,
+        bids: serde_json::from_str(&bids).map_err(Error::storage)?,
+        asks: serde_json::from_str(&asks).map_err(Error::storage)?,
+        midpoint: row.try_get::<DecimalColumn, _>("midpoint")?.into(),
+        spread: row.try_get::<DecimalColumn, _>("spread")?.into(),
+        bid_depth: row.try_get::<DecimalColumn, _>("bid_depth")?.try_into()?,
+        ask_depth: row.try_get::<DecimalColumn, _>("ask_depth")?.try_into()?,
+        imbalance: row.try_get("imbalance")?,
+        captured_at: row.try_get("captured_at")?,
+    })
+}
+
+/// Store comments, skipping ones already stored; returns how many were new
+/// Reaction counts of known comments are refreshed
+pub async fn upsert_comments(pool: &Pool<Sqlite>, comments: &[Comment]) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut inserted = 0;
+    for comment in comments {
+        let result = sqlx::query(
+            "INSERT INTO comments
+                (id, event_id, parent_comment_id, author, author_name, body, reaction_count, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO NOTHING"
+        )
+        .bind(&comment.id)
+        .bind(&comment.event_id)
+        .bind(&comment.parent_comment_id)
+        .bind(&comment.author)
+        .bind(&comment.author_name)
+        .bind(&comment.body)
+        .bind(comment.reaction_count)
+        .bind(comment.created_at)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() > 0 {
+            inserted += 1;
+            continue;
+        }
+
+        sqlx::query("UPDATE comments SET reaction_count = ? WHERE id = ?")
+            .bind(comment.reaction_count)
+            .bind(&comment.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+/// Get an event's comments, newest first
+pub async fn get_comments(pool: &Pool<Sqlite>, event_id: &str, limit: u32) -> Result<Vec<Comment>> {
+    let comments = sqlx::query_as::<_, Comment>(
+        "SELECT id, event_id, parent_comment_id, author, author_name, body, reaction_count, created_at
+         FROM comments
+         WHERE event_id = ?
+         ORDER BY created_at DESC
+         LIMIT ?"
+    )
+    .bind(event_id)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(comments)
 }
 
+/// Count the stored comments created after `since`, on one event or on all of them
+pub async fn count_comments_since(
+    pool: &Pool<Sqlite>,
+    event_id: Option<&str>,
+    since: DateTime<Utc>,
+) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM comments WHERE (? IS NULL OR event_id = ?) AND created_at > ?"
+    )
+    .bind(event_id)
+    .bind(event_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Store a capture of market holders, skipping positions already stored for it; returns how
+/// many were new
+pub async fn insert_positions(pool: &Pool<Sqlite>, positions: &[Position]) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut inserted = 0;
+    for position in positions {
+        inserted += sqlx::query(
+            "INSERT OR IGNORE INTO positions
+                (market_id, token_id, outcome_index, wallet, name, amount, captured_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&position.market_id)
+        .bind(&position.token_id)
+        .bind(position.outcome_index)
+        .bind(&position.wallet)
+        .bind(&position.name)
+        .bind(position.amount)
+        .bind(position.captured_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+/// Get a market's holders from its latest capture, or the latest one at or before `at`;
+/// largest first per token, empty when nothing was captured
+pub async fn get_holders(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    at: Option<DateTime<Utc>>,
+) -> Result<Vec<Position>> {
+    let positions = sqlx::query_as::<_, Position>(
+        "SELECT market_id, token_id, outcome_index, wallet, name, amount, captured_at
+         FROM positions
+         WHERE market_id = ? AND captured_at = (
+             SELECT MAX(captured_at) FROM positions
+             WHERE market_id = ? AND (? IS NULL OR captured_at <= ?)
+         )
+         ORDER BY token_id, amount DESC"
+    )
+    .bind(market_id)
+    .bind(market_id)
+    .bind(at)
+    .bind(at)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(positions)
+}
+
+/// Archive a compressed raw payload, skipping it when it matches the market's latest one
+pub async fn insert_raw_payload(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    source: &str,
+    payload: &[u8],
+    payload_hash: &str,
+    scraped_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO raw_payloads (market_id, source, payload, payload_hash, scraped_at)
+        SELECT ?1, ?2, ?3, ?4, ?5
+        WHERE ?4 IS NOT (
+            SELECT payload_hash FROM raw_payloads
+            WHERE market_id = ?1
+            ORDER BY scraped_at DESC
+            LIMIT 1
+        )
+        "#,
+    )
+    .bind(market_id)
+    .bind(source)
+    .bind(payload)
+    .bind(payload_hash)
+    .bind(scraped_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the most recent price observations for a market, newest first
+pub async fn get_price_history(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    limit: u32,
+) -> Result<Vec<PricePoint>> {
+    let points = sqlx::query_as::<_, PricePoint>(
+        "SELECT price, volume, scraped_at FROM price_history
+         WHERE market_id = ?
+         ORDER BY scraped_at DESC
+         LIMIT ?"
+    )
+    .bind(market_id)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(points)
+}
+
+/// Roll price history up into candles of `interval`; returns the number of candles written
+/// Candles before the latest stored one are complete, so only points from its start on are
+/// aggregated, rewriting the latest candle and adding any newer ones
+pub async fn aggregate_candles(pool: &Pool<Sqlite>, interval: CandleInterval) -> Result<u64> {
+    let since = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT MAX(start_at) FROM candles WHERE interval = ?"
+    )
+    .bind(interval.as_str())
+    .fetch_one(pool)
+    .await?;
+
+    // `WHERE true` keeps SQLite from parsing `ON CONFLICT` as part of the SELECT
+    let result = sqlx::query(
+        "INSERT INTO candles (market_id, interval, start_at, open, high, low, close, volume, points)
+         SELECT market_id, ?, start_at,
+                MAX(CASE WHEN first_in_period = 1 THEN price END),
+                MAX(price),
+                MIN(price),
+                MAX(CASE WHEN last_in_period = 1 THEN price END),
+                MAX(volume) - MIN(volume),
+                COUNT(*)
+         FROM (
+             SELECT market_id, price, volume, strftime(?, scraped_at) AS start_at,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY market_id, strftime(?, scraped_at) ORDER BY scraped_at
+                    ) AS first_in_period,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY market_id, strftime(?, scraped_at) ORDER BY scraped_at DESC
+                    ) AS last_in_period
+             FROM price_history
+             WHERE scraped_at >= ?
+         )
+         WHERE true
+         GROUP BY market_id, start_at
+         ON CONFLICT(market_id, interval, start_at) DO UPDATE SET
+             open = excluded.open,
+             high = excluded.high,
+             low = excluded.low,
+             close = excluded.close,
+             volume = excluded.volume,
+             points = excluded.points"
+    )
+    .bind(interval.as_str())
+    .bind(interval.bucket_format())
+    .bind(interval.bucket_format())
+    .bind(interval.bucket_format())
+    .bind(since.unwrap_or_default())
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Get the most recent candles of a market, newest first
+pub async fn get_candles(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    interval: CandleInterval,
+    limit: u32,
+) -> Result<Vec<Candle>> {
+    let candles = sqlx::query_as::<_, Candle>(
+        "SELECT start_at, open, high, low, close, volume, points FROM candles
+         WHERE market_id = ? AND interval = ?
+         ORDER BY start_at DESC
+         LIMIT ?"
+    )
+    .bind(market_id)
+    .bind(interval.as_str())
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(candles)
+}
+
+/// Get the markets with the given IDs; unknown IDs are skipped
+pub async fn get_markets_by_ids(pool: &Pool<Sqlite>, ids: &[String]) -> Result<Vec<Market>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {} FROM markets WHERE id IN (",
+        MARKET_COLUMNS
+    ));
+    let mut separated = qb.separated(", ");
+    for id in ids {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(")");
+
+    let markets = qb.build_query_as::<Market>().fetch_all(pool).await?;
+    Ok(markets)
+}
+
+/// Polymarket markets with one of the given condition IDs
+pub async fn get_markets_by_condition_ids(
+    pool: &Pool<Sqlite>,
+    condition_ids: &[String],
+) -> Result<Vec<Market>> {
+    if condition_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {} FROM markets WHERE source = ",
+        MARKET_COLUMNS
+    ));
+    qb.push_bind(source::POLYMARKET);
+    qb.push(" AND condition_id IN (");
+    let mut separated = qb.separated(", ");
+    for id in condition_ids {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(")");
+
+    let markets = qb.build_query_as::<Market>().fetch_all(pool).await?;
+    Ok(markets)
+}
+
+/// Active markets ending between `from` and `to`, soonest first
+pub async fn get_markets_ending_between(
+    pool: &Pool<Sqlite>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: u32,
+) -> Result<Vec<Market>> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets
+         WHERE status = 'active'
+           AND end_date BETWEEN ? AND ?
+         ORDER BY end_date ASC
+         LIMIT ?",
+        MARKET_COLUMNS
+    ))
+    .bind(end_date_column(from))
+    .bind(end_date_column(to))
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(markets)
+}
+
+/// Markets with the largest price change between their first and latest observation since `since`
+pub async fn get_top_movers(
+    pool: &Pool<Sqlite>,
+    since: DateTime<Utc>,
+    sort: MoverSort,
+    limit: u32,
+) -> Result<Vec<MarketMover>> {
+    let movers = sqlx::query_as::<_, MarketMover>(&format!(
+        "WITH windowed AS (
+            SELECT market_id, price,
+                ROW_NUMBER() OVER (PARTITION BY market_id ORDER BY scraped_at ASC) AS first_rank,
+                ROW_NUMBER() OVER (PARTITION BY market_id ORDER BY scraped_at DESC) AS last_rank
+            FROM price_history
+            WHERE scraped_at >= ? AND price IS NOT NULL
+        ),
+        moves AS (
+            SELECT market_id,
+                MAX(CASE WHEN first_rank = 1 THEN price END) AS start_price,
+                MAX(CASE WHEN last_rank = 1 THEN price END) AS end_price
+            FROM windowed
+            GROUP BY market_id
+        )
+        SELECT {}, start_price, end_price,
+            end_price - start_price AS price_change,
+            CASE WHEN start_price > 0 THEN (end_price - start_price) / start_price END AS relative_change
+        FROM moves
+        JOIN markets ON markets.id = moves.market_id
+        WHERE end_price != start_price
+        ORDER BY ABS({}) DESC NULLS LAST
+        LIMIT ?",
+        MARKET_COLUMNS,
+        sort.column()
+    ))
+    .bind(since)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(movers)
+}
+
+/// Markets whose price rose (`Desc`, biggest rise first) or fell (`Asc`, biggest fall first)
+/// between their first and last observation in `[from, to)`
+pub async fn get_price_changes_between(
+    pool: &Pool<Sqlite>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    order: SortOrder,
+    limit: u32,
+) -> Result<Vec<MarketMover>> {
+    let direction = match order {
+        SortOrder::Asc => "<",
+        SortOrder::Desc => ">",
+    };
+    let movers = sqlx::query_as::<_, MarketMover>(&format!(
+        "WITH windowed AS (
+            SELECT market_id, price,
+                ROW_NUMBER() OVER (PARTITION BY market_id ORDER BY scraped_at ASC) AS first_rank,
+                ROW_NUMBER() OVER (PARTITION BY market_id ORDER BY scraped_at DESC) AS last_rank
+            FROM price_history
+            WHERE scraped_at >= ? AND scraped_at < ? AND price IS NOT NULL
+        ),
+        moves AS (
+            SELECT market_id,
+                MAX(CASE WHEN first_rank = 1 THEN price END) AS start_price,
+                MAX(CASE WHEN last_rank = 1 THEN price END) AS end_price
+            FROM windowed
+            GROUP BY market_id
+        )
+        SELECT {}, start_price, end_price,
+            end_price - start_price AS price_change,
+            CASE WHEN start_price > 0 THEN (end_price - start_price) / start_price END AS relative_change
+        FROM moves
+        JOIN markets ON markets.id = moves.market_id
+        WHERE end_price {} start_price
+        ORDER BY price_change {}
+        LIMIT ?",
+        MARKET_COLUMNS,
+        direction,
+        order.sql()
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(movers)
+}
+
+/// Markets that traded the most volume in `[from, to)`, most first
+pub async fn get_volume_leaders_between(
+    pool: &Pool<Sqlite>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: u32,
+) -> Result<Vec<MarketVolume>> {
+    let leaders = sqlx::query_as::<_, MarketVolume>(&format!(
+        "WITH traded AS (
+            SELECT market_id, MAX(volume) - MIN(volume) AS volume_traded
+            FROM price_history
+            WHERE scraped_at >= ? AND scraped_at < ? AND volume IS NOT NULL
+            GROUP BY market_id
+        )
+        SELECT {}, volume_traded
+        FROM traded
+        JOIN markets ON markets.id = traded.market_id
+        WHERE volume_traded > 0
+        ORDER BY volume_traded DESC
+        LIMIT ?",
+        MARKET_COLUMNS
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(leaders)
+}
+
+/// Highest-volume markets discovered in `[from, to)`, with how many there were in total
+pub async fn get_markets_discovered_between(
+    pool: &Pool<Sqlite>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: u32,
+) -> Result<(Vec<Market>, i64)> {
+    markets_between(pool, "discovered_at", from, to, limit).await
+}
+
+/// Highest-volume markets resolved in `[from, to)`, with how many there were in total
+pub async fn get_markets_resolved_between(
+    pool: &Pool<Sqlite>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: u32,
+) -> Result<(Vec<Market>, i64)> {
+    markets_between(pool, "resolved_at", from, to, limit).await
+}
+
+/// `column` is one of the market timestamps above, so safe to interpolate
+async fn markets_between(
+    pool: &Pool<Sqlite>,
+    column: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: u32,
+) -> Result<(Vec<Market>, i64)> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets
+         WHERE {} >= ? AND {} < ?
+         ORDER BY volume DESC NULLS LAST
+         LIMIT ?",
+        MARKET_COLUMNS, column, column
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT COUNT(*) FROM markets WHERE {} >= ? AND {} < ?",
+        column, column
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((markets, total))
+}
+
+/// Store a daily report, replacing any earlier one for the same day
+pub async fn save_daily_report(pool: &Pool<Sqlite>, report: &DailyReport) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO daily_reports (date, report, generated_at) VALUES (?, ?, ?)
+         ON CONFLICT(date) DO UPDATE SET
+             report = excluded.report,
+             generated_at = excluded.generated_at"
+    )
+    .bind(report.date.to_string())
+    .bind(serde_json::to_string(report).map_err(Error::storage)?)
+    .bind(report.generated_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the report of a day, if it was generated
+pub async fn get_daily_report(pool: &Pool<Sqlite>, date: NaiveDate) -> Result<Option<DailyReport>> {
+    let report = sqlx::query_scalar::<_, String>("SELECT report FROM daily_reports WHERE date = ?")
+        .bind(date.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+    match report {
+        Some(json) => Ok(Some(serde_json::from_str(&json).map_err(Error::storage)?)),
+        None => Ok(None),
+    }
+}
+
+/// Price observations of the given markets since `since`, oldest first per market
+pub async fn get_price_history_since(
+    pool: &Pool<Sqlite>,
+    ids: &[String],
+    since: DateTime<Utc>,
+) -> Result<Vec<PriceHistoryRow>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT market_id, price, volume, scraped_at FROM price_history WHERE scraped_at >= ",
+    );
+    qb.push_bind(since);
+    qb.push(" AND market_id IN (");
+    let mut separated = qb.separated(", ");
+    for id in ids {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(") ORDER BY market_id ASC, scraped_at ASC");
+
+    let rows = qb.build_query_as::<PriceHistoryRow>().fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// Resolved markets with a known winner that haven't been scored for calibration yet
+pub async fn get_markets_pending_calibration(pool: &Pool<Sqlite>, limit: u32) -> Result<Vec<Market>> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets
+         WHERE winning_outcome IS NOT NULL AND resolved_at IS NOT NULL
+           AND id NOT IN (SELECT market_id FROM market_calibration)
+         ORDER BY resolved_at ASC
+         LIMIT ?",
+        MARKET_COLUMNS
+    ))
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(markets)
+}
+
+/// Latest recorded price of a market at or before `at`
+pub async fn get_last_price_at(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    at: DateTime<Utc>,
+) -> Result<Option<f64>> {
+    let price = sqlx::query_scalar::<_, f64>(
+        "SELECT price FROM price_history
+         WHERE market_id = ? AND scraped_at <= ? AND price IS NOT NULL
+         ORDER BY scraped_at DESC
+         LIMIT 1"
+    )
+    .bind(market_id)
+    .bind(at)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(price)
+}
+
+/// Store the calibration forecasts of a resolved market, replacing earlier ones
+pub async fn save_market_calibration(pool: &Pool<Sqlite>, calibration: &MarketCalibration) -> Result<()> {
+    let mut query = sqlx::query(
+        "INSERT OR REPLACE INTO market_calibration
+            (market_id, outcome, resolved_at, scored_at, forecast_1h, forecast_1d, forecast_7d, forecast_30d)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&calibration.market_id)
+    .bind(calibration.outcome)
+    .bind(calibration.resolved_at)
+    .bind(Utc::now());
+    for forecast in calibration.forecasts {
+        query = query.bind(forecast);
+    }
+    query.execute(pool).await?;
+
+    Ok(())
+}
+
+/// Brier score and reliability buckets of the stored forecasts at every horizon
+pub async fn get_calibration(pool: &Pool<Sqlite>) -> Result<CalibrationResponse> {
+    let markets_scored = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM market_calibration")
+        .fetch_one(pool)
+        .await?;
+
+    let mut horizons = Vec::with_capacity(CalibrationHorizon::ALL.len());
+    for horizon in CalibrationHorizon::ALL {
+        let column = horizon.column();
+        let (markets, brier_score) = sqlx::query_as::<_, (i64, Option<f64>)>(&format!(
+            "SELECT COUNT(*), AVG(({0} - outcome) * ({0} - outcome))
+             FROM market_calibration WHERE {0} IS NOT NULL",
+            column
+        ))
+        .fetch_one(pool)
+        .await?;
+
+        // Forecasts of exactly 1.0 go in the top bucket
+        let buckets = sqlx::query_as::<_, CalibrationBucket>(&format!(
+            "SELECT MIN(CAST({0} * 10 AS INTEGER), 9) / 10.0 AS lower,
+                COUNT(*) AS forecasts,
+                AVG({0}) AS mean_forecast,
+                AVG(outcome) AS observed_frequency
+             FROM market_calibration
+             WHERE {0} IS NOT NULL
+             GROUP BY lower
+             ORDER BY lower",
+            column
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        horizons.push(HorizonCalibration {
+            horizon,
+            markets,
+            brier_score,
+            buckets,
+        });
+    }
+
+    Ok(CalibrationResponse {
+        markets_scored,
+        horizons,
+    })
+}
+
+/// Polymarket markets with a condition ID whose resolution hasn't been confirmed on chain,
+/// least recently checked first (never-checked markets sort first as NULL)
+pub async fn get_markets_pending_onchain(pool: &Pool<Sqlite>, limit: u32) -> Result<Vec<Market>> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets
+         WHERE source = ? AND condition_id IS NOT NULL
+           AND id NOT IN (SELECT market_id FROM onchain_verifications WHERE resolved)
+         ORDER BY (SELECT checked_at FROM onchain_verifications WHERE market_id = markets.id) ASC
+         LIMIT ?",
+        MARKET_COLUMNS
+    ))
+    .bind(source::POLYMARKET)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(markets)
+}
+
+/// Add USDC volumes per token and move the scan checkpoint from `scanned_from` to
+/// `scanned_to` in one transaction, so no block range is counted twice
+/// The checkpoint only moves if it still holds `scanned_from` (is still unset for None);
+/// otherwise another scanner got there first, nothing is written and false is returned.
+pub async fn add_onchain_volumes(
+    pool: &Pool<Sqlite>,
+    volumes: &[(String, f64)],
+    checkpoint_key: &str,
+    scanned_from: Option<u64>,
+    scanned_to: u64,
+) -> Result<bool> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    let moved = match scanned_from {
+        Some(scanned_from) => {
+            sqlx::query(
+                "UPDATE scraper_state SET value = ?, updated_at = ? WHERE key = ? AND value = ?"
+            )
+            .bind(scanned_to.to_string())
+            .bind(now)
+            .bind(checkpoint_key)
+            .bind(scanned_from.to_string())
+            .execute(&mut *tx)
+            .await?
+        }
+        None => {
+            sqlx::query(
+                "INSERT INTO scraper_state (key, value, updated_at)
+                 SELECT ?, ?, ? WHERE NOT EXISTS (SELECT 1 FROM scraper_state WHERE key = ?)"
+            )
+            .bind(checkpoint_key)
+            .bind(scanned_to.to_string())
+            .bind(now)
+            .bind(checkpoint_key)
+            .execute(&mut *tx)
+            .await?
+        }
+    };
+    if moved.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    for (token_id, volume) in volumes {
+        sqlx::query(
+            "INSERT INTO onchain_volumes (token_id, volume, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(token_id) DO UPDATE SET
+                volume = onchain_volumes.volume + excluded.volume,
+                updated_at = excluded.updated_at"
+        )
+        .bind(token_id)
+        .bind(volume)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// USDC traded on chain across a market's outcome tokens; None if none of them traded
+pub async fn get_onchain_volume(pool: &Pool<Sqlite>, market_id: &str) -> Result<Option<f64>> {
+    let volume = sqlx::query_scalar::<_, Option<f64>>(
+        "SELECT SUM(v.volume) FROM onchain_volumes v
+         JOIN outcomes o ON o.token_id = v.token_id
+         WHERE o.market_id = ?"
+    )
+    .bind(market_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(volume)
+}
+
+/// Store the latest on-chain check of a market, replacing the previous one
+pub async fn save_onchain_verification(
+    pool: &Pool<Sqlite>,
+    verification: &OnchainVerification,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO onchain_verifications
+            (market_id, resolved, winning_outcome, volume, mismatch, checked_at)
+         VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&verification.market_id)
+    .bind(verification.resolved)
+    .bind(&verification.winning_outcome)
+    .bind(verification.volume)
+    .bind(&verification.mismatch)
+    .bind(verification.checked_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_onchain_verification(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+) -> Result<Option<OnchainVerification>> {
+    let verification = sqlx::query_as::<_, OnchainVerification>(
+        "SELECT market_id, resolved, winning_outcome, volume, mismatch, checked_at
+         FROM onchain_verifications WHERE market_id = ?"
+    )
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(verification)
+}
+
+/// Get the full price history of every market, oldest first per market
+pub async fn get_all_price_history(pool: &Pool<Sqlite>) -> Result<Vec<PriceHistoryRow>> {
+    let rows = sqlx::query_as::<_, PriceHistoryRow>(
+        "SELECT market_id, price, volume, scraped_at FROM price_history
+         ORDER BY market_id ASC, scraped_at ASC"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Price observations recorded after `after` (all of them when `None`) and up to `until`
+#[cfg(feature = "s3")]
+pub async fn get_price_history_between(
+    pool: &Pool<Sqlite>,
+    after: Option<DateTime<Utc>>,
+    until: DateTime<Utc>,
+) -> Result<Vec<PriceHistoryRow>> {
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT market_id, price, volume, scraped_at FROM price_history WHERE scraped_at <= ",
+    );
+    qb.push_bind(until);
+    if let Some(after) = after {
+        qb.push(" AND scraped_at > ").push_bind(after);
+    }
+    qb.push(" ORDER BY market_id ASC, scraped_at ASC");
+
+    let rows = qb.build_query_as::<PriceHistoryRow>().fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// Append the WHERE clause for a market filter to a query
+fn push_market_filters(qb: &mut QueryBuilder<'_, Sqlite>, filter: &MarketFilter) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(status) = filter.status {
+        qb.push(" AND status = ").push_bind(status);
+    } else if !filter.include_delisted {
+        qb.push(" AND status != 'delisted'");
+    }
+    if let Some(source) = &filter.source {
+        qb.push(" AND source = ").push_bind(source.clone());
+    }
+    if let Some(tag) = &filter.tag {
+        qb.push(" AND id IN (SELECT market_id FROM market_tags WHERE tag_slug = ")
+            .push_bind(tag.clone())
+            .push(")");
+    }
+    if let Some(min_volume) = filter.min_volume {
+        qb.push(" AND volume >= ").push_bind(min_volume);
+    }
+    if let Some(min_price) = filter.min_price {
+        qb.push(" AND current_price >= ").push_bind(min_price);
+    }
+    if let Some(max_price) = filter.max_price {
+        qb.push(" AND current_price <= ").push_bind(max_price);
+    }
+    if let Some(before) = filter.ending_before {
+        qb.push(" AND end_date < ").push_bind(end_date_column(before));
+    }
+    if let Some(after) = filter.ending_after {
+        qb.push(" AND end_date > ").push_bind(end_date_column(after));
+    }
+}
+
+/// Query for one page of markets matching a filter
+fn market_page_query(limit: u32, offset: u32, filter: &MarketFilter) -> QueryBuilder<'static, Sqlite> {
+    let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM markets", MARKET_COLUMNS));
+    push_market_filters(&mut qb, filter);
+    qb.push(format!(
+        " ORDER BY {} {}, id ASC LIMIT ",
+        filter.sort_by.column(),
+        filter.order.sql()
+    ))
+    .push_bind(limit as i64)
+    .push(" OFFSET ")
+    .push_bind(offset as i64);
+    qb
+}
+
+/// Run a query on a background task and hand its rows over a bounded channel,
+/// so large results can be streamed without buffering them or borrowing the pool
+fn stream_rows<T>(pool: Arc<Pool<Sqlite>>, mut qb: QueryBuilder<'static, Sqlite>) -> ReceiverStream<Result<T>>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin + 'static,
+{
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER_ROWS);
+    tokio::spawn(async move {
+        let mut rows = qb.build_query_as::<T>().fetch(&*pool);
+        while let Some(row) = rows.next().await {
+            // Stop reading once the consumer has gone away
+            if tx.send(row.map_err(Into::into)).await.is_err() {
+                break;
+            }
+        }
+    });
+    ReceiverStream::new(rx)
+}
+
+/// Stream one page of markets matching a filter, row by row
+pub fn stream_markets(
+    pool: Arc<Pool<Sqlite>>,
+    limit: u32,
+    offset: u32,
+    filter: &MarketFilter,
+) -> ReceiverStream<Result<Market>> {
+    stream_rows(pool, market_page_query(limit, offset, filter))
+}
+
+/// Stream the most recent price observations for a market, newest first
+pub fn stream_price_history(
+    pool: Arc<Pool<Sqlite>>,
+    market_id: &str,
+    limit: u32,
+) -> ReceiverStream<Result<PricePoint>> {
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT price, volume, scraped_at FROM price_history WHERE market_id = ",
+    );
+    qb.push_bind(market_id.to_string())
+        .push(" ORDER BY scraped_at DESC LIMIT ")
+        .push_bind(limit as i64);
+    stream_rows(pool, qb)
+}
+
+/// Get a page of the most recent archived payload of every market, or of a single market,
+/// ordered by market ID and starting after `after_market_id`
+/// Reprocessing writes between pages, so no connection is held while it does
+pub async fn get_latest_raw_payloads(
+    pool: &Pool<Sqlite>,
+    market_id: Option<&str>,
+    after_market_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<RawPayloadRow>> {
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT r.market_id, r.source, r.payload, r.scraped_at FROM raw_payloads r \
+         WHERE r.scraped_at = (SELECT MAX(scraped_at) FROM raw_payloads WHERE market_id = r.market_id)",
+    );
+    if let Some(market_id) = market_id {
+        qb.push(" AND r.market_id = ").push_bind(market_id.to_string());
+    }
+    if let Some(after) = after_market_id {
+        qb.push(" AND r.market_id > ").push_bind(after.to_string());
+    }
+    qb.push(" ORDER BY r.market_id LIMIT ").push_bind(limit);
+    Ok(qb.build_query_as::<RawPayloadRow>().fetch_all(pool).await?)
+}
+
+/// Get markets with pagination, filtering and sorting
+pub async fn get_markets(
+    pool: &Pool<Sqlite>,
+    limit: u32,
+    offset: u32,
+    filter: &MarketFilter,
+) -> Result<(Vec<Market>, i64)> {
+    let markets = market_page_query(limit, offset, filter)
+        .build_query_as::<Market>()
+        .fetch_all(pool)
+        .await?;
+
+    let mut count_qb = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM markets");
+    push_market_filters(&mut count_qb, filter);
+    let (total,) = count_qb.build_query_as::<(i64,)>().fetch_one(pool).await?;
+    Ok((markets, total))
+}
+
+/// Count markets grouped by lifecycle status
+pub async fn count_markets_by_status(pool: &Pool<Sqlite>) -> Result<Vec<(String, i64)>> {
+    let counts = sqlx::query_as::<_, (String, i64)>(
+        "SELECT status, COUNT(*) FROM markets GROUP BY status"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(counts)
+}
+
+/// Count every stored market
+pub async fn count_markets(pool: &Pool<Sqlite>) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM markets")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(count)
+}
+
+/// Compute market counts, volume and price aggregates in a single pass
+pub async fn get_market_stats(pool: &Pool<Sqlite>) -> Result<StatsResponse> {
+    let now = Utc::now();
+    let stats = sqlx::query_as::<_, StatsResponse>(
+        "SELECT
+            COUNT(*) AS total_markets,
+            COALESCE(SUM(discovered_at >= ?), 0) AS new_last_24h,
+            COALESCE(SUM(discovered_at >= ?), 0) AS new_last_7d,
+            TOTAL(volume) AS total_volume,
+            AVG(current_price) AS average_price,
+            COALESCE(SUM(end_date > ? AND end_date <= ?), 0) AS ending_next_24h
+         FROM markets"
+    )
+    .bind(now - chrono::Duration::hours(24))
+    .bind(now - chrono::Duration::days(7))
+    .bind(end_date_column(now))
+    .bind(end_date_column(now + chrono::Duration::hours(24)))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(stats)
+}
+
+/// Check that the database answers queries
+pub async fn ping(pool: &Pool<Sqlite>) -> Result<()> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
+/// Get markets discovered since a given timestamp
+pub async fn get_markets_since(
+    pool: &Pool<Sqlite>,
+    since: chrono::DateTime<Utc>,
+) -> Result<Vec<Market>> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets WHERE discovered_at >= ? ORDER BY discovered_at DESC",
+        MARKET_COLUMNS
+    ))
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(markets)
+}
+
+/// Get a single market by ID
+pub async fn get_market_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<Market>> {
+    let market = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets WHERE id = ?",
+        MARKET_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(market)
+}
+
+/// Get a market by its ID, slug, condition ID, question ID or any of its CLOB token IDs
+/// An exact ID match wins if a value is ambiguous
+pub async fn find_market(pool: &Pool<Sqlite>, key: &str) -> Result<Option<Market>> {
+    let market = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets
+         WHERE id = ?1 OR slug = ?1 OR condition_id = ?1 OR question_id = ?1
+            OR clob_token_id = ?1
+            OR id IN (SELECT market_id FROM outcomes WHERE token_id = ?1)
+         ORDER BY id = ?1 DESC
+         LIMIT 1",
+        MARKET_COLUMNS
+    ))
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(market)
+}
+
+
+/// Upsert an event into the database, preserving discovered_at
+pub async fn upsert_event(pool: &Pool<Sqlite>, event: &Event) -> Result<()> {
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO events (id, title, slug, description, volume, end_date, comment_count, discovered_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title,
+            slug = excluded.slug,
+            description = excluded.description,
+            volume = excluded.volume,
+            end_date = excluded.end_date,
+            comment_count = excluded.comment_count,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(&event.id)
+    .bind(&event.title)
+    .bind(&event.slug)
+    .bind(&event.description)
+    .bind(event.volume)
+    .bind(&event.end_date)
+    .bind(event.comment_count)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Link markets to their parent event
+/// Returns the number of markets that exist locally and were linked
+pub async fn link_markets_to_event(
+    pool: &Pool<Sqlite>,
+    event_id: &str,
+    market_ids: &[String],
+) -> Result<u64> {
+    let mut linked = 0;
+    for market_id in market_ids {
+        linked += sqlx::query("UPDATE markets SET event_id = ? WHERE id = ?")
+            .bind(event_id)
+            .bind(market_id)
+            .execute(pool)
+            .await?
+            .rows_affected();
+    }
+
+    Ok(linked)
+}
+
+/// Get all events with pagination
+pub async fn get_events(pool: &Pool<Sqlite>, limit: u32, offset: u32) -> Result<(Vec<Event>, i64)> {
+    let events = sqlx::query_as::<_, Event>(
+        "SELECT id, title, slug, description, volume, end_date, comment_count, discovered_at, updated_at
+         FROM events
+         ORDER BY discovered_at DESC
+         LIMIT ? OFFSET ?"
+    )
+    .bind(limit as i64)
+    .bind(offset as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM events")
+        .fetch_one(pool)
+        .await?;
+
+    Ok((events, total))
+}
+
+/// Get a single event by ID
+pub async fn get_event_by_id(pool: &Pool<Sqlite>, id: &str) -> Result<Option<Event>> {
+    let event = sqlx::query_as::<_, Event>(
+        "SELECT id, title, slug, description, volume, end_date, comment_count, discovered_at, updated_at
+         FROM events
+         WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(event)
+}
+
+/// Get all markets belonging to an event
+pub async fn get_markets_by_event(pool: &Pool<Sqlite>, event_id: &str) -> Result<Vec<Market>> {
+    let markets = sqlx::query_as::<_, Market>(&format!(
+        "SELECT {} FROM markets WHERE event_id = ? ORDER BY discovered_at DESC",
+        MARKET_COLUMNS
+    ))
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(markets)
+}
+
+/// Get IDs of unresolved Polymarket markets whose end date has passed; other venues
+/// report resolutions in their regular listing
+/// Least recently updated first, so repeated checks rotate through the backlog
+pub async fn get_markets_pending_resolution(pool: &Pool<Sqlite>) -> Result<Vec<String>> {
+    let now = end_date_column(Utc::now());
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT id FROM markets
+         WHERE status NOT IN ('resolved', 'archived') AND source = ?
+           AND end_date IS NOT NULL AND end_date < ?
+         ORDER BY updated_at ASC
+         LIMIT ?"
+    )
+    .bind(source::POLYMARKET)
+    .bind(now)
+    .bind(RESOLUTION_CHECK_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Store the lifecycle status and resolution of a market, logging a status transition in
+/// the change log
+/// A winner confirmed on chain outranks what the API reports later: the market stays
+/// resolved (or archived) with that winner and its first resolution time.
+pub async fn update_market_resolution(pool: &Pool<Sqlite>, market: &Market) -> Result<()> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let confirmed: Option<(String, Option<DateTime<Utc>>)> = sqlx::query_as(
+        "SELECT v.winning_outcome, m.resolved_at FROM onchain_verifications v
+         JOIN markets m ON m.id = v.market_id
+         WHERE v.market_id = ? AND v.resolved AND v.winning_outcome IS NOT NULL"
+    )
+    .bind(&market.id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let (status, winning_outcome, resolved_at) = match confirmed {
+        Some((winning_outcome, resolved_at)) => (
+            market.status.filter(|status| {
+                matches!(status, MarketStatus::Resolved | MarketStatus::Archived)
+            }),
+            Some(winning_outcome),
+            resolved_at.or(market.resolved_at),
+        ),
+        None => (market.status, market.winning_outcome.clone(), market.resolved_at),
+    };
+
+    if let Some(status) = status {
+        sqlx::query(
+            "INSERT INTO market_changes (market_id, field, old_value, new_value, changed_at)
+             SELECT id, 'status', status, ?, ? FROM markets WHERE id = ? AND status != ?"
+        )
+        .bind(status)
+        .bind(now)
+        .bind(&market.id)
+        .bind(status)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE markets SET
+            status = COALESCE(?, status),
+            winning_outcome = ?,
+            resolved_at = ?,
+            updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(status)
+    .bind(&winning_outcome)
+    .bind(resolved_at)
+    .bind(now)
+    .bind(&market.id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Register a webhook
+pub async fn insert_webhook(pool: &Pool<Sqlite>, url: &str, secret: Option<&str>) -> Result<Webhook> {
+    let webhook = sqlx::query_as::<_, Webhook>(
+        "INSERT INTO webhooks (url, secret, created_at) VALUES (?, ?, ?)
+         RETURNING id, url, secret, created_at"
+    )
+    .bind(url)
+    .bind(secret)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(webhook)
+}
+
+/// Get all registered webhooks
+pub async fn get_webhooks(pool: &Pool<Sqlite>) -> Result<Vec<Webhook>> {
+    let webhooks = sqlx::query_as::<_, Webhook>(
+        "SELECT id, url, secret, created_at FROM webhooks ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(webhooks)
+}
+
+/// Delete a webhook
+/// Returns true if a webhook was deleted
+/// Its outbox delivery state goes with it, so events it never acknowledged don't hold up
+/// outbox retention
+pub async fn delete_webhook(pool: &Pool<Sqlite>, id: i64) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM outbox_deliveries WHERE sink = ?")
+        .bind(format!("Webhook:{}", id))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Decode an `alert_rules` row, whose condition is stored as JSON
+fn alert_rule_from_row(row: SqliteRow) -> Result<AlertRule> {
+    let condition: String = row.try_get("condition")?;
+    Ok(AlertRule {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        condition: serde_json::from_str::<AlertCondition>(&condition).map_err(Error::storage)?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+/// Create an alert rule
+/// Returns None if a rule with the same name already exists
+pub async fn insert_alert_rule(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    condition: &AlertCondition,
+) -> Result<Option<AlertRule>> {
+    let row = sqlx::query(
+        "INSERT INTO alert_rules (name, condition, created_at) VALUES (?, ?, ?)
+         ON CONFLICT(name) DO NOTHING
+         RETURNING id, name, condition, created_at"
+    )
+    .bind(name)
+    .bind(serde_json::to_string(condition).map_err(Error::storage)?)
+    .bind(Utc::now())
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(alert_rule_from_row).transpose()
+}
+
+/// Create an alert rule, or replace the condition of the rule with the same name
+pub async fn upsert_alert_rule(pool: &Pool<Sqlite>, name: &str, condition: &AlertCondition) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO alert_rules (name, condition, created_at) VALUES (?, ?, ?)
+         ON CONFLICT(name) DO UPDATE SET condition = excluded.condition"
+    )
+    .bind(name)
+    .bind(serde_json::to_string(condition).map_err(Error::storage)?)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get all alert rules
+pub async fn get_alert_rules(pool: &Pool<Sqlite>) -> Result<Vec<AlertRule>> {
+    let rows = sqlx::query("SELECT id, name, condition, created_at FROM alert_rules ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+
+    rows.into_iter().map(alert_rule_from_row).collect()
+}
+
+/// Delete an alert rule; the alerts it fired are kept
+/// Returns true if a rule was deleted
+pub async fn delete_alert_rule(pool: &Pool<Sqlite>, id: i64) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM alert_rules WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record a match of an alert rule
+pub async fn insert_alert_fired(
+    pool: &Pool<Sqlite>,
+    rule_id: i64,
+    market_id: &str,
+    message: &str,
+) -> Result<AlertFired> {
+    let fired = sqlx::query_as::<_, AlertFired>(
+        "INSERT INTO alerts_fired (rule_id, market_id, message, fired_at) VALUES (?, ?, ?, ?)
+         RETURNING id, rule_id, market_id, message, fired_at"
+    )
+    .bind(rule_id)
+    .bind(market_id)
+    .bind(message)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(fired)
+}
+
+/// Get the most recently fired alerts, newest first
+pub async fn get_alerts_fired(pool: &Pool<Sqlite>, limit: u32) -> Result<Vec<AlertFired>> {
+    let fired = sqlx::query_as::<_, AlertFired>(
+        "SELECT id, rule_id, market_id, message, fired_at FROM alerts_fired
+         ORDER BY fired_at DESC, id DESC
+         LIMIT ?"
+    )
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(fired)
+}
+
+/// When a rule last fired for a market, if ever
+pub async fn get_last_alert_fired_at(
+    pool: &Pool<Sqlite>,
+    rule_id: i64,
+    market_id: &str,
+) -> Result<Option<DateTime<Utc>>> {
+    let fired_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "SELECT fired_at FROM alerts_fired WHERE rule_id = ? AND market_id = ?
+         ORDER BY fired_at DESC
+         LIMIT 1"
+    )
+    .bind(rule_id)
+    .bind(market_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(fired_at)
+}
+
+/// Oldest recorded price of a market at or after `since`
+pub async fn get_first_price_since(
+    pool: &Pool<Sqlite>,
+    market_id: &str,
+    since: DateTime<Utc>,
+) -> Result<Option<f64>> {
+    let price = sqlx::query_scalar::<_, f64>(
+        "SELECT price FROM price_history
+         WHERE market_id = ? AND scraped_at >= ? AND price IS NOT NULL
+         ORDER BY scraped_at
+         LIMIT 1"
+    )
+    .bind(market_id)
+    .bind(since)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(price)
+}
+
+/// Add markets to a watchlist, keeping their order
+async fn insert_watchlist_markets(
+    tx: &mut Transaction<'_, Sqlite>,
+    watchlist_id: i64,
+    market_ids: &[String],
+) -> Result<()> {
+    if market_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "INSERT OR IGNORE INTO watchlist_markets (watchlist_id, market_id, position) ",
+    );
+    qb.push_values(market_ids.iter().enumerate(), |mut row, (position, market_id)| {
+        row.push_bind(watchlist_id).push_bind(market_id).push_bind(position as i64);
+    });
+    qb.build().execute(&mut **tx).await?;
+    Ok(())
+}
+
+/// Market IDs of a watchlist, in order
+async fn get_watchlist_market_ids(pool: &Pool<Sqlite>, watchlist_id: i64) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT market_id FROM watchlist_markets WHERE watchlist_id = ? ORDER BY position"
+    )
+    .bind(watchlist_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Create a watchlist
+/// Returns None if a watchlist with the same name already exists
+pub async fn create_watchlist(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    market_ids: &[String],
+) -> Result<Option<Watchlist>> {
+    let mut tx = pool.begin().await?;
+
+    let created = sqlx::query_as::<_, (i64, DateTime<Utc>)>(
+        "INSERT INTO watchlists (name, created_at) VALUES (?, ?)
+         ON CONFLICT(name) DO NOTHING
+         RETURNING id, created_at"
+    )
+    .bind(name)
+    .bind(Utc::now())
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((id, created_at)) = created else {
+        return Ok(None);
+    };
+    insert_watchlist_markets(&mut tx, id, market_ids).await?;
+
+    tx.commit().await?;
+    Ok(Some(Watchlist {
+        name: name.to_string(),
+        market_ids: market_ids.to_vec(),
+        created_at,
+    }))
+}
+
+/// Get all watchlists, by name
+pub async fn get_watchlists(pool: &Pool<Sqlite>) -> Result<Vec<Watchlist>> {
+    let rows = sqlx::query_as::<_, (i64, String, DateTime<Utc>)>(
+        "SELECT id, name, created_at FROM watchlists ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut watchlists = Vec::with_capacity(rows.len());
+    for (id, name, created_at) in rows {
+        watchlists.push(Watchlist {
+            name,
+            market_ids: get_watchlist_market_ids(pool, id).await?,
+            created_at,
+        });
+    }
+    Ok(watchlists)
+}
+
+/// Get a watchlist by name
+pub async fn get_watchlist(pool: &Pool<Sqlite>, name: &str) -> Result<Option<Watchlist>> {
+    let row = sqlx::query_as::<_, (i64, DateTime<Utc>)>(
+        "SELECT id, created_at FROM watchlists WHERE name = ?"
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+    let Some((id, created_at)) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(Watchlist {
+        name: name.to_string(),
+        market_ids: get_watchlist_market_ids(pool, id).await?,
+        created_at,
+    }))
+}
+
+/// Replace the markets of a watchlist
+/// Returns the updated watchlist, or None if there is no watchlist with this name
+pub async fn set_watchlist_markets(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    market_ids: &[String],
+) -> Result<Option<Watchlist>> {
+    let mut tx = pool.begin().await?;
+
+    let row = sqlx::query_as::<_, (i64, DateTime<Utc>)>(
+        "SELECT id, created_at FROM watchlists WHERE name = ?"
+    )
+    .bind(name)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((id, created_at)) = row else {
+        return Ok(None);
+    };
+    sqlx::query("DELETE FROM watchlist_markets WHERE watchlist_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    insert_watchlist_markets(&mut tx, id, market_ids).await?;
+
+    tx.commit().await?;
+    Ok(Some(Watchlist {
+        name: name.to_string(),
+        market_ids: market_ids.to_vec(),
+        created_at,
+    }))
+}
+
+/// Delete a watchlist with its market list
+/// Returns true if a watchlist was deleted
+pub async fn delete_watchlist(pool: &Pool<Sqlite>, name: &str) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM watchlist_markets
+         WHERE watchlist_id IN (SELECT id FROM watchlists WHERE name = ?)"
+    )
+    .bind(name)
+    .execute(&mut *tx)
+    .await?;
+    let result = sqlx::query("DELETE FROM watchlists WHERE name = ?")
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// IDs of the highest-volume active markets of `source` with at least `min_volume`
+pub async fn get_top_volume_market_ids(
+    pool: &Pool<Sqlite>,
+    source: &str,
+    min_volume: f64,
+    limit: u32,
+) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT id FROM markets
+         WHERE status = 'active' AND source = ? AND volume >= ?
+         ORDER BY volume DESC
+         LIMIT ?"
+    )
+    .bind(source)
+    .bind(min_volume)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// IDs on any watchlist that belong to `source`, or aren't stored yet
+pub async fn get_watchlisted_market_ids(pool: &Pool<Sqlite>, source: &str) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT wm.market_id FROM watchlist_markets wm
+         LEFT JOIN markets m ON m.id = wm.market_id
+         WHERE m.id IS NULL OR m.source = ?
+         ORDER BY wm.market_id"
+    )
+    .bind(source)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Active markets whose volume since `window_start` is at least `multiplier` times their
+/// average per window over the baseline before it, skipping markets already flagged since
+/// `window_start`; biggest multiples first
+/// Markets whose history doesn't reach back to within a window of `baseline_start` are
+/// skipped, as their baseline would be understated
+pub async fn find_volume_spikes(
+    pool: &Pool<Sqlite>,
+    baseline_start: DateTime<Utc>,
+    window_start: DateTime<Utc>,
+    detected_at: DateTime<Utc>,
+    multiplier: f64,
+    min_volume: f64,
+) -> Result<Vec<VolumeSpike>> {
+    let window_secs = (detected_at - window_start).num_seconds() as f64;
+    let baseline_secs = (window_start - baseline_start).num_seconds() as f64;
+    if window_secs <= 0.0 || baseline_secs <= 0.0 {
+        return Ok(Vec::new());
+    }
+
+    // Volume is cumulative, so the latest value before the window is its starting volume
+    let spikes = sqlx::query_as::<_, VolumeSpike>(&format!(
+        "WITH recent AS (
+            SELECT market_id, MAX(volume) AS latest_volume
+            FROM price_history
+            WHERE scraped_at >= ? AND scraped_at <= ? AND volume IS NOT NULL
+            GROUP BY market_id
+        ),
+        baseline AS (
+            SELECT market_id, MAX(volume) - MIN(volume) AS baseline_total,
+                MAX(volume) AS window_start_volume
+            FROM price_history
+            WHERE scraped_at >= ? AND scraped_at < ? AND volume IS NOT NULL
+            GROUP BY market_id
+            HAVING MIN(scraped_at) < ?
+        ),
+        spikes AS (
+            SELECT recent.market_id,
+                recent.latest_volume - baseline.window_start_volume AS interval_volume,
+                baseline.baseline_total * ? AS baseline_volume
+            FROM recent
+            JOIN baseline ON baseline.market_id = recent.market_id
+        )
+        SELECT {}, ? AS detected_at, interval_volume, baseline_volume,
+            interval_volume / baseline_volume AS ratio
+        FROM spikes
+        JOIN markets ON markets.id = spikes.market_id
+        WHERE status = 'active'
+          AND baseline_volume > 0
+          AND interval_volume >= ?
+          AND interval_volume >= baseline_volume * ?
+          AND NOT EXISTS (
+              SELECT 1 FROM volume_spikes
+              WHERE volume_spikes.market_id = spikes.market_id AND volume_spikes.detected_at >= ?
+          )
+        ORDER BY ratio DESC",
+        MARKET_COLUMNS
+    ))
+    .bind(window_start)
+    .bind(detected_at)
+    .bind(baseline_start)
+    .bind(window_start)
+    .bind(baseline_start + (detected_at - window_start))
+    .bind(window_secs / baseline_secs)
+    .bind(detected_at)
+    .bind(min_volume)
+    .bind(multiplier)
+    .bind(window_start)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(spikes)
+}
+
+/// Record a detected volume spike
+pub async fn insert_volume_spike(pool: &Pool<Sqlite>, spike: &VolumeSpike) -> Result<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO volume_spikes
+            (market_id, detected_at, interval_volume, baseline_volume, ratio)
+         VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(&spike.market.id)
+    .bind(spike.detected_at)
+    .bind(spike.interval_volume)
+    .bind(spike.baseline_volume)
+    .bind(spike.ratio)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Volume spikes detected since `since` with their markets, newest first
+pub async fn get_volume_spikes(
+    pool: &Pool<Sqlite>,
+    since: DateTime<Utc>,
+    limit: u32,
+) -> Result<Vec<VolumeSpike>> {
+    let spikes = sqlx::query_as::<_, VolumeSpike>(&format!(
+        "SELECT {}, detected_at, interval_volume, baseline_volume, ratio
+         FROM volume_spikes
+         JOIN markets ON markets.id = volume_spikes.market_id
+         WHERE detected_at >= ?
+         ORDER BY detected_at DESC, ratio DESC
+         LIMIT ?",
+        MARKET_COLUMNS
+    ))
+    .bind(since)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(spikes)
+}
+
+/// Record a webhook delivery that failed after all retries
+pub async fn insert_webhook_dead_letter(
+    pool: &Pool<Sqlite>,
+    webhook: &Webhook,
+    payload: &str,
+    error: &str,
+    attempts: u32,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO webhook_dead_letters (webhook_id, url, payload, error, attempts, failed_at)
+         VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(webhook.id)
+    .bind(&webhook.url)
+    .bind(payload)
+    .bind(error)
+    .bind(attempts as i64)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the most recent failed webhook deliveries
+pub async fn get_webhook_dead_letters(pool: &Pool<Sqlite>, limit: u32) -> Result<Vec<WebhookDeadLetter>> {
+    let dead_letters = sqlx::query_as::<_, WebhookDeadLetter>(
+        "SELECT id, webhook_id, url, payload, error, attempts, failed_at
+         FROM webhook_dead_letters
+         ORDER BY failed_at DESC
+         LIMIT ?"
+    )
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(dead_letters)
+}
+
+/// Fill in the description and CLOB token of a market from its detail record
+/// The content hash is left alone, so an unchanged list payload doesn't clear them again
+pub async fn update_market_details(pool: &Pool<Sqlite>, market: &Market) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE markets SET
+            description = COALESCE(?, description),
+            clob_token_id = COALESCE(?, clob_token_id)
+        WHERE id = ?
+        "#,
+    )
+    .bind(&market.description)
+    .bind(&market.clob_token_id)
+    .bind(&market.id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Store CLOB orderbook prices for a market
+pub async fn update_market_quote(pool: &Pool<Sqlite>, id: &str, quote: &ClobQuote) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE markets SET
+            best_bid = ?,
+            best_ask = ?,
+            midpoint = ?,
+            spread = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(decimal_column(quote.best_bid))
+    .bind(decimal_column(quote.best_ask))
+    .bind(decimal_column(quote.midpoint))
+    .bind(decimal_column(quote.spread))
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get (market ID, CLOB token ID) pairs for all markets with a known token
+#[cfg(feature = "clob-ws")]
+pub async fn get_clob_token_ids(pool: &Pool<Sqlite>) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT id, clob_token_id FROM markets WHERE clob_token_id IS NOT NULL"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Queue a message for the Kafka outbox relay
+#[cfg(feature = "kafka")]
+pub async fn insert_kafka_outbox(
+    pool: &Pool<Sqlite>,
+    topic: &str,
+    message_key: &str,
+    payload: &str,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO kafka_outbox (topic, message_key, payload, created_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(topic)
+    .bind(message_key)
+    .bind(payload)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the oldest unpublished Kafka messages
+#[cfg(feature = "kafka")]
+pub async fn get_kafka_outbox(pool: &Pool<Sqlite>, limit: u32) -> Result<Vec<KafkaOutboxMessage>> {
+    let messages = sqlx::query_as::<_, KafkaOutboxMessage>(
+        "SELECT id, topic, message_key, payload FROM kafka_outbox ORDER BY id LIMIT ?"
+    )
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(messages)
+}
+
+/// Remove a Kafka message once the broker has acknowledged it
+#[cfg(feature = "kafka")]
+pub async fn delete_kafka_outbox(pool: &Pool<Sqlite>, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM kafka_outbox WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Queue a notification in the outbox as part of the transaction that produced it
+/// The dedup key names the market write, so a redelivered event carries the same key
+async fn insert_outbox(
+    tx: &mut Transaction<'_, Sqlite>,
+    update: &MarketUpdate,
+    created_at: DateTime<Utc>,
+) -> Result<()> {
+    let market_id = &update.market().id;
+    let dedup_key = format!(
+        "{}:{}:{}",
+        update.kind(),
+        market_id,
+        created_at.timestamp_micros()
+    );
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO outbox (dedup_key, market_id, payload, created_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(dedup_key)
+    .bind(market_id)
+    .bind(serde_json::to_string(update).map_err(Error::storage)?)
+    .bind(created_at)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Oldest outbox events that `sink` has neither acknowledged nor dead-lettered
+pub async fn get_pending_outbox(
+    pool: &Pool<Sqlite>,
+    sink: &str,
+    limit: u32,
+) -> Result<Vec<OutboxEvent>> {
+    let events = sqlx::query_as::<_, OutboxEvent>(
+        "SELECT o.id, o.dedup_key, o.payload FROM outbox o
+         LEFT JOIN outbox_deliveries d ON d.outbox_id = o.id AND d.sink = ?
+         WHERE d.outbox_id IS NULL OR (d.delivered_at IS NULL AND d.dead_lettered_at IS NULL)
+         ORDER BY o.id
+         LIMIT ?"
+    )
+    .bind(sink)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+/// Mark an outbox event as delivered to `sink`
+pub async fn record_outbox_delivery(
+    pool: &Pool<Sqlite>,
+    outbox_id: i64,
+    sink: &str,
+    delivered_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO outbox_deliveries (outbox_id, sink, attempts, delivered_at) VALUES (?, ?, 1, ?)
+         ON CONFLICT(outbox_id, sink) DO UPDATE SET
+             attempts = outbox_deliveries.attempts + 1,
+             delivered_at = excluded.delivered_at"
+    )
+    .bind(outbox_id)
+    .bind(sink)
+    .bind(delivered_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Count a failed delivery of an outbox event to `sink`
+/// Returns how many times delivering the event to `sink` has failed
+pub async fn record_outbox_failure(
+    pool: &Pool<Sqlite>,
+    outbox_id: i64,
+    sink: &str,
+    error: &str,
+) -> Result<u32> {
+    let attempts = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO outbox_deliveries (outbox_id, sink, attempts, last_error) VALUES (?, ?, 1, ?)
+         ON CONFLICT(outbox_id, sink) DO UPDATE SET
+             attempts = outbox_deliveries.attempts + 1,
+             last_error = excluded.last_error
+         RETURNING attempts"
+    )
+    .bind(outbox_id)
+    .bind(sink)
+    .bind(error)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(attempts as u32)
+}
+
+/// Give up on delivering an outbox event to `sink`, so it no longer holds up later events
+pub async fn dead_letter_outbox(
+    pool: &Pool<Sqlite>,
+    outbox_id: i64,
+    sink: &str,
+    error: &str,
+    dead_lettered_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO outbox_deliveries (outbox_id, sink, attempts, last_error, dead_lettered_at)
+         VALUES (?, ?, 1, ?, ?)
+         ON CONFLICT(outbox_id, sink) DO UPDATE SET
+             attempts = outbox_deliveries.attempts + 1,
+             last_error = excluded.last_error,
+             dead_lettered_at = excluded.dead_lettered_at"
+    )
+    .bind(outbox_id)
+    .bind(sink)
+    .bind(error)
+    .bind(dead_lettered_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Sinks that acknowledged or dead-lettered an outbox event
+pub async fn get_settled_outbox_sinks(pool: &Pool<Sqlite>, outbox_id: i64) -> Result<Vec<String>> {
+    let sinks = sqlx::query_scalar::<_, String>(
+        "SELECT sink FROM outbox_deliveries
+         WHERE outbox_id = ? AND (delivered_at IS NOT NULL OR dead_lettered_at IS NOT NULL)"
+    )
+    .bind(outbox_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sinks)
+}
+
+/// Delete outbox events created before `before` together with their delivery state
+/// Events from the oldest one a sink is still retrying on are kept, so a sink that is down
+/// for longer than the retention window still gets every event once it recovers. With
+/// `sinks`, only those and the registered webhooks count, so a sink removed from the
+/// configuration doesn't hold up retention forever; without, every sink does.
+/// Returns the number of events deleted
+pub async fn delete_outbox_before(
+    pool: &Pool<Sqlite>,
+    before: DateTime<Utc>,
+    sinks: Option<&[String]>,
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+
+    let mut oldest = QueryBuilder::<Sqlite>::new(
+        "SELECT MIN(outbox_id) FROM outbox_deliveries
+         WHERE delivered_at IS NULL AND dead_lettered_at IS NULL",
+    );
+    if let Some(sinks) = sinks {
+        oldest.push(" AND (sink IN (SELECT 'Webhook:' || id FROM webhooks)");
+        if !sinks.is_empty() {
+            oldest.push(" OR sink IN (");
+            let mut separated = oldest.separated(", ");
+            for sink in sinks {
+                separated.push_bind(sink);
+            }
+            separated.push_unseparated(")");
+        }
+        oldest.push(")");
+    }
+    let oldest_pending = oldest
+        .build_query_scalar::<Option<i64>>()
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(i64::MAX);
+    sqlx::query(
+        "DELETE FROM outbox_deliveries
+         WHERE outbox_id IN (SELECT id FROM outbox WHERE created_at < ? AND id < ?)"
+    )
+    .bind(before)
+    .bind(oldest_pending)
+    .execute(&mut *tx)
+    .await?;
+    let result = sqlx::query("DELETE FROM outbox WHERE created_at < ? AND id < ?")
+        .bind(before)
+        .bind(oldest_pending)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// Scraper checkpoint stored under `key`
+pub async fn get_scraper_state(pool: &Pool<Sqlite>, key: &str) -> Result<Option<String>> {
+    let value = sqlx::query_scalar::<_, String>("SELECT value FROM scraper_state WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(value)
+}
+
+/// Store a scraper checkpoint so a restarted scraper can resume from it
+pub async fn set_scraper_state(pool: &Pool<Sqlite>, key: &str, value: &str) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO scraper_state (key, value, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at"
+    )
+    .bind(key)
+    .bind(value)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Take lease `name` for `holder` until `now + ttl`, or extend it if `holder` already has it;
+/// fails while another holder's lease hasn't expired. Returns whether `holder` holds the lease
+pub async fn try_acquire_lease(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    holder: &str,
+    now: DateTime<Utc>,
+    ttl: chrono::Duration,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO leader_leases (name, holder, acquired_at, expires_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(name) DO UPDATE SET
+            holder = excluded.holder,
+            acquired_at = CASE WHEN leader_leases.holder = excluded.holder
+                THEN leader_leases.acquired_at ELSE excluded.acquired_at END,
+            expires_at = excluded.expires_at
+         WHERE leader_leases.holder = excluded.holder OR leader_leases.expires_at <= excluded.acquired_at"
+    )
+    .bind(name)
+    .bind(holder)
+    .bind(now)
+    .bind(now + ttl)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Give up lease `name` if `holder` has it, so another replica can take it right away
+pub async fn release_lease(pool: &Pool<Sqlite>, name: &str, holder: &str) -> Result<()> {
+    sqlx::query("DELETE FROM leader_leases WHERE name = ? AND holder = ?")
+        .bind(name)
+        .bind(holder)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Record the progress of a shard reported by its holder; a missing `last_success_at` keeps
+/// the one reported before, e.g. by the previous holder
+pub async fn record_shard_progress(pool: &Pool<Sqlite>, progress: &ShardProgress) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO shard_progress (shard_count, shard_index, instance_id, last_success_at, updated_at)
+         VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(shard_count, shard_index) DO UPDATE SET
+            instance_id = excluded.instance_id,
+            last_success_at = COALESCE(excluded.last_success_at, shard_progress.last_success_at),
+            updated_at = excluded.updated_at"
+    )
+    .bind(progress.shard_count)
+    .bind(progress.shard_index)
+    .bind(&progress.instance_id)
+    .bind(progress.last_success_at)
+    .bind(progress.updated_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Progress of every shard of the most recently reported shard count, by shard index;
+/// shards no instance has claimed yet are listed without a holder
+pub async fn get_shard_progress(pool: &Pool<Sqlite>) -> Result<Vec<ShardProgress>> {
+    let reported = sqlx::query_as::<_, ShardProgress>(
+        "SELECT shard_index, shard_count, instance_id, last_success_at, updated_at
+         FROM shard_progress
+         WHERE shard_count = (SELECT shard_count FROM shard_progress ORDER BY updated_at DESC LIMIT 1)
+         ORDER BY shard_index"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let Some(shard_count) = reported.first().map(|shard| shard.shard_count) else {
+        return Ok(reported);
+    };
+    let mut reported = reported.into_iter().peekable();
+    let shards = (0..shard_count)
+        .map(|shard_index| {
+            reported
+                .next_if(|shard| shard.shard_index == shard_index)
+                .unwrap_or(ShardProgress {
+                    shard_index,
+                    shard_count,
+                    instance_id: None,
+                    last_success_at: None,
+                    updated_at: None,
+                    lag_secs: None,
+                })
+        })
+        .collect();
+
+    Ok(shards)
+}
+
+/// Cumulative counters saved by the last metrics snapshot; all zero before the first one
+pub async fn get_metrics_snapshot(pool: &Pool<Sqlite>) -> Result<MetricsSnapshot> {
+    let rows = sqlx::query_as::<_, (String, i64)>("SELECT name, value FROM metrics_snapshot")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(MetricsSnapshot::from_counters(
+        rows.into_iter().map(|(name, value)| (name, value.max(0) as u64)),
+    ))
+}
+
+/// Overwrite the saved cumulative counters with the current ones
+pub async fn save_metrics_snapshot(pool: &Pool<Sqlite>, snapshot: &MetricsSnapshot) -> Result<()> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    for (name, value) in snapshot.counters() {
+        sqlx::query(
+            "INSERT INTO metrics_snapshot (name, value, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(name) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at"
+        )
+        .bind(name)
+        .bind(value as i64)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Time up to which a snapshot stream has been exported
+#[cfg(feature = "s3")]
+pub async fn get_snapshot_watermark(pool: &Pool<Sqlite>, name: &str) -> Result<Option<DateTime<Utc>>> {
+    let exported_until = sqlx::query_scalar::<_, DateTime<Utc>>(
+        "SELECT exported_until FROM snapshot_watermarks WHERE name = ?"
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(exported_until)
+}
+
+/// Record that a snapshot stream has been exported up to `exported_until`
+#[cfg(feature = "s3")]
+pub async fn set_snapshot_watermark(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    exported_until: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO snapshot_watermarks (name, exported_until) VALUES (?, ?)
+         ON CONFLICT(name) DO UPDATE SET exported_until = excluded.exported_until"
+    )
+    .bind(name)
+    .bind(exported_until)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
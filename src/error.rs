@@ -0,0 +1,261 @@
+use reqwest::StatusCode;
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::throttle;
+use crate::validation::RejectReason;
+
+/// Result of scraper and database operations
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Failure of a scrape or database operation, by what went wrong
+/// Each variant keeps the underlying error with its context and displays it unchanged
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Upstream unreachable, timed out or answering with an error status
+    #[error(transparent)]
+    UpstreamHttp(anyhow::Error),
+    /// Upstream answered with a body we can't read
+    #[error(transparent)]
+    UpstreamSchema(anyhow::Error),
+    /// Upstream kept throttling us after the client backed off
+    #[error(transparent)]
+    RateLimited(anyhow::Error),
+    /// Failed database read or write
+    #[error(transparent)]
+    Storage(anyhow::Error),
+    /// Invalid configuration, such as a bad proxy URL or database path
+    #[error(transparent)]
+    Config(anyhow::Error),
+}
+
+/// What kind of failure an `Error` is, for retries, the circuit breaker and metrics to
+/// branch on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UpstreamHttp,
+    UpstreamSchema,
+    RateLimited,
+    Storage,
+    Config,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::UpstreamHttp => "upstream_http",
+            ErrorKind::UpstreamSchema => "upstream_schema",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Storage => "storage",
+            ErrorKind::Config => "config",
+        }
+    }
+
+    /// Whether trying the same operation again may succeed
+    /// A payload we can't read or a bad configuration fails the same way every time
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::UpstreamHttp | ErrorKind::RateLimited | ErrorKind::Storage
+        )
+    }
+
+    /// Whether upstream is to blame, as opposed to our database or configuration
+    pub fn is_upstream(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::UpstreamHttp | ErrorKind::UpstreamSchema | ErrorKind::RateLimited
+        )
+    }
+
+    /// Kind of the first error in the chain we know how to classify
+    pub fn of(error: &(dyn StdError + 'static)) -> Option<Self> {
+        causes(error).find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<Error>() {
+                Some(e.kind())
+            } else if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                Some(match e.status() {
+                    Some(status) if throttle::is_throttling(status) => ErrorKind::RateLimited,
+                    _ if e.is_decode() => ErrorKind::UpstreamSchema,
+                    _ => ErrorKind::UpstreamHttp,
+                })
+            } else if cause.is::<serde_json::Error>() || cause.is::<RejectReason>() {
+                Some(ErrorKind::UpstreamSchema)
+            } else if cause.is::<sqlx::Error>() || cause.is::<sqlx::migrate::MigrateError>() {
+                Some(ErrorKind::Storage)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error {
+    fn new(kind: ErrorKind, error: anyhow::Error) -> Self {
+        match kind {
+            ErrorKind::UpstreamHttp => Error::UpstreamHttp(error),
+            ErrorKind::UpstreamSchema => Error::UpstreamSchema(error),
+            ErrorKind::RateLimited => Error::RateLimited(error),
+            ErrorKind::Storage => Error::Storage(error),
+            ErrorKind::Config => Error::Config(error),
+        }
+    }
+
+    /// A failed database operation whose error doesn't come from sqlx, such as a stored
+    /// column that doesn't deserialize
+    pub(crate) fn storage(error: impl Into<anyhow::Error>) -> Self {
+        Error::Storage(error.into())
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::UpstreamHttp(_) => ErrorKind::UpstreamHttp,
+            Error::UpstreamSchema(_) => ErrorKind::UpstreamSchema,
+            Error::RateLimited(_) => ErrorKind::RateLimited,
+            Error::Storage(_) => ErrorKind::Storage,
+            Error::Config(_) => ErrorKind::Config,
+        }
+    }
+
+    /// Status upstream answered with, when the failure was an error status
+    pub fn status(&self) -> Option<StatusCode> {
+        status(self)
+    }
+
+    fn inner(&self) -> &anyhow::Error {
+        match self {
+            Error::UpstreamHttp(e)
+            | Error::UpstreamSchema(e)
+            | Error::RateLimited(e)
+            | Error::Storage(e)
+            | Error::Config(e) => e,
+        }
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(error: sqlx::Error) -> Self {
+        Error::Storage(error.into())
+    }
+}
+
+impl From<sqlx::migrate::MigrateError> for Error {
+    fn from(error: sqlx::migrate::MigrateError) -> Self {
+        Error::Storage(error.into())
+    }
+}
+
+impl From<RejectReason> for Error {
+    fn from(reason: RejectReason) -> Self {
+        Error::UpstreamSchema(reason.into())
+    }
+}
+
+/// Classify a failed upstream call where it happens, adding context like `anyhow::Context`
+pub(crate) trait UpstreamContext<T> {
+    /// The request failed or upstream answered with an error status; a throttling status
+    /// is `RateLimited`
+    fn http_context<C>(self, context: C) -> Result<T>
+    where
+        C: fmt::Display + Send + Sync + 'static;
+
+    /// Upstream answered with a body we can't read
+    fn schema_context<C>(self, context: C) -> Result<T>
+    where
+        C: fmt::Display + Send + Sync + 'static;
+}
+
+impl<T, E: Into<anyhow::Error>> UpstreamContext<T> for std::result::Result<T, E> {
+    fn http_context<C>(self, context: C) -> Result<T>
+    where
+        C: fmt::Display + Send + Sync + 'static,
+    {
+        self.map_err(|error| {
+            let error = error.into().context(context);
+            let kind = ErrorKind::of(&*error)
+                .filter(|kind| kind.is_upstream())
+                .unwrap_or(ErrorKind::UpstreamHttp);
+            Error::new(kind, error)
+        })
+    }
+
+    fn schema_context<C>(self, context: C) -> Result<T>
+    where
+        C: fmt::Display + Send + Sync + 'static,
+    {
+        self.map_err(|error| Error::UpstreamSchema(error.into().context(context)))
+    }
+}
+
+/// Every error in the chain of `error`, looking through the variants of `Error` that only
+/// forward to the error they wrap
+pub(crate) fn causes<'a>(
+    error: &'a (dyn StdError + 'static),
+) -> impl Iterator<Item = &'a (dyn StdError + 'static)> {
+    std::iter::successors(Some(error), |&cause| match cause.downcast_ref::<Error>() {
+        Some(error) => {
+            let inner: &(dyn StdError + 'static) = &**error.inner();
+            Some(inner)
+        }
+        None => cause.source(),
+    })
+}
+
+/// Status of the first failed upstream response in the chain of `error`
+pub(crate) fn status(error: &(dyn StdError + 'static)) -> Option<StatusCode> {
+    causes(error).find_map(|cause| cause.downcast_ref::<reqwest::Error>()?.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kinds() {
+        let schema = serde_json::from_str::<u32>("{").schema_context("Failed to parse markets");
+        assert_eq!(schema.unwrap_err().kind(), ErrorKind::UpstreamSchema);
+        assert_eq!(
+            Error::from(sqlx::Error::PoolTimedOut).kind(),
+            ErrorKind::Storage
+        );
+        assert_eq!(
+            Error::from(RejectReason::MissingId).kind(),
+            ErrorKind::UpstreamSchema
+        );
+        // Failures that carry no kind of their own are taken for what the call site says
+        let unknown = Err::<(), _>(anyhow::anyhow!("unknown")).http_context("Failed to fetch");
+        assert_eq!(unknown.unwrap_err().kind(), ErrorKind::UpstreamHttp);
+        assert_eq!(ErrorKind::of(&*anyhow::anyhow!("unknown")), None);
+
+        assert!(ErrorKind::RateLimited.is_retryable());
+        assert!(ErrorKind::Storage.is_retryable());
+        assert!(!ErrorKind::UpstreamSchema.is_retryable());
+        assert!(!ErrorKind::Config.is_retryable());
+        assert!(ErrorKind::UpstreamSchema.is_upstream());
+        assert!(!ErrorKind::Storage.is_upstream());
+    }
+
+    #[test]
+    fn test_error_kind_survives_context() {
+        let storage = Error::from(sqlx::Error::RowNotFound);
+        let wrapped = anyhow::Error::from(storage).context("Failed to load alert rules");
+        assert_eq!(ErrorKind::of(&*wrapped), Some(ErrorKind::Storage));
+        assert!(causes(&*wrapped).any(|cause| cause.is::<sqlx::Error>()));
+        assert_eq!(status(&*wrapped), None);
+
+        // An upstream call classifies with the kind already in the chain
+        let config = anyhow::Error::from(Error::Config(anyhow::anyhow!("Invalid proxy #1")));
+        let error = Err::<(), _>(config).http_context("Failed to fetch").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UpstreamHttp);
+        assert_eq!(error.to_string(), "Failed to fetch");
+        let rate_limited = anyhow::Error::from(Error::RateLimited(anyhow::anyhow!("429")));
+        let error = Err::<(), _>(rate_limited).http_context("Failed to fetch").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::RateLimited);
+    }
+}
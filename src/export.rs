@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::models::{Market, PriceHistoryRow, PricePoint};
+
+pub const CSV_HEADER: &str =
+    "id,source,title,current_price,volume,volume_24hr,liquidity,open_interest,end_date,status,\
+     winning_outcome,event_id,discovered_at";
+pub const PRICE_HISTORY_CSV_HEADER: &str = "scraped_at,price,volume";
+const PRICE_HISTORY_EXPORT_CSV_HEADER: &str = "market_id,scraped_at,price,volume";
+
+/// Row type with a fixed CSV layout
+pub trait CsvRow {
+    const HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+}
+
+impl CsvRow for Market {
+    const HEADER: &'static str = CSV_HEADER;
+
+    fn csv_row(&self) -> String {
+        market_csv_row(self)
+    }
+}
+
+impl CsvRow for PriceHistoryRow {
+    const HEADER: &'static str = PRICE_HISTORY_EXPORT_CSV_HEADER;
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            escape_csv(&self.market_id),
+            self.scraped_at.to_rfc3339(),
+            optional(self.price),
+            optional(self.volume)
+        )
+    }
+}
+
+/// Write rows as a pretty-printed JSON array
+pub fn write_json<T: Serialize>(rows: &[T], out: &mut impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *out, rows).context("Failed to write JSON export")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Write rows as newline-delimited JSON, one object per line
+pub fn write_jsonl<T: Serialize>(rows: &[T], out: &mut impl Write) -> Result<()> {
+    for row in rows {
+        serde_json::to_writer(&mut *out, row).context("Failed to write JSON Lines export")?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write rows as CSV with a header row
+pub fn write_csv<T: CsvRow>(rows: &[T], out: &mut impl Write) -> Result<()> {
+    writeln!(out, "{}", T::HEADER)?;
+    for row in rows {
+        writeln!(out, "{}", row.csv_row())?;
+    }
+    Ok(())
+}
+
+/// Parquet output for the exported tables, Snappy-compressed
+#[cfg(feature = "parquet")]
+pub mod parquet {
+    use anyhow::Result;
+    use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray};
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use rust_decimal::prelude::ToPrimitive;
+    use ::parquet::arrow::ArrowWriter;
+    use ::parquet::basic::Compression;
+    use ::parquet::file::properties::WriterProperties;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use crate::models::{Market, MarketStatus, PriceHistoryRow};
+
+    fn timestamp_field(name: &str, nullable: bool) -> Field {
+        Field::new(
+            name,
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            nullable,
+        )
+    }
+
+    fn write_batch<W: Write + Send>(batch: RecordBatch, out: W) -> Result<()> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = ArrowWriter::try_new(out, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Write markets as a single Parquet row group
+    pub fn write_markets<W: Write + Send>(markets: &[Market], out: W) -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("source", DataType::Utf8, false),
+            Field::new("title", DataType::Utf8, false),
+            Field::new("current_price", DataType::Float64, true),
+            Field::new("volume", DataType::Float64, true),
+            Field::new("volume_24hr", DataType::Float64, true),
+            Field::new("liquidity", DataType::Float64, true),
+            Field::new("open_interest", DataType::Float64, true),
+            timestamp_field("end_date", true),
+            Field::new("status", DataType::Utf8, true),
+            Field::new("winning_outcome", DataType::Utf8, true),
+            Field::new("event_id", DataType::Utf8, true),
+            timestamp_field("discovered_at", true),
+        ]));
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(markets.iter().map(|m| &m.id))),
+            Arc::new(StringArray::from_iter_values(markets.iter().map(|m| &m.source))),
+            Arc::new(StringArray::from_iter_values(markets.iter().map(|m| &m.title))),
+            Arc::new(Float64Array::from_iter(
+                markets.iter().map(|m| m.current_price.and_then(|p| p.to_f64())),
+            )),
+            Arc::new(Float64Array::from_iter(
+                markets.iter().map(|m| m.volume.and_then(|v| v.to_f64())),
+            )),
+            Arc::new(Float64Array::from_iter(
+                markets.iter().map(|m| m.volume_24hr.and_then(|v| v.to_f64())),
+            )),
+            Arc::new(Float64Array::from_iter(
+                markets.iter().map(|m| m.liquidity.and_then(|v| v.to_f64())),
+            )),
+            Arc::new(Float64Array::from_iter(
+                markets.iter().map(|m| m.open_interest.and_then(|v| v.to_f64())),
+            )),
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    markets.iter().map(|m| m.end_date.map(|t| t.timestamp_millis())),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(StringArray::from_iter(
+                markets.iter().map(|m| m.status.map(MarketStatus::as_str)),
+            )),
+            Arc::new(StringArray::from_iter(
+                markets.iter().map(|m| m.winning_outcome.as_deref()),
+            )),
+            Arc::new(StringArray::from_iter(markets.iter().map(|m| m.event_id.as_deref()))),
+            Arc::new(
+                TimestampMillisecondArray::from_iter(
+                    markets
+                        .iter()
+                        .map(|m| m.discovered_at.map(|t| t.timestamp_millis())),
+                )
+                .with_timezone("UTC"),
+            ),
+        ];
+
+        write_batch(RecordBatch::try_new(schema, columns)?, out)
+    }
+
+    /// Write price history rows as a single Parquet row group
+    pub fn write_price_history<W: Write + Send>(rows: &[PriceHistoryRow], out: W) -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("market_id", DataType::Utf8, false),
+            timestamp_field("scraped_at", false),
+            Field::new("price", DataType::Float64, true),
+            Field::new("volume", DataType::Float64, true),
+        ]));
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(rows.iter().map(|r| &r.market_id))),
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    rows.iter().map(|r| r.scraped_at.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(Float64Array::from_iter(
+                rows.iter().map(|r| r.price.and_then(|p| p.to_f64())),
+            )),
+            Arc::new(Float64Array::from_iter(
+                rows.iter().map(|r| r.volume.and_then(|v| v.to_f64())),
+            )),
+        ];
+
+        write_batch(RecordBatch::try_new(schema, columns)?, out)
+    }
+}
+
+/// One CSV row (without line terminator) matching `CSV_HEADER`
+pub fn market_csv_row(market: &Market) -> String {
+    let fields = [
+        market.id.clone(),
+        market.source.clone(),
+        market.title.clone(),
+        optional(market.current_price),
+        optional(market.volume),
+        optional(market.volume_24hr),
+        optional(market.liquidity),
+        optional(market.open_interest),
+        market.end_date.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        optional(market.status),
+        market.winning_outcome.clone().unwrap_or_default(),
+        market.event_id.clone().unwrap_or_default(),
+        market
+            .discovered_at
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+    ];
+    let row: Vec<String> = fields.iter().map(|f| escape_csv(f)).collect();
+    row.join(",")
+}
+
+/// One CSV row (without line terminator) matching `PRICE_HISTORY_CSV_HEADER`
+pub fn price_point_csv_row(point: &PricePoint) -> String {
+    format!(
+        "{},{},{}",
+        point.scraped_at.to_rfc3339(),
+        optional(point.price),
+        optional(point.volume)
+    )
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quote a field if it contains a delimiter, quote or newline
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MarketStatus;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_write_csv_escapes_fields() {
+        let markets = vec![Market {
+            id: "1".to_string(),
+            source: "polymarket".to_string(),
+            title: "Will \"X\" happen, or not?".to_string(),
+            current_price: Some(dec!(0.42)),
+            status: Some(MarketStatus::Active),
+            ..Default::default()
+        }];
+
+        let mut out = Vec::new();
+        write_csv(&markets, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("1,polymarket,\"Will \"\"X\"\" happen, or not?\",0.42,,,,,,active,,,")
+        );
+    }
+
+    #[test]
+    fn test_write_jsonl_one_object_per_line() {
+        let markets = vec![
+            Market {
+                id: "1".to_string(),
+                ..Default::default()
+            },
+            Market {
+                id: "2".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_jsonl(&markets, &mut out).unwrap();
+        let jsonl = String::from_utf8(out).unwrap();
+        let ids: Vec<String> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str::<Market>(line).unwrap().id)
+            .collect();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+}
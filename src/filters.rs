@@ -0,0 +1,180 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use regex::Regex;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::metrics;
+use crate::models::{Market, ScrapedMarket};
+
+/// Rule of the ingest filter a market failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterRule {
+    Tag,
+    Title,
+    Volume,
+    EndDate,
+}
+
+impl FilterRule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilterRule::Tag => "tag",
+            FilterRule::Title => "title",
+            FilterRule::Volume => "volume",
+            FilterRule::EndDate => "end_date",
+        }
+    }
+}
+
+/// Which listed markets are stored; a market must pass every rule that is set, and an
+/// empty filter stores everything
+#[derive(Debug, Clone, Default)]
+pub struct IngestFilter {
+    /// Tag slugs of which a market needs at least one
+    pub include_tags: Vec<String>,
+    /// Tag slugs a market may have none of
+    pub exclude_tags: Vec<String>,
+    /// Pattern the title has to match
+    pub title_include: Option<Regex>,
+    /// Pattern the title must not match
+    pub title_exclude: Option<Regex>,
+    /// Volume a market needs; missing volumes count as zero
+    pub min_volume: Option<f64>,
+    /// Days within which a market has to end; markets without an end date fail
+    pub max_days_to_end: Option<u32>,
+}
+
+impl IngestFilter {
+    /// First rule `market` fails at `now`, if any
+    pub fn check(&self, market: &Market, now: DateTime<Utc>) -> Result<(), FilterRule> {
+        let has_tag = |slugs: &[String]| {
+            market
+                .tags
+                .iter()
+                .any(|tag| slugs.iter().any(|slug| slug.eq_ignore_ascii_case(&tag.slug)))
+        };
+        if !self.include_tags.is_empty() && !has_tag(&self.include_tags) {
+            return Err(FilterRule::Tag);
+        }
+        if has_tag(&self.exclude_tags) {
+            return Err(FilterRule::Tag);
+        }
+
+        if let Some(pattern) = &self.title_include {
+            if !pattern.is_match(&market.title) {
+                return Err(FilterRule::Title);
+            }
+        }
+        if let Some(pattern) = &self.title_exclude {
+            if pattern.is_match(&market.title) {
+                return Err(FilterRule::Title);
+            }
+        }
+
+        if let Some(min_volume) = self.min_volume {
+            let volume = market.volume.and_then(|v| v.to_f64()).unwrap_or(0.0);
+            if volume < min_volume {
+                return Err(FilterRule::Volume);
+            }
+        }
+
+        if let Some(days) = self.max_days_to_end {
+            let horizon = now + ChronoDuration::days(days as i64);
+            if market.end_date.is_none_or(|end| end > horizon) {
+                return Err(FilterRule::EndDate);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Split scraped markets into those to store and the IDs of those filtered out, counting
+    /// the latter by rule
+    pub fn apply(&self, scraped: Vec<ScrapedMarket>) -> (Vec<ScrapedMarket>, Vec<String>) {
+        let now = Utc::now();
+        let mut kept = Vec::with_capacity(scraped.len());
+        let mut filtered_ids = Vec::new();
+        for scraped_market in scraped {
+            match self.check(&scraped_market.market, now) {
+                Ok(()) => kept.push(scraped_market),
+                Err(rule) => {
+                    ::metrics::counter!(metrics::MARKETS_FILTERED_TOTAL, "rule" => rule.as_str())
+                        .increment(1);
+                    filtered_ids.push(scraped_market.market.id);
+                }
+            }
+        }
+        (kept, filtered_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Tag;
+    use rust_decimal_macros::dec;
+
+    fn market(title: &str, tags: &[&str]) -> Market {
+        Market {
+            id: "1".to_string(),
+            title: title.to_string(),
+            tags: tags.iter().map(|&label| Tag::from_label(label)).collect(),
+            volume: Some(dec!(5000)),
+            end_date: Some(Utc::now() + ChronoDuration::days(10)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_filter_keeps_everything() {
+        let filter = IngestFilter::default();
+        let mut untagged = market("Anything", &[]);
+        untagged.volume = None;
+        untagged.end_date = None;
+        assert_eq!(filter.check(&untagged, Utc::now()), Ok(()));
+    }
+
+    #[test]
+    fn test_filter_rules() {
+        let now = Utc::now();
+        let nba = market("Will the Lakers win?", &["Sports", "NBA"]);
+        let election = market("Will the incumbent win?", &["Politics"]);
+
+        let sports = IngestFilter {
+            include_tags: vec!["sports".to_string()],
+            exclude_tags: vec!["nfl".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(sports.check(&nba, now), Ok(()));
+        assert_eq!(sports.check(&election, now), Err(FilterRule::Tag));
+        let no_nba = IngestFilter {
+            exclude_tags: vec!["NBA".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(no_nba.check(&nba, now), Err(FilterRule::Tag));
+
+        let titles = IngestFilter {
+            title_include: Some(Regex::new("(?i)win").unwrap()),
+            title_exclude: Some(Regex::new("Lakers").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(titles.check(&election, now), Ok(()));
+        assert_eq!(titles.check(&nba, now), Err(FilterRule::Title));
+
+        let volume = IngestFilter {
+            min_volume: Some(10_000.0),
+            ..Default::default()
+        };
+        assert_eq!(volume.check(&nba, now), Err(FilterRule::Volume));
+
+        let horizon = IngestFilter {
+            max_days_to_end: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(horizon.check(&nba, now), Ok(()));
+        let this_week = IngestFilter {
+            max_days_to_end: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(this_week.check(&nba, now), Err(FilterRule::EndDate));
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, CONNECTION, CONTENT_LENGTH, SET_COOKIE, TRANSFER_ENCODING};
+use reqwest::{Response, ResponseBuilderExt, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const MAX_NAME_LEN: usize = 80; // Readable part of a fixture file name, before the URL hash
+
+/// Whether upstream responses are saved to or served from a fixture directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Send requests as usual and save every response
+    Record(PathBuf),
+    /// Serve saved responses instead of touching the network
+    Replay(PathBuf),
+}
+
+/// Upstream response as saved on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Fixture {
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+/// Upstream responses recorded to, or replayed from, one JSON file per request URL
+pub struct Fixtures {
+    mode: FixtureMode,
+}
+
+impl Fixtures {
+    pub fn new(mode: FixtureMode) -> Result<Self> {
+        match &mode {
+            FixtureMode::Record(dir) => {
+                fs::create_dir_all(dir).with_context(|| {
+                    format!("Failed to create fixture directory {}", dir.display())
+                })?;
+                info!("Recording upstream responses to {}", dir.display());
+            }
+            FixtureMode::Replay(dir) => {
+                anyhow::ensure!(
+                    dir.is_dir(),
+                    "Fixture directory {} does not exist",
+                    dir.display()
+                );
+                info!("Replaying upstream responses from {}", dir.display());
+            }
+        }
+        Ok(Self { mode })
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, FixtureMode::Replay(_))
+    }
+
+    fn dir(&self) -> &Path {
+        match &self.mode {
+            FixtureMode::Record(dir) | FixtureMode::Replay(dir) => dir,
+        }
+    }
+
+    /// Saved response to a request for the URL
+    pub fn replay(&self, url: &Url) -> Result<Response> {
+        let path = self.dir().join(fixture_name(url));
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("No fixture recorded for {} ({})", url, path.display()))?;
+        let fixture: Fixture = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse fixture {}", path.display()))?;
+        fixture.into_response(url.clone())
+    }
+
+    /// Save a response to a request for the URL and return it unread
+    /// 304s aren't saved, so the fixture keeps the full response they refer to
+    pub async fn record(&self, url: &Url, response: Response) -> Result<Response> {
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response
+            .bytes()
+            .await
+            .context("Failed to read upstream response")?;
+        let fixture = Fixture {
+            url: url.to_string(),
+            status: status.as_u16(),
+            headers: saved_headers(&headers),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        };
+
+        let path = self.dir().join(fixture_name(url));
+        let json = serde_json::to_string_pretty(&fixture)?;
+        tokio::fs::write(&path, json)
+            .await
+            .with_context(|| format!("Failed to write fixture {}", path.display()))?;
+        fixture.into_response(url.clone())
+    }
+}
+
+impl Fixture {
+    fn into_response(self, url: Url) -> Result<Response> {
+        let mut builder = http::Response::builder().status(self.status).url(url);
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let response = builder
+            .body(self.body)
+            .context("Invalid status or header in fixture")?;
+        Ok(Response::from(response))
+    }
+}
+
+/// Headers worth replaying; framing headers no longer match the saved body and cookies
+/// don't belong in fixture files
+fn saved_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            ![CONTENT_LENGTH, TRANSFER_ENCODING, CONNECTION, SET_COOKIE].contains(*name)
+        })
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+/// File name of the fixture for a URL: the host, path and query made file-name safe and
+/// shortened, then a hash of the full URL so distinct URLs never share a file
+fn fixture_name(url: &Url) -> String {
+    let readable = format!(
+        "{}{}{}",
+        url.host_str().unwrap_or_default(),
+        url.path(),
+        url.query().map(|q| format!("?{}", q)).unwrap_or_default()
+    );
+    let mut name: String = readable
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_NAME_LEN)
+        .collect();
+    let hash = hex::encode(Sha256::digest(url.as_str().as_bytes()));
+    name.push('-');
+    name.push_str(&hash[..16]);
+    name.push_str(".json");
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, CONTENT_TYPE, ETAG};
+
+    #[test]
+    fn test_fixture_names_and_responses() {
+        let url =
+            Url::parse("https://gamma-api.polymarket.com/markets?limit=100&offset=0").unwrap();
+        let name = fixture_name(&url);
+        assert!(name.starts_with("gamma-api.polymarket.com_markets_limit_100_offset_0-"));
+        assert!(name.ends_with(".json"));
+        assert_eq!(name, fixture_name(&url));
+
+        let next =
+            Url::parse("https://gamma-api.polymarket.com/markets?limit=100&offset=100").unwrap();
+        assert_ne!(name, fixture_name(&next));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(ETAG, HeaderValue::from_static("W/\"abc\""));
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("2"));
+        headers.insert(SET_COOKIE, HeaderValue::from_static("session=secret"));
+        let fixture = Fixture {
+            url: url.to_string(),
+            status: 200,
+            headers: saved_headers(&headers),
+            body: "[]".to_string(),
+        };
+        assert_eq!(fixture.headers.len(), 2);
+
+        let response = fixture.into_response(url.clone()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.url(), &url);
+        assert_eq!(response.headers()[ETAG], "W/\"abc\"");
+        assert!(response.headers().get(SET_COOKIE).is_none());
+    }
+}
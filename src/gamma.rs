@@ -0,0 +1,597 @@
+use async_trait::async_trait;
+use reqwest::RequestBuilder;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::conditional::{Conditional, ListingPage, Validators};
+use crate::error::{Error, Result, UpstreamContext};
+use crate::metrics::{Metrics, ScrapePhase};
+use crate::models::{Comment, GammaComment, GammaEvent, GammaMarket, Market};
+use crate::source::{self, FetchContext, FetchedMarkets, MarketSource, PageSink};
+use crate::throttle::ThrottledClient;
+
+// Official Polymarket Gamma API endpoint
+const GAMMA_MARKETS_URL: &str = "https://gamma-api.polymarket.com/markets";
+const GAMMA_EVENTS_URL: &str = "https://gamma-api.polymarket.com/events";
+const GAMMA_COMMENTS_URL: &str = "https://gamma-api.polymarket.com/comments";
+/// Comments requested per event and scrape, newest first
+const COMMENTS_PER_FETCH: u32 = 50;
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+pub const DEFAULT_MAX_PAGES: u32 = 50;
+
+/// Query parameters accepted by the Gamma `/markets` and `/events` endpoints
+#[derive(Debug, Serialize)]
+struct GammaListQuery {
+    limit: u32,
+    offset: u32,
+    active: bool,
+    closed: bool,
+    /// Embed each market's tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    include_tag: Option<bool>,
+}
+
+/// Send a request and deserialize the JSON body
+/// Download and deserialization are timed separately as the fetch and parse phases
+async fn get_json<T: DeserializeOwned>(
+    client: &ThrottledClient,
+    request: RequestBuilder,
+    metrics: &Metrics,
+) -> Result<T> {
+    let fetch_started = Instant::now();
+    let body = client
+        .send(request.header("Accept", "application/json"))
+        .await
+        .http_context("Failed to fetch from Gamma API")?
+        .error_for_status()
+        .http_context("Gamma API returned an error status")?
+        .bytes()
+        .await
+        .http_context("Failed to read Gamma API response")?;
+    metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+
+    let parse_started = Instant::now();
+    let parsed =
+        serde_json::from_slice(&body).schema_context("Failed to parse Gamma API response")?;
+    metrics.record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+
+    Ok(parsed)
+}
+
+/// Page of the active market listing
+pub enum MarketsPage {
+    /// Raw markets, with the listing to remember once they are stored if upstream sent
+    /// validators
+    Fetched {
+        raw: Vec<serde_json::Value>,
+        listing: Option<ListingPage>,
+    },
+    /// Unchanged since it was last stored; only the IDs it listed are known
+    NotModified { ids: Vec<String> },
+}
+
+impl MarketsPage {
+    /// Markets listed on the page
+    fn len(&self) -> usize {
+        match self {
+            MarketsPage::Fetched { raw, .. } => raw.len(),
+            MarketsPage::NotModified { ids } => ids.len(),
+        }
+    }
+}
+
+/// Parse one raw Gamma market object
+pub fn parse_market(raw: &serde_json::Value) -> Result<Market> {
+    let market = GammaMarket::deserialize(raw).schema_context("Failed to parse Gamma market")?;
+    Ok(Market::try_from(market)?)
+}
+
+/// Fetch a page of active markets from the Gamma API as raw JSON, to be parsed with
+/// `parse_market`
+/// The request is conditional on the page having changed since it was last stored
+pub async fn fetch_markets(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    limit: u32,
+    offset: u32,
+) -> Result<MarketsPage> {
+    let query = GammaListQuery {
+        limit,
+        offset,
+        active: true,
+        closed: false,
+        include_tag: Some(true),
+    };
+
+    info!(
+        "Fetching markets from Gamma API with limit={}, offset={}",
+        limit, offset
+    );
+
+    let fetch_started = Instant::now();
+    let request = client
+        .get(GAMMA_MARKETS_URL)
+        .query(&query)
+        .header("Accept", "application/json");
+    let (response, url) = match client
+        .send_conditional(request)
+        .await
+        .http_context("Failed to fetch from Gamma API")?
+    {
+        Conditional::Modified { response, url } => (response, url),
+        Conditional::NotModified { ids } => {
+            metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+            info!(
+                "Market page at offset={} unchanged since the last scrape",
+                offset
+            );
+            return Ok(MarketsPage::NotModified { ids });
+        }
+    };
+    let response = response
+        .error_for_status()
+        .http_context("Gamma API returned an error status")?;
+    let validators = Validators::from_headers(response.headers());
+    let body = response
+        .bytes()
+        .await
+        .http_context("Failed to read Gamma API response")?;
+    metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+
+    let parse_started = Instant::now();
+    let raw: Vec<serde_json::Value> =
+        serde_json::from_slice(&body).schema_context("Failed to parse Gamma API response")?;
+    metrics.record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+
+    let listing = validators.map(|validators| ListingPage {
+        url,
+        validators,
+        ids: raw.iter().filter_map(source::raw_market_id).collect(),
+    });
+    Ok(MarketsPage::Fetched { raw, listing })
+}
+
+/// Fetch every active market by walking offset pages from `start_offset` until a short page
+/// is returned, sending each page to `pages`
+/// Stops after `max_pages` pages as a safety cap; the next walk resumes where this one stopped
+pub async fn fetch_all_markets(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    pages: &PageSink,
+    page_size: u32,
+    max_pages: u32,
+    start_offset: u32,
+) -> Result<FetchedMarkets> {
+    let mut fetched = FetchedMarkets::default();
+    let (mut listed, mut unchanged) = (0, 0);
+    if start_offset > 0 {
+        info!("Resuming market listing at offset={}", start_offset);
+    }
+
+    for page in 0..max_pages {
+        let offset = start_offset.saturating_add(page.saturating_mul(page_size));
+        let batch = fetch_markets(client, metrics, page_size, offset).await?;
+        let is_last_page = (batch.len() as u32) < page_size;
+        listed += batch.len();
+        let fetched_page = match batch {
+            MarketsPage::Fetched { raw, listing } => FetchedMarkets {
+                raw,
+                listings: listing.into_iter().collect(),
+                ..Default::default()
+            },
+            MarketsPage::NotModified { ids } => {
+                unchanged += ids.len();
+                FetchedMarkets {
+                    unchanged_ids: ids,
+                    ..Default::default()
+                }
+            }
+        };
+        pages.send(fetched_page).await;
+
+        if is_last_page {
+            info!(
+                "Listed {} markets across {} pages, {} of them on unchanged pages",
+                listed,
+                page + 1,
+                unchanged
+            );
+            // Only a catalogue walk from the start that ended before the page cap lists every
+            // active market
+            fetched.complete = start_offset == 0;
+            return Ok(fetched);
+        }
+    }
+
+    fetched.resume_offset = start_offset.saturating_add(max_pages.saturating_mul(page_size));
+    warn!(
+        "Reached max page cap ({}) with {} markets listed, next scrape resumes at offset={}",
+        max_pages, listed, fetched.resume_offset
+    );
+    Ok(fetched)
+}
+
+/// The official Gamma API as a market source
+pub struct GammaSource;
+
+#[async_trait]
+impl MarketSource for GammaSource {
+    fn name(&self) -> &'static str {
+        "gamma"
+    }
+
+    fn venue(&self) -> &'static str {
+        source::POLYMARKET
+    }
+
+    async fn fetch_markets(
+        &self,
+        ctx: &FetchContext<'_>,
+        pages: &PageSink,
+    ) -> Result<FetchedMarkets> {
+        fetch_all_markets(
+            ctx.client,
+            ctx.metrics,
+            pages,
+            ctx.page_size,
+            ctx.max_pages,
+            ctx.resume_offset,
+        )
+        .await
+    }
+
+    fn parse_market(&self, raw: &serde_json::Value) -> Result<Market> {
+        parse_market(raw)
+    }
+}
+
+/// URL of a single market, with the ID percent-encoded as one path segment so it can't
+/// point the request at another endpoint
+/// Dot segments would still be resolved against the path, so they are rejected
+fn market_url(id: &str) -> Result<reqwest::Url> {
+    if matches!(id, "" | "." | "..") {
+        return Err(Error::UpstreamSchema(anyhow::anyhow!("{:?} is not a market ID", id)));
+    }
+    let mut url = reqwest::Url::parse(GAMMA_MARKETS_URL).expect("valid Gamma markets URL");
+    url.path_segments_mut()
+        .expect("Gamma markets URL has a path")
+        .push(id);
+    Ok(url)
+}
+
+/// Fetch the raw JSON of a single market by ID, for callers that archive the payload
+pub async fn fetch_raw_market(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    id: &str,
+) -> Result<serde_json::Value> {
+    get_json(client, client.get(market_url(id)?), metrics).await
+}
+
+/// Fetch a single market by ID, including closed and resolved markets
+pub async fn fetch_market(client: &ThrottledClient, metrics: &Metrics, id: &str) -> Result<Market> {
+    let market: GammaMarket = get_json(client, client.get(market_url(id)?), metrics).await?;
+
+    Ok(Market::try_from(market)?)
+}
+
+/// Convert a Gamma comment of `event_id`; None when it lacks an ID, body or creation time
+fn comment_from_gamma(event_id: &str, gc: GammaComment) -> Option<Comment> {
+    let author_name = gc
+        .profile
+        .and_then(|profile| profile.name.filter(|name| !name.is_empty()).or(profile.pseudonym));
+    Some(Comment {
+        id: gc.id?,
+        event_id: event_id.to_string(),
+        parent_comment_id: gc.parent_comment_id,
+        author: gc.user_address,
+        author_name,
+        body: gc.body?,
+        reaction_count: gc.reaction_count,
+        created_at: gc.created_at?,
+    })
+}
+
+/// Fetch the latest comments on an event; unreadable comments are skipped
+pub async fn fetch_comments(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    event_id: &str,
+) -> Result<Vec<Comment>> {
+    let request = client
+        .get(GAMMA_COMMENTS_URL)
+        .query(&[
+            ("parent_entity_type", "Event"),
+            ("parent_entity_id", event_id),
+            ("order", "createdAt"),
+            ("ascending", "false"),
+        ])
+        .query(&[("limit", COMMENTS_PER_FETCH)]);
+    let comments: Vec<GammaComment> = get_json(client, request, metrics).await?;
+
+    Ok(comments
+        .into_iter()
+        .filter_map(|comment| comment_from_gamma(event_id, comment))
+        .collect())
+}
+
+/// Fetch a page of active events (with their child market IDs) from the Gamma API
+pub async fn fetch_events(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<GammaEvent>> {
+    let query = GammaListQuery {
+        limit,
+        offset,
+        active: true,
+        closed: false,
+        include_tag: None,
+    };
+
+    info!(
+        "Fetching events from Gamma API with limit={}, offset={}",
+        limit, offset
+    );
+
+    get_json(client, client.get(GAMMA_EVENTS_URL).query(&query), metrics).await
+}
+
+/// Fetch every active event, paginating the same way as `fetch_all_markets`
+pub async fn fetch_all_events(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    page_size: u32,
+    max_pages: u32,
+) -> Result<Vec<GammaEvent>> {
+    let mut events = Vec::new();
+
+    for page in 0..max_pages {
+        let batch = fetch_events(client, metrics, page_size, page.saturating_mul(page_size)).await?;
+        let is_last_page = (batch.len() as u32) < page_size;
+        events.extend(batch);
+
+        if is_last_page {
+            info!("Fetched {} events across {} pages", events.len(), page + 1);
+            return Ok(events);
+        }
+    }
+
+    warn!(
+        "Reached max page cap ({}) with {} events fetched, event list may be incomplete",
+        max_pages,
+        events.len()
+    );
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MarketStatus;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_market_url_escapes_the_id() {
+        assert_eq!(
+            market_url("253591").unwrap().as_str(),
+            "https://gamma-api.polymarket.com/markets/253591"
+        );
+        assert_eq!(
+            market_url("../events?limit=1").unwrap().as_str(),
+            "https://gamma-api.polymarket.com/markets/..%2Fevents%3Flimit=1"
+        );
+        assert!(market_url("..").is_err());
+    }
+
+    #[test]
+    fn test_gamma_market_into_market() {
+        let json = serde_json::json!({
+            "id": "253591",
+            "question": "Will it rain tomorrow?",
+            "description": "Resolves YES if it rains.",
+            "outcomePrices": "[\"0.42\", \"0.58\"]",
+            "volumeNum": 1234.5,
+            "endDate": "2025-01-01T00:00:00Z",
+            "slug": "will-it-rain-tomorrow",
+            "conditionId": "0xabc",
+            "questionID": "0xdef",
+            "image": "https://example.com/rain.png",
+            "icon": "https://example.com/rain-icon.png"
+        });
+
+        let gamma: GammaMarket = serde_json::from_value(json).unwrap();
+        let market = Market::try_from(gamma).unwrap();
+        assert_eq!(market.id, "253591");
+        assert_eq!(market.slug.as_deref(), Some("will-it-rain-tomorrow"));
+        assert_eq!(market.condition_id.as_deref(), Some("0xabc"));
+        assert_eq!(market.question_id.as_deref(), Some("0xdef"));
+        assert_eq!(market.image.as_deref(), Some("https://example.com/rain.png"));
+        assert_eq!(market.icon.as_deref(), Some("https://example.com/rain-icon.png"));
+        assert_eq!(market.title, "Will it rain tomorrow?");
+        assert_eq!(market.description, Some("Resolves YES if it rains.".to_string()));
+        assert_eq!(market.current_price, Some(dec!(0.42)));
+        assert_eq!(market.volume, Some(dec!(1234.5)));
+        assert_eq!(
+            market.end_date.map(|d| d.to_rfc3339()),
+            Some("2025-01-01T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_gamma_market_resolution() {
+        let json = serde_json::json!({
+            "id": "42",
+            "question": "Resolved market",
+            "outcomes": "[\"Yes\", \"No\"]",
+            "outcomePrices": "[\"0\", \"1\"]",
+            "closed": true,
+            "closedTime": "2024-11-06 12:34:56+00",
+            "umaResolutionStatus": "resolved"
+        });
+
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(json).unwrap()).unwrap();
+        assert_eq!(market.status, Some(MarketStatus::Resolved));
+        assert_eq!(market.winning_outcome, Some("No".to_string()));
+        assert_eq!(
+            market.resolved_at.map(|t| t.to_rfc3339()),
+            Some("2024-11-06T12:34:56+00:00".to_string())
+        );
+
+        let closed = serde_json::json!({
+            "id": "43",
+            "outcomes": "[\"Yes\", \"No\"]",
+            "outcomePrices": "[\"0.5\", \"0.5\"]",
+            "closed": true
+        });
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(closed).unwrap()).unwrap();
+        assert_eq!(market.status, Some(MarketStatus::Closed));
+        assert_eq!(market.winning_outcome, None);
+
+        let inactive = serde_json::json!({ "id": "44", "active": false, "closed": false });
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(inactive).unwrap()).unwrap();
+        assert_eq!(market.status, Some(MarketStatus::Closed));
+
+        let archived = serde_json::json!({
+            "id": "45",
+            "outcomes": "[\"Yes\", \"No\"]",
+            "outcomePrices": "[\"1\", \"0\"]",
+            "closed": true,
+            "archived": true
+        });
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(archived).unwrap()).unwrap();
+        assert_eq!(market.status, Some(MarketStatus::Archived));
+        assert_eq!(market.winning_outcome, Some("Yes".to_string()));
+    }
+
+    #[test]
+    fn test_gamma_market_outcomes() {
+        let json = serde_json::json!({
+            "id": "44",
+            "question": "Who wins?",
+            "outcomes": "[\"Alice\", \"Bob\", \"Carol\"]",
+            "outcomePrices": "[\"0.5\", \"0.3\", \"0.2\"]",
+            "clobTokenIds": "[\"111\", \"222\", \"333\"]"
+        });
+
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(json).unwrap()).unwrap();
+        let outcomes: Vec<(&str, Option<&str>, Option<Decimal>)> = market
+            .outcomes
+            .iter()
+            .map(|o| (o.name.as_str(), o.token_id.as_deref(), o.price))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("Alice", Some("111"), Some(dec!(0.5))),
+                ("Bob", Some("222"), Some(dec!(0.3))),
+                ("Carol", Some("333"), Some(dec!(0.2))),
+            ]
+        );
+        assert_eq!(market.clob_token_id.as_deref(), Some("111"));
+    }
+
+    #[test]
+    fn test_gamma_market_tags() {
+        let json = serde_json::json!({
+            "id": "45",
+            "category": "US Politics",
+            "tags": [
+                { "label": "Politics", "slug": "politics" },
+                { "label": "US Politics", "slug": "us-politics" },
+                { "label": "Elections" }
+            ]
+        });
+
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(json).unwrap()).unwrap();
+        let slugs: Vec<&str> = market.tags.iter().map(|t| t.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["us-politics", "politics", "elections"]);
+        assert_eq!(market.tags[0].label, "US Politics");
+    }
+
+    #[test]
+    fn test_gamma_market_liquidity_and_quotes() {
+        let json = serde_json::json!({
+            "id": "46",
+            "volume24hr": 2500.75,
+            "liquidity": "12000.5",
+            "liquidityNum": 12000.25,
+            "openInterest": 8000,
+            "bestBid": 0.41,
+            "bestAsk": 0.43
+        });
+
+        let market = Market::try_from(serde_json::from_value::<GammaMarket>(json).unwrap()).unwrap();
+        assert_eq!(market.volume_24hr, Some(dec!(2500.75)));
+        assert_eq!(market.liquidity, Some(dec!(12000.25)));
+        assert_eq!(market.open_interest, Some(dec!(8000)));
+        assert_eq!(market.best_bid, Some(dec!(0.41)));
+        assert_eq!(market.midpoint, Some(dec!(0.42)));
+        assert_eq!(market.spread, Some(dec!(0.02)));
+    }
+
+    #[test]
+    fn test_gamma_event_into_event() {
+        let json = serde_json::json!({
+            "id": "903",
+            "title": "Presidential Election Winner",
+            "slug": "presidential-election-winner",
+            "volume": 1500000.25,
+            "commentCount": 4821,
+            "markets": [{"id": "253591"}, {"id": "253592"}]
+        });
+
+        let gamma: GammaEvent = serde_json::from_value(json).unwrap();
+        let event = crate::models::Event::from(&gamma);
+        assert_eq!(event.id, "903");
+        assert_eq!(event.title, "Presidential Election Winner");
+        assert_eq!(event.volume, Some(1500000.25));
+        assert_eq!(event.comment_count, Some(4821));
+        assert_eq!(gamma.markets.len(), 2);
+        assert_eq!(gamma.markets[1].id, "253592");
+    }
+
+    #[test]
+    fn test_comment_from_gamma() {
+        let json = serde_json::json!({
+            "id": "1777",
+            "body": "Polls are moving",
+            "parentEntityType": "Event",
+            "parentEntityID": 903,
+            "parentCommentID": 1700,
+            "userAddress": "0x56687bf447db6ffa42ffe2204a05edaa20f55839",
+            "createdAt": "2024-01-15T10:29:41.123Z",
+            "profile": {"name": "", "pseudonym": "Calm-Owl"},
+            "reactionCount": 3
+        });
+
+        let gamma: GammaComment = serde_json::from_value(json).unwrap();
+        let comment = comment_from_gamma("903", gamma).unwrap();
+        assert_eq!(comment.id, "1777");
+        assert_eq!(comment.event_id, "903");
+        assert_eq!(comment.parent_comment_id.as_deref(), Some("1700"));
+        assert_eq!(comment.author_name.as_deref(), Some("Calm-Owl"));
+        assert_eq!(comment.reaction_count, 3);
+
+        let deleted: GammaComment = serde_json::from_value(serde_json::json!({"id": 1})).unwrap();
+        assert!(comment_from_gamma("903", deleted).is_none());
+    }
+
+    #[test]
+    fn test_gamma_market_price_fallback() {
+        let json = serde_json::json!({
+            "id": "1",
+            "question": "Fallback price",
+            "lastTradePrice": 0.3,
+            "volume": "99.5"
+        });
+
+        let gamma: GammaMarket = serde_json::from_value(json).unwrap();
+        let market = Market::try_from(gamma).unwrap();
+        assert_eq!(market.current_price, Some(dec!(0.3)));
+        assert_eq!(market.volume, Some(dec!(99.5)));
+    }
+}
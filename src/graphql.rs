@@ -0,0 +1,283 @@
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, InputObject, Object, Result, Schema,
+    SimpleObject,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::models::{
+    Event, Market, MarketFilter, MarketSortField, MarketStatus, Outcome, PricePoint, SortOrder, Tag,
+};
+use crate::error::Error;
+use crate::storage::Storage;
+
+const MAX_PAGE_SIZE: u32 = 500;
+const MAX_PRICE_HISTORY_LIMIT: u32 = 1000;
+const MAX_QUERY_DEPTH: usize = 8; // Bounds nested event -> markets -> event chains
+/// Fields a query may resolve, counting list fields once per requested item: a full page of
+/// markets with a dozen fields each fits, a page of markets with full price histories doesn't
+const MAX_QUERY_COMPLEXITY: usize = 10_000;
+
+pub type MarketSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the GraphQL schema; resolvers read through the shared storage backend
+pub fn build_schema(storage: Arc<dyn Storage>) -> MarketSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(OutcomeLoader(storage.clone()), tokio::spawn))
+        .data(DataLoader::new(TagLoader(storage.clone()), tokio::spawn))
+        .data(storage)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+fn storage<'a>(ctx: &Context<'a>) -> Result<&'a Arc<dyn Storage>> {
+    ctx.data::<Arc<dyn Storage>>()
+}
+
+/// Batches the outcome lookups of every market in a response into one query
+pub struct OutcomeLoader(Arc<dyn Storage>);
+
+impl Loader<String> for OutcomeLoader {
+    type Value = Vec<Outcome>;
+    type Error = Arc<Error>;
+
+    async fn load(
+        &self,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, Vec<Outcome>>, Arc<Error>> {
+        self.0.get_outcomes_by_market(market_ids).await.map_err(Arc::new)
+    }
+}
+
+/// Batches the tag lookups of every market in a response into one query
+pub struct TagLoader(Arc<dyn Storage>);
+
+impl Loader<String> for TagLoader {
+    type Value = Vec<Tag>;
+    type Error = Arc<Error>;
+
+    async fn load(&self, market_ids: &[String]) -> Result<HashMap<String, Vec<Tag>>, Arc<Error>> {
+        self.0.get_tags_by_market(market_ids).await.map_err(Arc::new)
+    }
+}
+
+/// Market filters and ordering, mirroring the `/markets` query parameters
+#[derive(Debug, Default, InputObject)]
+pub struct MarketFilterInput {
+    pub status: Option<MarketStatus>,
+    pub min_volume: Option<f64>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub ending_before: Option<DateTime<Utc>>,
+    pub ending_after: Option<DateTime<Utc>>,
+    /// Include markets no longer returned by Polymarket; ignored when `status` is set
+    pub include_delisted: Option<bool>,
+    /// Only markets carrying this tag slug
+    pub tag: Option<String>,
+    /// Only markets listed on this venue (`polymarket`, `manifold`)
+    pub source: Option<String>,
+    pub sort_by: Option<MarketSortField>,
+    pub order: Option<SortOrder>,
+}
+
+impl From<MarketFilterInput> for MarketFilter {
+    fn from(input: MarketFilterInput) -> Self {
+        MarketFilter {
+            status: input.status,
+            min_volume: input.min_volume,
+            min_price: input.min_price,
+            max_price: input.max_price,
+            ending_before: input.ending_before,
+            ending_after: input.ending_after,
+            include_delisted: input.include_delisted.unwrap_or(false),
+            tag: input.tag,
+            source: input.source,
+            sort_by: input.sort_by.unwrap_or_default(),
+            order: input.order.unwrap_or_default(),
+        }
+    }
+}
+
+/// A page of markets with the total matching count
+#[derive(SimpleObject)]
+pub struct MarketPage {
+    pub markets: Vec<Market>,
+    pub total: i64,
+}
+
+/// A page of events with the total count
+#[derive(SimpleObject)]
+pub struct EventPage {
+    pub events: Vec<Event>,
+    pub total: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct StatusCount {
+    pub status: String,
+    pub count: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct MarketStats {
+    pub total_markets: i64,
+    pub by_status: Vec<StatusCount>,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Markets matching the filter, newest first by default
+    #[graphql(complexity = "limit.min(MAX_PAGE_SIZE) as usize * child_complexity")]
+    async fn markets(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u32,
+        #[graphql(default = 0)] offset: u32,
+        filter: Option<MarketFilterInput>,
+    ) -> Result<MarketPage> {
+        let filter = MarketFilter::from(filter.unwrap_or_default());
+        let (markets, total) =
+            storage(ctx)?.get_markets(limit.min(MAX_PAGE_SIZE), offset, &filter).await?;
+        Ok(MarketPage { markets, total })
+    }
+
+    /// Look up a market by ID, slug, condition ID, question ID or CLOB token ID
+    async fn market(&self, ctx: &Context<'_>, id: String) -> Result<Option<Market>> {
+        Ok(storage(ctx)?.find_market(&id).await?)
+    }
+
+    #[graphql(complexity = "limit.min(MAX_PAGE_SIZE) as usize * child_complexity")]
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u32,
+        #[graphql(default = 0)] offset: u32,
+    ) -> Result<EventPage> {
+        let (events, total) = storage(ctx)?.get_events(limit.min(MAX_PAGE_SIZE), offset).await?;
+        Ok(EventPage { events, total })
+    }
+
+    async fn event(&self, ctx: &Context<'_>, id: String) -> Result<Option<Event>> {
+        Ok(storage(ctx)?.get_event_by_id(&id).await?)
+    }
+
+    /// Market counts overall and by lifecycle status
+    async fn stats(&self, ctx: &Context<'_>) -> Result<MarketStats> {
+        let counts = storage(ctx)?.count_markets_by_status().await?;
+        Ok(MarketStats {
+            total_markets: counts.iter().map(|(_, count)| count).sum(),
+            by_status: counts
+                .into_iter()
+                .map(|(status, count)| StatusCount { status, count })
+                .collect(),
+        })
+    }
+}
+
+#[ComplexObject]
+impl Market {
+    /// Parent event, if the market belongs to one
+    async fn event(&self, ctx: &Context<'_>) -> Result<Option<Event>> {
+        match &self.event_id {
+            Some(event_id) => Ok(storage(ctx)?.get_event_by_id(event_id).await?),
+            None => Ok(None),
+        }
+    }
+
+    /// Every outcome with its price and CLOB quotes, in listing order
+    async fn outcomes(&self, ctx: &Context<'_>) -> Result<Vec<Outcome>> {
+        let loader = ctx.data::<DataLoader<OutcomeLoader>>()?;
+        Ok(loader.load_one(self.id.clone()).await?.unwrap_or_default())
+    }
+
+    /// Category and tags, alphabetically by slug
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<Tag>> {
+        let loader = ctx.data::<DataLoader<TagLoader>>()?;
+        Ok(loader.load_one(self.id.clone()).await?.unwrap_or_default())
+    }
+
+    /// Most recent price observations, newest first
+    #[graphql(complexity = "limit.min(MAX_PRICE_HISTORY_LIMIT) as usize * child_complexity")]
+    async fn price_history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 100)] limit: u32,
+    ) -> Result<Vec<PricePoint>> {
+        let limit = limit.min(MAX_PRICE_HISTORY_LIMIT);
+        Ok(storage(ctx)?.get_price_history(&self.id, limit).await?)
+    }
+}
+
+#[ComplexObject]
+impl Event {
+    async fn markets(&self, ctx: &Context<'_>) -> Result<Vec<Market>> {
+        Ok(storage(ctx)?.get_markets_by_event(&self.id).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::storage::SqliteStorage;
+
+    async fn test_storage() -> Arc<dyn Storage> {
+        Arc::new(SqliteStorage::new(db::test_pools().await))
+    }
+
+    #[test]
+    fn test_schema_exposes_nested_fields() {
+        let sdl = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .finish()
+            .sdl();
+
+        assert!(sdl.contains("type MarketPage"));
+        assert!(sdl.contains("priceHistory("));
+        assert!(sdl.contains("input MarketFilterInput"));
+        assert!(sdl.contains("stats: MarketStats!"));
+    }
+
+    #[tokio::test]
+    async fn test_nested_lists_are_loaded_per_market() {
+        let storage = test_storage().await;
+        for (id, outcome, tag) in [("101", "Yes", "Weather"), ("102", "Rain", "Climate")] {
+            let market = Market {
+                id: id.to_string(),
+                source: "polymarket".to_string(),
+                title: format!("Market {}", id),
+                ..Default::default()
+            };
+            storage.upsert_market(&market).await.unwrap();
+            let outcome = Outcome {
+                name: outcome.to_string(),
+                ..Default::default()
+            };
+            storage.upsert_outcomes(id, &[outcome]).await.unwrap();
+            storage.set_market_tags(id, &[Tag::from_label(tag)]).await.unwrap();
+        }
+        let schema = build_schema(storage);
+
+        let response = schema
+            .execute("{ markets { markets { id outcomes { name } tags { slug } } } }")
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        let markets = data["markets"]["markets"].as_array().unwrap();
+        let market = |id: &str| markets.iter().find(|market| market["id"] == id).unwrap();
+        assert_eq!(market("101")["outcomes"][0]["name"], "Yes");
+        assert_eq!(market("101")["tags"][0]["slug"], "weather");
+        assert_eq!(market("102")["outcomes"][0]["name"], "Rain");
+        assert_eq!(market("102")["tags"][0]["slug"], "climate");
+
+        // A full page of markets with their full price histories is too costly to serve
+        let response = schema
+            .execute("{ markets(limit: 500) { markets { priceHistory(limit: 1000) { price } } } }")
+            .await;
+        assert!(response.errors[0].message.contains("complex"));
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use rust_decimal::prelude::ToPrimitive;
+use sqlx::{Pool, Sqlite};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::{error, info};
+
+use crate::db;
+use crate::error::Error;
+use crate::models::{Market, MarketFilter};
+use crate::updates::{MarketUpdate, UpdateFilter, UpdateSender};
+
+pub mod proto {
+    tonic::include_proto!("polymarket.v1");
+}
+
+use proto::market_service_server::{MarketService, MarketServiceServer};
+
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// gRPC `MarketService` backed by the same database and update channel as the REST API
+pub struct MarketGrpcService {
+    pool: Arc<Pool<Sqlite>>,
+    updates: UpdateSender,
+}
+
+impl From<Market> for proto::Market {
+    fn from(market: Market) -> Self {
+        proto::Market {
+            id: market.id,
+            source: market.source,
+            slug: market.slug,
+            condition_id: market.condition_id,
+            question_id: market.question_id,
+            title: market.title,
+            description: market.description,
+            image: market.image,
+            icon: market.icon,
+            current_price: market.current_price.and_then(|p| p.to_f64()),
+            volume: market.volume.and_then(|v| v.to_f64()),
+            volume_24hr: market.volume_24hr.and_then(|v| v.to_f64()),
+            liquidity: market.liquidity.and_then(|v| v.to_f64()),
+            open_interest: market.open_interest.and_then(|v| v.to_f64()),
+            end_date: market.end_date.map(|t| t.to_rfc3339()),
+            clob_token_id: market.clob_token_id,
+            best_bid: market.best_bid.and_then(|p| p.to_f64()),
+            best_ask: market.best_ask.and_then(|p| p.to_f64()),
+            midpoint: market.midpoint.and_then(|p| p.to_f64()),
+            spread: market.spread.and_then(|p| p.to_f64()),
+            event_id: market.event_id,
+            status: market.status.map(|status| status.to_string()),
+            winning_outcome: market.winning_outcome,
+            resolved_at: market.resolved_at.map(|t| t.to_rfc3339()),
+            discovered_at: market.discovered_at.map(|t| t.to_rfc3339()),
+            updated_at: market.updated_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+fn internal_error(e: Error) -> Status {
+    error!("Database error in gRPC service: {}", e);
+    Status::internal("database error")
+}
+
+type MarketStream = Pin<Box<dyn Stream<Item = Result<proto::Market, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl MarketService for MarketGrpcService {
+    async fn list_markets(
+        &self,
+        request: Request<proto::ListMarketsRequest>,
+    ) -> Result<Response<proto::ListMarketsResponse>, Status> {
+        let request = request.into_inner();
+        let limit = match request.limit {
+            0 => DEFAULT_PAGE_SIZE,
+            limit => limit.min(MAX_PAGE_SIZE),
+        };
+        let status = request
+            .status
+            .map(|status| status.parse())
+            .transpose()
+            .map_err(|e: anyhow::Error| Status::invalid_argument(e.to_string()))?;
+        let filter = MarketFilter {
+            status,
+            min_volume: request.min_volume,
+            ..Default::default()
+        };
+
+        let (markets, total) = db::get_markets(&self.pool, limit, request.offset, &filter)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Response::new(proto::ListMarketsResponse {
+            markets: markets.into_iter().map(proto::Market::from).collect(),
+            total,
+        }))
+    }
+
+    async fn get_market(
+        &self,
+        request: Request<proto::GetMarketRequest>,
+    ) -> Result<Response<proto::Market>, Status> {
+        let id = request.into_inner().id;
+        match db::find_market(&self.pool, &id)
+            .await
+            .map_err(internal_error)?
+        {
+            Some(market) => Ok(Response::new(market.into())),
+            None => Err(Status::not_found(format!("market {} not found", id))),
+        }
+    }
+
+    type StreamNewMarketsStream = MarketStream;
+
+    async fn stream_new_markets(
+        &self,
+        request: Request<proto::StreamNewMarketsRequest>,
+    ) -> Result<Response<Self::StreamNewMarketsStream>, Status> {
+        let request = request.into_inner();
+        let filter = UpdateFilter {
+            min_volume: request.min_volume,
+            keyword: request.keyword,
+            prices_only: false,
+        };
+        info!("New gRPC subscriber for new markets with filter: {:?}", filter);
+
+        // Lagged messages are skipped rather than ending the stream
+        let stream = BroadcastStream::new(self.updates.subscribe()).filter_map(move |msg| {
+            match msg {
+                Ok(update @ MarketUpdate::NewMarket { .. }) if filter.matches(&update) => {
+                    Some(Ok(update.market().clone().into()))
+                }
+                _ => None,
+            }
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// Serve the gRPC API on its own port until the task is aborted
+pub async fn serve(port: u16, pool: Arc<Pool<Sqlite>>, updates: UpdateSender) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!("gRPC server listening on {}", addr);
+
+    Server::builder()
+        .add_service(MarketServiceServer::new(MarketGrpcService { pool, updates }))
+        .serve(addr)
+        .await
+        .context("gRPC server error")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_market_into_proto() {
+        let market = Market {
+            id: "1".to_string(),
+            title: "Will it rain?".to_string(),
+            current_price: Some(dec!(0.42)),
+            discovered_at: Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+
+        let proto = proto::Market::from(market);
+        assert_eq!(proto.id, "1");
+        assert_eq!(proto.current_price, Some(0.42));
+        assert_eq!(proto.volume, None);
+        assert_eq!(proto.discovered_at.as_deref(), Some("2025-01-01T00:00:00+00:00"));
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::models::Position;
+use crate::throttle::ThrottledClient;
+
+// Polymarket data API
+const DATA_API_HOLDERS_URL: &str = "https://data-api.polymarket.com/holders";
+/// Holders requested per outcome token and capture, largest first
+const HOLDERS_PER_TOKEN: u32 = 20;
+
+/// Holders of one outcome token as returned by the data API's `GET /holders`
+#[derive(Debug, Deserialize)]
+struct TokenHolders {
+    token: String,
+    #[serde(default)]
+    holders: Vec<DataApiHolder>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataApiHolder {
+    proxy_wallet: String,
+    amount: f64,
+    outcome_index: Option<i64>,
+    /// Profile name, empty when the wallet has none
+    name: Option<String>,
+    pseudonym: Option<String>,
+}
+
+/// Convert the holders of every token of a market into positions captured at `captured_at`
+fn parse_holders(
+    market_id: &str,
+    tokens: Vec<TokenHolders>,
+    captured_at: DateTime<Utc>,
+) -> Vec<Position> {
+    tokens
+        .into_iter()
+        .flat_map(|token| {
+            let token_id = token.token;
+            token.holders.into_iter().map(move |holder| Position {
+                market_id: market_id.to_string(),
+                token_id: token_id.clone(),
+                outcome_index: holder.outcome_index,
+                wallet: holder.proxy_wallet,
+                name: holder
+                    .name
+                    .filter(|name| !name.is_empty())
+                    .or(holder.pseudonym),
+                amount: holder.amount,
+                captured_at,
+            })
+        })
+        .collect()
+}
+
+/// Fetch the top holders of each outcome token of a market from the data API, which looks
+/// markets up by condition ID
+pub async fn fetch_holders(
+    client: &ThrottledClient,
+    market_id: &str,
+    condition_id: &str,
+) -> Result<Vec<Position>> {
+    let request = client
+        .get(DATA_API_HOLDERS_URL)
+        .query(&[("market", condition_id)])
+        .query(&[("limit", HOLDERS_PER_TOKEN)])
+        .header("Accept", "application/json");
+    let tokens: Vec<TokenHolders> = client
+        .send(request)
+        .await
+        .context("Failed to fetch holders from data API")?
+        .error_for_status()
+        .context("Data API returned an error status")?
+        .json()
+        .await
+        .context("Failed to parse data API holders")?;
+
+    Ok(parse_holders(market_id, tokens, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_holders() {
+        let tokens: Vec<TokenHolders> = serde_json::from_value(serde_json::json!([
+            {"token": "111", "holders": [
+                {"proxyWallet": "0x1", "amount": 5000.5, "outcomeIndex": 0, "name": "whale",
+                 "pseudonym": "Big-Fish"},
+                {"proxyWallet": "0x2", "amount": 120.0, "outcomeIndex": 0, "name": "",
+                 "pseudonym": "Small-Fry"}
+            ]},
+            {"token": "222", "holders": [
+                {"proxyWallet": "0x3", "amount": 800.0, "outcomeIndex": 1}
+            ]}
+        ]))
+        .unwrap();
+
+        let positions = parse_holders("101", tokens, Utc::now());
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[0].token_id, "111");
+        assert_eq!(positions[0].name.as_deref(), Some("whale"));
+        assert_eq!(positions[1].name.as_deref(), Some("Small-Fry"));
+        assert_eq!(positions[2].token_id, "222");
+        assert_eq!(positions[2].outcome_index, Some(1));
+        assert!(positions[2].name.is_none());
+    }
+}
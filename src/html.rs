@@ -0,0 +1,178 @@
+use ::scraper::{Html, Selector};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::error::{Error, Result, UpstreamContext};
+use crate::gamma;
+use crate::metrics::{Metrics, ScrapePhase};
+use crate::models::Market;
+use crate::scraper::parse_single_market;
+use crate::source::{self, FetchContext, FetchedMarkets, MarketSource, PageSink};
+use crate::throttle::ThrottledClient;
+
+// Server-rendered polymarket.com pages embedding market data, tried in order
+const HTML_PAGES: [&str; 2] = ["https://polymarket.com/markets", "https://polymarket.com"];
+const HTML_SOURCE: &str = "html";
+
+/// JSON of the `__NEXT_DATA__` script a server-rendered Next.js page embeds: the page props
+/// (including any prefetched queries) as well as the build ID
+pub fn next_data(html: &str) -> Option<Value> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("script#__NEXT_DATA__").expect("valid selector");
+    let script = document.select(&selector).next()?;
+    serde_json::from_str(&script.text().collect::<String>()).ok()
+}
+
+/// Market objects (an `id` and a `question`) found anywhere in the page data, whichever
+/// query or component embedded them; markets embedded more than once are kept once
+fn markets_in(data: &Value) -> Vec<Value> {
+    fn collect(value: &Value, seen: &mut HashSet<String>, markets: &mut Vec<Value>) {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    collect(item, seen, markets);
+                }
+            }
+            Value::Object(object) => {
+                if object.get("question").is_some_and(Value::is_string) {
+                    if let Some(id) = source::raw_market_id(value) {
+                        if seen.insert(id) {
+                            markets.push(value.clone());
+                        }
+                        return;
+                    }
+                }
+                for field in object.values() {
+                    collect(field, seen, markets);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut markets = Vec::new();
+    collect(data, &mut HashSet::new(), &mut markets);
+    markets
+}
+
+/// Download a server-rendered page
+async fn fetch_page(client: &ThrottledClient, metrics: &Metrics, url: &str) -> Result<String> {
+    info!("Fetching server-rendered page {}", url);
+
+    let fetch_started = Instant::now();
+    let html = client
+        .send(client.get(url).header("Accept", "text/html"))
+        .await
+        .http_context("Failed to fetch polymarket.com page")?
+        .error_for_status()
+        .http_context("polymarket.com returned an error status")?
+        .text()
+        .await
+        .http_context("Failed to read polymarket.com page")?;
+    metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+
+    Ok(html)
+}
+
+/// The server-rendered polymarket.com HTML as a market source of last resort
+/// Only the markets the pages embed are found, with titles, slugs and prices but few
+/// other details
+pub struct HtmlSource;
+
+#[async_trait]
+impl MarketSource for HtmlSource {
+    fn name(&self) -> &'static str {
+        HTML_SOURCE
+    }
+
+    fn venue(&self) -> &'static str {
+        source::POLYMARKET
+    }
+
+    async fn fetch_markets(
+        &self,
+        ctx: &FetchContext<'_>,
+        _pages: &PageSink,
+    ) -> Result<FetchedMarkets> {
+        for url in HTML_PAGES {
+            let html = match fetch_page(ctx.client, ctx.metrics, url).await {
+                Ok(html) => html,
+                Err(e) => {
+                    warn!("{:#}", e);
+                    continue;
+                }
+            };
+
+            let parse_started = Instant::now();
+            let raw = next_data(&html)
+                .map(|data| markets_in(&data))
+                .unwrap_or_default();
+            ctx.metrics
+                .record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+
+            if raw.is_empty() {
+                warn!("No embedded market data found in {}", url);
+                continue;
+            }
+            info!("Found {} markets in the HTML of {}", raw.len(), url);
+            return Ok(FetchedMarkets {
+                raw,
+                complete: false,
+                ..Default::default()
+            });
+        }
+
+        Err(Error::UpstreamSchema(anyhow::anyhow!(
+            "No market data found in the polymarket.com HTML"
+        )))
+    }
+
+    fn parse_market(&self, raw: &Value) -> Result<Market> {
+        // The frontend embeds Gamma market objects; anything else gets the lenient Next.js parser
+        gamma::parse_market(raw).or_else(|_| parse_single_market(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_markets_from_next_data() {
+        let html = r#"<!DOCTYPE html><html><head><title>Polymarket</title></head><body>
+            <div id="__next"><a href="/event/fed-decision">Fed decision</a></div>
+            <script id="__NEXT_DATA__" type="application/json">{
+                "buildId": "abc123def456",
+                "props": {"pageProps": {"dehydratedState": {"queries": [
+                    {"state": {"data": {"pages": [{"events": [
+                        {"id": "900", "title": "Fed decision", "markets": [
+                            {"id": "501", "question": "Will the Fed cut rates?", "slug": "fed-cut",
+                             "outcomes": "[\"Yes\", \"No\"]", "outcomePrices": "[\"0.62\", \"0.38\"]"},
+                            {"id": "502", "question": "Will the Fed hike rates?", "slug": "fed-hike"}
+                        ]}
+                    ]}]}}},
+                    {"state": {"data": [
+                        {"id": "501", "question": "Will the Fed cut rates?", "slug": "fed-cut"}
+                    ]}}
+                ]}}}
+            }</script></body></html>"#;
+
+        let data = next_data(html).unwrap();
+        assert_eq!(data["buildId"], "abc123def456");
+
+        let raw = markets_in(&data);
+        assert_eq!(raw.len(), 2);
+
+        let market = HtmlSource.parse_market(&raw[0]).unwrap();
+        assert_eq!(market.id, "501");
+        assert_eq!(market.slug.as_deref(), Some("fed-cut"));
+        assert_eq!(market.title, "Will the Fed cut rates?");
+        assert_eq!(market.current_price, Some(dec!(0.62)));
+
+        assert!(next_data("<html><body>No data</body></html>").is_none());
+    }
+}
@@ -0,0 +1,1299 @@
+//! End-to-end scrapes: `run_scraper` against a mock upstream server and an in-memory
+//! database, driven through on-demand triggers
+
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use wiremock::matchers::{method, path, query_param, query_param_contains};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::archive;
+use crate::circuit_breaker::BreakerConfig;
+use crate::clob;
+use crate::calibration;
+use crate::candles;
+use crate::db;
+use crate::error;
+use crate::filters::IngestFilter;
+use crate::metrics::{self, Metrics};
+use crate::models::{
+    CandleInterval, Market, MarketStatus, OnchainVerification, Outcome, Position, Precision,
+    ShardProgress, TradeSide,
+};
+use crate::reports;
+use crate::retention;
+use crate::scraper::{self, ScrapeSource, ScraperConfig, ScraperControl, ScraperState};
+use crate::shards::{self, Shard};
+use crate::spikes;
+use crate::storage::{SqliteStorage, Storage};
+use crate::subgraph;
+use crate::throttle::ThrottleConfig;
+use crate::tiers::{self, TierConfig};
+use crate::updates;
+
+const BUILD_ID: &str = "currentBuild123";
+const STALE_BUILD_ID: &str = "staleBuild456";
+// Upstream paths as seen by the mock server, prefixed with the original host
+const GAMMA_MARKETS_PATH: &str = "/gamma-api.polymarket.com/markets";
+const GAMMA_EVENTS_PATH: &str = "/gamma-api.polymarket.com/events";
+const GAMMA_COMMENTS_PATH: &str = "/gamma-api.polymarket.com/comments";
+const HOMEPAGE_PATH: &str = "/polymarket.com/";
+const CLOB_BOOK_PATH: &str = "/clob.polymarket.com/book";
+const DATA_API_HOLDERS_PATH: &str = "/data-api.polymarket.com/holders";
+const DATA_API_TRADES_PATH: &str = "/data-api.polymarket.com/trades";
+const SUBGRAPH_PATH: &str = "/api.goldsky.com/api/public/project_cl6mb8i9h0003e201j6li0diw/subgraphs/orderbook-subgraph/0.0.1/gn";
+
+async fn test_storage() -> Arc<dyn Storage> {
+    Arc::new(SqliteStorage::new(db::test_pools().await))
+}
+
+fn test_config(server: &MockServer, source: ScrapeSource) -> ScraperConfig {
+    ScraperConfig {
+        source,
+        additional_sources: Vec::new(),
+        page_size: 100,
+        max_pages: 2,
+        delist_after_missed_scrapes: scraper::DEFAULT_DELIST_AFTER_MISSED_SCRAPES,
+        precision: Precision::default(),
+        filter: IngestFilter::default(),
+        enrich_concurrency: 2,
+        tiers: TierConfig {
+            hot_interval_secs: 0,
+            hot_min_volume: tiers::DEFAULT_HOT_MIN_VOLUME,
+            hot_max_markets: tiers::DEFAULT_HOT_MAX_MARKETS,
+            long_tail_interval_secs: 0,
+            enrich_every: 1,
+            enrich_max_markets: tiers::DEFAULT_ENRICH_MAX_MARKETS,
+        },
+        throttle: ThrottleConfig {
+            requests_per_second: 1000.0,
+            burst: 100,
+        },
+        proxies: Vec::new(),
+        headers: Vec::new(),
+        user_agents: Vec::new(),
+        fixtures: None,
+        upstream_base_url: Some(Url::parse(&server.uri()).unwrap()),
+        subgraph_url: subgraph::DEFAULT_SUBGRAPH_URL.to_string(),
+        positions_subgraph_url: subgraph::DEFAULT_POSITIONS_SUBGRAPH_URL.to_string(),
+        orderbook_depth: clob::DEFAULT_ORDERBOOK_DEPTH,
+    }
+}
+
+/// Spawn `run_scraper` with scheduled scrapes paused, so only triggered scrapes run
+fn start_scraper(storage: &Arc<dyn Storage>, config: ScraperConfig) -> ScraperControl {
+    spawn_scraper(storage, config, CancellationToken::new()).0
+}
+
+/// Like `start_scraper`, also returning the task so tests can wait for it to stop
+fn spawn_scraper(
+    storage: &Arc<dyn Storage>,
+    config: ScraperConfig,
+    shutdown: CancellationToken,
+) -> (ScraperControl, JoinHandle<error::Result<()>>) {
+    let (control, signals) = scraper::control(
+        3600,
+        config.source,
+        BreakerConfig {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(60),
+        },
+    );
+    control.set_state(ScraperState::Paused);
+    let handle = tokio::spawn(scraper::run_scraper(
+        Arc::clone(storage),
+        config,
+        Arc::new(Metrics::new()),
+        updates::channel(),
+        signals,
+        shutdown,
+    ));
+    (control, handle)
+}
+
+fn gamma_market(id: &str, question: &str) -> Value {
+    json!({
+        "id": id,
+        "question": question,
+        "description": "Resolves YES if it happens.",
+        "slug": question.to_lowercase().replace(' ', "-"),
+        "outcomes": "[\"Yes\", \"No\"]",
+        "outcomePrices": "[\"0.6\", \"0.4\"]",
+        "volumeNum": 1000.0,
+        "endDate": "2099-01-01T00:00:00Z"
+    })
+}
+
+async fn mount_homepage(server: &MockServer, build_id: &str) {
+    let html = format!(
+        r#"<html><body><script id="__NEXT_DATA__" type="application/json">{{"buildId":"{}","props":{{}}}}</script></body></html>"#,
+        build_id
+    );
+    Mock::given(method("GET"))
+        .and(path(HOMEPAGE_PATH))
+        .respond_with(ResponseTemplate::new(200).set_body_string(html))
+        .mount(server)
+        .await;
+}
+
+async fn mount_json(server: &MockServer, route: &str, body: Value) {
+    Mock::given(method("GET"))
+        .and(path(route))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(server)
+        .await;
+}
+
+async fn mount_nextjs_markets(server: &MockServer, build_id: &str) {
+    let route = format!("/polymarket.com/_next/data/{}/index.json", build_id);
+    let markets = json!({"pageProps": {"markets": [
+        {"id": "201", "question": "Will the Next.js route work?", "slug": "nextjs-route"}
+    ]}});
+    mount_json(server, &route, markets).await;
+}
+
+/// Answer subgraph queries whose document contains `field`
+async fn mount_subgraph(server: &MockServer, field: &str, data: Value) {
+    Mock::given(method("GET"))
+        .and(path(SUBGRAPH_PATH))
+        .and(query_param_contains("query", field))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": data })))
+        .mount(server)
+        .await;
+}
+
+/// Store an active market directly, for tests of what builds on stored markets rather than
+/// of the scrape itself
+async fn seed_market(storage: &dyn Storage, id: &str) {
+    let market = Market {
+        id: id.to_string(),
+        source: "polymarket".to_string(),
+        title: "Will it rain tomorrow".to_string(),
+        status: Some(MarketStatus::Active),
+        ..Default::default()
+    };
+    storage.upsert_market(&market).await.unwrap();
+}
+
+async fn requests_to(server: &MockServer, route: &str) -> usize {
+    let requests = server.received_requests().await.unwrap();
+    requests.iter().filter(|r| r.url.path() == route).count()
+}
+
+#[tokio::test]
+async fn test_scrape_stores_gamma_markets() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    let markets = json!([
+        gamma_market("101", "Will it rain tomorrow"),
+        gamma_market("102", "Will it snow tomorrow")
+    ]);
+    mount_json(&server, GAMMA_MARKETS_PATH, markets).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+
+    let storage = test_storage().await;
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::Gamma));
+
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 2);
+    assert_eq!(summary.new_markets, 2);
+    assert_eq!(control.status().build_id.as_deref(), Some(BUILD_ID));
+
+    let market = storage.get_market_by_id("101").await.unwrap().unwrap();
+    assert_eq!(market.title, "Will it rain tomorrow");
+    // Outcomes are stored in their own table and loaded separately
+    assert_eq!(storage.get_outcomes("101").await.unwrap().len(), 2);
+
+    // Known markets aren't new the second time round
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 2);
+    assert_eq!(summary.new_markets, 0);
+}
+
+#[tokio::test]
+async fn test_upsert_stores_every_changed_column() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+
+    // Columns that only change on their own still count as a change of content
+    let market = Market {
+        id: "101".to_string(),
+        source: "manifold".to_string(),
+        title: "Will it rain tomorrow".to_string(),
+        midpoint: Some(dec!(0.55)),
+        ..Default::default()
+    };
+    assert!(!storage.upsert_market(&market).await.unwrap());
+    let stored = storage.get_market_by_id("101").await.unwrap().unwrap();
+    assert_eq!(stored.source, "manifold");
+    assert_eq!(stored.midpoint, Some(dec!(0.55)));
+}
+
+#[tokio::test]
+async fn test_scrape_updates_markets_from_the_subgraph() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+    let condition_id = format!("0x{}", "ab".repeat(32));
+    let unknown_condition_id = format!("0x{}", "cd".repeat(32));
+    let conditions = json!({"conditions": [
+        {
+            "id": condition_id,
+            "questionId": "0x01",
+            "outcomeSlotCount": 2,
+            "resolutionTimestamp": "1700000000",
+            "payoutNumerators": ["1", "0"],
+            "payoutDenominator": "1"
+        },
+        {
+            "id": unknown_condition_id,
+            "questionId": "0x02",
+            "outcomeSlotCount": 2,
+            "resolutionTimestamp": null,
+            "payoutNumerators": [],
+            "payoutDenominator": "0"
+        }
+    ]});
+    mount_subgraph(&server, "conditions(", conditions).await;
+    let tokens = json!({"marketDatas": [
+        {"id": "111", "condition": {"id": condition_id}, "outcomeIndex": "0", "priceOrderbook": "0.7"},
+        {"id": "222", "condition": {"id": condition_id}, "outcomeIndex": "1", "priceOrderbook": "0.3"},
+        {"id": "333", "condition": {"id": unknown_condition_id}, "outcomeIndex": "0", "priceOrderbook": "0.4"},
+        {"id": "444", "condition": {"id": unknown_condition_id}, "outcomeIndex": "1", "priceOrderbook": "0.6"}
+    ]});
+    mount_subgraph(&server, "marketDatas(", tokens).await;
+    let orderbooks = json!({"orderbooks": [
+        {"id": "111", "scaledCollateralVolume": "1200.5"},
+        {"id": "222", "scaledCollateralVolume": "300"}
+    ]});
+    mount_subgraph(&server, "orderbooks(", orderbooks).await;
+
+    let storage = test_storage().await;
+    let market = Market {
+        id: "101".to_string(),
+        source: crate::source::POLYMARKET.to_string(),
+        condition_id: Some(condition_id.clone()),
+        title: "Will it rain tomorrow".to_string(),
+        current_price: Some(dec!(0.5)),
+        status: Some(MarketStatus::Active),
+        ..Default::default()
+    };
+    storage.upsert_market(&market).await.unwrap();
+    let outcomes: Vec<Outcome> = [("Rain", "111"), ("No rain", "222")]
+        .into_iter()
+        .map(|(name, token_id)| Outcome {
+            name: name.to_string(),
+            token_id: Some(token_id.to_string()),
+            ..Default::default()
+        })
+        .collect();
+    storage.upsert_outcomes("101", &outcomes).await.unwrap();
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::Subgraph));
+
+    // Only the condition of a stored market counts
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 1);
+    assert_eq!(summary.new_markets, 0);
+
+    // The stored market takes the chain's price and resolution, naming the winner after
+    // its own outcome of the winning token
+    let market = storage.get_market_by_id("101").await.unwrap().unwrap();
+    assert_eq!(market.title, "Will it rain tomorrow");
+    assert_eq!(market.current_price, Some(dec!(0.7)));
+    assert_eq!(market.status, Some(MarketStatus::Resolved));
+    assert_eq!(market.winning_outcome.as_deref(), Some("Rain"));
+    assert_eq!(storage.get_outcomes("101").await.unwrap().len(), 2);
+    // Conditions have no title to store a market with
+    assert!(storage.get_market_by_id(&condition_id).await.unwrap().is_none());
+    assert!(storage
+        .get_market_by_id(&unknown_condition_id)
+        .await
+        .unwrap()
+        .is_none());
+    // Condition IDs have no Gamma detail record to enrich from
+    let detail_path = format!("{}/{}", GAMMA_MARKETS_PATH, condition_id);
+    assert_eq!(requests_to(&server, &detail_path).await, 0);
+}
+
+#[tokio::test]
+async fn test_filtered_markets_are_not_stored() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    let markets = json!([
+        gamma_market("101", "Will it rain tomorrow"),
+        gamma_market("102", "Will it snow tomorrow")
+    ]);
+    mount_json(&server, GAMMA_MARKETS_PATH, markets).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+
+    let storage = test_storage().await;
+    let mut config = test_config(&server, ScrapeSource::Gamma);
+    config.filter.title_exclude = Some(regex::Regex::new("(?i)snow").unwrap());
+    let control = start_scraper(&storage, config);
+
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 1);
+    assert!(storage.get_market_by_id("101").await.unwrap().is_some());
+    assert!(storage.get_market_by_id("102").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_shards_split_scraped_markets() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    let ids = ["101", "102", "103", "104"];
+    let markets: Vec<Value> = ids
+        .iter()
+        .map(|id| gamma_market(id, &format!("Will market {} resolve", id)))
+        .collect();
+    mount_json(&server, GAMMA_MARKETS_PATH, Value::Array(markets)).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+
+    // Each instance stores only the markets of its own shard
+    for index in 0..2 {
+        let storage = test_storage().await;
+        let control = start_scraper(&storage, test_config(&server, ScrapeSource::Gamma));
+        control.set_shard(Shard { index, count: 2 });
+        let summary = control.trigger_scrape().await.unwrap();
+        assert_eq!(summary.markets_fetched, 2);
+
+        for id in ids {
+            let stored = storage.get_market_by_id(id).await.unwrap().is_some();
+            assert_eq!(stored, shards::shard_of(id, 2) == index, "market {}", id);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_only_the_first_shard_delists() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_json(&server, GAMMA_MARKETS_PATH, json!([gamma_market("101", "Will it snow")])).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "999").await;
+    let mut config = test_config(&server, ScrapeSource::Gamma);
+    config.delist_after_missed_scrapes = 1;
+    let control = start_scraper(&storage, config);
+
+    // Every shard walks the whole listing, but only the first counts the missing market
+    control.set_shard(Shard { index: 1, count: 2 });
+    control.trigger_scrape().await.unwrap();
+    let market = storage.get_market_by_id("999").await.unwrap().unwrap();
+    assert_eq!(market.status, Some(MarketStatus::Active));
+
+    control.set_shard(Shard { index: 0, count: 2 });
+    control.trigger_scrape().await.unwrap();
+    let market = storage.get_market_by_id("999").await.unwrap().unwrap();
+    assert_eq!(market.status, Some(MarketStatus::Delisted));
+}
+
+#[tokio::test]
+async fn test_last_seen_never_moves_backwards() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+    let ids = vec!["101".to_string()];
+    let later = Utc::now();
+    let earlier = later - chrono::Duration::minutes(5);
+
+    // A shard whose walk started earlier finishes after one that started later
+    storage.mark_markets_seen(&ids, later).await.unwrap();
+    storage.mark_markets_seen(&ids, earlier).await.unwrap();
+    let market = storage.get_market_by_id("101").await.unwrap().unwrap();
+    assert_eq!(market.last_seen_at.map(|at| at.timestamp()), Some(later.timestamp()));
+}
+
+#[tokio::test]
+async fn test_single_market_refresh() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    let mut market = gamma_market("101", "Will it rain tomorrow");
+    mount_json(&server, &format!("{}/101", GAMMA_MARKETS_PATH), market.clone()).await;
+
+    let storage = test_storage().await;
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::Gamma));
+
+    // Refreshes run while scheduled scrapes are paused
+    let refreshed = control.refresh_market("101").await.unwrap().unwrap();
+    assert_eq!(refreshed.title, "Will it rain tomorrow");
+    assert!(storage.get_market_by_id("101").await.unwrap().is_some());
+    assert_eq!(requests_to(&server, GAMMA_MARKETS_PATH).await, 0);
+
+    server.reset().await;
+    market["outcomePrices"] = json!("[\"0.7\", \"0.3\"]");
+    mount_json(&server, &format!("{}/101", GAMMA_MARKETS_PATH), market).await;
+    let refreshed = control.refresh_market("101").await.unwrap().unwrap();
+    assert_eq!(refreshed.current_price, Some(dec!(0.7)));
+    let changes = storage.get_market_changes("101", 10).await.unwrap();
+    assert_eq!(changes[0].field, "current_price");
+
+    // Unknown to upstream
+    assert!(control.refresh_market("999").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_trades_of_hot_markets_are_stored_once() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+    let mut hot = gamma_market("101", "Will it rain tomorrow");
+    hot["volumeNum"] = json!(500_000.0);
+    hot["conditionId"] = json!("0xabc");
+    mount_json(&server, GAMMA_MARKETS_PATH, json!([hot])).await;
+    let trade = |wallet: &str, side: &str, size: f64, price: f64, timestamp: i64, hash: &str| {
+        json!({"proxyWallet": wallet, "side": side, "asset": "111", "size": size, "price": price,
+               "timestamp": timestamp, "outcome": "Yes", "transactionHash": hash})
+    };
+    let takers = [
+        trade("0x1", "SELL", 5.0, 0.59, 1700000060, "0xbb"),
+        trade("0x2", "BUY", 10.0, 0.6, 1700000000, "0xaa"),
+    ];
+    // Without `takerOnly` the makers' trades of each fill are listed too
+    let mut listed = takers.to_vec();
+    listed.insert(1, trade("0x3", "BUY", 5.0, 0.59, 1700000060, "0xbb"));
+    listed.push(trade("0x4", "SELL", 6.0, 0.6, 1700000000, "0xaa"));
+    listed.push(trade("0x5", "SELL", 4.0, 0.6, 1700000000, "0xaa"));
+    for (taker_only, trades) in [("true", json!(takers)), ("false", json!(listed))] {
+        Mock::given(method("GET"))
+            .and(path(DATA_API_TRADES_PATH))
+            .and(query_param("takerOnly", taker_only))
+            .respond_with(ResponseTemplate::new(200).set_body_json(trades))
+            .mount(&server)
+            .await;
+    }
+
+    // Secondary passes run before `scrape_once` returns, unlike after a triggered scrape
+    let storage = test_storage().await;
+    let scrape = || {
+        let config = test_config(&server, ScrapeSource::Gamma);
+        scraper::scrape_once(Arc::clone(&storage), config, Arc::new(Metrics::new()), updates::channel())
+    };
+    scrape().await.unwrap();
+    // The second fetch lists trades since the latest one stored, which is stored already
+    scrape().await.unwrap();
+    assert_eq!(requests_to(&server, DATA_API_TRADES_PATH).await, 4);
+
+    // One trade per maker order the taker's filled
+    let stored = storage.get_trades("101", None, 100).await.unwrap();
+    assert_eq!(stored.len(), 3);
+    assert_eq!(stored[0].side, TradeSide::Sell);
+    assert_eq!(stored[0].taker.as_deref(), Some("0x1"));
+    assert_eq!(stored[0].maker.as_deref(), Some("0x3"));
+    let mut makers: Vec<_> = stored[1..].iter().filter_map(|t| t.maker.as_deref()).collect();
+    makers.sort();
+    assert_eq!(makers, ["0x4", "0x5"]);
+
+    let since = DateTime::from_timestamp(1_700_000_030, 0).unwrap();
+    let recent = storage.get_trades("101", Some(since), 100).await.unwrap();
+    assert_eq!(recent.len(), 1);
+}
+
+#[tokio::test]
+async fn test_comments_of_hot_market_events_are_stored() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    let events = json!([{
+        "id": "903", "title": "Weather", "commentCount": 12, "markets": [{"id": "101"}]
+    }]);
+    mount_json(&server, GAMMA_EVENTS_PATH, events).await;
+    let mut hot = gamma_market("101", "Will it rain tomorrow");
+    hot["volumeNum"] = json!(500_000.0);
+    mount_json(&server, GAMMA_MARKETS_PATH, json!([hot])).await;
+    let recent = (Utc::now() - chrono::Duration::minutes(5)).to_rfc3339();
+    let comments = json!([
+        {"id": 2, "body": "Clouds rolling in", "userAddress": "0x2", "createdAt": recent,
+         "reactionCount": 1},
+        {"id": 1, "body": "Dry all week", "userAddress": "0x1", "createdAt": "2024-01-15T10:00:00Z",
+         "profile": {"name": "forecaster"}}
+    ]);
+    mount_json(&server, GAMMA_COMMENTS_PATH, comments).await;
+
+    let storage = test_storage().await;
+    let scrape = || {
+        let config = test_config(&server, ScrapeSource::Gamma);
+        scraper::scrape_once(Arc::clone(&storage), config, Arc::new(Metrics::new()), updates::channel())
+    };
+    scrape().await.unwrap();
+    // The second fetch returns the same comments
+    scrape().await.unwrap();
+    assert_eq!(requests_to(&server, GAMMA_COMMENTS_PATH).await, 2);
+
+    let stored = storage.get_comments("903", 100).await.unwrap();
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0].body, "Clouds rolling in");
+    assert_eq!(stored[1].author_name.as_deref(), Some("forecaster"));
+    // Known comments aren't new again, but their reaction counts are refreshed
+    let mut known = stored[0].clone();
+    known.reaction_count = 5;
+    assert_eq!(storage.upsert_comments(&[known]).await.unwrap(), 0);
+    let stored = storage.get_comments("903", 100).await.unwrap();
+    assert_eq!(stored[0].reaction_count, 5);
+    let hour_ago = Utc::now() - chrono::Duration::hours(1);
+    assert_eq!(storage.count_comments_since(Some("903"), hour_ago).await.unwrap(), 1);
+    let event = storage.get_event_by_id("903").await.unwrap().unwrap();
+    assert_eq!(event.comment_count, Some(12));
+}
+
+#[tokio::test]
+async fn test_holders_of_hot_markets_are_captured() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+    let mut hot = gamma_market("101", "Will it rain tomorrow");
+    hot["volumeNum"] = json!(500_000.0);
+    hot["conditionId"] = json!("0xabc");
+    mount_json(&server, GAMMA_MARKETS_PATH, json!([hot])).await;
+    let holders = json!([
+        {"token": "111", "holders": [
+            {"proxyWallet": "0x2", "amount": 150.0, "outcomeIndex": 0, "name": ""},
+            {"proxyWallet": "0x1", "amount": 9000.0, "outcomeIndex": 0, "name": "whale"}
+        ]},
+        {"token": "222", "holders": [
+            {"proxyWallet": "0x3", "amount": 400.0, "outcomeIndex": 1, "pseudonym": "Quiet-Fox"}
+        ]}
+    ]);
+    mount_json(&server, DATA_API_HOLDERS_PATH, holders).await;
+
+    let storage = test_storage().await;
+    assert!(storage.get_holders("101", None).await.unwrap().is_empty());
+    let config = test_config(&server, ScrapeSource::Gamma);
+    scraper::scrape_once(Arc::clone(&storage), config, Arc::new(Metrics::new()), updates::channel())
+        .await
+        .unwrap();
+
+    let stored = storage.get_holders("101", None).await.unwrap();
+    let wallets: Vec<&str> = stored.iter().map(|p| p.wallet.as_str()).collect();
+    assert_eq!(wallets, vec!["0x1", "0x2", "0x3"]);
+    assert_eq!(stored[0].name.as_deref(), Some("whale"));
+    assert_eq!(stored[2].name.as_deref(), Some("Quiet-Fox"));
+
+    // Nothing was captured before the scrape
+    let before = stored[0].captured_at - chrono::Duration::seconds(1);
+    assert!(storage.get_holders("101", Some(before)).await.unwrap().is_empty());
+
+    // The same holders again aren't captured a second time
+    let config = test_config(&server, ScrapeSource::Gamma);
+    scraper::scrape_once(Arc::clone(&storage), config, Arc::new(Metrics::new()), updates::channel())
+        .await
+        .unwrap();
+    assert_eq!(requests_to(&server, DATA_API_HOLDERS_PATH).await, 2);
+    let latest = storage.get_holders("101", None).await.unwrap();
+    assert_eq!(latest[0].captured_at, stored[0].captured_at);
+}
+
+#[tokio::test]
+async fn test_retention_keeps_the_latest_holder_capture() {
+    let pools = db::test_pools().await;
+    let storage = SqliteStorage::new(pools.clone());
+    for id in ["101", "102"] {
+        let market = Market {
+            id: id.to_string(),
+            title: "Will it rain tomorrow".to_string(),
+            ..Default::default()
+        };
+        storage.upsert_market(&market).await.unwrap();
+    }
+    let position = |market_id: &str, wallet: &str, days_ago: i64| Position {
+        market_id: market_id.to_string(),
+        token_id: "111".to_string(),
+        outcome_index: Some(0),
+        wallet: wallet.to_string(),
+        name: None,
+        amount: 100.0,
+        captured_at: Utc::now() - chrono::Duration::days(days_ago),
+    };
+    let captures = [
+        position("101", "0x1", 40),
+        position("101", "0x2", 35),
+        position("101", "0x3", 1),
+        // Holders that haven't changed in a long while
+        position("102", "0x1", 60),
+    ];
+    storage.insert_positions(&captures).await.unwrap();
+
+    let policy = retention::RetentionPolicy {
+        price_history_days: 0,
+        archive_resolved_after_days: 0,
+        trades_days: 0,
+        comments_days: 0,
+        positions_days: 30,
+        orderbook_snapshots_days: 0,
+    };
+    let summary = retention::prune(&pools.write, policy, None).await.unwrap();
+    assert_eq!(summary.positions_deleted, 2);
+    assert_eq!(storage.get_holders("101", None).await.unwrap()[0].wallet, "0x3");
+    assert_eq!(storage.get_holders("102", None).await.unwrap()[0].wallet, "0x1");
+}
+
+#[tokio::test]
+async fn test_outbox_retention_skips_removed_sinks() {
+    let pools = db::test_pools().await;
+    let storage = SqliteStorage::new(pools.clone());
+    let market = Market {
+        id: "101".to_string(),
+        title: "Will it rain tomorrow".to_string(),
+        ..Default::default()
+    };
+    storage.upsert_market_with_outbox(&market, None).await.unwrap();
+    let event = &storage.get_pending_outbox("Discord", 10).await.unwrap()[0];
+    let webhook = db::insert_webhook(&pools.write, "https://example.com/hook", None)
+        .await
+        .unwrap();
+    let webhook_sink = format!("Webhook:{}", webhook.id);
+    for sink in ["Discord", webhook_sink.as_str()] {
+        db::record_outbox_failure(&pools.write, event.id, sink, "unreachable").await.unwrap();
+    }
+    let later = Utc::now() + chrono::Duration::minutes(1);
+    let registered = ["Webhook".to_string()];
+
+    // Events a registered sink still retries on are kept
+    let deleted = db::delete_outbox_before(&pools.write, later, Some(&registered)).await;
+    assert_eq!(deleted.unwrap(), 0);
+    // Without the registered sinks, a sink that was removed from the configuration counts too
+    assert!(db::delete_webhook(&pools.write, webhook.id).await.unwrap());
+    let deleted = db::delete_outbox_before(&pools.write, later, None).await;
+    assert_eq!(deleted.unwrap(), 0);
+    // A deleted webhook and an unregistered sink no longer hold the event back
+    let deleted = db::delete_outbox_before(&pools.write, later, Some(&registered)).await;
+    assert_eq!(deleted.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_orderbooks_of_hot_markets_are_captured() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+    let mut hot = gamma_market("101", "Will it rain tomorrow");
+    hot["volumeNum"] = json!(500_000.0);
+    hot["clobTokenIds"] = json!("[\"111\", \"222\"]");
+    let cold = gamma_market("102", "Will it snow tomorrow");
+    mount_json(&server, GAMMA_MARKETS_PATH, json!([hot, cold])).await;
+    let book = json!({
+        "bids": [{"price": "0.58", "size": "30"}, {"price": "0.59", "size": "10"}],
+        "asks": [{"price": "0.61", "size": "20"}]
+    });
+    mount_json(&server, CLOB_BOOK_PATH, book).await;
+
+    let storage = test_storage().await;
+    let mut config = test_config(&server, ScrapeSource::Gamma);
+    config.orderbook_depth = 1;
+    scraper::scrape_once(Arc::clone(&storage), config, Arc::new(Metrics::new()), updates::channel())
+        .await
+        .unwrap();
+
+    let snapshot = storage.get_latest_orderbook_snapshot("101").await.unwrap().unwrap();
+    assert_eq!(snapshot.token_id, "111");
+    assert_eq!(snapshot.bids.len(), 1);
+    assert_eq!(snapshot.bids[0].price, dec!(0.59));
+    assert_eq!(snapshot.bid_depth, dec!(10));
+    assert_eq!(snapshot.ask_depth, dec!(20));
+    assert!((snapshot.imbalance.unwrap() + 1.0 / 3.0).abs() < 1e-9);
+    assert!(storage.get_latest_orderbook_snapshot("102").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_reprocess_pages_through_archived_payloads() {
+    let pools = db::test_pools().await;
+    // More payloads than a page, written back on the single writer connection while reading
+    let count = 300;
+    for i in 0..count {
+        let raw = gamma_market(&format!("{:03}", i), "Will it rain tomorrow");
+        let encoded = archive::encode(&raw).unwrap();
+        db::insert_raw_payload(&pools.write, &format!("{:03}", i), "gamma", &encoded.compressed, &encoded.hash, Utc::now())
+            .await
+            .unwrap();
+    }
+
+    let summary = tokio::time::timeout(
+        Duration::from_secs(30),
+        archive::reprocess(&pools, None, Precision::default()),
+    )
+    .await
+    .expect("reprocessing deadlocked")
+    .unwrap();
+    assert_eq!((summary.reprocessed, summary.failed), (count, 0));
+    let storage = SqliteStorage::new(pools);
+    assert!(storage.get_market_by_id("299").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_throttled_listing_is_retried() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    Mock::given(method("GET"))
+        .and(path(GAMMA_MARKETS_PATH))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_json(
+        &server,
+        GAMMA_MARKETS_PATH,
+        json!([gamma_market("101", "Will it rain tomorrow")]),
+    )
+    .await;
+
+    let storage = test_storage().await;
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::Gamma));
+
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 1);
+    assert_eq!(requests_to(&server, GAMMA_MARKETS_PATH).await, 3);
+}
+
+#[tokio::test]
+async fn test_malformed_listing_falls_back_to_nextjs() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    Mock::given(method("GET"))
+        .and(path(GAMMA_MARKETS_PATH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(r#"[{"id": "101", "question": "#, "application/json"),
+        )
+        .mount(&server)
+        .await;
+    mount_nextjs_markets(&server, BUILD_ID).await;
+
+    let storage = test_storage().await;
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::Gamma));
+
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 1);
+    assert!(storage.get_market_by_id("101").await.unwrap().is_none());
+    let market = storage.get_market_by_id("201").await.unwrap().unwrap();
+    assert_eq!(market.title, "Will the Next.js route work?");
+}
+
+#[tokio::test]
+async fn test_pages_are_stored_as_they_are_fetched() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    Mock::given(method("GET"))
+        .and(path(GAMMA_MARKETS_PATH))
+        .and(query_param("offset", "0"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([gamma_market("101", "Will it rain tomorrow")])),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(GAMMA_MARKETS_PATH))
+        .and(query_param("offset", "1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(r#"[{"id": "102", "question": "#, "application/json"),
+        )
+        .mount(&server)
+        .await;
+    mount_nextjs_markets(&server, BUILD_ID).await;
+
+    let storage = test_storage().await;
+    let mut config = test_config(&server, ScrapeSource::Gamma);
+    config.page_size = 1;
+    let control = start_scraper(&storage, config);
+
+    // The first page was already stored when the second one failed to parse
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 2);
+    assert!(storage.get_market_by_id("101").await.unwrap().is_some());
+    assert!(storage.get_market_by_id("201").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_unreadable_listing_is_not_retried() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    let nextjs_route = format!("/polymarket.com/_next/data/{}/index.json", BUILD_ID);
+    mount_json(&server, &nextjs_route, json!({"unexpected": true})).await;
+    Mock::given(method("GET"))
+        .and(path(GAMMA_MARKETS_PATH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(r#"[{"id": "101", "question": "#, "application/json"),
+        )
+        .mount(&server)
+        .await;
+
+    let storage = test_storage().await;
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::NextJs));
+
+    // Another attempt would read the same payloads, so the scrape fails without one
+    assert!(control.trigger_scrape().await.is_err());
+    assert_eq!(requests_to(&server, &nextjs_route).await, 1);
+    assert_eq!(requests_to(&server, GAMMA_MARKETS_PATH).await, 1);
+}
+
+#[tokio::test]
+async fn test_changed_build_id_is_rediscovered() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_nextjs_markets(&server, BUILD_ID).await;
+
+    // The previous run saved a build ID the frontend no longer serves
+    let storage = test_storage().await;
+    storage
+        .set_scraper_state("build_id", STALE_BUILD_ID)
+        .await
+        .unwrap();
+    let control = start_scraper(&storage, test_config(&server, ScrapeSource::NextJs));
+
+    // Each failed scrape retries with backoff; the third in a row triggers rediscovery
+    for _ in 0..3 {
+        assert!(control.trigger_scrape().await.is_err());
+    }
+    assert_eq!(control.status().build_id.as_deref(), Some(BUILD_ID));
+    assert_eq!(
+        storage
+            .get_scraper_state("build_id")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(BUILD_ID)
+    );
+
+    let summary = control.trigger_scrape().await.unwrap();
+    assert_eq!(summary.markets_fetched, 1);
+    assert!(storage.get_market_by_id("201").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_cancelled_scraper_stops() {
+    let server = MockServer::start().await;
+    mount_homepage(&server, BUILD_ID).await;
+    mount_json(
+        &server,
+        GAMMA_MARKETS_PATH,
+        json!([gamma_market("101", "Will it rain tomorrow")]),
+    )
+    .await;
+    mount_json(&server, GAMMA_EVENTS_PATH, json!([])).await;
+
+    let storage = test_storage().await;
+    let shutdown = CancellationToken::new();
+    let (control, handle) = spawn_scraper(
+        &storage,
+        test_config(&server, ScrapeSource::Gamma),
+        shutdown.clone(),
+    );
+    control.trigger_scrape().await.unwrap();
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("scraper should stop once cancelled")
+        .unwrap()
+        .unwrap();
+    assert!(control.trigger_scrape().await.is_err());
+    assert!(storage.get_market_by_id("101").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_metrics_counters_survive_restart() {
+    let storage = test_storage().await;
+    let metrics = Metrics::new();
+    metrics.record_scrape(true);
+    metrics.record_scrape(false);
+    metrics.record_markets_discovered(3);
+    metrics::save_snapshot(storage.as_ref(), &metrics).await;
+
+    // A restarted process continues counting from the snapshot
+    let restarted = Metrics::new();
+    metrics::restore_snapshot(storage.as_ref(), &restarted)
+        .await
+        .unwrap();
+    assert_eq!(restarted.snapshot(), metrics.snapshot());
+    restarted.record_scrape(true);
+    assert_eq!(restarted.get_total_scrapes(), 3);
+    assert_eq!(restarted.get_successful_scrapes(), 2);
+    assert_eq!(restarted.get_markets_discovered(), 3);
+}
+
+#[tokio::test]
+async fn test_scraper_lease_fails_over() {
+    let storage = test_storage().await;
+    let ttl = chrono::Duration::seconds(30);
+    let now = Utc::now();
+
+    // Only one replica holds the lease; the holder can renew it
+    assert!(storage.try_acquire_lease("scraper", "a", now, ttl).await.unwrap());
+    assert!(!storage.try_acquire_lease("scraper", "b", now, ttl).await.unwrap());
+    let renewed_at = now + chrono::Duration::seconds(10);
+    assert!(storage.try_acquire_lease("scraper", "a", renewed_at, ttl).await.unwrap());
+
+    // A leader that stops renewing is replaced once its lease expires
+    let expired_at = renewed_at + ttl;
+    assert!(storage.try_acquire_lease("scraper", "b", expired_at, ttl).await.unwrap());
+    assert!(!storage.try_acquire_lease("scraper", "a", expired_at, ttl).await.unwrap());
+
+    // A released lease is free right away, and only its holder can release it
+    storage.release_lease("scraper", "a").await.unwrap();
+    assert!(!storage.try_acquire_lease("scraper", "a", expired_at, ttl).await.unwrap());
+    storage.release_lease("scraper", "b").await.unwrap();
+    assert!(storage.try_acquire_lease("scraper", "a", expired_at, ttl).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_followers_leave_background_jobs_to_the_primary() {
+    let pools = db::test_pools().await;
+    let storage = SqliteStorage::new(pools.clone());
+    seed_market(&storage, "101").await;
+    storage
+        .insert_price_point("101", Some(dec!(0.5)), Some(dec!(100)), Utc::now())
+        .await
+        .unwrap();
+    let (control, _signals) = scraper::control(
+        3600,
+        ScrapeSource::Gamma,
+        BreakerConfig {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(60),
+        },
+    );
+    control.set_leader(false);
+
+    // A follower skips its ticks instead of scanning the price history
+    let aggregation = tokio::spawn(candles::run_aggregation(
+        Arc::clone(&pools.write),
+        1,
+        control.clone(),
+    ));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let hourly = storage.get_candles("101", CandleInterval::Hour, 10).await.unwrap();
+    assert!(hourly.is_empty());
+
+    // Once elected it takes the job over at its next tick
+    control.set_leader(true);
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    let hourly = storage.get_candles("101", CandleInterval::Hour, 10).await.unwrap();
+    assert_eq!(hourly.len(), 1);
+    aggregation.abort();
+}
+
+#[tokio::test]
+async fn test_shard_progress_survives_handover() {
+    let storage = test_storage().await;
+    let scraped_at = Utc::now() - chrono::Duration::seconds(90);
+    let progress = |index: u32, instance: &str, last_success_at| ShardProgress {
+        shard_index: index,
+        shard_count: 3,
+        instance_id: Some(instance.to_string()),
+        last_success_at,
+        updated_at: Some(Utc::now()),
+        lag_secs: None,
+    };
+    storage.record_shard_progress(&progress(1, "a", None)).await.unwrap();
+    storage.record_shard_progress(&progress(0, "a", Some(scraped_at))).await.unwrap();
+
+    // A new holder that hasn't scraped yet keeps the previous holder's last success
+    storage.record_shard_progress(&progress(0, "b", None)).await.unwrap();
+    let shards = storage.get_shard_progress().await.unwrap();
+    assert_eq!(shards.len(), 3);
+    assert_eq!(shards[0].instance_id.as_deref(), Some("b"));
+    assert_eq!(
+        shards[0].last_success_at.map(|at| at.timestamp()),
+        Some(scraped_at.timestamp())
+    );
+    assert!(shards[1].last_success_at.is_none());
+    // A shard no instance claimed is still listed
+    assert_eq!(shards[2].shard_index, 2);
+    assert!(shards[2].instance_id.is_none());
+    assert!(shards[2].updated_at.is_none());
+}
+
+#[tokio::test]
+async fn test_price_history_rolls_up_into_candles() {
+    let pools = db::test_pools().await;
+    let storage = SqliteStorage::new(pools.clone());
+    seed_market(&storage, "101").await;
+
+    // Earlier observations on a day of their own: (minutes past midnight, price, volume)
+    let day = DateTime::parse_from_rfc3339("2024-01-15T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    for (minutes, price, volume) in [(60, 5, 100), (600, 7, 150), (610, 4, 180), (1200, 6, 200)] {
+        storage
+            .insert_price_point(
+                "101",
+                Some(Decimal::new(price, 1)),
+                Some(Decimal::from(volume)),
+                day + chrono::Duration::minutes(minutes),
+            )
+            .await
+            .unwrap();
+    }
+    candles::aggregate(&pools.write).await.unwrap();
+
+    let daily = storage
+        .get_candles("101", CandleInterval::Day, 10)
+        .await
+        .unwrap();
+    assert_eq!(daily.len(), 1);
+    let candle = &daily[0];
+    assert_eq!(candle.start_at, day);
+    assert_eq!(candle.open, Some(dec!(0.5)));
+    assert_eq!(candle.high, Some(dec!(0.7)));
+    assert_eq!(candle.low, Some(dec!(0.4)));
+    assert_eq!(candle.close, Some(dec!(0.6)));
+    assert_eq!(candle.volume, Some(dec!(100)));
+    assert_eq!(candle.points, 4);
+
+    let hourly = storage
+        .get_candles("101", CandleInterval::Hour, 10)
+        .await
+        .unwrap();
+    let ten_am = hourly
+        .iter()
+        .find(|c| c.start_at == day + chrono::Duration::hours(10))
+        .unwrap();
+    assert_eq!((ten_am.open, ten_am.close, ten_am.points), (Some(dec!(0.7)), Some(dec!(0.4)), 2));
+}
+
+#[tokio::test]
+async fn test_volume_spikes_are_flagged_once() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+
+    // 2400 traded over the baseline day is 100 an hour; the last hour saw 600
+    let now = DateTime::parse_from_rfc3339("2024-01-16T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    for (minutes_ago, volume) in [(1490, 1000), (70, 3400), (30, 4000)] {
+        storage
+            .insert_price_point(
+                "101",
+                Some(Decimal::new(5, 1)),
+                Some(Decimal::from(volume)),
+                now - chrono::Duration::minutes(minutes_ago),
+            )
+            .await
+            .unwrap();
+    }
+    let strict = spikes::SpikeConfig {
+        window_secs: 3600,
+        multiplier: 10.0,
+        min_volume: 500.0,
+    };
+    assert!(spikes::detect(storage.as_ref(), &strict, now).await.unwrap().is_empty());
+
+    let config = spikes::SpikeConfig {
+        multiplier: 5.0,
+        ..strict
+    };
+    let flagged = spikes::detect(storage.as_ref(), &config, now).await.unwrap();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].market.id, "101");
+    assert_eq!(flagged[0].interval_volume, 600.0);
+    assert!((flagged[0].baseline_volume - 100.0).abs() < 1e-9);
+    assert!((flagged[0].ratio - 6.0).abs() < 1e-9);
+
+    // Already flagged within the window
+    let again = spikes::detect(storage.as_ref(), &config, now + chrono::Duration::minutes(1))
+        .await
+        .unwrap();
+    assert!(again.is_empty());
+
+    let listed = storage
+        .get_volume_spikes(now - chrono::Duration::hours(1), 10)
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].detected_at, now);
+}
+
+#[tokio::test]
+async fn test_daily_report() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+
+    let day = DateTime::parse_from_rfc3339("2024-01-15T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    for (hours, price, volume) in [(1, 5, 100), (12, 8, 250), (23, 7, 400)] {
+        storage
+            .insert_price_point(
+                "101",
+                Some(Decimal::new(price, 1)),
+                Some(Decimal::from(volume)),
+                day + chrono::Duration::hours(hours),
+            )
+            .await
+            .unwrap();
+    }
+
+    let report = reports::generate(storage.as_ref(), day.date_naive()).await.unwrap();
+    assert_eq!(report.new_market_count, 0);
+    assert_eq!(report.gainers.len(), 1);
+    assert_eq!(report.gainers[0].market.id, "101");
+    assert!((report.gainers[0].price_change - 0.2).abs() < 1e-9);
+    assert!(report.losers.is_empty());
+    assert_eq!(report.volume_leaders[0].volume_traded, 300.0);
+    storage.save_daily_report(&report).await.unwrap();
+    let stored = storage.get_daily_report(day.date_naive()).await.unwrap().unwrap();
+    assert_eq!(stored.volume_leaders[0].market.id, "101");
+
+    let market = storage.get_market_by_id("101").await.unwrap().unwrap();
+    let discovered = market.discovered_at.unwrap().date_naive();
+    let report = reports::generate(storage.as_ref(), discovered).await.unwrap();
+    assert_eq!(report.new_market_count, 1);
+    assert_eq!(report.new_markets[0].id, "101");
+}
+
+#[tokio::test]
+async fn test_resolved_markets_are_scored_for_calibration() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+
+    let resolved_at = DateTime::parse_from_rfc3339("2024-01-15T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    for (hours_before, price) in [(48, 6), (2, 8)] {
+        storage
+            .insert_price_point(
+                "101",
+                Some(Decimal::new(price, 1)),
+                None,
+                resolved_at - chrono::Duration::hours(hours_before),
+            )
+            .await
+            .unwrap();
+    }
+    storage
+        .update_market_resolution(&Market {
+            id: "101".to_string(),
+            status: Some(MarketStatus::Resolved),
+            winning_outcome: Some("Yes".to_string()),
+            resolved_at: Some(resolved_at),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(calibration::score_pending(storage.as_ref()).await.unwrap(), 1);
+    assert_eq!(calibration::score_pending(storage.as_ref()).await.unwrap(), 0);
+
+    let summary = storage.get_calibration().await.unwrap();
+    assert_eq!(summary.markets_scored, 1);
+    let [hour, day, week, _] = &summary.horizons[..] else {
+        panic!("expected a summary per horizon");
+    };
+    assert_eq!(hour.markets, 1);
+    assert!((hour.brier_score.unwrap() - 0.04).abs() < 1e-9);
+    assert_eq!(hour.buckets.len(), 1);
+    assert!((hour.buckets[0].lower - 0.8).abs() < 1e-9);
+    assert_eq!(hour.buckets[0].observed_frequency, 1.0);
+    assert!((day.brier_score.unwrap() - 0.16).abs() < 1e-9);
+    assert_eq!((week.markets, week.brier_score), (0, None));
+}
+
+#[tokio::test]
+async fn test_chain_confirmed_resolutions_outrank_the_api() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+    let resolved_at = DateTime::parse_from_rfc3339("2024-01-15T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let resolution = |status, winning_outcome: Option<&str>, resolved_at| Market {
+        id: "101".to_string(),
+        status: Some(status),
+        winning_outcome: winning_outcome.map(str::to_string),
+        resolved_at,
+        ..Default::default()
+    };
+    let chain_resolution = resolution(MarketStatus::Resolved, Some("No"), Some(resolved_at));
+    storage.update_market_resolution(&chain_resolution).await.unwrap();
+    storage
+        .save_onchain_verification(&OnchainVerification {
+            market_id: "101".to_string(),
+            resolved: true,
+            winning_outcome: Some("No".to_string()),
+            volume: None,
+            mismatch: None,
+            checked_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+    // A later listing that lost the resolution doesn't clear it
+    storage
+        .update_market_resolution(&resolution(MarketStatus::Closed, None, None))
+        .await
+        .unwrap();
+    let market = storage.get_market_by_id("101").await.unwrap().unwrap();
+    assert_eq!(market.status, Some(MarketStatus::Resolved));
+    assert_eq!(market.winning_outcome.as_deref(), Some("No"));
+    assert_eq!(market.resolved_at, Some(resolved_at));
+
+    // Archiving a resolved market still goes through
+    storage
+        .update_market_resolution(&resolution(MarketStatus::Archived, Some("Yes"), None))
+        .await
+        .unwrap();
+    let market = storage.get_market_by_id("101").await.unwrap().unwrap();
+    assert_eq!(market.status, Some(MarketStatus::Archived));
+    assert_eq!(market.winning_outcome.as_deref(), Some("No"));
+}
+
+#[tokio::test]
+async fn test_onchain_block_ranges_are_counted_once() {
+    let storage = test_storage().await;
+    seed_market(storage.as_ref(), "101").await;
+    let outcome = Outcome {
+        name: "Yes".to_string(),
+        token_id: Some("111".to_string()),
+        ..Default::default()
+    };
+    storage.upsert_outcomes("101", &[outcome]).await.unwrap();
+    let volumes = [("111".to_string(), 10.0)];
+    let key = "onchain_volume_block";
+
+    // Two scanners that started from the same checkpoint: only the first one counts
+    assert!(storage.add_onchain_volumes(&volumes, key, None, 100).await.unwrap());
+    assert!(!storage.add_onchain_volumes(&volumes, key, None, 100).await.unwrap());
+    assert!(storage.add_onchain_volumes(&volumes, key, Some(100), 200).await.unwrap());
+    assert!(!storage.add_onchain_volumes(&volumes, key, Some(100), 200).await.unwrap());
+
+    assert_eq!(storage.get_onchain_volume("101").await.unwrap(), Some(20.0));
+    assert_eq!(storage.get_scraper_state(key).await.unwrap().as_deref(), Some("200"));
+}
+
+#[tokio::test]
+async fn test_watchlist_markets() {
+    let storage = test_storage().await;
+    let ids = vec!["101".to_string(), "999".to_string()];
+    let created = storage.create_watchlist("rain", &ids).await.unwrap().unwrap();
+    assert_eq!(created.market_ids, ids);
+    assert!(storage.create_watchlist("rain", &[]).await.unwrap().is_none());
+    seed_market(storage.as_ref(), "101").await;
+
+    // Markets not stored yet are refreshed too, markets of other venues aren't
+    assert_eq!(
+        storage.get_watchlisted_market_ids("polymarket").await.unwrap(),
+        ids
+    );
+    assert_eq!(
+        storage.get_watchlisted_market_ids("manifold").await.unwrap(),
+        vec!["999".to_string()]
+    );
+
+    let updated = storage
+        .set_watchlist_markets("rain", &["999".to_string()])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.market_ids, vec!["999".to_string()]);
+    assert_eq!(storage.get_watchlists().await.unwrap().len(), 1);
+
+    assert!(storage.delete_watchlist("rain").await.unwrap());
+    assert!(storage.get_watchlist("rain").await.unwrap().is_none());
+    assert!(storage.get_watchlisted_market_ids("polymarket").await.unwrap().is_empty());
+}
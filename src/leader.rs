@@ -0,0 +1,184 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+
+use crate::metrics::Metrics;
+use crate::models::ShardProgress;
+use crate::scraper::ScraperControl;
+use crate::shards::Shard;
+use crate::storage::Storage;
+
+pub const DEFAULT_LEASE_SECS: u64 = 30;
+/// Lease the replicas sharing a database compete for; its holder runs scheduled scrapes
+const SCRAPER_LEASE: &str = "scraper";
+
+/// Leader election between replicas sharing one database
+#[derive(Debug, Clone)]
+pub struct LeaderConfig {
+    /// Name this instance holds the lease under, unique among the replicas
+    pub instance_id: String,
+    /// How long a lease lasts without renewal, i.e. how long a dead leader blocks failover
+    pub lease_ttl: Duration,
+    /// Shards the market universe is split into, each scraped by the holder of its own
+    /// lease; 1 elects a single scraper
+    pub shards: u32,
+}
+
+/// Host name and process ID, unique among replicas on one host and across containers
+pub fn default_instance_id() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+    format!("{}-{}", host, std::process::id())
+}
+
+/// Lease held by the scraper of `shard`
+fn lease_name(shard: Shard) -> String {
+    if shard.is_all() {
+        SCRAPER_LEASE.to_string()
+    } else {
+        format!("{}-shard-{}", SCRAPER_LEASE, shard.index)
+    }
+}
+
+/// Compete for a scraper lease, renewing it every third of its TTL while held, and start or
+/// stop scheduled scrapes of its shard through `scraper` as it is won or lost
+/// An instance holds at most one shard; one without a shard takes over the first whose
+/// lease has expired. While it holds one, its progress is recorded for the `/metrics` view,
+/// and the holder of the first shard logs an error when other shards go unclaimed
+/// A replica that can't reach the database steps down; its lease then expires and another
+/// replica takes over
+pub async fn run_election(
+    storage: Arc<dyn Storage>,
+    config: LeaderConfig,
+    scraper: ScraperControl,
+    metrics: Arc<Metrics>,
+) {
+    info!(
+        "Competing for {} scraper lease(s) as {} with a {} second TTL",
+        config.shards,
+        config.instance_id,
+        config.lease_ttl.as_secs()
+    );
+
+    let ttl = chrono::Duration::from_std(config.lease_ttl)
+        .unwrap_or_else(|_| chrono::Duration::seconds(DEFAULT_LEASE_SECS as i64));
+    let mut interval = tokio::time::interval(config.lease_ttl / 3);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut unclaimed = Vec::new();
+    loop {
+        interval.tick().await;
+        let held = scraper.is_leader().then(|| scraper.shard());
+        let candidates: Vec<Shard> = match held {
+            Some(shard) => vec![shard],
+            None => (0..config.shards)
+                .map(|index| Shard {
+                    index,
+                    count: config.shards,
+                })
+                .collect(),
+        };
+
+        let mut acquired = None;
+        for shard in candidates {
+            let lease = lease_name(shard);
+            match storage
+                .try_acquire_lease(&lease, &config.instance_id, Utc::now(), ttl)
+                .await
+            {
+                Ok(true) => {
+                    acquired = Some(shard);
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("Could not renew the {} lease: {}", lease, e);
+                    break;
+                }
+            }
+        }
+
+        match (held, acquired) {
+            (None, Some(shard)) if shard.is_all() => {
+                info!("Elected scraper leader, starting scheduled scrapes")
+            }
+            (None, Some(shard)) => {
+                info!("Took over shard {}, starting scheduled scrapes", shard)
+            }
+            (Some(_), None) => warn!("Lost the scraper lease, stopping scheduled scrapes"),
+            _ => {}
+        }
+        if let Some(shard) = acquired {
+            scraper.set_shard(shard);
+        }
+        scraper.set_leader(acquired.is_some());
+
+        if let (Some(shard), Some(since)) = (acquired, scraper.leader_since()) {
+            let progress = ShardProgress {
+                shard_index: shard.index,
+                shard_count: shard.count,
+                instance_id: Some(config.instance_id.clone()),
+                // Scrapes from before this instance took the shard may have been of another
+                last_success_at: metrics.get_last_success_time().filter(|&at| at >= since),
+                updated_at: Some(Utc::now()),
+                lag_secs: None,
+            };
+            if let Err(e) = storage.record_shard_progress(&progress).await {
+                warn!("Could not record the progress of shard {}: {}", shard, e);
+            }
+        }
+
+        // The first shard's holder watches over the others, which go unscraped while
+        // there are fewer replicas than shards; the other holders get a TTL to report first
+        let settled = scraper
+            .leader_since()
+            .is_some_and(|since| Utc::now() - since >= ttl);
+        if scraper.is_primary() && config.shards > 1 && settled {
+            let now_unclaimed = unclaimed_shards(storage.as_ref(), config.shards, ttl).await;
+            if now_unclaimed != unclaimed {
+                if now_unclaimed.is_empty() {
+                    info!("Every shard has a live holder again");
+                } else {
+                    error!(
+                        "Shards {:?} of {} have no live holder and aren't scraped; run at least {} replicas",
+                        now_unclaimed, config.shards, config.shards
+                    );
+                }
+            }
+            unclaimed = now_unclaimed;
+        }
+    }
+}
+
+/// Indices of the shards whose holder hasn't reported within a lease TTL, or never has
+async fn unclaimed_shards(storage: &dyn Storage, shards: u32, ttl: chrono::Duration) -> Vec<u32> {
+    let progress = match storage.get_shard_progress().await {
+        Ok(progress) => progress,
+        Err(e) => {
+            warn!("Could not load the progress of the shards: {}", e);
+            return Vec::new();
+        }
+    };
+    let live_since = Utc::now() - ttl;
+    (0..shards)
+        .filter(|&index| {
+            !progress.iter().any(|shard| {
+                shard.shard_count == shards
+                    && shard.shard_index == index
+                    && shard.updated_at.is_some_and(|at| at >= live_since)
+            })
+        })
+        .collect()
+}
+
+/// Give up the scraper lease held, if any, on shutdown so another replica takes over right
+/// away instead of after the TTL
+pub async fn release(storage: &dyn Storage, config: &LeaderConfig, scraper: &ScraperControl) {
+    if !scraper.is_leader() {
+        return;
+    }
+    let lease = lease_name(scraper.shard());
+    if let Err(e) = storage.release_lease(&lease, &config.instance_id).await {
+        warn!("Could not release the {} lease: {}", lease, e);
+    }
+}
@@ -0,0 +1,73 @@
+//! Polymarket market scraper with REST, GraphQL and WebSocket APIs over the scraped data
+//!
+//! The `polymarket-scraper` binary is a thin command line over this crate. To embed the
+//! scraper in another service instead, open the database with [`init_db`] and wrap the
+//! pools in a [`SqliteStorage`] (or bring your own [`Storage`] implementation), create the
+//! control handle with [`control`] and spawn [`run_scraper`] with its signals, then mount
+//! [`create_router`] in your own axum server.
+
+mod analytics;
+pub mod api;
+mod archive;
+mod auth;
+pub mod cache;
+mod calibration;
+mod candles;
+pub mod circuit_breaker;
+pub mod cli;
+mod clob;
+#[cfg(feature = "clob-ws")]
+mod clob_ws;
+pub mod commands;
+mod conditional;
+pub mod db;
+pub mod error;
+mod export;
+pub mod filters;
+pub mod fixtures;
+mod gamma;
+mod graphql;
+mod holders;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(test)]
+mod integration_tests;
+mod leader;
+#[cfg(feature = "manifold")]
+mod manifold;
+pub mod metrics;
+pub mod models;
+mod notify;
+#[cfg(feature = "onchain")]
+mod onchain;
+pub mod rate_limit;
+mod reports;
+mod retention;
+mod rules;
+pub mod scraper;
+pub mod shards;
+#[cfg(feature = "s3")]
+mod snapshot;
+mod source;
+mod spikes;
+mod subgraph;
+pub mod storage;
+pub mod telemetry;
+pub mod throttle;
+pub mod tiers;
+mod trades;
+pub mod updates;
+mod validation;
+mod watchdog;
+
+pub use api::create_router;
+pub use db::{init_db, DbOptions, DbPools};
+pub use error::{Error, ErrorKind};
+pub use metrics::Metrics;
+pub use scraper::{
+    control, run_scraper, scrape_once, ScrapeSource, ScraperConfig, ScraperControl,
+    ScraperSignals,
+};
+pub use storage::{SqliteStorage, Storage};
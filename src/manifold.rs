@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tracing::info;
+
+use crate::error::{Result, UpstreamContext};
+use crate::metrics::{Metrics, ScrapePhase};
+use crate::models::{Market, MarketStatus, Outcome};
+use crate::source::{FetchContext, FetchedMarkets, MarketSource, PageSink};
+use crate::throttle::ThrottledClient;
+use crate::validation::RejectReason;
+
+// Manifold Markets public API
+const MANIFOLD_SEARCH_URL: &str = "https://api.manifold.markets/v0/search-markets";
+const MANIFOLD_VENUE: &str = "manifold";
+const MAX_PAGE_SIZE: u32 = 1000; // Largest page the search endpoint serves
+
+/// Query parameters of `GET /v0/search-markets`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchQuery {
+    term: &'static str,
+    filter: &'static str,
+    sort: &'static str,
+    contract_type: &'static str,
+    limit: u32,
+    offset: u32,
+}
+
+/// Binary market as returned by the Manifold API (`LiteMarket`)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifoldMarket {
+    pub id: String,
+    pub slug: Option<String>,
+    pub question: Option<String>,
+    pub text_description: Option<String>,
+    pub cover_image_url: Option<String>,
+    pub probability: Option<f64>,
+    pub volume: Option<f64>,
+    #[serde(rename = "volume24Hours")]
+    pub volume_24_hours: Option<f64>,
+    pub total_liquidity: Option<f64>,
+    /// Epoch milliseconds
+    pub close_time: Option<i64>,
+    #[serde(default)]
+    pub is_resolved: bool,
+    /// `YES`, `NO`, `MKT` (resolved to a probability) or `CANCEL`
+    pub resolution: Option<String>,
+    pub resolution_time: Option<i64>,
+}
+
+impl ManifoldMarket {
+    /// Derive lifecycle status, winning outcome and resolution time
+    fn resolution_state(&self) -> (MarketStatus, Option<String>, Option<DateTime<Utc>>) {
+        if self.is_resolved {
+            let winning_outcome = self
+                .resolution
+                .as_deref()
+                .map(|resolution| match resolution {
+                    "YES" => "Yes".to_string(),
+                    "NO" => "No".to_string(),
+                    other => other.to_string(),
+                });
+            let resolved_at = self
+                .resolution_time
+                .and_then(DateTime::from_timestamp_millis);
+            return (MarketStatus::Resolved, winning_outcome, resolved_at);
+        }
+
+        let closed = self
+            .close_time
+            .is_some_and(|ms| ms < Utc::now().timestamp_millis());
+        let status = if closed {
+            MarketStatus::Closed
+        } else {
+            MarketStatus::Active
+        };
+        (status, None, None)
+    }
+}
+
+impl TryFrom<ManifoldMarket> for Market {
+    type Error = RejectReason;
+
+    fn try_from(mm: ManifoldMarket) -> Result<Self, RejectReason> {
+        if mm.id.trim().is_empty() {
+            return Err(RejectReason::MissingId);
+        }
+        let end_date = mm
+            .close_time
+            .map(|ms| DateTime::from_timestamp_millis(ms).ok_or(RejectReason::UnparsableDate))
+            .transpose()?;
+        let (status, winning_outcome, resolved_at) = mm.resolution_state();
+        let outcomes = mm
+            .probability
+            .and_then(Decimal::from_f64)
+            .map(|p| {
+                vec![
+                    Outcome {
+                        name: "Yes".to_string(),
+                        price: Some(p),
+                        ..Default::default()
+                    },
+                    Outcome {
+                        name: "No".to_string(),
+                        price: Some(Decimal::ONE - p),
+                        ..Default::default()
+                    },
+                ]
+            })
+            .unwrap_or_default();
+
+        Ok(Market {
+            // Prefixed so IDs can't collide with Polymarket's numeric ones
+            id: format!("{}:{}", MANIFOLD_VENUE, mm.id),
+            slug: mm.slug,
+            title: mm.question.unwrap_or_else(|| "Untitled Market".to_string()),
+            description: mm.text_description,
+            image: mm.cover_image_url,
+            current_price: mm.probability.and_then(Decimal::from_f64),
+            volume: mm.volume.and_then(Decimal::from_f64),
+            volume_24hr: mm.volume_24_hours.and_then(Decimal::from_f64),
+            liquidity: mm.total_liquidity.and_then(Decimal::from_f64),
+            end_date,
+            status: Some(status),
+            winning_outcome,
+            resolved_at,
+            outcomes,
+            ..Default::default()
+        })
+    }
+}
+
+/// Fetch a page of binary markets, most recently updated first, as raw JSON
+async fn fetch_markets(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<serde_json::Value>> {
+    let query = SearchQuery {
+        term: "",
+        filter: "all",
+        sort: "last-updated",
+        contract_type: "BINARY",
+        limit,
+        offset,
+    };
+
+    info!(
+        "Fetching markets from Manifold API with limit={}, offset={}",
+        limit, offset
+    );
+
+    let fetch_started = Instant::now();
+    let request = client
+        .get(MANIFOLD_SEARCH_URL)
+        .query(&query)
+        .header("Accept", "application/json");
+    let body = client
+        .send(request)
+        .await
+        .http_context("Failed to fetch from Manifold API")?
+        .error_for_status()
+        .http_context("Manifold API returned an error status")?
+        .bytes()
+        .await
+        .http_context("Failed to read Manifold API response")?;
+    metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+
+    let parse_started = Instant::now();
+    let parsed =
+        serde_json::from_slice(&body).schema_context("Failed to parse Manifold API response")?;
+    metrics.record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+
+    Ok(parsed)
+}
+
+/// Manifold Markets (`api.manifold.markets`) as a market source
+/// Each scrape walks the most recently updated binary markets, so closures and
+/// resolutions are picked up by regular scrapes instead of the resolution pass
+pub struct ManifoldSource;
+
+#[async_trait]
+impl MarketSource for ManifoldSource {
+    fn name(&self) -> &'static str {
+        MANIFOLD_VENUE
+    }
+
+    fn venue(&self) -> &'static str {
+        MANIFOLD_VENUE
+    }
+
+    async fn fetch_markets(
+        &self,
+        ctx: &FetchContext<'_>,
+        pages: &PageSink,
+    ) -> Result<FetchedMarkets> {
+        let page_size = ctx.page_size.min(MAX_PAGE_SIZE);
+        let mut fetched = 0;
+
+        for page in 0..ctx.max_pages {
+            let offset = page.saturating_mul(page_size);
+            let raw = fetch_markets(ctx.client, ctx.metrics, page_size, offset).await?;
+            let is_last_page = (raw.len() as u32) < page_size;
+            fetched += raw.len();
+            pages
+                .send(FetchedMarkets {
+                    raw,
+                    ..Default::default()
+                })
+                .await;
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        info!("Fetched {} markets from Manifold", fetched);
+        // The listing spans every market ever created, so it is never walked to the end
+        Ok(FetchedMarkets {
+            complete: false,
+            ..Default::default()
+        })
+    }
+
+    fn parse_market(&self, raw: &serde_json::Value) -> Result<Market> {
+        let market =
+            ManifoldMarket::deserialize(raw).schema_context("Failed to parse Manifold market")?;
+        Ok(Market::try_from(market)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_manifold_market() {
+        let raw = serde_json::json!({
+            "id": "abc123XYZ",
+            "slug": "will-it-rain-tomorrow",
+            "question": "Will it rain tomorrow?",
+            "outcomeType": "BINARY",
+            "probability": 0.25,
+            "volume": 1520.5,
+            "volume24Hours": 80,
+            "totalLiquidity": 300,
+            "closeTime": 4102444800000_i64,
+            "isResolved": false
+        });
+
+        let market = ManifoldSource.parse_market(&raw).unwrap();
+        assert_eq!(market.id, "manifold:abc123XYZ");
+        assert_eq!(market.slug.as_deref(), Some("will-it-rain-tomorrow"));
+        assert_eq!(market.current_price, Some(dec!(0.25)));
+        assert_eq!(market.volume_24hr, Some(dec!(80)));
+        assert_eq!(market.status, Some(MarketStatus::Active));
+        assert_eq!(market.outcomes.len(), 2);
+        assert_eq!(market.outcomes[1].price, Some(dec!(0.75)));
+        assert_eq!(
+            market.end_date.unwrap().to_rfc3339(),
+            "2100-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_resolved_manifold_market() {
+        let raw = serde_json::json!({
+            "id": "def456",
+            "question": "Resolved?",
+            "probability": 1,
+            "closeTime": 1700000000000_i64,
+            "isResolved": true,
+            "resolution": "YES",
+            "resolutionTime": 1700000100000_i64
+        });
+
+        let market = ManifoldSource.parse_market(&raw).unwrap();
+        assert_eq!(market.status, Some(MarketStatus::Resolved));
+        assert_eq!(market.winning_outcome.as_deref(), Some("Yes"));
+        assert!(market.resolved_at.is_some());
+
+        let missing_id = serde_json::json!({ "id": "", "question": "No ID" });
+        let error = ManifoldSource.parse_market(&missing_id).unwrap_err();
+        assert_eq!(
+            crate::error::causes(&error).find_map(|cause| cause.downcast_ref::<RejectReason>()),
+            Some(&RejectReason::MissingId)
+        );
+    }
+}
@@ -1,6 +1,309 @@
+use anyhow::Result;
 use chrono::Utc;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::error::{self, ErrorKind};
+use crate::storage::Storage;
+use crate::validation::RejectReason;
+
+pub const DEFAULT_SNAPSHOT_INTERVAL_SECS: u64 = 60;
+
+// Prometheus metric names
+pub const SCRAPES_TOTAL: &str = "polymarket_scrapes_total";
+pub const SCRAPE_DURATION_SECONDS: &str = "polymarket_scrape_duration_seconds";
+pub const SCRAPE_PHASE_DURATION_SECONDS: &str = "polymarket_scrape_phase_duration_seconds";
+pub const MARKETS: &str = "polymarket_markets";
+pub const MARKETS_DISCOVERED_TOTAL: &str = "polymarket_markets_discovered_total";
+pub const HTTP_REQUESTS_TOTAL: &str = "polymarket_http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "polymarket_http_request_duration_seconds";
+pub const HTTP_RATE_LIMITED_TOTAL: &str = "polymarket_http_rate_limited_total";
+pub const PARSE_FALLBACKS_TOTAL: &str = "polymarket_parse_fallbacks_total";
+pub const MARKETS_REJECTED_TOTAL: &str = "polymarket_markets_rejected_total";
+pub const MARKETS_FILTERED_TOTAL: &str = "polymarket_markets_filtered_total";
+pub const SCRAPE_ERRORS_TOTAL: &str = "polymarket_scrape_errors_total";
+pub const UPSTREAM_THROTTLED_TOTAL: &str = "polymarket_upstream_throttled_total";
+pub const UPSTREAM_REQUEST_RATE: &str = "polymarket_upstream_request_rate";
+pub const UPSTREAM_CIRCUIT_STATE: &str = "polymarket_upstream_circuit_state";
+pub const UPSTREAM_NOT_MODIFIED_TOTAL: &str = "polymarket_upstream_not_modified_total";
+pub const SCRAPER_HEALTH: &str = "polymarket_scraper_health";
+pub const SCRAPER_RESTARTS_TOTAL: &str = "polymarket_scraper_restarts_total";
+pub const SCRAPER_STALLED: &str = "polymarket_scraper_stalled";
+pub const SCRAPER_LEADER: &str = "polymarket_scraper_leader";
+pub const PIPELINE_BATCHES_TOTAL: &str = "polymarket_pipeline_batches_total";
+pub const PIPELINE_QUEUE_DEPTH: &str = "polymarket_pipeline_queue_depth";
+pub const PIPELINE_WAIT_SECONDS: &str = "polymarket_pipeline_wait_seconds";
+pub const TIER_MARKETS: &str = "polymarket_tier_markets";
+pub const VOLUME_SPIKES_TOTAL: &str = "polymarket_volume_spikes_total";
+pub const TRADES_STORED_TOTAL: &str = "polymarket_trades_stored_total";
+pub const COMMENTS_STORED_TOTAL: &str = "polymarket_comments_stored_total";
+pub const COMMENTS_LAST_HOUR: &str = "polymarket_comments_last_hour";
+pub const POSITIONS_STORED_TOTAL: &str = "polymarket_positions_stored_total";
+pub const ORDERBOOK_SNAPSHOTS_TOTAL: &str = "polymarket_orderbook_snapshots_total";
+pub const ONCHAIN_MISMATCHES_TOTAL: &str = "polymarket_onchain_mismatches_total";
+
+/// Histogram buckets (seconds) shared by all duration metrics
+const DURATION_BUCKETS_SECS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Install the global Prometheus recorder and describe the exported metrics
+/// Must be called once at startup, before any metrics are recorded
+pub fn install_prometheus_recorder() -> Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets(DURATION_BUCKETS_SECS)?
+        .install_recorder()?;
+
+    ::metrics::describe_counter!(SCRAPES_TOTAL, "Scrape cycles by result (success/failure)");
+    ::metrics::describe_histogram!(
+        SCRAPE_DURATION_SECONDS,
+        ::metrics::Unit::Seconds,
+        "Duration of a full scrape cycle including retries"
+    );
+    ::metrics::describe_histogram!(
+        SCRAPE_PHASE_DURATION_SECONDS,
+        ::metrics::Unit::Seconds,
+        "Duration of individual scrape phases (fetch, parse, db_write, enrich)"
+    );
+    ::metrics::describe_gauge!(MARKETS, "Markets stored in the database by status");
+    ::metrics::describe_counter!(MARKETS_DISCOVERED_TOTAL, "Markets stored for the first time");
+    ::metrics::describe_counter!(HTTP_REQUESTS_TOTAL, "HTTP requests by method, route and status");
+    ::metrics::describe_histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        ::metrics::Unit::Seconds,
+        "HTTP request latency by method and route"
+    );
+    ::metrics::describe_counter!(
+        HTTP_RATE_LIMITED_TOTAL,
+        "HTTP requests rejected by the per-client rate limit, by route"
+    );
+    ::metrics::describe_counter!(
+        PARSE_FALLBACKS_TOTAL,
+        "Next.js payloads in an unrecognised shape, by shape (response/market)"
+    );
+    ::metrics::describe_counter!(
+        MARKETS_REJECTED_TOTAL,
+        "Scraped markets skipped by validation, by reason"
+    );
+    ::metrics::describe_counter!(
+        MARKETS_FILTERED_TOTAL,
+        "Scraped markets not stored because of the ingest filter, by rule"
+    );
+    ::metrics::describe_counter!(
+        SCRAPE_ERRORS_TOTAL,
+        "Scrape errors by category (network/http_status/parse/rejected/database)"
+    );
+    ::metrics::describe_counter!(
+        UPSTREAM_THROTTLED_TOTAL,
+        "Upstream API responses asking us to slow down, by status (429/503)"
+    );
+    ::metrics::describe_gauge!(
+        UPSTREAM_REQUEST_RATE,
+        "Current outbound request rate limit in requests per second"
+    );
+    ::metrics::describe_gauge!(
+        UPSTREAM_CIRCUIT_STATE,
+        "Upstream circuit breaker state, 1 for the current state (closed/open/half_open)"
+    );
+    ::metrics::describe_counter!(
+        UPSTREAM_NOT_MODIFIED_TOTAL,
+        "Upstream listing requests answered 304 Not Modified"
+    );
+    ::metrics::describe_gauge!(
+        SCRAPER_HEALTH,
+        "Scraper task health, 1 for the current state (running/restarting/dead)"
+    );
+    ::metrics::describe_counter!(SCRAPER_RESTARTS_TOTAL, "Scraper task restarts after a failure");
+    ::metrics::describe_gauge!(
+        SCRAPER_STALLED,
+        "1 while no scrape has succeeded for the configured number of intervals"
+    );
+    ::metrics::describe_gauge!(
+        SCRAPER_LEADER,
+        "1 while this instance holds the scraper lease and runs scheduled scrapes"
+    );
+    ::metrics::describe_counter!(
+        PIPELINE_BATCHES_TOTAL,
+        "Batches of parsed markets handled by each scrape pipeline stage (fetch/write)"
+    );
+    ::metrics::describe_gauge!(
+        TIER_MARKETS,
+        "Markets in each scrape tier (hot/long_tail); long-tail counts those stored so far"
+    );
+    ::metrics::describe_counter!(
+        VOLUME_SPIKES_TOTAL,
+        "Markets flagged for trading far more volume than their 24h average"
+    );
+    ::metrics::describe_counter!(
+        TRADES_STORED_TOTAL,
+        "Trades of hot markets stored from the data API, not counting ones seen before"
+    );
+    ::metrics::describe_counter!(
+        COMMENTS_STORED_TOTAL,
+        "Comments on the events of hot markets stored from the Gamma API"
+    );
+    ::metrics::describe_gauge!(
+        COMMENTS_LAST_HOUR,
+        "Stored comments posted in the last hour, across the events of hot markets"
+    );
+    ::metrics::describe_counter!(
+        POSITIONS_STORED_TOTAL,
+        "Top-holder positions of hot markets stored from the data API"
+    );
+    ::metrics::describe_counter!(
+        ORDERBOOK_SNAPSHOTS_TOTAL,
+        "Orderbook snapshots of hot markets captured from the CLOB"
+    );
+    ::metrics::describe_counter!(
+        ONCHAIN_MISMATCHES_TOTAL,
+        "Markets whose scraped resolution or volume disagreed with Polygon, by field"
+    );
+    ::metrics::describe_gauge!(
+        PIPELINE_QUEUE_DEPTH,
+        "Parsed batches waiting between the fetch and write stages"
+    );
+    ::metrics::describe_histogram!(
+        PIPELINE_WAIT_SECONDS,
+        ::metrics::Unit::Seconds,
+        "Time a pipeline stage spent blocked: fetch on a full queue, write on an empty one"
+    );
+
+    Ok(handle)
+}
+
+/// Phase of a scrape cycle, used to attribute latency to Polymarket or SQLite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrapePhase {
+    /// HTTP request and body download
+    Fetch,
+    /// JSON deserialization
+    Parse,
+    /// Database writes
+    DbWrite,
+    /// Per-market detail and orderbook requests after the list scrape
+    Enrich,
+}
+
+impl ScrapePhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScrapePhase::Fetch => "fetch",
+            ScrapePhase::Parse => "parse",
+            ScrapePhase::DbWrite => "db_write",
+            ScrapePhase::Enrich => "enrich",
+        }
+    }
+}
+
+/// Stage of the scrape pipeline, connected by a bounded channel of parsed batches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStage {
+    /// Fetches and parses market listings
+    Fetch,
+    /// Stores parsed markets in the database
+    Write,
+}
+
+impl PipelineStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PipelineStage::Fetch => "fetch",
+            PipelineStage::Write => "write",
+        }
+    }
+}
+
+/// What kind of failure a scrape error was, to tell an upstream schema change from a full disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Connection failures, timeouts and truncated responses
+    Network,
+    /// Upstream responses with an unsuccessful status
+    HttpStatus,
+    /// Upstream bodies that aren't the JSON we expect
+    Parse,
+    /// Individual markets skipped by validation
+    Rejected,
+    /// Failed database reads and writes
+    Database,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 5] = [
+        ErrorCategory::Network,
+        ErrorCategory::HttpStatus,
+        ErrorCategory::Parse,
+        ErrorCategory::Rejected,
+        ErrorCategory::Database,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCategory::Network => "network",
+            ErrorCategory::HttpStatus => "http_status",
+            ErrorCategory::Parse => "parse",
+            ErrorCategory::Rejected => "rejected",
+            ErrorCategory::Database => "database",
+        }
+    }
+
+    /// Category of an error by its kind; errors we can't classify, and configuration
+    /// errors, have none
+    pub fn of(error: &(dyn std::error::Error + Send + Sync + 'static)) -> Option<Self> {
+        Some(match ErrorKind::of(error)? {
+            ErrorKind::UpstreamHttp if error::status(error).is_none() => ErrorCategory::Network,
+            ErrorKind::UpstreamHttp | ErrorKind::RateLimited => ErrorCategory::HttpStatus,
+            ErrorKind::UpstreamSchema
+                if error::causes(error).any(|cause| cause.is::<RejectReason>()) =>
+            {
+                ErrorCategory::Rejected
+            }
+            ErrorKind::UpstreamSchema => ErrorCategory::Parse,
+            ErrorKind::Storage => ErrorCategory::Database,
+            ErrorKind::Config => return None,
+        })
+    }
+}
+
+/// Cumulative counters persisted in the `metrics_snapshot` table, so they survive restarts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub total_scrapes: u64,
+    pub successful_scrapes: u64,
+    pub failed_scrapes: u64,
+    pub markets_discovered: u64,
+}
+
+impl MetricsSnapshot {
+    /// Counters by the name they are stored under
+    pub fn counters(&self) -> [(&'static str, u64); 4] {
+        [
+            ("total_scrapes", self.total_scrapes),
+            ("successful_scrapes", self.successful_scrapes),
+            ("failed_scrapes", self.failed_scrapes),
+            ("markets_discovered", self.markets_discovered),
+        ]
+    }
+
+    /// Inverse of `counters`; unknown names are ignored and missing ones stay zero
+    pub fn from_counters(counters: impl IntoIterator<Item = (String, u64)>) -> Self {
+        let mut snapshot = Self::default();
+        for (name, value) in counters {
+            match name.as_str() {
+                "total_scrapes" => snapshot.total_scrapes = value,
+                "successful_scrapes" => snapshot.successful_scrapes = value,
+                "failed_scrapes" => snapshot.failed_scrapes = value,
+                "markets_discovered" => snapshot.markets_discovered = value,
+                _ => {}
+            }
+        }
+        snapshot
+    }
+}
 
 /// Shared metrics state
 #[derive(Clone)]
@@ -8,7 +311,15 @@ pub struct Metrics {
     total_scrapes: Arc<AtomicU64>,
     successful_scrapes: Arc<AtomicU64>,
     failed_scrapes: Arc<AtomicU64>,
+    markets_discovered: Arc<AtomicU64>,
     last_scrape_time: Arc<Mutex<Option<chrono::DateTime<Utc>>>>,
+    last_success_time: Arc<Mutex<Option<chrono::DateTime<Utc>>>>,
+    /// Rejected markets, indexed like `RejectReason::ALL`
+    rejected_markets: Arc<[AtomicU64; RejectReason::ALL.len()]>,
+    /// Scrape errors, indexed like `ErrorCategory::ALL`
+    errors: Arc<[AtomicU64; ErrorCategory::ALL.len()]>,
+    started_at: chrono::DateTime<Utc>,
+    prometheus: Option<PrometheusHandle>,
 }
 
 impl Metrics {
@@ -17,10 +328,27 @@ impl Metrics {
             total_scrapes: Arc::new(AtomicU64::new(0)),
             successful_scrapes: Arc::new(AtomicU64::new(0)),
             failed_scrapes: Arc::new(AtomicU64::new(0)),
+            markets_discovered: Arc::new(AtomicU64::new(0)),
             last_scrape_time: Arc::new(Mutex::new(None)),
+            last_success_time: Arc::new(Mutex::new(None)),
+            rejected_markets: Arc::new(Default::default()),
+            errors: Arc::new(Default::default()),
+            started_at: Utc::now(),
+            prometheus: None,
         }
     }
 
+    /// Attach the Prometheus handle used to render `/metrics/prometheus`
+    pub fn with_prometheus(mut self, handle: PrometheusHandle) -> Self {
+        self.prometheus = Some(handle);
+        self
+    }
+
+    /// Render all recorded metrics in Prometheus text format
+    pub fn render_prometheus(&self) -> Option<String> {
+        self.prometheus.as_ref().map(|handle| handle.render())
+    }
+
     pub fn record_scrape(&self, success: bool) {
         self.total_scrapes.fetch_add(1, Ordering::Relaxed);
         if success {
@@ -28,13 +356,81 @@ impl Metrics {
         } else {
             self.failed_scrapes.fetch_add(1, Ordering::Relaxed);
         }
+        let result = if success { "success" } else { "failure" };
+        ::metrics::counter!(SCRAPES_TOTAL, "result" => result).increment(1);
         
         // Update timestamp - quick operation, safe to use blocking Mutex
+        let now = Utc::now();
         if let Ok(mut last_time) = self.last_scrape_time.lock() {
-            *last_time = Some(Utc::now());
+            *last_time = Some(now);
+        }
+        if success {
+            if let Ok(mut last_success) = self.last_success_time.lock() {
+                *last_success = Some(now);
+            }
         }
     }
 
+    pub fn record_markets_discovered(&self, count: u64) {
+        self.markets_discovered.fetch_add(count, Ordering::Relaxed);
+        ::metrics::counter!(MARKETS_DISCOVERED_TOTAL).increment(count);
+    }
+
+    pub fn record_scrape_duration(&self, duration: Duration) {
+        ::metrics::histogram!(SCRAPE_DURATION_SECONDS).record(duration.as_secs_f64());
+    }
+
+    pub fn record_phase_duration(&self, phase: ScrapePhase, duration: Duration) {
+        ::metrics::histogram!(SCRAPE_PHASE_DURATION_SECONDS, "phase" => phase.as_str())
+            .record(duration.as_secs_f64());
+    }
+
+    /// The fetch stage queued a batch of parsed markets or the write stage took one, after
+    /// waiting `waited` for room in the queue or for the batch respectively
+    pub fn record_pipeline_batch(&self, stage: PipelineStage, waited: Duration) {
+        ::metrics::counter!(PIPELINE_BATCHES_TOTAL, "stage" => stage.as_str()).increment(1);
+        ::metrics::histogram!(PIPELINE_WAIT_SECONDS, "stage" => stage.as_str())
+            .record(waited.as_secs_f64());
+        match stage {
+            PipelineStage::Fetch => ::metrics::gauge!(PIPELINE_QUEUE_DEPTH).increment(1.0),
+            PipelineStage::Write => ::metrics::gauge!(PIPELINE_QUEUE_DEPTH).decrement(1.0),
+        }
+    }
+
+    pub fn record_rejection(&self, reason: RejectReason) {
+        self.rejected_markets[reason as usize].fetch_add(1, Ordering::Relaxed);
+        ::metrics::counter!(MARKETS_REJECTED_TOTAL, "reason" => reason.as_str()).increment(1);
+        self.record_error(ErrorCategory::Rejected);
+    }
+
+    pub fn record_error(&self, category: ErrorCategory) {
+        self.errors[category as usize].fetch_add(1, Ordering::Relaxed);
+        ::metrics::counter!(SCRAPE_ERRORS_TOTAL, "category" => category.as_str()).increment(1);
+    }
+
+    /// Record a failed operation under its category; errors we can't classify aren't counted
+    pub fn record_error_from(&self, error: &(dyn std::error::Error + Send + Sync + 'static)) {
+        if let Some(category) = ErrorCategory::of(error) {
+            self.record_error(category);
+        }
+    }
+
+    /// Scrape error counts for every category, including those never seen
+    pub fn get_errors(&self) -> Vec<(ErrorCategory, u64)> {
+        ErrorCategory::ALL
+            .iter()
+            .map(|&category| (category, self.errors[category as usize].load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Rejected market counts for every reason, including those never seen
+    pub fn get_rejections(&self) -> Vec<(RejectReason, u64)> {
+        RejectReason::ALL
+            .iter()
+            .map(|&reason| (reason, self.rejected_markets[reason as usize].load(Ordering::Relaxed)))
+            .collect()
+    }
+
     pub fn get_total_scrapes(&self) -> u64 {
         self.total_scrapes.load(Ordering::Relaxed)
     }
@@ -47,12 +443,51 @@ impl Metrics {
         self.failed_scrapes.load(Ordering::Relaxed)
     }
 
+    pub fn get_markets_discovered(&self) -> u64 {
+        self.markets_discovered.load(Ordering::Relaxed)
+    }
+
+    /// Current values of the counters persisted across restarts
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            total_scrapes: self.get_total_scrapes(),
+            successful_scrapes: self.get_successful_scrapes(),
+            failed_scrapes: self.get_failed_scrapes(),
+            markets_discovered: self.get_markets_discovered(),
+        }
+    }
+
+    /// Add the counters of a previous process on top of this one's
+    /// Prometheus counters are seeded too, so they continue from where they stopped
+    pub fn restore(&self, snapshot: &MetricsSnapshot) {
+        self.total_scrapes.fetch_add(snapshot.total_scrapes, Ordering::Relaxed);
+        self.successful_scrapes.fetch_add(snapshot.successful_scrapes, Ordering::Relaxed);
+        self.failed_scrapes.fetch_add(snapshot.failed_scrapes, Ordering::Relaxed);
+        self.markets_discovered.fetch_add(snapshot.markets_discovered, Ordering::Relaxed);
+        ::metrics::counter!(SCRAPES_TOTAL, "result" => "success")
+            .increment(snapshot.successful_scrapes);
+        ::metrics::counter!(SCRAPES_TOTAL, "result" => "failure").increment(snapshot.failed_scrapes);
+        ::metrics::counter!(MARKETS_DISCOVERED_TOTAL).increment(snapshot.markets_discovered);
+    }
+
     pub fn get_last_scrape_time(&self) -> Option<chrono::DateTime<Utc>> {
         self.last_scrape_time
             .lock()
             .ok()
             .and_then(|guard| *guard)
     }
+
+    pub fn get_last_success_time(&self) -> Option<chrono::DateTime<Utc>> {
+        self.last_success_time
+            .lock()
+            .ok()
+            .and_then(|guard| *guard)
+    }
+
+    /// When this process started recording metrics
+    pub fn started_at(&self) -> chrono::DateTime<Utc> {
+        self.started_at
+    }
 }
 
 impl Default for Metrics {
@@ -61,9 +496,41 @@ impl Default for Metrics {
     }
 }
 
+/// Load the counters saved by the previous process into `metrics`
+/// Must run once at startup before snapshots are saved, or the saved totals would be lost
+pub async fn restore_snapshot(storage: &dyn Storage, metrics: &Metrics) -> Result<()> {
+    let snapshot = storage.get_metrics_snapshot().await?;
+    metrics.restore(&snapshot);
+    info!(
+        "Restored metrics counters: {} scrapes, {} markets discovered",
+        snapshot.total_scrapes, snapshot.markets_discovered
+    );
+    Ok(())
+}
+
+/// Save the cumulative counters every `interval_secs`, see `restore_snapshot`
+pub async fn run_snapshots(storage: Arc<dyn Storage>, metrics: Arc<Metrics>, interval_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    // The first tick completes immediately and there is nothing new to save yet
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        save_snapshot(storage.as_ref(), &metrics).await;
+    }
+}
+
+/// Save the cumulative counters once, e.g. on shutdown
+pub async fn save_snapshot(storage: &dyn Storage, metrics: &Metrics) {
+    if let Err(e) = storage.save_metrics_snapshot(&metrics.snapshot()).await {
+        warn!("Failed to save metrics snapshot: {}", e);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Error;
 
     #[test]
     fn test_metrics_recording() {
@@ -85,6 +552,38 @@ mod tests {
         
         let last_time = metrics.get_last_scrape_time();
         assert!(last_time.is_some());
+        assert!(metrics.get_last_success_time().unwrap() <= last_time.unwrap());
+
+        metrics.record_rejection(RejectReason::BadPrice);
+        metrics.record_rejection(RejectReason::BadPrice);
+        let rejections = metrics.get_rejections();
+        assert_eq!(rejections.len(), RejectReason::ALL.len());
+        assert!(rejections.contains(&(RejectReason::BadPrice, 2)));
+        assert!(rejections.contains(&(RejectReason::MissingId, 0)));
+    }
+
+    #[test]
+    fn test_error_categories() {
+        let parse = anyhow::Error::from(serde_json::from_str::<u32>("{").unwrap_err())
+            .context("Failed to parse markets");
+        assert_eq!(ErrorCategory::of(&*parse), Some(ErrorCategory::Parse));
+        let database = anyhow::Error::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(ErrorCategory::of(&*database), Some(ErrorCategory::Database));
+        assert_eq!(ErrorCategory::of(&*anyhow::anyhow!("unknown")), None);
+        let config = Error::Config(anyhow::anyhow!("Invalid proxy #1"));
+        assert_eq!(ErrorCategory::of(&config), None);
+
+        let metrics = Metrics::new();
+        metrics.record_error_from(&*parse);
+        metrics.record_error_from(&*anyhow::anyhow!("unknown"));
+        metrics.record_error_from(&Error::from(sqlx::Error::PoolTimedOut));
+        metrics.record_rejection(RejectReason::BadPrice);
+        let errors = metrics.get_errors();
+        assert_eq!(errors.len(), ErrorCategory::ALL.len());
+        assert!(errors.contains(&(ErrorCategory::Parse, 1)));
+        assert!(errors.contains(&(ErrorCategory::Database, 1)));
+        assert!(errors.contains(&(ErrorCategory::Rejected, 1)));
+        assert!(errors.contains(&(ErrorCategory::Network, 0)));
     }
 }
 
@@ -1,24 +1,897 @@
+use async_graphql::{Enum, SimpleObject};
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{FromRow, ValueRef};
+use utoipa::ToSchema;
 
-/// Market data structure representing a prediction market from Polymarket
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+use crate::circuit_breaker::CircuitState;
+use crate::scraper::ScraperHealth;
+use crate::shards::Shard;
+use crate::validation::RejectReason;
+
+/// Market data structure representing a prediction market from Polymarket or another venue
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+#[graphql(complex)]
 pub struct Market {
     pub id: String,
+    /// Venue the market is listed on (`polymarket`, `manifold`)
+    pub source: String,
+    /// URL slug on polymarket.com
+    pub slug: Option<String>,
+    /// CTF condition ID, shared with the CLOB and on-chain data
+    pub condition_id: Option<String>,
+    /// UMA question ID used for resolution
+    pub question_id: Option<String>,
     pub title: String,
     pub description: Option<String>,
-    pub current_price: Option<f64>,
+    /// Market card image URL
+    pub image: Option<String>,
+    /// Small icon URL, often the same picture as `image`
+    pub icon: Option<String>,
+    /// Exact decimal, serialized as a JSON number rounded to the configured precision
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub current_price: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub volume: Option<Decimal>,
+    /// Trading volume over the last 24 hours
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub volume_24hr: Option<Decimal>,
+    /// Liquidity available in the orderbook and AMM
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub liquidity: Option<Decimal>,
+    /// Value of outstanding shares
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub open_interest: Option<Decimal>,
+    pub end_date: Option<DateTime<Utc>>,
+    /// CLOB token ID of the first outcome, used to query orderbook prices
+    pub clob_token_id: Option<String>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub best_bid: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub best_ask: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub midpoint: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub spread: Option<Decimal>,
+    /// Parent event grouping related markets, if any
+    pub event_id: Option<String>,
+    /// Lifecycle status; unknown until a source that reports it has been scraped
+    pub status: Option<MarketStatus>,
+    /// Winning outcome name, set once the market resolves
+    pub winning_outcome: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discovered_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Last scrape that returned this market
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen_at: Option<DateTime<Utc>>,
+    /// Every outcome with its own price and quotes; stored in the `outcomes` table
+    #[sqlx(skip)]
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outcomes: Vec<Outcome>,
+    /// Category and tags; stored in the `tags` and `market_tags` tables
+    #[sqlx(skip)]
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+}
+
+/// Lifecycle status of a market, stored as lowercase text
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema, Enum,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum MarketStatus {
+    /// Open for trading
+    #[default]
+    Active,
+    /// Trading has stopped but no winner is known yet
+    Closed,
+    /// Settled with a winning outcome
+    Resolved,
+    /// Hidden by Polymarket, usually long after resolution
+    Archived,
+    /// No longer returned by the API
+    Delisted,
+}
+
+impl MarketStatus {
+    pub const ALL: [MarketStatus; 5] = [
+        MarketStatus::Active,
+        MarketStatus::Closed,
+        MarketStatus::Resolved,
+        MarketStatus::Archived,
+        MarketStatus::Delisted,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MarketStatus::Active => "active",
+            MarketStatus::Closed => "closed",
+            MarketStatus::Resolved => "resolved",
+            MarketStatus::Archived => "archived",
+            MarketStatus::Delisted => "delisted",
+        }
+    }
+}
+
+impl FromStr for MarketStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        MarketStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown market status '{}', expected one of active, closed, resolved, archived, delisted",
+                    s
+                )
+            })
+    }
+}
+
+impl fmt::Display for MarketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Market {
+    /// Round the prices and the volume-like amounts to the configured number of decimal places
+    pub fn round_to(&mut self, precision: Precision) {
+        for price in [
+            &mut self.current_price,
+            &mut self.best_bid,
+            &mut self.best_ask,
+            &mut self.midpoint,
+            &mut self.spread,
+        ] {
+            *price = price.map(|p| p.round_dp(precision.price_decimals));
+        }
+        for outcome in &mut self.outcomes {
+            for price in [&mut outcome.price, &mut outcome.best_bid, &mut outcome.best_ask] {
+                *price = price.map(|p| p.round_dp(precision.price_decimals));
+            }
+        }
+        for amount in [
+            &mut self.volume,
+            &mut self.volume_24hr,
+            &mut self.liquidity,
+            &mut self.open_interest,
+        ] {
+            *amount = amount.map(|v| v.round_dp(precision.volume_decimals));
+        }
+    }
+}
+
+/// Decimal places kept for market prices and volumes
+#[derive(Debug, Clone, Copy)]
+pub struct Precision {
+    pub price_decimals: u32,
+    pub volume_decimals: u32,
+}
+
+pub const DEFAULT_PRICE_DECIMALS: u32 = 4;
+pub const DEFAULT_VOLUME_DECIMALS: u32 = 2;
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision {
+            price_decimals: DEFAULT_PRICE_DECIMALS,
+            volume_decimals: DEFAULT_VOLUME_DECIMALS,
+        }
+    }
+}
+
+/// `REAL` column decoded as an optional `Decimal`, since SQLite has no decimal type
+/// Floats convert via their shortest representation, so a stored 0.61 reads back as exactly 0.61
+pub struct DecimalColumn(Option<Decimal>);
+
+impl sqlx::Type<Sqlite> for DecimalColumn {
+    fn type_info() -> SqliteTypeInfo {
+        <f64 as sqlx::Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <f64 as sqlx::Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for DecimalColumn {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.is_null() {
+            return Ok(DecimalColumn(None));
+        }
+        let float = <f64 as sqlx::Decode<Sqlite>>::decode(value)?;
+        Ok(DecimalColumn(Decimal::from_f64(float)))
+    }
+}
+
+impl From<DecimalColumn> for Option<Decimal> {
+    fn from(column: DecimalColumn) -> Self {
+        column.0
+    }
+}
+
+/// `NOT NULL` column decoded as a `Decimal`; fails on a float no decimal can represent
+impl TryFrom<DecimalColumn> for Decimal {
+    type Error = sqlx::Error;
+
+    fn try_from(column: DecimalColumn) -> Result<Self, sqlx::Error> {
+        column
+            .0
+            .ok_or_else(|| sqlx::Error::Decode("Column is not a finite decimal".into()))
+    }
+}
+
+/// `rust_decimal::serde::float_option` that also reads back a `null` buffered by serde, which
+/// arrives as a unit rather than a none inside internally tagged enums like `MarketUpdate`
+pub mod decimal_option {
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(transparent)]
+    struct Float(#[serde(with = "rust_decimal::serde::float")] Decimal);
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<Float>::deserialize(deserializer)?.map(|float| float.0))
+    }
+
+    pub fn serialize<S>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        rust_decimal::serde::float_option::serialize(value, serializer)
+    }
+}
+
+/// Category or tag attached to markets, e.g. `politics`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+pub struct Tag {
+    pub slug: String,
+    pub label: String,
+}
+
+impl Tag {
+    /// Tag with a slug derived from its label (`"US Politics"` becomes `us-politics`)
+    pub fn from_label(label: &str) -> Self {
+        let slug = label
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        Tag {
+            slug,
+            label: label.trim().to_string(),
+        }
+    }
+}
+
+/// Tag with the number of markets carrying it
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TagCount {
+    pub slug: String,
+    pub label: String,
+    pub market_count: i64,
+}
+
+/// One outcome of a market, e.g. `Yes`/`No` or a candidate in a multi-outcome market
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+pub struct Outcome {
+    pub name: String,
+    /// CLOB token ID used to query this outcome's orderbook
+    pub token_id: Option<String>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub price: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub best_bid: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub best_ask: Option<Decimal>,
+}
+
+/// Event grouping related markets under a single question (e.g. "Who will win the election?")
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+#[graphql(complex)]
+pub struct Event {
+    pub id: String,
+    pub title: String,
+    pub slug: Option<String>,
+    pub description: Option<String>,
     pub volume: Option<f64>,
     pub end_date: Option<String>,
+    /// Comments on the event according to Gamma, as of the last event scrape
+    pub comment_count: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub discovered_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+/// Column used to order market listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema, Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketSortField {
+    Volume,
+    #[serde(rename = "volume_24hr")]
+    #[graphql(name = "VOLUME_24HR")]
+    Volume24hr,
+    Liquidity,
+    OpenInterest,
+    CurrentPrice,
+    EndDate,
+    #[default]
+    DiscoveredAt,
+}
+
+impl MarketSortField {
+    /// SQL column name; only ever one of a fixed set, so safe to interpolate
+    pub fn column(self) -> &'static str {
+        match self {
+            MarketSortField::Volume => "volume",
+            MarketSortField::Volume24hr => "volume_24hr",
+            MarketSortField::Liquidity => "liquidity",
+            MarketSortField::OpenInterest => "open_interest",
+            MarketSortField::CurrentPrice => "current_price",
+            MarketSortField::EndDate => "end_date",
+            MarketSortField::DiscoveredAt => "discovered_at",
+        }
+    }
+}
+
+/// How top movers are ranked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MoverSort {
+    /// Largest absolute price change
+    #[default]
+    Absolute,
+    /// Largest change relative to the starting price
+    Relative,
+}
+
+impl MoverSort {
+    /// SQL column name; only ever one of a fixed set, so safe to interpolate
+    pub fn column(self) -> &'static str {
+        match self {
+            MoverSort::Absolute => "price_change",
+            MoverSort::Relative => "relative_change",
+        }
+    }
+}
+
+/// Sort direction for listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Filters and ordering applied when listing markets
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    pub status: Option<MarketStatus>,
+    pub min_volume: Option<f64>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub ending_before: Option<DateTime<Utc>>,
+    pub ending_after: Option<DateTime<Utc>>,
+    /// Include `delisted` markets when no status filter is given
+    pub include_delisted: bool,
+    /// Only markets carrying this tag slug
+    pub tag: Option<String>,
+    /// Only markets listed on this venue
+    pub source: Option<String>,
+    pub sort_by: MarketSortField,
+    pub order: SortOrder,
+}
+
+/// A single price/volume observation from the price history
+#[derive(Debug, Clone, Serialize, FromRow, SimpleObject, ToSchema)]
+pub struct PricePoint {
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub price: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub volume: Option<Decimal>,
+    pub scraped_at: DateTime<Utc>,
+}
+
+/// Period covered by a candle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum CandleInterval {
+    #[serde(rename = "1h")]
+    Hour,
+    #[default]
+    #[serde(rename = "1d")]
+    Day,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 2] = [CandleInterval::Hour, CandleInterval::Day];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CandleInterval::Hour => "1h",
+            CandleInterval::Day => "1d",
+        }
+    }
+
+    /// `strftime` format truncating a timestamp to the start of its candle, matching the
+    /// format timestamps are stored in so candles compare with them as text
+    pub fn bucket_format(self) -> &'static str {
+        match self {
+            CandleInterval::Hour => "%Y-%m-%dT%H:00:00+00:00",
+            CandleInterval::Day => "%Y-%m-%dT00:00:00+00:00",
+        }
+    }
+}
+
+/// Open/high/low/close of a market's price over one hour or day, rolled up from the price history
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Candle {
+    /// Start of the period (UTC)
+    pub start_at: DateTime<Utc>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub open: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub high: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub low: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub close: Option<Decimal>,
+    /// Volume traded during the period, from the change in the market's total volume
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = Option<f64>)]
+    pub volume: Option<Decimal>,
+    /// Price observations the candle was built from
+    pub points: i64,
+}
+
+/// How long before resolution a forecast is taken for calibration scoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum CalibrationHorizon {
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "1d")]
+    Day,
+    #[serde(rename = "7d")]
+    Week,
+    #[serde(rename = "30d")]
+    Month,
+}
+
+impl CalibrationHorizon {
+    pub const ALL: [CalibrationHorizon; 4] = [
+        CalibrationHorizon::Hour,
+        CalibrationHorizon::Day,
+        CalibrationHorizon::Week,
+        CalibrationHorizon::Month,
+    ];
+
+    pub fn duration(self) -> chrono::Duration {
+        match self {
+            CalibrationHorizon::Hour => chrono::Duration::hours(1),
+            CalibrationHorizon::Day => chrono::Duration::days(1),
+            CalibrationHorizon::Week => chrono::Duration::days(7),
+            CalibrationHorizon::Month => chrono::Duration::days(30),
+        }
+    }
+
+    /// SQL column holding forecasts at this horizon; only ever one of a fixed set, so safe
+    /// to interpolate
+    pub fn column(self) -> &'static str {
+        match self {
+            CalibrationHorizon::Hour => "forecast_1h",
+            CalibrationHorizon::Day => "forecast_1d",
+            CalibrationHorizon::Week => "forecast_7d",
+            CalibrationHorizon::Month => "forecast_30d",
+        }
+    }
+}
+
+/// Forecasts of a resolved market at each horizon and what actually happened
+#[derive(Debug, Clone)]
+pub struct MarketCalibration {
+    pub market_id: String,
+    /// 1.0 if the first outcome (the one `current_price` quotes) won, else 0.0
+    pub outcome: f64,
+    pub resolved_at: DateTime<Utc>,
+    /// Last recorded price at each horizon of `CalibrationHorizon::ALL`, in that order
+    pub forecasts: [Option<f64>; 4],
+}
+
+/// Forecasts falling in one probability range and how often their outcome happened
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct CalibrationBucket {
+    /// Lower bound of the forecast range, in steps of 0.1
+    pub lower: f64,
+    pub forecasts: i64,
+    pub mean_forecast: f64,
+    /// Share of these forecasts whose outcome happened; close to `mean_forecast` when calibrated
+    pub observed_frequency: f64,
+}
+
+/// Accuracy of forecasts taken at one horizon before resolution
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HorizonCalibration {
+    pub horizon: CalibrationHorizon,
+    /// Resolved markets with a recorded price at this horizon
+    pub markets: i64,
+    /// Mean squared error of the forecasts, from 0 (perfect) to 1; absent without markets
+    pub brier_score: Option<f64>,
+    pub buckets: Vec<CalibrationBucket>,
+}
+
+/// Calibration of market prices against resolved outcomes, returned by `/analytics/calibration`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalibrationResponse {
+    /// Resolved markets scored so far
+    pub markets_scored: i64,
+    pub horizons: Vec<HorizonCalibration>,
+}
+
+/// A market's resolution and volume as read from Polygon, returned by `/markets/{id}/onchain`
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct OnchainVerification {
+    pub market_id: String,
+    /// Whether the CTF condition has reported payouts
+    pub resolved: bool,
+    /// Outcome paid out in full, if resolved
+    pub winning_outcome: Option<String>,
+    /// USDC traded since the scan started, summed over the market's tokens
+    pub volume: Option<f64>,
+    /// How the scraped data disagreed with the chain, if it did
+    pub mismatch: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Side of a trade, from the point of view of the taker, stored as lowercase text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// A trade of one of a market's outcome tokens, returned by `/markets/{id}/trades`
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Trade {
+    /// Identifies the fill across overlapping fetches
+    pub id: String,
+    pub market_id: String,
+    /// CLOB token traded
+    pub token_id: Option<String>,
+    pub outcome: Option<String>,
+    pub side: TradeSide,
+    /// Outcome tokens traded
+    #[serde(with = "rust_decimal::serde::float")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = f64)]
+    pub size: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    #[sqlx(try_from = "DecimalColumn")]
+    #[schema(value_type = f64)]
+    pub price: Decimal,
+    pub traded_at: DateTime<Utc>,
+    /// Wallet whose resting order was filled, if the source reports it
+    pub maker: Option<String>,
+    /// Wallet that took liquidity, if the source reports it
+    pub taker: Option<String>,
+    pub transaction_hash: Option<String>,
+}
+
+/// A comment on an event, shared by the event's markets
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Comment {
+    pub id: String,
+    pub event_id: String,
+    /// Comment this one replies to
+    pub parent_comment_id: Option<String>,
+    /// Wallet of the author
+    pub author: Option<String>,
+    /// Profile name or pseudonym of the author
+    pub author_name: Option<String>,
+    pub body: String,
+    pub reaction_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A market's comment feed with its activity counts
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CommentsResponse {
+    pub market_id: String,
+    /// Event the comments belong to; markets without one have no comments
+    pub event_id: Option<String>,
+    /// Comments on the event according to Gamma
+    pub comment_count: Option<i64>,
+    /// Stored comments posted in the last hour
+    pub comments_last_hour: i64,
+    /// Newest first
+    pub comments: Vec<Comment>,
+}
+
+/// A wallet's holding of one outcome token of a market, as of a capture
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Position {
+    pub market_id: String,
+    /// CLOB token held
+    pub token_id: String,
+    pub outcome_index: Option<i64>,
+    pub wallet: String,
+    /// Display name of the wallet's profile, if public
+    pub name: Option<String>,
+    /// Outcome tokens held
+    pub amount: f64,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Top holders of a market at one capture, largest first per outcome token
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HoldersResponse {
+    pub market_id: String,
+    pub captured_at: DateTime<Utc>,
+    pub holders: Vec<Position>,
+}
+
+/// A price level of an orderbook snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BookLevel {
+    #[serde(with = "rust_decimal::serde::float")]
+    #[schema(value_type = f64)]
+    pub price: Decimal,
+    /// Outcome tokens offered at this price
+    #[serde(with = "rust_decimal::serde::float")]
+    #[schema(value_type = f64)]
+    pub size: Decimal,
+}
+
+/// Top levels of a market's CLOB orderbook at one point in time
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OrderbookSnapshot {
+    pub market_id: String,
+    /// CLOB token whose book was captured, the market's first outcome
+    pub token_id: String,
+    /// Best bids, highest price first
+    pub bids: Vec<BookLevel>,
+    /// Best asks, lowest price first
+    pub asks: Vec<BookLevel>,
+    #[serde(with = "decimal_option")]
+    #[schema(value_type = Option<f64>)]
+    pub midpoint: Option<Decimal>,
+    #[serde(with = "decimal_option")]
+    #[schema(value_type = Option<f64>)]
+    pub spread: Option<Decimal>,
+    /// Total size of the captured bid levels
+    #[serde(with = "rust_decimal::serde::float")]
+    #[schema(value_type = f64)]
+    pub bid_depth: Decimal,
+    /// Total size of the captured ask levels
+    #[serde(with = "rust_decimal::serde::float")]
+    #[schema(value_type = f64)]
+    pub ask_depth: Decimal,
+    /// `(bid_depth - ask_depth) / (bid_depth + ask_depth)`, from -1 (all asks) to 1 (all
+    /// bids); absent for an empty book
+    pub imbalance: Option<f64>,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// A recorded transition of a tracked market field
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct MarketChange {
+    /// Field name (`title`, `current_price`, `volume` or `end_date`)
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Price observation with its market ID, as exported from the price history table
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PriceHistoryRow {
+    pub market_id: String,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    pub price: Option<Decimal>,
+    #[serde(default, with = "decimal_option")]
+    #[sqlx(try_from = "DecimalColumn")]
+    pub volume: Option<Decimal>,
+    pub scraped_at: DateTime<Utc>,
+}
+
+/// Outbound webhook receiving a POST for every newly discovered market
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// HMAC-SHA256 signing secret; never returned by the API
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for registering a webhook
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+/// What an alert rule fires on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// A market's price moved by at least `threshold` relative to its price `window` ago
+    PriceMove {
+        /// Relative change, e.g. `0.1` for 10%, in either direction
+        threshold: f64,
+        /// Lookback window such as `30m`, `1h` or `1d`
+        window: String,
+    },
+    /// A new market with at least `min_volume`, whose title contains one of `keywords`
+    NewMarket {
+        #[serde(default)]
+        min_volume: Option<f64>,
+        /// Case-insensitive; empty matches every title
+        #[serde(default)]
+        keywords: Vec<String>,
+    },
+}
+
+/// A user-defined rule evaluated against every scrape's market updates
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AlertRule {
+    pub id: i64,
+    pub name: String,
+    pub condition: AlertCondition,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /alerts`, and the entries of an `--alert-rules` file
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateAlertRuleRequest {
+    /// Unique name, shown in alert messages
+    pub name: String,
+    pub condition: AlertCondition,
+}
+
+/// A match of an alert rule, as dispatched to the notifiers
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct AlertFired {
+    pub id: i64,
+    pub rule_id: i64,
+    pub market_id: String,
+    pub message: String,
+    pub fired_at: DateTime<Utc>,
+}
+
+/// A named list of markets, refreshed between full scrapes and streamed on its own
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Watchlist {
+    pub name: String,
+    /// In the order they were given
+    pub market_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /watchlists`
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateWatchlistRequest {
+    pub name: String,
+    #[serde(default)]
+    pub market_ids: Vec<String>,
+}
+
+/// Request body for `PUT /watchlists/{name}`, replacing the watchlist's markets
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateWatchlistRequest {
+    pub market_ids: Vec<String>,
+}
+
+/// Request and response body for `/admin/scrape-interval`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ScrapeIntervalRequest {
+    pub interval_secs: u64,
+}
+
+/// Webhook delivery that failed after all retries
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct WebhookDeadLetter {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub url: String,
+    pub payload: String,
+    pub error: String,
+    pub attempts: i64,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Market event waiting to be published to Kafka
+#[cfg(feature = "kafka")]
+#[derive(Debug, Clone, FromRow)]
+pub struct KafkaOutboxMessage {
+    pub id: i64,
+    pub topic: String,
+    pub message_key: String,
+    pub payload: String,
+}
+
+/// Notification in the outbox that a sink hasn't acknowledged yet
+#[derive(Debug, Clone, FromRow)]
+pub struct OutboxEvent {
+    pub id: i64,
+    /// Stable across redeliveries, so receivers can drop repeats
+    pub dedup_key: String,
+    /// The `MarketUpdate` as JSON
+    pub payload: String,
+}
+
 /// Response structure for paginated market lists
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MarketsResponse {
     pub markets: Vec<Market>,
     pub total: i64,
@@ -26,61 +899,753 @@ pub struct MarketsResponse {
     pub offset: u32,
 }
 
+/// Response for `POST /markets/batch`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchMarketsResponse {
+    /// Found markets, in the order their IDs were requested
+    pub markets: Vec<Market>,
+    /// Requested IDs with no stored market
+    pub missing: Vec<String>,
+}
+
+/// Response structure for paginated event lists
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventsResponse {
+    pub events: Vec<Event>,
+    pub total: i64,
+    pub limit: u32,
+    pub offset: u32,
+}
+
 /// Health check response
-#[derive(Debug, Serialize)]
+/// Everything but `status` is omitted by the liveness probe
+#[derive(Debug, Default, Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
+    /// Circuit breaker around the upstream API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream_circuit: Option<CircuitState>,
+    /// Whether the scraper task is alive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scraper: Option<ScraperHealth>,
+    /// Whether this instance runs scheduled scrapes; only one replica leads with leader
+    /// election, the others just serve the API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leader: Option<bool>,
+    /// Shard of the market universe the leader scrapes, when it is split between replicas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    /// Crate version and the git commit it was built from, e.g. `0.1.0+1a2b3c4`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<chrono::DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<u64>,
+    /// Current scrape interval, including changes through the admin API
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scrape_interval_secs: Option<u64>,
+    /// Primary source markets are listed from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Next.js build ID discovered from the homepage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+}
+
+/// Market whose price moved over a window, computed from the price history
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MarketMover {
+    #[sqlx(flatten)]
+    pub market: Market,
+    /// First recorded price within the window
+    pub start_price: f64,
+    /// Latest recorded price within the window
+    pub end_price: f64,
+    pub price_change: f64,
+    /// Change as a fraction of the starting price; absent when it was zero
+    pub relative_change: Option<f64>,
+}
+
+/// Market that traded far more volume in one window than its baseline average
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct VolumeSpike {
+    #[sqlx(flatten)]
+    pub market: Market,
+    pub detected_at: DateTime<Utc>,
+    /// Volume traded in the spike window
+    pub interval_volume: f64,
+    /// Average volume per window over the preceding 24 hours
+    pub baseline_volume: f64,
+    /// `interval_volume` as a multiple of `baseline_volume`
+    pub ratio: f64,
+}
+
+/// Price correlation of two markets over a window
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MarketCorrelation {
+    pub market_a: String,
+    pub market_b: String,
+    /// Pearson correlation of the two markets' price changes between consecutive time
+    /// buckets, from -1 to 1; absent when either market has too little history in the
+    /// window or its price never moved
+    pub correlation: Option<f64>,
+    /// Consecutive bucket pairs in which both markets had a price
+    pub samples: usize,
+}
+
+/// Pairwise price correlations returned by `/analytics/correlation`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CorrelationResponse {
+    pub window: String,
+    /// Width of the buckets prices were sampled into
+    pub bucket_secs: i64,
+    pub correlations: Vec<MarketCorrelation>,
+}
+
+/// Volume a market traded over a period, computed from the price history
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MarketVolume {
+    #[sqlx(flatten)]
+    pub market: Market,
+    /// Increase in the market's total volume over the period
+    pub volume_traded: f64,
+}
+
+/// Summary of one UTC day, generated once the day is over
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DailyReport {
+    /// Day covered, `YYYY-MM-DD`
+    pub date: chrono::NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub new_market_count: i64,
+    /// Highest-volume markets discovered during the day
+    pub new_markets: Vec<Market>,
+    /// Largest price rises over the day
+    pub gainers: Vec<MarketMover>,
+    /// Largest price falls over the day
+    pub losers: Vec<MarketMover>,
+    /// Markets that traded the most volume during the day
+    pub volume_leaders: Vec<MarketVolume>,
+    pub resolved_count: i64,
+    /// Highest-volume markets resolved during the day
+    pub resolved_markets: Vec<Market>,
+}
+
+/// Aggregate market statistics returned by `/stats`
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StatsResponse {
+    pub total_markets: i64,
+    pub new_last_24h: i64,
+    pub new_last_7d: i64,
+    /// Sum of volume across all tracked markets
+    pub total_volume: f64,
+    /// Mean current price of markets with a known price
+    pub average_price: Option<f64>,
+    pub ending_next_24h: i64,
+}
+
+/// Outcome of one readiness check
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessCheck {
+    pub name: String,
+    pub ok: bool,
+    /// Reason the check failed or was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Readiness probe response; `status` is `ready` only when every check passes, and
+/// `degraded` when stalled scrapes are the only failure
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub status: String,
+    pub checks: Vec<ReadinessCheck>,
 }
 
 /// Metrics response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MetricsResponse {
     pub total_markets: i64,
     pub total_scrapes: u64,
     pub successful_scrapes: u64,
     pub failed_scrapes: u64,
+    /// Markets stored for the first time, across restarts like the scrape counts
+    #[serde(default)]
+    pub markets_discovered: u64,
     pub last_scrape_time: Option<chrono::DateTime<Utc>>,
+    /// Markets skipped by validation since startup, by reason
+    #[serde(default)]
+    pub rejected_markets: BTreeMap<String, u64>,
+    /// Scrape errors since startup, by category
+    #[serde(default)]
+    pub errors: BTreeMap<String, u64>,
+    /// Circuit breaker around the upstream API
+    #[serde(default)]
+    pub upstream_circuit: CircuitState,
+    /// Whether the scraper task is alive
+    #[serde(default)]
+    pub scraper: ScraperHealth,
+    /// With sharded scraping, how far behind each shard of the market universe is
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shards: Vec<ShardProgress>,
 }
 
-/// Polymarket API response structure (simplified - actual structure may vary)
-/// These structs are kept for potential future use with typed deserialization
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct PolymarketResponse {
-    #[serde(rename = "pageProps")]
-    pub page_props: Option<PageProps>,
+/// Progress of one shard of the market universe, as reported by the instance holding it
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ShardProgress {
+    pub shard_index: u32,
+    pub shard_count: u32,
+    /// Instance that last reported on the shard; None while no instance ever claimed it
+    pub instance_id: Option<String>,
+    /// Last successful scrape of the shard
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// When the holder last reported; falls behind once nobody holds the shard
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Seconds since the last successful scrape of the shard
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub lag_secs: Option<i64>,
+}
+
+/// Parsed market together with the raw JSON it was parsed from
+#[derive(Debug, Clone)]
+pub struct ScrapedMarket {
+    pub market: Market,
+    pub raw: serde_json::Value,
+}
+
+/// Archived raw payload of a scraped market
+#[derive(Debug, Clone, FromRow)]
+pub struct RawPayloadRow {
+    pub market_id: String,
+    /// Scrape source that produced the payload (`gamma` or `nextjs`)
+    pub source: String,
+    /// Gzip-compressed JSON
+    pub payload: Vec<u8>,
+    pub scraped_at: DateTime<Utc>,
 }
 
-#[allow(dead_code)]
+/// Market as returned by the official Gamma API (`gamma-api.polymarket.com/markets`)
 #[derive(Debug, Deserialize)]
-pub struct PageProps {
-    pub markets: Option<Vec<PolymarketMarket>>,
+#[serde(rename_all = "camelCase")]
+pub struct GammaMarket {
+    /// Empty when missing, so validation reports it as a missing ID
+    #[serde(default)]
+    pub id: String,
+    pub slug: Option<String>,
+    pub condition_id: Option<String>,
+    #[serde(rename = "questionID")]
+    pub question_id: Option<String>,
+    pub question: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub icon: Option<String>,
+    /// JSON-encoded array of outcome prices, e.g. `"[\"0.42\", \"0.58\"]"`
+    pub outcome_prices: Option<String>,
+    /// JSON-encoded array of CLOB token IDs, one per outcome
+    pub clob_token_ids: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub last_trade_price: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub volume_num: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub volume: Option<Decimal>,
+    #[serde(rename = "volume24hr", default, deserialize_with = "deserialize_optional_decimal")]
+    pub volume_24hr: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub liquidity_num: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub liquidity: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub open_interest: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub best_bid: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub best_ask: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub spread: Option<Decimal>,
+    pub end_date: Option<String>,
+    /// JSON-encoded array of outcome names, e.g. `"[\"Yes\", \"No\"]"`
+    pub outcomes: Option<String>,
+    pub active: Option<bool>,
+    pub closed: Option<bool>,
+    pub archived: Option<bool>,
+    pub closed_time: Option<String>,
+    pub uma_resolution_status: Option<String>,
+    pub category: Option<String>,
+    /// Only present when requested with `include_tag=true`
+    #[serde(default)]
+    pub tags: Vec<GammaTag>,
 }
 
+/// Tag embedded in a Gamma or Next.js market
 #[derive(Debug, Deserialize)]
-pub struct PolymarketMarket {
+pub struct GammaTag {
+    pub label: Option<String>,
+    pub slug: Option<String>,
+}
+
+impl GammaTag {
+    /// Canonical tag, deriving whichever of slug and label is missing
+    fn to_tag(&self) -> Option<Tag> {
+        match (&self.slug, &self.label) {
+            (Some(slug), Some(label)) => Some(Tag {
+                slug: slug.clone(),
+                label: label.clone(),
+            }),
+            (None, Some(label)) => Some(Tag::from_label(label)),
+            (Some(slug), None) => Some(Tag::from_label(slug)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Category first, then tags, dropping empty and repeated slugs
+fn collect_tags(category: Option<&str>, tags: impl Iterator<Item = Tag>) -> Vec<Tag> {
+    let mut collected: Vec<Tag> = Vec::new();
+    for tag in category.map(Tag::from_label).into_iter().chain(tags) {
+        if !tag.slug.is_empty() && !collected.iter().any(|t| t.slug == tag.slug) {
+            collected.push(tag);
+        }
+    }
+    collected
+}
+
+impl GammaMarket {
+    /// Price of the first outcome, falling back to the last trade price
+    fn first_outcome_price(&self) -> Option<Decimal> {
+        self.outcome_prices
+            .as_deref()
+            .and_then(|s| serde_json::from_str::<Vec<String>>(s).ok())
+            .and_then(|prices| prices.first().and_then(|p| p.parse::<Decimal>().ok()))
+            .or(self.last_trade_price)
+    }
+
+    /// CLOB token ID of the first outcome
+    fn first_clob_token_id(&self) -> Option<String> {
+        self.clob_token_ids
+            .as_deref()
+            .and_then(|s| serde_json::from_str::<Vec<String>>(s).ok())
+            .and_then(|ids| ids.into_iter().next())
+    }
+
+    /// Outcome names zipped with their prices and CLOB token IDs
+    fn outcome_list(&self) -> Vec<Outcome> {
+        fn parse_list(s: Option<&str>) -> Vec<String> {
+            s.and_then(|s| serde_json::from_str(s).ok()).unwrap_or_default()
+        }
+
+        let prices = parse_list(self.outcome_prices.as_deref());
+        let token_ids = parse_list(self.clob_token_ids.as_deref());
+        parse_list(self.outcomes.as_deref())
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Outcome {
+                name,
+                token_id: token_ids.get(i).cloned(),
+                price: prices.get(i).and_then(|p| p.parse().ok()),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Tags plus the category, deduplicated by slug
+    fn tag_list(&self) -> Vec<Tag> {
+        collect_tags(
+            self.category.as_deref(),
+            self.tags.iter().filter_map(GammaTag::to_tag),
+        )
+    }
+
+    /// Name of the outcome whose price settled at (or near) 1.0
+    fn winning_outcome(&self) -> Option<String> {
+        let prices: Vec<String> = self
+            .outcome_prices
+            .as_deref()
+            .and_then(|s| serde_json::from_str(s).ok())?;
+        let outcomes: Vec<String> = self
+            .outcomes
+            .as_deref()
+            .and_then(|s| serde_json::from_str(s).ok())?;
+
+        prices
+            .iter()
+            .position(|p| p.parse::<f64>().map(|p| p >= 0.99).unwrap_or(false))
+            .and_then(|i| outcomes.get(i).cloned())
+    }
+
+    /// Derive lifecycle status, winning outcome and resolution time from the `active`,
+    /// `closed` and `archived` flags; archived markets keep their resolution
+    fn resolution(&self) -> (MarketStatus, Option<String>, Option<DateTime<Utc>>) {
+        let (status, winning_outcome, resolved_at) = self.closing();
+        if self.archived.unwrap_or(false) {
+            return (MarketStatus::Archived, winning_outcome, resolved_at);
+        }
+        (status, winning_outcome, resolved_at)
+    }
+
+    fn closing(&self) -> (MarketStatus, Option<String>, Option<DateTime<Utc>>) {
+        if !self.closed.unwrap_or(false) {
+            let status = if self.active.unwrap_or(true) {
+                MarketStatus::Active
+            } else {
+                MarketStatus::Closed
+            };
+            return (status, None, None);
+        }
+
+        let winning_outcome = self.winning_outcome();
+        let uma_resolved = self.uma_resolution_status.as_deref() == Some("resolved");
+        if winning_outcome.is_none() && !uma_resolved {
+            return (MarketStatus::Closed, None, None);
+        }
+
+        let resolved_at = self.closed_time.as_deref().and_then(parse_timestamp);
+        (MarketStatus::Resolved, winning_outcome, resolved_at)
+    }
+}
+
+/// Parse API timestamps: RFC 3339, Gamma's `2024-11-06 12:34:56+00`, a plain date
+/// (midnight UTC) or Unix epoch milliseconds
+pub fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        return s.parse().ok().and_then(DateTime::from_timestamp_millis);
+    }
+
+    DateTime::parse_from_rfc3339(s)
+        .or_else(|_| DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%#z"))
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+/// Parse an optional end date, rejecting values in an unknown format
+fn parse_end_date(raw: Option<&str>) -> Result<Option<DateTime<Utc>>, RejectReason> {
+    raw.filter(|s| !s.trim().is_empty())
+        .map(|s| parse_timestamp(s).ok_or(RejectReason::UnparsableDate))
+        .transpose()
+}
+
+impl TryFrom<GammaMarket> for Market {
+    type Error = RejectReason;
+
+    fn try_from(gm: GammaMarket) -> Result<Self, RejectReason> {
+        let end_date = parse_end_date(gm.end_date.as_deref())?;
+        let current_price = gm.first_outcome_price();
+        let clob_token_id = gm.first_clob_token_id();
+        let (status, winning_outcome, resolved_at) = gm.resolution();
+        let outcomes = gm.outcome_list();
+        let tags = gm.tag_list();
+        Ok(Market {
+            id: gm.id,
+            slug: gm.slug,
+            condition_id: gm.condition_id,
+            question_id: gm.question_id,
+            title: gm.question.unwrap_or_else(|| "Untitled Market".to_string()),
+            description: gm.description,
+            image: gm.image,
+            icon: gm.icon,
+            current_price,
+            volume: gm.volume_num.or(gm.volume),
+            volume_24hr: gm.volume_24hr,
+            liquidity: gm.liquidity_num.or(gm.liquidity),
+            open_interest: gm.open_interest,
+            end_date,
+            clob_token_id,
+            best_bid: gm.best_bid,
+            best_ask: gm.best_ask,
+            midpoint: gm
+                .best_bid
+                .zip(gm.best_ask)
+                .map(|(bid, ask)| (bid + ask) / Decimal::TWO),
+            spread: gm
+                .spread
+                .or_else(|| gm.best_bid.zip(gm.best_ask).map(|(bid, ask)| ask - bid)),
+            status: Some(status),
+            winning_outcome,
+            resolved_at,
+            outcomes,
+            tags,
+            ..Default::default()
+        })
+    }
+}
+
+/// Market as embedded in the polymarket.com Next.js data route
+/// Page versions name the same field differently; every known spelling is kept and
+/// `TryFrom<NextJsMarket>` picks the first one present
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextJsMarket {
+    #[serde(default, deserialize_with = "deserialize_string_or_integer")]
     pub id: Option<String>,
+    pub slug: Option<String>,
+    pub market_id: Option<String>,
+    #[serde(rename = "market_slug", alias = "marketSlug")]
+    pub market_slug: Option<String>,
+    #[serde(alias = "condition_id")]
+    pub condition_id: Option<String>,
+    #[serde(rename = "questionID", alias = "question_id")]
+    pub question_id: Option<String>,
+    pub question: Option<String>,
+    pub title: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub description_text: Option<String>,
+    pub image: Option<String>,
+    pub icon: Option<String>,
+    /// One entry per outcome; the first is the primary (e.g. `Yes`) outcome
+    #[serde(default)]
+    pub tokens: Vec<NextJsToken>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub current_price: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub price: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub probability: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub volume_num: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub volume: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub total_volume: Option<Decimal>,
+    #[serde(rename = "volume24hr", default, deserialize_with = "deserialize_optional_decimal")]
+    pub volume_24hr: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub liquidity: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub open_interest: Option<Decimal>,
+    /// End date fields hold a timestamp string or epoch milliseconds
+    #[serde(rename = "end_date_iso", default, deserialize_with = "deserialize_string_or_integer")]
+    pub end_date_iso: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_string_or_integer")]
+    pub end_date: Option<String>,
+    #[serde(rename = "end_date", default, deserialize_with = "deserialize_string_or_integer")]
+    pub end_date_snake: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_string_or_integer")]
+    pub end_time: Option<String>,
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<NextJsTag>,
+}
+
+/// Outcome token embedded in a Next.js market
+#[derive(Debug, Deserialize)]
+pub struct NextJsToken {
+    pub outcome: Option<String>,
+    #[serde(alias = "tokenId")]
+    pub token_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_decimal")]
+    pub price: Option<Decimal>,
+}
+
+/// Next.js tags are either plain labels or Gamma-style objects
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NextJsTag {
+    Label(String),
+    Object(GammaTag),
+}
+
+impl TryFrom<NextJsMarket> for Market {
+    type Error = RejectReason;
+
+    fn try_from(nm: NextJsMarket) -> Result<Self, RejectReason> {
+        // Stored markets are keyed by the first of these, so the order can't change without
+        // re-keying them; the slug is also stored as such
+        let id = nm
+            .id
+            .or_else(|| nm.slug.clone())
+            .or_else(|| nm.market_id.clone())
+            .or_else(|| nm.market_slug.clone())
+            .ok_or(RejectReason::MissingId)?;
+        let slug = nm.slug.or(nm.market_slug);
+        let end_date = parse_end_date(
+            nm.end_date_iso
+                .or(nm.end_date)
+                .or(nm.end_date_snake)
+                .or(nm.end_time)
+                .as_deref(),
+        )?;
+        let first_token = nm.tokens.first();
+        let current_price = first_token
+            .and_then(|token| token.price)
+            .or(nm.current_price)
+            .or(nm.price)
+            .or(nm.probability);
+        let clob_token_id = first_token.and_then(|token| token.token_id.clone());
+        let outcomes = nm
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                Some(Outcome {
+                    name: token.outcome.clone()?,
+                    token_id: token.token_id.clone(),
+                    price: token.price,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let tags = collect_tags(
+            nm.category.as_deref(),
+            nm.tags.iter().filter_map(|tag| match tag {
+                NextJsTag::Label(label) => Some(Tag::from_label(label)),
+                NextJsTag::Object(tag) => tag.to_tag(),
+            }),
+        );
+
+        Ok(Market {
+            id,
+            slug,
+            condition_id: nm.condition_id,
+            question_id: nm.question_id,
+            title: nm
+                .question
+                .or(nm.title)
+                .or(nm.name)
+                .unwrap_or_else(|| "Untitled Market".to_string()),
+            description: nm.description.or(nm.description_text),
+            image: nm.image,
+            icon: nm.icon,
+            current_price,
+            volume: nm.volume_num.or(nm.volume).or(nm.total_volume),
+            volume_24hr: nm.volume_24hr,
+            liquidity: nm.liquidity,
+            open_interest: nm.open_interest,
+            end_date,
+            clob_token_id,
+            outcomes,
+            tags,
+            ..Default::default()
+        })
+    }
+}
+
+/// Event as returned by the Gamma API (`gamma-api.polymarket.com/events`)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GammaEvent {
+    pub id: String,
     pub title: Option<String>,
+    pub slug: Option<String>,
     pub description: Option<String>,
-    #[serde(rename = "currentPrice")]
-    pub current_price: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_optional_f64")]
     pub volume: Option<f64>,
-    #[serde(rename = "endDate")]
     pub end_date: Option<String>,
-    // Polymarket API may have different field names, so we'll need to adapt
+    pub comment_count: Option<i64>,
+    /// Child markets; only their IDs are needed to link them to the event
+    #[serde(default)]
+    pub markets: Vec<GammaEventMarket>,
+}
+
+/// Comment as returned by the Gamma API (`gamma-api.polymarket.com/comments`)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GammaComment {
+    #[serde(default, deserialize_with = "deserialize_string_or_integer")]
+    pub id: Option<String>,
+    pub body: Option<String>,
+    #[serde(rename = "parentCommentID", default, deserialize_with = "deserialize_string_or_integer")]
+    pub parent_comment_id: Option<String>,
+    pub user_address: Option<String>,
+    pub profile: Option<GammaProfile>,
+    #[serde(default)]
+    pub reaction_count: i64,
+    pub created_at: Option<DateTime<Utc>>,
 }
 
-impl From<PolymarketMarket> for Market {
-    fn from(pm: PolymarketMarket) -> Self {
-        Market {
-            id: pm.id.unwrap_or_default(),
-            title: pm.title.unwrap_or_default(),
-            description: pm.description,
-            current_price: pm.current_price,
-            volume: pm.volume,
-            end_date: pm.end_date,
-            discovered_at: None,
-            updated_at: None,
+/// Public profile of a comment's author
+#[derive(Debug, Deserialize)]
+pub struct GammaProfile {
+    pub name: Option<String>,
+    pub pseudonym: Option<String>,
+}
+
+/// Minimal child market reference embedded in a Gamma event
+#[derive(Debug, Deserialize)]
+pub struct GammaEventMarket {
+    pub id: String,
+}
+
+impl From<&GammaEvent> for Event {
+    fn from(ge: &GammaEvent) -> Self {
+        Event {
+            id: ge.id.clone(),
+            title: ge.title.clone().unwrap_or_else(|| "Untitled Event".to_string()),
+            slug: ge.slug.clone(),
+            description: ge.description.clone(),
+            volume: ge.volume,
+            end_date: ge.end_date.clone(),
+            comment_count: ge.comment_count,
+            ..Default::default()
         }
     }
 }
+
+/// Deserialize a number that may be encoded either as a JSON number or a string
+fn deserialize_optional_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(f64),
+        String(String),
+    }
+
+    Ok(match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::Number(n)) => Some(n),
+        Some(NumberOrString::String(s)) => s.parse::<f64>().ok(),
+        None => None,
+    })
+}
+
+/// Deserialize a value (an ID or epoch timestamp) that may be encoded either as a JSON
+/// string or an integer
+fn deserialize_string_or_integer<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrInteger {
+        String(String),
+        Unsigned(u64),
+        Signed(i64),
+    }
+
+    Ok(match Option::<StringOrInteger>::deserialize(deserializer)? {
+        Some(StringOrInteger::String(s)) => Some(s),
+        Some(StringOrInteger::Unsigned(n)) => Some(n.to_string()),
+        Some(StringOrInteger::Signed(n)) => Some(n.to_string()),
+        None => None,
+    })
+}
+
+/// Deserialize a decimal that may be encoded either as a JSON number or a string
+/// Strings are parsed exactly; numbers convert via their shortest representation
+fn deserialize_optional_decimal<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(f64),
+        String(String),
+    }
+
+    Ok(match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::Number(n)) => Decimal::from_f64(n),
+        Some(NumberOrString::String(s)) => s.trim().parse::<Decimal>().ok(),
+        None => None,
+    })
+}
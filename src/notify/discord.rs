@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::time::Duration;
+use tracing::info;
+
+use crate::models::Market;
+use crate::notify::{NotificationFilter, Notifier};
+use crate::updates::MarketUpdate;
+
+const DISCORD_TIMEOUT_SECS: u64 = 10;
+const DISCORD_EMBED_COLOR: u32 = 0x2E5CFF; // Polymarket blue
+const MAX_DESCRIPTION_CHARS: usize = 300;
+
+/// Posts an embed to a Discord webhook for every new market matching the filter
+pub struct DiscordNotifier {
+    client: Client,
+    webhook_url: String,
+    filter: NotificationFilter,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: String, filter: NotificationFilter) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(DISCORD_TIMEOUT_SECS))
+            .build()
+            .context("Failed to create Discord HTTP client")?;
+
+        Ok(Self {
+            client,
+            webhook_url,
+            filter,
+        })
+    }
+
+    async fn post(&self, body: &serde_json::Value) -> Result<()> {
+        self.client
+            .post(&self.webhook_url)
+            .json(body)
+            .send()
+            .await
+            .context("Failed to send Discord webhook")?
+            .error_for_status()
+            .context("Discord webhook returned an error status")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "Discord"
+    }
+
+    async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+        let MarketUpdate::NewMarket { market } = update else {
+            return Ok(());
+        };
+        if !self.filter.matches(market) {
+            return Ok(());
+        }
+
+        self.post(&build_embed(market)).await?;
+        info!("Sent Discord notification for market {}", market.id);
+        Ok(())
+    }
+
+    async fn alert(&self, message: &str) -> Result<()> {
+        self.post(&json!({ "content": format!("⚠️ {}", message) })).await
+    }
+}
+
+/// Build the webhook body with a single embed describing the market
+fn build_embed(market: &Market) -> serde_json::Value {
+    let mut fields = Vec::new();
+    if let Some(price) = market.current_price {
+        fields.push(json!({ "name": "Price", "value": format!("{:.1}%", price * Decimal::ONE_HUNDRED), "inline": true }));
+    }
+    if let Some(volume) = market.volume {
+        fields.push(json!({ "name": "Volume", "value": format!("${:.0}", volume), "inline": true }));
+    }
+    if let Some(end_date) = market.end_date {
+        fields.push(json!({ "name": "Ends", "value": end_date.format("%Y-%m-%d %H:%M UTC").to_string(), "inline": true }));
+    }
+
+    let description: String = market
+        .description
+        .as_deref()
+        .unwrap_or_default()
+        .chars()
+        .take(MAX_DESCRIPTION_CHARS)
+        .collect();
+
+    json!({
+        "embeds": [{
+            "title": format!("New market: {}", market.title),
+            "description": description,
+            "color": DISCORD_EMBED_COLOR,
+            "fields": fields,
+            "footer": { "text": format!("Market ID: {}", market.id) },
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_build_embed() {
+        let market = Market {
+            id: "1".to_string(),
+            title: "Will it rain?".to_string(),
+            current_price: Some(dec!(0.655)),
+            volume: Some(dec!(1234)),
+            ..Default::default()
+        };
+
+        let body = build_embed(&market);
+        let embed = &body["embeds"][0];
+        assert_eq!(embed["title"], "New market: Will it rain?");
+        assert_eq!(embed["fields"][0]["value"], "65.5%");
+        assert_eq!(embed["fields"][1]["value"], "$1234");
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::db;
+use crate::notify::Notifier;
+use crate::scraper::ScraperControl;
+use crate::updates::MarketUpdate;
+
+pub const DEFAULT_NEW_MARKET_TOPIC: &str = "polymarket.markets.new";
+pub const DEFAULT_PRICE_UPDATE_TOPIC: &str = "polymarket.markets.price";
+const OUTBOX_BATCH_SIZE: u32 = 100;
+const OUTBOX_POLL_INTERVAL_MILLIS: u64 = 1000;
+const OUTBOX_RETRY_DELAY_SECS: u64 = 5; // Back off when the broker is unreachable
+const PRODUCE_TIMEOUT_SECS: u64 = 30;
+
+/// Kafka brokers and the topics each kind of update is published to
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    pub brokers: String,
+    pub new_market_topic: String,
+    pub price_update_topic: String,
+}
+
+impl KafkaConfig {
+    /// Topic for an update, or `None` if it isn't published (non-price field changes)
+    fn topic_for(&self, update: &MarketUpdate) -> Option<&str> {
+        match update {
+            MarketUpdate::NewMarket { .. } => Some(&self.new_market_topic),
+            MarketUpdate::MarketChanged { .. } if update.is_price_change() => {
+                Some(&self.price_update_topic)
+            }
+            MarketUpdate::MarketChanged { .. } => None,
+        }
+    }
+}
+
+/// Writes new-market and price-update events to the outbox table
+/// `run_outbox_relay` publishes them, so nothing is lost if Kafka is down
+pub struct KafkaNotifier {
+    pool: Arc<Pool<Sqlite>>,
+    config: KafkaConfig,
+}
+
+impl KafkaNotifier {
+    pub fn new(pool: Arc<Pool<Sqlite>>, config: KafkaConfig) -> Self {
+        Self { pool, config }
+    }
+}
+
+#[async_trait]
+impl Notifier for KafkaNotifier {
+    fn name(&self) -> &'static str {
+        "Kafka"
+    }
+
+    async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+        let Some(topic) = self.config.topic_for(update) else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_string(update).context("Failed to serialize Kafka payload")?;
+        db::insert_kafka_outbox(&self.pool, topic, &update.market().id, &payload)
+            .await
+            .context("Failed to write Kafka outbox message")?;
+
+        Ok(())
+    }
+}
+
+/// Publish outbox messages to Kafka in insertion order, deleting each once the broker acks it
+/// A message is only removed after delivery, so it may be sent more than once but never dropped
+/// Only the primary instance relays, so replicas sharing the outbox don't publish it twice
+pub async fn run_outbox_relay(
+    pool: Arc<Pool<Sqlite>>,
+    config: KafkaConfig,
+    scraper: ScraperControl,
+) -> Result<()> {
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &config.brokers)
+        .set("acks", "all")
+        .set("message.timeout.ms", (PRODUCE_TIMEOUT_SECS * 1000).to_string())
+        .create()
+        .context("Failed to create Kafka producer")?;
+
+    info!("Kafka outbox relay started for brokers {}", config.brokers);
+
+    loop {
+        if !scraper.is_primary() {
+            sleep(Duration::from_millis(OUTBOX_POLL_INTERVAL_MILLIS)).await;
+            continue;
+        }
+        let messages = match db::get_kafka_outbox(&pool, OUTBOX_BATCH_SIZE).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                error!("Failed to read Kafka outbox: {}", e);
+                sleep(Duration::from_secs(OUTBOX_RETRY_DELAY_SECS)).await;
+                continue;
+            }
+        };
+
+        if messages.is_empty() {
+            sleep(Duration::from_millis(OUTBOX_POLL_INTERVAL_MILLIS)).await;
+            continue;
+        }
+
+        for message in messages {
+            let record = FutureRecord::to(&message.topic)
+                .key(&message.message_key)
+                .payload(&message.payload);
+
+            match producer
+                .send(record, Timeout::After(Duration::from_secs(PRODUCE_TIMEOUT_SECS)))
+                .await
+            {
+                Ok(_) => {
+                    if let Err(e) = db::delete_kafka_outbox(&pool, message.id).await {
+                        error!("Failed to delete Kafka outbox message {}: {}", message.id, e);
+                    }
+                }
+                Err((e, _)) => {
+                    // Stop at the first failure to preserve ordering; the batch is retried
+                    warn!(
+                        "Failed to publish outbox message {} to {}: {}, retrying in {} seconds...",
+                        message.id, message.topic, e, OUTBOX_RETRY_DELAY_SECS
+                    );
+                    sleep(Duration::from_secs(OUTBOX_RETRY_DELAY_SECS)).await;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Market;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_topic_for_update() {
+        let config = KafkaConfig {
+            brokers: "localhost:9092".to_string(),
+            new_market_topic: "new".to_string(),
+            price_update_topic: "price".to_string(),
+        };
+        let market = Market::default();
+
+        let new_market = MarketUpdate::NewMarket {
+            market: market.clone(),
+        };
+        let price_change = MarketUpdate::MarketChanged {
+            market: market.clone(),
+            changed_fields: vec!["current_price", "volume"],
+            previous_price: Some(dec!(0.4)),
+        };
+        let volume_change = MarketUpdate::MarketChanged {
+            market,
+            changed_fields: vec!["volume"],
+            previous_price: Some(dec!(0.4)),
+        };
+
+        assert_eq!(config.topic_for(&new_market), Some("new"));
+        assert_eq!(config.topic_for(&price_change), Some("price"));
+        assert_eq!(config.topic_for(&volume_change), None);
+    }
+}
@@ -0,0 +1,367 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::prelude::ToPrimitive;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+use tracing::{error, info};
+
+use crate::models::{Market, OutboxEvent};
+use crate::scraper::ScraperControl;
+use crate::storage::Storage;
+use crate::updates::MarketUpdate;
+
+pub mod discord;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod telegram;
+pub mod webhook;
+
+/// Outbox events fetched per poll of a sink
+const OUTBOX_BATCH_SIZE: u32 = 100;
+const OUTBOX_POLL_INTERVAL_MILLIS: u64 = 1000;
+/// Delay before retrying a failing sink, doubled on every consecutive failure
+const OUTBOX_RETRY_DELAY_SECS: u64 = 5;
+const OUTBOX_MAX_RETRY_DELAY_SECS: u64 = 600;
+
+/// A destination for market notifications (webhooks, Discord, Telegram, ...)
+/// New sinks only need to implement this trait and be registered with the `Dispatcher`
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Handle a single update; sinks decide which update types they care about
+    async fn notify(&self, update: &MarketUpdate) -> Result<()>;
+
+    /// Deliver an update from the outbox; its `dedup_key` is the same each time an event is
+    /// redelivered, so sinks whose receivers can drop repeats pass it along
+    async fn deliver(&self, update: &MarketUpdate, _event: &OutboxEvent) -> Result<()> {
+        self.notify(update).await
+    }
+
+    /// Deliver an operational alert about the service itself, such as stalled scrapes
+    /// Only sinks read by people implement this; the rest ignore alerts
+    async fn alert(&self, _message: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Which new markets are worth a notification
+#[derive(Debug, Clone, Default)]
+pub struct NotificationFilter {
+    /// Title must contain at least one of these (case-insensitive); empty matches everything
+    pub keywords: Vec<String>,
+    pub min_volume: Option<f64>,
+}
+
+impl NotificationFilter {
+    pub fn matches(&self, market: &Market) -> bool {
+        if let Some(min_volume) = self.min_volume {
+            if market.volume.and_then(|v| v.to_f64()).unwrap_or(0.0) < min_volume {
+                return false;
+            }
+        }
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let title = market.title.to_lowercase();
+        self.keywords
+            .iter()
+            .any(|keyword| title.contains(&keyword.to_lowercase()))
+    }
+}
+
+/// Delivers the notifications the scraper queued in the outbox to every registered sink
+/// Each sink has its own worker and delivery state, so a slow or failing sink never
+/// delays the others, and an event is retried until its sink acknowledges it
+/// Only the primary replica delivers, so replicas sharing a database don't notify twice
+#[derive(Default)]
+pub struct Dispatcher {
+    sinks: Vec<Arc<dyn Notifier>>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_sink(&mut self, sink: Arc<dyn Notifier>) {
+        self.sinks.push(sink);
+    }
+
+    /// Names the registered sinks keep their outbox delivery state under
+    pub fn sink_names(&self) -> Vec<String> {
+        self.sinks.iter().map(|sink| sink.name().to_string()).collect()
+    }
+
+    /// Handle for sending operational alerts to the registered sinks
+    pub fn alerts(&self) -> Alerts {
+        Alerts {
+            sinks: self.sinks.clone(),
+        }
+    }
+
+    /// Spawn one outbox worker per sink; workers stop when this future is dropped
+    pub async fn run(self, storage: Arc<dyn Storage>, scraper: ScraperControl) -> Result<()> {
+        let mut workers = JoinSet::new();
+        for sink in self.sinks {
+            workers.spawn(run_sink_worker(Arc::clone(&storage), sink, scraper.clone()));
+        }
+        info!("Notification dispatcher started with {} sinks", workers.len());
+
+        while workers.join_next().await.is_some() {}
+        Ok(())
+    }
+}
+
+/// Sends operational alerts to every notification sink that accepts them
+#[derive(Clone, Default)]
+pub struct Alerts {
+    sinks: Vec<Arc<dyn Notifier>>,
+}
+
+impl Alerts {
+    /// Send an alert to every sink; failures are logged, not returned
+    pub async fn send(&self, message: &str) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.alert(message).await {
+                error!("{} alert failed: {}", sink.name(), e);
+            }
+        }
+    }
+}
+
+/// Poll the outbox for events a single sink hasn't acknowledged and deliver them in order
+/// A failing sink is retried with exponential backoff for as long as it keeps failing
+async fn run_sink_worker(
+    storage: Arc<dyn Storage>,
+    sink: Arc<dyn Notifier>,
+    scraper: ScraperControl,
+) {
+    let mut failures = 0;
+    loop {
+        if !scraper.is_primary() {
+            sleep(Duration::from_millis(OUTBOX_POLL_INTERVAL_MILLIS)).await;
+            continue;
+        }
+
+        let delay = match deliver_pending(storage.as_ref(), sink.as_ref()).await {
+            Ok(attempted) => {
+                failures = 0;
+                if attempted == OUTBOX_BATCH_SIZE as usize {
+                    continue;
+                }
+                Duration::from_millis(OUTBOX_POLL_INTERVAL_MILLIS)
+            }
+            Err(e) => {
+                failures += 1;
+                let delay = retry_delay(failures);
+                error!(
+                    "{} outbox delivery failed, retrying in {} seconds: {:#}",
+                    sink.name(),
+                    delay.as_secs(),
+                    e
+                );
+                delay
+            }
+        };
+        sleep(delay).await;
+    }
+}
+
+/// Backoff after `failures` consecutive failed deliveries
+fn retry_delay(failures: u32) -> Duration {
+    let factor = 2_u64.saturating_pow(failures.saturating_sub(1));
+    Duration::from_secs(
+        OUTBOX_RETRY_DELAY_SECS
+            .saturating_mul(factor)
+            .min(OUTBOX_MAX_RETRY_DELAY_SECS),
+    )
+}
+
+/// Deliver one batch of outbox events pending for `sink`, recording each acknowledgement
+/// Stops at the first failed delivery, which is counted against the event and returned;
+/// otherwise returns how many events were settled. Events whose payload can't be read are
+/// dead-lettered, as no retry can deliver them
+async fn deliver_pending(storage: &dyn Storage, sink: &dyn Notifier) -> Result<usize> {
+    let events = storage
+        .get_pending_outbox(sink.name(), OUTBOX_BATCH_SIZE)
+        .await?;
+
+    for event in &events {
+        let update = match serde_json::from_str::<MarketUpdate>(&event.payload) {
+            Ok(update) => update,
+            Err(e) => {
+                error!(
+                    "Dead-lettering unreadable notification {} for {}: {}",
+                    event.dedup_key,
+                    sink.name(),
+                    e
+                );
+                storage
+                    .dead_letter_outbox(event.id, sink.name(), &e.to_string())
+                    .await?;
+                continue;
+            }
+        };
+        if let Err(e) = sink.deliver(&update, event).await {
+            storage.record_outbox_failure(event.id, sink.name(), &e.to_string()).await?;
+            return Err(e.context(format!("Failed to deliver notification {}", event.dedup_key)));
+        }
+        storage.record_outbox_delivery(event.id, sink.name(), Utc::now()).await?;
+    }
+
+    Ok(events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::storage::SqliteStorage;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+
+    /// Sink that records the IDs of the markets it was notified about and the alerts it got
+    #[derive(Default)]
+    struct MockSink {
+        name: &'static str,
+        received: Mutex<Vec<String>>,
+        alerts: Mutex<Vec<String>>,
+        fail: bool,
+    }
+
+    impl MockSink {
+        fn named(name: &'static str) -> Self {
+            Self {
+                name,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Notifier for MockSink {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+            if self.fail {
+                return Err(anyhow::anyhow!("mock failure"));
+            }
+            self.received
+                .lock()
+                .unwrap()
+                .push(update.market().id.clone());
+            Ok(())
+        }
+
+        async fn alert(&self, message: &str) -> Result<()> {
+            if self.fail {
+                return Err(anyhow::anyhow!("mock failure"));
+            }
+            self.alerts.lock().unwrap().push(message.to_string());
+            Ok(())
+        }
+    }
+
+    async fn test_storage() -> SqliteStorage {
+        SqliteStorage::new(db::test_pools().await)
+    }
+
+    #[tokio::test]
+    async fn test_alerts_reach_all_sinks() {
+        let first = Arc::new(MockSink::named("First"));
+        let second = Arc::new(MockSink::named("Second"));
+        let failing = Arc::new(MockSink {
+            fail: true,
+            ..MockSink::named("Failing")
+        });
+
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.add_sink(first.clone());
+        dispatcher.add_sink(failing);
+        dispatcher.add_sink(second.clone());
+
+        // Alerts skip the failing sink without stopping
+        dispatcher.alerts().send("Scraper stalled").await;
+        assert_eq!(*first.alerts.lock().unwrap(), vec!["Scraper stalled"]);
+        assert_eq!(*second.alerts.lock().unwrap(), vec!["Scraper stalled"]);
+    }
+
+    #[tokio::test]
+    async fn test_outbox_is_delivered_once_per_sink() {
+        let storage = test_storage().await;
+        let first = MockSink::named("First");
+        let second = MockSink::named("Second");
+        let failing = MockSink {
+            fail: true,
+            ..MockSink::named("Failing")
+        };
+
+        // New markets queue a notification; unchanged rows don't
+        for id in ["a", "b", "a"] {
+            storage.upsert_market_with_outbox(&market(id, None), None).await.unwrap();
+        }
+
+        assert_eq!(deliver_pending(&storage, &first).await.unwrap(), 2);
+        assert_eq!(*first.received.lock().unwrap(), vec!["a", "b"]);
+        assert_eq!(deliver_pending(&storage, &first).await.unwrap(), 0);
+
+        // A failing sink keeps its events pending without holding up the others
+        for _ in 0..20 {
+            assert!(deliver_pending(&storage, &failing).await.is_err());
+        }
+        assert_eq!(storage.get_pending_outbox("Failing", 10).await.unwrap().len(), 2);
+        assert_eq!(deliver_pending(&storage, &second).await.unwrap(), 2);
+
+        let changed = market("a", Some(dec!(5000)));
+        let change = MarketUpdate::MarketChanged {
+            market: changed.clone(),
+            changed_fields: vec!["volume"],
+            previous_price: None,
+        };
+        assert!(!storage.upsert_market_with_outbox(&changed, Some(&change)).await.unwrap());
+        assert_eq!(deliver_pending(&storage, &first).await.unwrap(), 1);
+        assert_eq!(*first.received.lock().unwrap(), vec!["a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_without_giving_up() {
+        assert_eq!(retry_delay(1), Duration::from_secs(OUTBOX_RETRY_DELAY_SECS));
+        assert_eq!(retry_delay(2), Duration::from_secs(2 * OUTBOX_RETRY_DELAY_SECS));
+        assert_eq!(retry_delay(1000), Duration::from_secs(OUTBOX_MAX_RETRY_DELAY_SECS));
+    }
+
+    fn market(title: &str, volume: Option<Decimal>) -> Market {
+        Market {
+            id: title.to_string(),
+            title: title.to_string(),
+            volume,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_notification_filter() {
+        let filter = NotificationFilter {
+            keywords: vec!["Election".to_string(), "bitcoin".to_string()],
+            min_volume: Some(1000.0),
+        };
+
+        assert!(filter.matches(&market("Who wins the election?", Some(dec!(5000)))));
+        assert!(filter.matches(&market("Bitcoin above $100k?", Some(dec!(1000)))));
+        assert!(!filter.matches(&market("Will it rain?", Some(dec!(5000)))));
+        assert!(!filter.matches(&market("Election turnout", Some(dec!(10)))));
+        assert!(NotificationFilter::default().matches(&market("Anything", None)));
+    }
+}
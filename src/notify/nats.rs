@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::notify::Notifier;
+use crate::updates::MarketUpdate;
+
+pub const DEFAULT_SUBJECT_PREFIX: &str = "polymarket.markets";
+
+/// Publishes new markets to `<prefix>.new` and price changes to `<prefix>.price`
+pub struct NatsNotifier {
+    client: async_nats::Client,
+    subject_prefix: String,
+}
+
+impl NatsNotifier {
+    pub async fn connect(url: &str, subject_prefix: String) -> Result<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .with_context(|| format!("Failed to connect to NATS at {}", url))?;
+        info!("Connected to NATS at {}", url);
+
+        Ok(Self {
+            client,
+            subject_prefix,
+        })
+    }
+}
+
+/// Subject for an update, or `None` if it isn't published (non-price field changes)
+fn subject_for(prefix: &str, update: &MarketUpdate) -> Option<String> {
+    match update {
+        MarketUpdate::NewMarket { .. } => Some(format!("{}.new", prefix)),
+        MarketUpdate::MarketChanged { .. } if update.is_price_change() => {
+            Some(format!("{}.price", prefix))
+        }
+        MarketUpdate::MarketChanged { .. } => None,
+    }
+}
+
+#[async_trait]
+impl Notifier for NatsNotifier {
+    fn name(&self) -> &'static str {
+        "NATS"
+    }
+
+    async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+        let Some(subject) = subject_for(&self.subject_prefix, update) else {
+            return Ok(());
+        };
+
+        let payload = serde_json::to_vec(update).context("Failed to serialize NATS payload")?;
+        self.client
+            .publish(subject, payload.into())
+            .await
+            .context("Failed to publish to NATS")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Market;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_subject_for_update() {
+        let new_market = MarketUpdate::NewMarket {
+            market: Market::default(),
+        };
+        let price_change = MarketUpdate::MarketChanged {
+            market: Market::default(),
+            changed_fields: vec!["current_price"],
+            previous_price: Some(dec!(0.4)),
+        };
+        let title_change = MarketUpdate::MarketChanged {
+            market: Market::default(),
+            changed_fields: vec!["title"],
+            previous_price: Some(dec!(0.4)),
+        };
+
+        assert_eq!(
+            subject_for("polymarket.markets", &new_market),
+            Some("polymarket.markets.new".to_string())
+        );
+        assert_eq!(
+            subject_for("polymarket.markets", &price_change),
+            Some("polymarket.markets.price".to_string())
+        );
+        assert_eq!(subject_for("polymarket.markets", &title_change), None);
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::notify::Notifier;
+use crate::updates::MarketUpdate;
+
+pub const DEFAULT_NEW_MARKET_CHANNEL: &str = "polymarket:markets:new";
+
+/// Publishes every newly discovered market on a Redis pub/sub channel
+pub struct RedisNotifier {
+    conn: ConnectionManager,
+    channel: String,
+}
+
+impl RedisNotifier {
+    pub fn new(conn: ConnectionManager, channel: String) -> Self {
+        Self { conn, channel }
+    }
+}
+
+#[async_trait]
+impl Notifier for RedisNotifier {
+    fn name(&self) -> &'static str {
+        "Redis"
+    }
+
+    async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+        if !matches!(update, MarketUpdate::NewMarket { .. }) {
+            return Ok(());
+        }
+
+        let payload = serde_json::to_string(update).context("Failed to serialize Redis payload")?;
+        self.conn
+            .clone()
+            .publish::<_, _, ()>(&self.channel, payload)
+            .await
+            .context("Failed to publish to Redis")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::time::Duration;
+use tracing::info;
+
+use crate::models::Market;
+use crate::notify::{NotificationFilter, Notifier};
+use crate::updates::MarketUpdate;
+
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+const TELEGRAM_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_PRICE_MOVE_THRESHOLD: f64 = 0.10; // 10 percentage points
+
+/// Sends Telegram bot messages for new markets and large price moves
+pub struct TelegramNotifier {
+    client: Client,
+    bot_token: String,
+    chat_id: String,
+    filter: NotificationFilter,
+    /// Minimum absolute price change (0.0-1.0) that counts as a large move
+    price_move_threshold: f64,
+}
+
+impl TelegramNotifier {
+    pub fn new(
+        bot_token: String,
+        chat_id: String,
+        filter: NotificationFilter,
+        price_move_threshold: f64,
+    ) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(TELEGRAM_TIMEOUT_SECS))
+            .build()
+            .context("Failed to create Telegram HTTP client")?;
+
+        Ok(Self {
+            client,
+            bot_token,
+            chat_id,
+            filter,
+            price_move_threshold,
+        })
+    }
+
+    /// Format the message for an update, or None if it isn't worth sending
+    fn format_update(&self, update: &MarketUpdate) -> Option<String> {
+        let market = update.market();
+        if !self.filter.matches(market) {
+            return None;
+        }
+
+        match update {
+            MarketUpdate::NewMarket { .. } => Some(format_new_market(market)),
+            MarketUpdate::MarketChanged { previous_price, .. } => {
+                let price_move = update.price_move()?;
+                if price_move.abs().to_f64().unwrap_or(0.0) < self.price_move_threshold {
+                    return None;
+                }
+                Some(format_price_move(market, (*previous_price)?, price_move))
+            }
+        }
+    }
+
+    async fn send_message(&self, text: &str) -> Result<()> {
+        self.client
+            .post(format!("{}/bot{}/sendMessage", TELEGRAM_API_URL, self.bot_token))
+            .json(&json!({
+                "chat_id": self.chat_id,
+                "text": text,
+                "parse_mode": "HTML",
+                "disable_web_page_preview": true,
+            }))
+            .send()
+            .await
+            .context("Failed to send Telegram message")?
+            .error_for_status()
+            .context("Telegram API returned an error status")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+        let Some(text) = self.format_update(update) else {
+            return Ok(());
+        };
+
+        self.send_message(&text).await?;
+        info!("Sent Telegram notification for market {}", update.market().id);
+        Ok(())
+    }
+
+    async fn alert(&self, message: &str) -> Result<()> {
+        self.send_message(&format!("⚠️ {}", escape_html(message))).await
+    }
+}
+
+fn format_new_market(market: &Market) -> String {
+    let mut text = format!("🆕 <b>New market</b>\n{}", escape_html(&market.title));
+    if let Some(price) = market.current_price {
+        text.push_str(&format!("\nPrice: {:.1}%", price * Decimal::ONE_HUNDRED));
+    }
+    if let Some(volume) = market.volume {
+        text.push_str(&format!("\nVolume: ${:.0}", volume));
+    }
+    if let Some(end_date) = market.end_date {
+        text.push_str(&format!("\nEnds: {}", end_date.format("%Y-%m-%d %H:%M UTC")));
+    }
+    text
+}
+
+fn format_price_move(market: &Market, previous_price: Decimal, price_move: Decimal) -> String {
+    let arrow = if price_move > Decimal::ZERO { "📈" } else { "📉" };
+    format!(
+        "{} <b>Price move</b>\n{}\n{:.1}% → {:.1}% ({:+.1} pts)",
+        arrow,
+        escape_html(&market.title),
+        previous_price * Decimal::ONE_HUNDRED,
+        (previous_price + price_move) * Decimal::ONE_HUNDRED,
+        price_move * Decimal::ONE_HUNDRED
+    )
+}
+
+/// Escape the characters Telegram's HTML parse mode treats as markup
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn notifier() -> TelegramNotifier {
+        TelegramNotifier::new(
+            "token".to_string(),
+            "chat".to_string(),
+            NotificationFilter::default(),
+            DEFAULT_PRICE_MOVE_THRESHOLD,
+        )
+        .unwrap()
+    }
+
+    fn market(price: Decimal) -> Market {
+        Market {
+            id: "1".to_string(),
+            title: "Fed cuts < 25bps?".to_string(),
+            current_price: Some(price),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_new_market() {
+        let update = MarketUpdate::NewMarket { market: market(dec!(0.42)) };
+        let text = notifier().format_update(&update).unwrap();
+        assert!(text.contains("Fed cuts &lt; 25bps?"));
+        assert!(text.contains("Price: 42.0%"));
+    }
+
+    #[test]
+    fn test_price_move_threshold() {
+        let small = MarketUpdate::MarketChanged {
+            market: market(dec!(0.45)),
+            changed_fields: vec!["current_price"],
+            previous_price: Some(dec!(0.42)),
+        };
+        assert!(notifier().format_update(&small).is_none());
+
+        let large = MarketUpdate::MarketChanged {
+            market: market(dec!(0.30)),
+            changed_fields: vec!["current_price"],
+            previous_price: Some(dec!(0.42)),
+        };
+        let text = notifier().format_update(&large).unwrap();
+        assert!(text.contains("42.0% → 30.0% (-12.0 pts)"));
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+use crate::db;
+use crate::models::{OutboxEvent, Webhook};
+use crate::notify::Notifier;
+use crate::updates::MarketUpdate;
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+/// Failed deliveries of an event to one webhook before it is dead-lettered for that webhook,
+/// so an endpoint that stays down doesn't hold up the others
+const WEBHOOK_MAX_ATTEMPTS: u32 = 10;
+pub const SIGNATURE_HEADER: &str = "X-Polymarket-Signature-256";
+/// Same for every redelivery of a notification, so receivers can drop repeats
+pub const DELIVERY_ID_HEADER: &str = "X-Polymarket-Delivery-Id";
+
+/// Delivers a signed POST to every registered webhook whenever a new market is discovered
+/// The POSTs run concurrently so a slow endpoint doesn't hold up the others, and outbox
+/// deliveries are tracked per webhook so a retry only goes to the ones that failed
+pub struct WebhookNotifier {
+    client: Client,
+    pool: Arc<Pool<Sqlite>>,
+}
+
+/// Outbox delivery state of each webhook is kept under its own sink name
+fn sink_name(webhook: &Webhook) -> String {
+    format!("Webhook:{}", webhook.id)
+}
+
+impl WebhookNotifier {
+    pub fn new(pool: Arc<Pool<Sqlite>>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()
+            .context("Failed to create webhook HTTP client")?;
+
+        Ok(Self { client, pool })
+    }
+
+    /// POST the update to every registered webhook, or those that haven't settled `event`
+    /// yet, if it's a new market
+    /// Fails if any webhook did not acknowledge it, after recording how each one did
+    async fn send(&self, update: &MarketUpdate, event: Option<&OutboxEvent>) -> Result<()> {
+        if !matches!(update, MarketUpdate::NewMarket { .. }) {
+            return Ok(());
+        }
+
+        let mut webhooks = db::get_webhooks(&self.pool)
+            .await
+            .context("Failed to load webhooks")?;
+        if let Some(event) = event {
+            let settled = db::get_settled_outbox_sinks(&self.pool, event.id)
+                .await
+                .context("Failed to load webhook delivery state")?;
+            webhooks.retain(|webhook| !settled.contains(&sink_name(webhook)));
+        }
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+
+        let payload = Arc::new(
+            serde_json::to_string(update).context("Failed to serialize webhook payload")?,
+        );
+        let event = event.cloned().map(Arc::new);
+
+        let mut deliveries = JoinSet::new();
+        for webhook in webhooks {
+            let client = self.client.clone();
+            let pool = Arc::clone(&self.pool);
+            let payload = Arc::clone(&payload);
+            let event = event.clone();
+            deliveries.spawn(async move {
+                let result = deliver(&client, &webhook, &payload, event.as_deref()).await;
+                match event {
+                    Some(event) => record(&pool, &webhook, &payload, &event, result).await,
+                    None => result,
+                }
+            });
+        }
+
+        let mut failed = 0;
+        while let Some(result) = deliveries.join_next().await {
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    warn!("{:#}", e);
+                    failed += 1;
+                }
+                Err(e) => {
+                    error!("Webhook delivery task failed: {}", e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} webhook deliveries failed", failed);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "Webhook"
+    }
+
+    async fn notify(&self, update: &MarketUpdate) -> Result<()> {
+        self.send(update, None).await
+    }
+
+    async fn deliver(&self, update: &MarketUpdate, event: &OutboxEvent) -> Result<()> {
+        self.send(update, Some(event)).await
+    }
+}
+
+/// Record the outcome of delivering an outbox event to one webhook
+/// A webhook that failed `WEBHOOK_MAX_ATTEMPTS` times gets a dead letter and is no longer
+/// retried for the event; otherwise the failure is returned so the event is retried
+async fn record(
+    pool: &Pool<Sqlite>,
+    webhook: &Webhook,
+    payload: &str,
+    event: &OutboxEvent,
+    result: Result<()>,
+) -> Result<()> {
+    let sink = sink_name(webhook);
+    let error = match result {
+        Ok(()) => {
+            info!("Delivered webhook {} to {}", webhook.id, webhook.url);
+            db::record_outbox_delivery(pool, event.id, &sink, Utc::now()).await?;
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+
+    let message = format!("{:#}", error);
+    let attempts = db::record_outbox_failure(pool, event.id, &sink, &message).await?;
+    if attempts < WEBHOOK_MAX_ATTEMPTS {
+        return Err(error.context(format!(
+            "Webhook {} delivery attempt {} failed",
+            webhook.id, attempts
+        )));
+    }
+
+    error!(
+        "Webhook {} delivery to {} failed after {} attempts: {}",
+        webhook.id, webhook.url, attempts, message
+    );
+    db::insert_webhook_dead_letter(pool, webhook, payload, &message, attempts).await?;
+    db::dead_letter_outbox(pool, event.id, &sink, &message, Utc::now()).await?;
+    Ok(())
+}
+
+/// Send a single signed POST
+async fn deliver(
+    client: &Client,
+    webhook: &Webhook,
+    payload: &str,
+    event: Option<&OutboxEvent>,
+) -> Result<()> {
+    let mut request = client
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .body(payload.to_string());
+
+    if let Some(event) = event {
+        request = request.header(DELIVERY_ID_HEADER, &event.dedup_key);
+    }
+
+    if let Some(secret) = webhook.secret.as_deref() {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, payload)?));
+    }
+
+    request
+        .send()
+        .await
+        .context("Failed to send webhook")?
+        .error_for_status()
+        .context("Webhook endpoint returned an error status")?;
+
+    Ok(())
+}
+
+/// Hex-encoded HMAC-SHA256 of the payload, so receivers can verify the sender
+pub fn sign(secret: &str, payload: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid webhook secret: {}", e))?;
+    mac.update(payload.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Market;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_deliver_retries_only_failed_webhooks() {
+        let pool = db::test_pools().await.write;
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/down"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&server)
+            .await;
+        db::insert_webhook(&pool, &format!("{}/ok", server.uri()), None).await.unwrap();
+        db::insert_webhook(&pool, &format!("{}/down", server.uri()), None).await.unwrap();
+
+        let notifier = WebhookNotifier::new(Arc::clone(&pool)).unwrap();
+        let update = MarketUpdate::NewMarket {
+            market: Market {
+                id: "1".to_string(),
+                title: "Test".to_string(),
+                ..Default::default()
+            },
+        };
+        let event = OutboxEvent {
+            id: 1,
+            dedup_key: "new_market:1:0".to_string(),
+            payload: serde_json::to_string(&update).unwrap(),
+        };
+
+        // Every POST is awaited, and a failed one fails the delivery
+        assert!(notifier.deliver(&update, &event).await.is_err());
+        // The retry only goes to the webhook that failed
+        assert!(notifier.deliver(&update, &event).await.is_err());
+        let settled = db::get_settled_outbox_sinks(&pool, event.id).await.unwrap();
+        assert_eq!(settled.len(), 1);
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog").unwrap(),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use alloy::primitives::{address, Address, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder, ReqwestProvider};
+use alloy::rpc::types::Filter;
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info, warn};
+
+use crate::metrics;
+use crate::models::{Market, MarketStatus, OnchainVerification};
+use crate::scraper::ScraperControl;
+use crate::storage::Storage;
+
+pub const DEFAULT_ONCHAIN_INTERVAL_SECS: u64 = 300;
+/// Polymarket's Conditional Token Framework contract on Polygon
+const CONDITIONAL_TOKENS: Address = address!("4D97DCd97eC945f40cF65F87097ACe5EA0476045");
+/// CTF Exchange and Neg Risk CTF Exchange, which emit `OrderFilled` for every CLOB trade
+const EXCHANGES: [Address; 2] = [
+    address!("4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E"),
+    address!("C5d563A36AE78145C45a50134d48A1215220f80a"),
+];
+/// `scraper_state` key of the last block whose fills were counted
+const CHECKPOINT_KEY: &str = "onchain_volume_block";
+/// Blocks behind the head left unscanned, so reorganised fills aren't counted
+const CONFIRMATIONS: u64 = 64;
+/// Blocks per `eth_getLogs` request; most public RPCs reject larger ranges
+const LOG_CHUNK_BLOCKS: u64 = 2_000;
+/// Markets checked against the chain per pass
+const VERIFY_BATCH_SIZE: u32 = 50;
+/// USDC has six decimals
+const USDC_UNIT: f64 = 1_000_000.0;
+/// Relative shortfall of the scraped volume below the on-chain one reported as a mismatch
+const VOLUME_TOLERANCE: f64 = 0.01;
+
+sol! {
+    #[sol(rpc)]
+    interface IConditionalTokens {
+        function payoutDenominator(bytes32 conditionId) external view returns (uint256);
+        function payoutNumerators(bytes32 conditionId, uint256 index) external view returns (uint256);
+    }
+
+    event OrderFilled(
+        bytes32 indexed orderHash,
+        address indexed maker,
+        address indexed taker,
+        uint256 makerAssetId,
+        uint256 takerAssetId,
+        uint256 makerAmountFilled,
+        uint256 takerAmountFilled,
+        uint256 fee
+    );
+}
+
+/// On-chain verification settings supplied from the command line
+#[derive(Debug, Clone)]
+pub struct OnchainConfig {
+    pub rpc_url: String,
+    /// First block scanned for fills when there is no checkpoint; the chain head if unset
+    pub start_block: Option<u64>,
+    pub interval_secs: u64,
+}
+
+/// CLOB token and USDC amount traded by a fill, or None for the fill of a taker order
+/// The exchange emits one fill per matched maker order and another for the taker order
+/// with itself as taker, which repeats the maker fills and would count them twice
+fn fill_volume(fill: &OrderFilled, exchange: Address) -> Option<(U256, f64)> {
+    if fill.taker == exchange {
+        return None;
+    }
+    // Asset ID 0 is USDC; the other side is the outcome token
+    let (token_id, usdc) = if fill.makerAssetId.is_zero() {
+        (fill.takerAssetId, fill.makerAmountFilled)
+    } else {
+        (fill.makerAssetId, fill.takerAmountFilled)
+    };
+    Some((token_id, usdc.saturating_to::<u128>() as f64 / USDC_UNIT))
+}
+
+/// Index of the outcome paid out in full; None for a split payout
+fn winning_index(numerators: &[U256], denominator: U256) -> Option<usize> {
+    numerators
+        .iter()
+        .position(|numerator| *numerator == denominator)
+}
+
+/// Count the USDC volume of every fill between the checkpoint and the confirmed head
+/// Returns the number of fills counted
+async fn scan_volumes(
+    storage: &dyn Storage,
+    provider: &ReqwestProvider,
+    start_block: Option<u64>,
+) -> Result<usize> {
+    let head = provider
+        .get_block_number()
+        .await?
+        .saturating_sub(CONFIRMATIONS);
+    let mut checkpoint = match storage.get_scraper_state(CHECKPOINT_KEY).await? {
+        Some(block) => Some(block.parse::<u64>().context("Invalid on-chain checkpoint")?),
+        None => None,
+    };
+    let mut from = match (checkpoint, start_block) {
+        (Some(block), _) => block + 1,
+        (None, Some(block)) => block,
+        (None, None) => {
+            if storage
+                .add_onchain_volumes(&[], CHECKPOINT_KEY, None, head)
+                .await?
+            {
+                info!("Counting on-chain volume from block {}", head + 1);
+            }
+            return Ok(0);
+        }
+    };
+
+    let mut fills = 0;
+    while from <= head {
+        let to = (from + LOG_CHUNK_BLOCKS - 1).min(head);
+        let filter = Filter::new()
+            .address(EXCHANGES.to_vec())
+            .event_signature(OrderFilled::SIGNATURE_HASH)
+            .from_block(from)
+            .to_block(to);
+        let logs = provider
+            .get_logs(&filter)
+            .await
+            .with_context(|| format!("Failed to fetch fills of blocks {}-{}", from, to))?;
+
+        let mut volumes: HashMap<String, f64> = HashMap::new();
+        let mut chunk_fills = 0;
+        for log in logs {
+            let fill = match log.log_decode::<OrderFilled>() {
+                Ok(fill) => fill,
+                Err(e) => {
+                    warn!("Skipping undecodable OrderFilled log: {}", e);
+                    continue;
+                }
+            };
+            if let Some((token_id, usdc)) = fill_volume(&fill.inner.data, fill.inner.address) {
+                *volumes.entry(token_id.to_string()).or_default() += usdc;
+                chunk_fills += 1;
+            }
+        }
+        let volumes: Vec<(String, f64)> = volumes.into_iter().collect();
+        if !storage
+            .add_onchain_volumes(&volumes, CHECKPOINT_KEY, checkpoint, to)
+            .await?
+        {
+            // Another scanner counted these blocks; the next scan resumes from its checkpoint
+            warn!(
+                "On-chain checkpoint moved during the scan, leaving blocks {}-{} to its scanner",
+                from, to
+            );
+            return Ok(fills);
+        }
+        fills += chunk_fills;
+        checkpoint = Some(to);
+        from = to + 1;
+    }
+    Ok(fills)
+}
+
+/// Compare a market with its condition on chain, filling in a resolution the API is missing
+/// and correcting a winner that disagrees with the payouts
+async fn verify_market(
+    storage: &dyn Storage,
+    provider: &ReqwestProvider,
+    market: &Market,
+) -> Result<OnchainVerification> {
+    let condition_id: B256 = market
+        .condition_id
+        .as_deref()
+        .unwrap_or_default()
+        .parse()
+        .context("Invalid condition ID")?;
+    let outcomes = storage.get_outcomes(&market.id).await?;
+    let ctf = IConditionalTokens::new(CONDITIONAL_TOKENS, provider);
+
+    let denominator = ctf.payoutDenominator(condition_id).call().await?._0;
+    let resolved = !denominator.is_zero();
+    let mut winning_outcome = None;
+    if resolved {
+        let mut numerators = Vec::with_capacity(outcomes.len().max(2));
+        for index in 0..outcomes.len().max(2) {
+            numerators.push(
+                ctf.payoutNumerators(condition_id, U256::from(index))
+                    .call()
+                    .await?
+                    ._0,
+            );
+        }
+        // Markets without stored outcomes are taken to be `Yes`/`No` markets
+        winning_outcome = winning_index(&numerators, denominator).map(|index| {
+            outcomes
+                .get(index)
+                .map_or(["Yes", "No"][index.min(1)], |outcome| outcome.name.as_str())
+                .to_string()
+        });
+    }
+
+    let mut mismatches = Vec::new();
+    match (&market.winning_outcome, &winning_outcome) {
+        (scraped, Some(chain))
+            if !scraped
+                .as_deref()
+                .is_some_and(|scraped| scraped.eq_ignore_ascii_case(chain)) =>
+        {
+            mismatches.push(format!(
+                "winner {} on chain but {} in the API",
+                chain,
+                scraped.as_deref().unwrap_or("unknown")
+            ));
+            ::metrics::counter!(metrics::ONCHAIN_MISMATCHES_TOTAL, "field" => "resolution")
+                .increment(1);
+            storage
+                .update_market_resolution(&Market {
+                    id: market.id.clone(),
+                    status: Some(MarketStatus::Resolved),
+                    winning_outcome: Some(chain.clone()),
+                    resolved_at: market.resolved_at.or_else(|| Some(Utc::now())),
+                    ..Default::default()
+                })
+                .await?;
+        }
+        (Some(scraped), None) if !resolved => {
+            mismatches.push(format!(
+                "winner {} in the API but unresolved on chain",
+                scraped
+            ));
+            ::metrics::counter!(metrics::ONCHAIN_MISMATCHES_TOTAL, "field" => "resolution")
+                .increment(1);
+        }
+        _ => {}
+    }
+
+    // The scan only covers fills since it started, so only a shortfall means the API lags
+    let volume = storage.get_onchain_volume(&market.id).await?;
+    let scraped_volume = market.volume.and_then(|v| v.to_f64()).unwrap_or(0.0);
+    if let Some(chain_volume) = volume.filter(|v| scraped_volume < v * (1.0 - VOLUME_TOLERANCE)) {
+        mismatches.push(format!(
+            "volume {:.2} on chain but {:.2} in the API",
+            chain_volume, scraped_volume
+        ));
+        ::metrics::counter!(metrics::ONCHAIN_MISMATCHES_TOTAL, "field" => "volume").increment(1);
+    }
+
+    let mismatch = (!mismatches.is_empty()).then(|| mismatches.join("; "));
+    if let Some(mismatch) = &mismatch {
+        warn!(
+            "Market {} disagrees with the chain: {}",
+            market.id, mismatch
+        );
+    }
+    Ok(OnchainVerification {
+        market_id: market.id.clone(),
+        resolved,
+        winning_outcome,
+        volume,
+        mismatch,
+        checked_at: Utc::now(),
+    })
+}
+
+/// Check a batch of markets not yet confirmed resolved on chain; returns how many were checked
+async fn verify_pending(storage: &dyn Storage, provider: &ReqwestProvider) -> Result<usize> {
+    let pending = storage
+        .get_markets_pending_onchain(VERIFY_BATCH_SIZE)
+        .await?;
+    for market in &pending {
+        match verify_market(storage, provider, market).await {
+            Ok(verification) => storage.save_onchain_verification(&verification).await?,
+            Err(e) => warn!("Failed to verify market {} on chain: {:#}", market.id, e),
+        }
+    }
+    Ok(pending.len())
+}
+
+/// Scan new fills and verify pending markets against Polygon every interval on the primary
+/// instance, so no replica counts the same fills again
+pub async fn run_verification(
+    storage: Arc<dyn Storage>,
+    config: OnchainConfig,
+    scraper: ScraperControl,
+) -> Result<()> {
+    let provider =
+        ProviderBuilder::new().on_http(config.rpc_url.parse().context("Invalid Polygon RPC URL")?);
+    info!(
+        "Verifying markets against Polygon every {} seconds",
+        config.interval_secs
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        match scan_volumes(storage.as_ref(), &provider, config.start_block).await {
+            Ok(fills) => debug!("Counted {} on-chain fills", fills),
+            Err(e) => error!("On-chain volume scan failed: {:#}", e),
+        }
+        match verify_pending(storage.as_ref(), &provider).await {
+            Ok(checked) => debug!("Verified {} markets on chain", checked),
+            Err(e) => error!("On-chain verification failed: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(maker_asset: u64, taker_asset: u64, taker: Address) -> OrderFilled {
+        OrderFilled {
+            orderHash: B256::ZERO,
+            maker: Address::ZERO,
+            taker,
+            makerAssetId: U256::from(maker_asset),
+            takerAssetId: U256::from(taker_asset),
+            makerAmountFilled: U256::from(2_500_000u64),
+            takerAmountFilled: U256::from(5_000_000u64),
+            fee: U256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_fill_volume() {
+        let exchange = EXCHANGES[0];
+        let buyer = Address::repeat_byte(1);
+        // Maker pays USDC for outcome tokens
+        assert_eq!(
+            fill_volume(&fill(0, 42, buyer), exchange),
+            Some((U256::from(42u64), 2.5))
+        );
+        // Maker sells outcome tokens for USDC
+        assert_eq!(
+            fill_volume(&fill(42, 0, buyer), exchange),
+            Some((U256::from(42u64), 5.0))
+        );
+        assert_eq!(fill_volume(&fill(0, 42, exchange), exchange), None);
+    }
+
+    #[test]
+    fn test_winning_index() {
+        let denominator = U256::from(1u64);
+        assert_eq!(
+            winning_index(&[U256::ZERO, U256::from(1u64)], denominator),
+            Some(1)
+        );
+        assert_eq!(
+            winning_index(&[U256::from(1u64), U256::from(1u64)], U256::from(2u64)),
+            None
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+const MAX_TRACKED_CLIENTS: usize = 10_000; // Idle buckets are pruned beyond this
+const IDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Per-client token bucket refilled at `requests_per_minute`, allowing bursts up to the same amount
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct Buckets {
+    by_client: HashMap<String, Bucket>,
+    last_sweep: Instant,
+}
+
+/// Request-per-minute limiter keyed by known API key or client IP
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    api_keys: HashSet<String>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// `api_keys` get a budget of their own; any other `X-API-Key` counts against the
+    /// client's IP, so made-up keys can't buy more requests
+    pub fn new(requests_per_minute: u32, api_keys: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            capacity: requests_per_minute as f64,
+            refill_per_sec: requests_per_minute as f64 / 60.0,
+            api_keys: api_keys.into_iter().collect(),
+            buckets: Mutex::new(Buckets {
+                by_client: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token for `key`, or return how long until one is available
+    fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let sweep_due = now.saturating_duration_since(buckets.last_sweep) >= IDLE_SWEEP_INTERVAL;
+        let full = buckets.by_client.len() >= MAX_TRACKED_CLIENTS;
+        if sweep_due || (full && !buckets.by_client.contains_key(key)) {
+            // A bucket that has fully refilled is indistinguishable from a new one
+            let full_after = Duration::from_secs_f64(self.capacity / self.refill_per_sec);
+            buckets
+                .by_client
+                .retain(|_, bucket| now.saturating_duration_since(bucket.last_refill) < full_after);
+            buckets.last_sweep = now;
+        }
+
+        let bucket = buckets.by_client.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now
+            .saturating_duration_since(bucket.last_refill)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_per_sec,
+            ))
+        }
+    }
+
+    /// Identify the client by API key when it is a known one, otherwise by peer IP
+    fn client_key(&self, req: &Request) -> String {
+        if let Some(key) = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|key| self.api_keys.contains(*key))
+        {
+            return format!("key:{}", key);
+        }
+
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| format!("ip:{}", addr.ip()))
+            .unwrap_or_else(|| "ip:unknown".to_string())
+    }
+
+    #[cfg(test)]
+    fn tracked_clients(&self) -> usize {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .by_client
+            .len()
+    }
+}
+
+/// Reject requests over the client's limit with `429 Too Many Requests` and `Retry-After`
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let retry_after = match limiter.check(&limiter.client_key(&req), Instant::now()) {
+        Ok(()) => return next.run(req).await,
+        Err(retry_after) => retry_after,
+    };
+
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    ::metrics::counter!(metrics::HTTP_RATE_LIMITED_TOTAL, "path" => path).increment(1);
+
+    let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_refills_per_client() {
+        let limiter = RateLimiter::new(2, []);
+        let start = Instant::now();
+
+        assert!(limiter.check("a", start).is_ok());
+        assert!(limiter.check("a", start).is_ok());
+        let retry_after = limiter.check("a", start).unwrap_err();
+        assert_eq!(retry_after.as_secs(), 30);
+
+        // Other clients have their own budget
+        assert!(limiter.check("b", start).is_ok());
+
+        // One token refills every 30 seconds at 2 requests per minute
+        assert!(limiter.check("a", start + Duration::from_secs(30)).is_ok());
+        assert!(limiter.check("a", start + Duration::from_secs(30)).is_err());
+    }
+
+    #[test]
+    fn test_unknown_api_keys_share_the_client_ip_budget() {
+        let limiter = RateLimiter::new(2, ["known".to_string()]);
+        let request = |key: Option<&str>| {
+            let mut builder = Request::builder().uri("/v1/markets");
+            if let Some(key) = key {
+                builder = builder.header(API_KEY_HEADER, key);
+            }
+            let mut request = builder.body(axum::body::Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+            request
+        };
+
+        assert_eq!(limiter.client_key(&request(Some("known"))), "key:known");
+        assert_eq!(limiter.client_key(&request(Some("made-up"))), "ip:10.0.0.1");
+        assert_eq!(limiter.client_key(&request(None)), "ip:10.0.0.1");
+    }
+
+    #[test]
+    fn test_idle_clients_are_evicted() {
+        let limiter = RateLimiter::new(60, []);
+        let start = Instant::now();
+
+        assert!(limiter.check("a", start).is_ok());
+        assert!(limiter.check("b", start + Duration::from_secs(30)).is_ok());
+        assert_eq!(limiter.tracked_clients(), 2);
+
+        // "a" has fully refilled a minute later and is dropped at the next sweep; "b" isn't yet
+        assert!(limiter.check("c", start + Duration::from_secs(75)).is_ok());
+        assert_eq!(limiter.tracked_clients(), 2);
+        assert!(limiter.check("c", start + Duration::from_secs(200)).is_ok());
+        assert_eq!(limiter.tracked_clients(), 1);
+    }
+}
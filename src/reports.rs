@@ -0,0 +1,195 @@
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+use crate::models::{DailyReport, SortOrder};
+use crate::notify::Alerts;
+use crate::scraper::ScraperControl;
+use crate::storage::Storage;
+
+/// Markets listed in each section of a report
+const REPORT_LIST_LIMIT: u32 = 10;
+/// Markets per section in the summary sent to the notifiers
+const SUMMARY_LIST_LIMIT: usize = 3;
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Summarize the UTC day `date` from the stored markets and price history
+pub async fn generate(storage: &dyn Storage, date: NaiveDate) -> Result<DailyReport> {
+    let from = date
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc();
+    let to = from + ChronoDuration::days(1);
+
+    let (new_markets, new_market_count) = storage
+        .get_markets_discovered_between(from, to, REPORT_LIST_LIMIT)
+        .await?;
+    let (resolved_markets, resolved_count) = storage
+        .get_markets_resolved_between(from, to, REPORT_LIST_LIMIT)
+        .await?;
+    Ok(DailyReport {
+        date,
+        generated_at: Utc::now(),
+        new_market_count,
+        new_markets,
+        gainers: storage
+            .get_price_changes_between(from, to, SortOrder::Desc, REPORT_LIST_LIMIT)
+            .await?,
+        losers: storage
+            .get_price_changes_between(from, to, SortOrder::Asc, REPORT_LIST_LIMIT)
+            .await?,
+        volume_leaders: storage
+            .get_volume_leaders_between(from, to, REPORT_LIST_LIMIT)
+            .await?,
+        resolved_count,
+        resolved_markets,
+    })
+}
+
+/// Generate and store the report of each UTC day once it is over, checking every five minutes
+/// A day missed while the service was down is caught up on the next start, as long as it
+/// was yesterday; when `notify` is set the summary is also sent to the notifiers
+/// Only the primary instance generates reports, so replicas don't send the summary twice
+pub async fn run_daily_reports(
+    storage: Arc<dyn Storage>,
+    alerts: Alerts,
+    notify: bool,
+    scraper: ScraperControl,
+) {
+    info!("Generating a daily report after each UTC day");
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        let yesterday = (Utc::now() - ChronoDuration::days(1)).date_naive();
+        match storage.get_daily_report(yesterday).await {
+            Ok(Some(_)) => continue,
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to load the daily report of {}: {:#}", yesterday, e);
+                continue;
+            }
+        }
+
+        let report = match generate(storage.as_ref(), yesterday).await {
+            Ok(report) => report,
+            Err(e) => {
+                error!(
+                    "Failed to generate the daily report of {}: {:#}",
+                    yesterday, e
+                );
+                continue;
+            }
+        };
+        if let Err(e) = storage.save_daily_report(&report).await {
+            error!("Failed to store the daily report of {}: {:#}", yesterday, e);
+            continue;
+        }
+        info!(
+            "Generated the daily report of {}: {} new markets, {} resolved",
+            yesterday, report.new_market_count, report.resolved_count
+        );
+        if notify {
+            alerts.send(&summary_message(&report)).await;
+        }
+    }
+}
+
+/// Plain-text digest of a report for chat notifiers
+fn summary_message(report: &DailyReport) -> String {
+    let mut text = format!(
+        "Daily report for {}: {} new markets, {} resolved",
+        report.date, report.new_market_count, report.resolved_count
+    );
+    let sections = [
+        (
+            "Top gainers",
+            report
+                .gainers
+                .iter()
+                .map(|mover| format_move(&mover.market.title, mover.price_change))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "Top losers",
+            report
+                .losers
+                .iter()
+                .map(|mover| format_move(&mover.market.title, mover.price_change))
+                .collect(),
+        ),
+        (
+            "Volume leaders",
+            report
+                .volume_leaders
+                .iter()
+                .map(|leader| format!("{} (${:.0})", leader.market.title, leader.volume_traded))
+                .collect(),
+        ),
+    ];
+    for (heading, lines) in sections {
+        if lines.is_empty() {
+            continue;
+        }
+        let _ = write!(text, "\n{}:", heading);
+        for line in lines.iter().take(SUMMARY_LIST_LIMIT) {
+            let _ = write!(text, "\n- {}", line);
+        }
+    }
+    text
+}
+
+fn format_move(title: &str, price_change: f64) -> String {
+    format!("{} ({:+.1} pts)", title, price_change * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Market, MarketMover, MarketVolume};
+
+    fn market(title: &str) -> Market {
+        Market {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summary_message() {
+        let report = DailyReport {
+            date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            generated_at: Utc::now(),
+            new_market_count: 12,
+            new_markets: Vec::new(),
+            gainers: vec![MarketMover {
+                market: market("Will it snow?"),
+                start_price: 0.2,
+                end_price: 0.35,
+                price_change: 0.15,
+                relative_change: Some(0.75),
+            }],
+            losers: Vec::new(),
+            volume_leaders: vec![MarketVolume {
+                market: market("Who wins the election?"),
+                volume_traded: 250_000.0,
+            }],
+            resolved_count: 3,
+            resolved_markets: Vec::new(),
+        };
+        assert_eq!(
+            summary_message(&report),
+            "Daily report for 2024-01-15: 12 new markets, 3 resolved\n\
+             Top gainers:\n- Will it snow? (+15.0 pts)\n\
+             Volume leaders:\n- Who wins the election? ($250000)"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+use crate::db;
+use crate::scraper::ScraperControl;
+
+pub const DEFAULT_MAINTENANCE_INTERVAL_SECS: u64 = 3600;
+/// Delivered notification outbox events are kept for a day to inspect failed deliveries;
+/// events a sink is still retrying are kept until it acknowledges them
+const OUTBOX_RETENTION_DAYS: u32 = 1;
+
+/// How long data is kept; a value of 0 disables the policy
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Delete price history rows older than this many days
+    pub price_history_days: u32,
+    /// Move markets resolved more than this many days ago to `archived_markets`
+    pub archive_resolved_after_days: u32,
+    /// Delete trades older than this many days
+    pub trades_days: u32,
+    /// Delete comments older than this many days
+    pub comments_days: u32,
+    /// Delete holder captures older than this many days, except each market's latest one
+    pub positions_days: u32,
+    /// Delete orderbook snapshots older than this many days
+    pub orderbook_snapshots_days: u32,
+}
+
+/// Rows affected by one pruning run
+#[derive(Debug, Default, Serialize)]
+pub struct PruneSummary {
+    pub price_history_deleted: u64,
+    pub markets_archived: u64,
+    pub trades_deleted: u64,
+    pub comments_deleted: u64,
+    pub positions_deleted: u64,
+    pub orderbook_snapshots_deleted: u64,
+    pub outbox_deleted: u64,
+}
+
+/// Point in time before which data falls outside a retention window of `days`
+fn cutoff(days: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    (days > 0).then(|| now - ChronoDuration::days(days as i64))
+}
+
+/// Apply every enabled retention policy once
+/// `sinks` are the notification sinks still registered, whose pending deliveries keep
+/// outbox events; None keeps events pending for any sink
+pub async fn prune(
+    pool: &Pool<Sqlite>,
+    policy: RetentionPolicy,
+    sinks: Option<&[String]>,
+) -> Result<PruneSummary> {
+    let now = Utc::now();
+    let mut summary = PruneSummary::default();
+
+    if let Some(before) = cutoff(policy.price_history_days, now) {
+        summary.price_history_deleted = db::delete_price_history_before(pool, before).await?;
+    }
+    if let Some(before) = cutoff(policy.archive_resolved_after_days, now) {
+        summary.markets_archived = db::archive_markets_resolved_before(pool, before, now).await?;
+    }
+    if let Some(before) = cutoff(policy.trades_days, now) {
+        summary.trades_deleted = db::delete_trades_before(pool, before).await?;
+    }
+    if let Some(before) = cutoff(policy.comments_days, now) {
+        summary.comments_deleted = db::delete_comments_before(pool, before).await?;
+    }
+    if let Some(before) = cutoff(policy.positions_days, now) {
+        summary.positions_deleted = db::delete_positions_before(pool, before).await?;
+    }
+    if let Some(before) = cutoff(policy.orderbook_snapshots_days, now) {
+        summary.orderbook_snapshots_deleted =
+            db::delete_orderbook_snapshots_before(pool, before).await?;
+    }
+    if let Some(before) = cutoff(OUTBOX_RETENTION_DAYS, now) {
+        summary.outbox_deleted = db::delete_outbox_before(pool, before, sinks).await?;
+    }
+
+    Ok(summary)
+}
+
+/// Run `prune` at a fixed interval on the primary instance, so replicas sharing the
+/// database don't prune it at the same time
+pub async fn run_maintenance(
+    pool: Arc<Pool<Sqlite>>,
+    policy: RetentionPolicy,
+    interval_secs: u64,
+    scraper: ScraperControl,
+    sinks: Vec<String>,
+) -> Result<()> {
+    info!(
+        "Pruning price history after {} days and archiving markets {} days after resolution, every {} seconds",
+        policy.price_history_days, policy.archive_resolved_after_days, interval_secs
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        match prune(&pool, policy, Some(&sinks)).await {
+            Ok(summary) => info!(
+                "Maintenance pruned {} price history rows, {} trades, {} comments, {} positions, {} orderbook snapshots and {} outbox events and archived {} markets",
+                summary.price_history_deleted,
+                summary.trades_deleted,
+                summary.comments_deleted,
+                summary.positions_deleted,
+                summary.orderbook_snapshots_deleted,
+                summary.outbox_deleted,
+                summary.markets_archived
+            ),
+            Err(e) => error!("Maintenance failed: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff() {
+        let now = Utc::now();
+        assert_eq!(cutoff(0, now), None);
+        assert_eq!(cutoff(90, now), Some(now - ChronoDuration::days(90)));
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rust_decimal::prelude::ToPrimitive;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Instant};
+use tracing::{error, info, warn};
+
+use crate::api::window_start;
+use crate::models::{AlertCondition, AlertRule, CreateAlertRuleRequest, Market};
+use crate::notify::{Alerts, NotificationFilter};
+use crate::scraper::ScraperControl;
+use crate::storage::Storage;
+use crate::updates::{MarketUpdate, UpdateSender};
+
+/// How long rules are cached; rules added through the API apply within this delay
+const RULES_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// Delay before a failed rules engine is restarted
+const RULES_RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Why a condition can't be evaluated, if it can't
+pub fn validate(condition: &AlertCondition) -> Result<()> {
+    match condition {
+        AlertCondition::PriceMove { threshold, window } => {
+            anyhow::ensure!(*threshold > 0.0, "threshold must be positive");
+            window_start(window, Utc::now()).context("window must look like 30m, 1h or 1d")?;
+        }
+        AlertCondition::NewMarket { min_volume, .. } => {
+            anyhow::ensure!(
+                min_volume.is_none_or(|volume| volume >= 0.0),
+                "min_volume must not be negative"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Read rules from a JSON file holding an array of `{"name": ..., "condition": ...}` objects
+pub fn load_rules_file(path: &Path) -> Result<Vec<CreateAlertRuleRequest>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read alert rules from {}", path.display()))?;
+    let rules: Vec<CreateAlertRuleRequest> = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse alert rules in {}", path.display()))?;
+    for rule in &rules {
+        validate(&rule.condition).with_context(|| format!("Invalid alert rule {}", rule.name))?;
+    }
+    Ok(rules)
+}
+
+/// Store rules from the configuration, replacing the condition of rules with the same name
+pub async fn sync_config_rules(
+    storage: &dyn Storage,
+    rules: &[CreateAlertRuleRequest],
+) -> Result<()> {
+    for rule in rules {
+        storage
+            .upsert_alert_rule(&rule.name, &rule.condition)
+            .await?;
+    }
+    if !rules.is_empty() {
+        info!("Loaded {} alert rules from configuration", rules.len());
+    }
+    Ok(())
+}
+
+/// Run `run_rules`, restarting it whenever it fails or panics, until the update channel
+/// closes
+pub async fn run_supervised(
+    storage: Arc<dyn Storage>,
+    alerts: Alerts,
+    updates: UpdateSender,
+    scraper: ScraperControl,
+) {
+    loop {
+        // Dropping the set aborts the engine along with this task
+        let mut engine = JoinSet::new();
+        engine.spawn(run_rules(
+            Arc::clone(&storage),
+            alerts.clone(),
+            updates.subscribe(),
+            scraper.clone(),
+        ));
+        match engine.join_next().await {
+            Some(Ok(Ok(()))) | None => return,
+            Some(Ok(Err(e))) => error!("Alert rules task failed, restarting: {:#}", e),
+            Some(Err(e)) => error!("Alert rules task panicked, restarting: {}", e),
+        }
+        sleep(RULES_RESTART_DELAY).await;
+    }
+}
+
+/// Evaluate every alert rule against the market updates of each scrape
+/// Matches are recorded in `alerts_fired` and sent to the notifiers as alerts. Only scraper
+/// leaders evaluate, one per shard, each over the updates of its own scrapes, so replicas
+/// don't fire the same alert twice
+pub async fn run_rules(
+    storage: Arc<dyn Storage>,
+    alerts: Alerts,
+    mut updates: broadcast::Receiver<MarketUpdate>,
+    scraper: ScraperControl,
+) -> Result<()> {
+    let mut rules: Vec<AlertRule> = Vec::new();
+    let mut loaded_at: Option<Instant> = None;
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Alert rules lagging behind, skipped {} updates", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if !scraper.is_leader() {
+            continue;
+        }
+
+        if loaded_at.is_none_or(|at| at.elapsed() >= RULES_REFRESH_INTERVAL) {
+            match storage.get_alert_rules().await {
+                Ok(loaded) => rules = loaded,
+                Err(e) => warn!("Failed to load alert rules: {}", e),
+            }
+            loaded_at = Some(Instant::now());
+        }
+
+        for rule in &rules {
+            match evaluate(storage.as_ref(), rule, &update).await {
+                Ok(Some(message)) => {
+                    fire(storage.as_ref(), &alerts, rule, update.market(), &message).await
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to evaluate alert rule {}: {}", rule.name, e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The alert message if the rule matches the update
+async fn evaluate(
+    storage: &dyn Storage,
+    rule: &AlertRule,
+    update: &MarketUpdate,
+) -> Result<Option<String>> {
+    let market = update.market();
+    match (&rule.condition, update) {
+        (
+            AlertCondition::NewMarket {
+                min_volume,
+                keywords,
+            },
+            MarketUpdate::NewMarket { .. },
+        ) => Ok(new_market_matches(market, *min_volume, keywords).then(|| {
+            format!(
+                "{}: new market \"{}\" with volume {}",
+                rule.name,
+                market.title,
+                market.volume.unwrap_or_default()
+            )
+        })),
+        (AlertCondition::PriceMove { threshold, window }, MarketUpdate::MarketChanged { .. })
+            if update.is_price_change() =>
+        {
+            let since = window_start(window, Utc::now()).context("invalid window")?;
+            let (Some(start), Some(current)) = (
+                storage.get_first_price_since(&market.id, since).await?,
+                market.current_price.and_then(|price| price.to_f64()),
+            ) else {
+                return Ok(None);
+            };
+            let Some(change) = relative_change(start, current).filter(|c| c.abs() >= *threshold)
+            else {
+                return Ok(None);
+            };
+
+            // Fire once per market and window, not on every scrape while the move lasts
+            let last_fired = storage.get_last_alert_fired_at(rule.id, &market.id).await?;
+            if last_fired.is_some_and(|at| at >= since) {
+                return Ok(None);
+            }
+            Ok(Some(format!(
+                "{}: \"{}\" moved {:+.1}% in {} ({:.3} -> {:.3})",
+                rule.name,
+                market.title,
+                change * 100.0,
+                window,
+                start,
+                current
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn new_market_matches(market: &Market, min_volume: Option<f64>, keywords: &[String]) -> bool {
+    NotificationFilter {
+        keywords: keywords.to_vec(),
+        min_volume,
+    }
+    .matches(market)
+}
+
+/// Change from `start` to `current` as a fraction of `start`; None when `start` is zero
+fn relative_change(start: f64, current: f64) -> Option<f64> {
+    (start != 0.0).then(|| (current - start) / start)
+}
+
+/// Record a match and send it to the notifiers
+async fn fire(
+    storage: &dyn Storage,
+    alerts: &Alerts,
+    rule: &AlertRule,
+    market: &Market,
+    message: &str,
+) {
+    info!("Alert rule fired: {}", message);
+    if let Err(e) = storage
+        .insert_alert_fired(rule.id, &market.id, message)
+        .await
+    {
+        error!("Failed to record alert of rule {}: {}", rule.name, e);
+    }
+    alerts.send(message).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_validate() {
+        let price_move = |threshold: f64, window: &str| AlertCondition::PriceMove {
+            threshold,
+            window: window.to_string(),
+        };
+        assert!(validate(&price_move(0.1, "1h")).is_ok());
+        assert!(validate(&price_move(0.0, "1h")).is_err());
+        assert!(validate(&price_move(0.1, "hourly")).is_err());
+        assert!(validate(&price_move(0.1, "106751991d")).is_err());
+        assert!(validate(&AlertCondition::NewMarket {
+            min_volume: Some(-1.0),
+            keywords: Vec::new(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let market = Market {
+            title: "Who will win the 2028 election?".to_string(),
+            volume: Some(Decimal::from(60_000)),
+            ..Default::default()
+        };
+        let keywords = vec!["Election".to_string()];
+        assert!(new_market_matches(&market, Some(50_000.0), &keywords));
+        assert!(!new_market_matches(&market, Some(100_000.0), &keywords));
+        assert!(!new_market_matches(&market, None, &["weather".to_string()]));
+
+        assert_eq!(
+            relative_change(0.5, 0.55).map(|c| (c * 100.0).round()),
+            Some(10.0)
+        );
+        assert_eq!(
+            relative_change(0.5, 0.4).map(|c| (c * 100.0).round()),
+            Some(-20.0)
+        );
+        assert_eq!(relative_change(0.0, 0.4), None);
+    }
+}
@@ -1,37 +1,949 @@
-use anyhow::{Context, Result};
-use reqwest::Client;
-use sqlx::Pool;
-use sqlx::Sqlite;
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::sleep;
-use tracing::{error, info, warn};
+use tokio::sync::{mpsc, oneshot, watch, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Instant, Interval, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
+use utoipa::ToSchema;
 
-use crate::db;
-use crate::metrics::Metrics;
-use crate::models::Market;
+use crate::archive;
+use crate::circuit_breaker::{BreakerConfig, CircuitBreaker, CircuitState};
+use crate::clob;
+use crate::conditional::ListingPage;
+use crate::error::{Error, Result, UpstreamContext};
+use crate::filters::IngestFilter;
+use crate::fixtures::{FixtureMode, Fixtures};
+use crate::gamma::{self, GammaSource};
+use crate::holders;
+#[cfg(feature = "html")]
+use crate::html::HtmlSource;
+#[cfg(feature = "manifold")]
+use crate::manifold::ManifoldSource;
+use crate::metrics::{self, ErrorCategory, Metrics, PipelineStage, ScrapePhase};
+use crate::models::{
+    Event, Market, MarketStatus, NextJsMarket, Position, Precision, ScrapedMarket,
+};
+use crate::shards::Shard;
+use crate::source::{self, FetchContext, FetchedMarkets, MarketSource, PageSink};
+use crate::storage::Storage;
+use crate::subgraph::{self, SubgraphSource};
+use crate::throttle::{ThrottleConfig, ThrottledClient};
+use crate::tiers::{EnrichPass, TierConfig, TierScheduler};
+use crate::trades;
+use crate::updates::{self, MarketUpdate, UpdateSender};
+use crate::validation::{self, Rejection};
 
 // Polymarket API endpoints
 const POLYMARKET_BASE_URL: &str = "https://polymarket.com/_next/data";
 const DEFAULT_BUILD_ID: &str = "keyXdCWmEdmqkd-AH927v"; // Default build ID from assignment
-const MIN_REQUEST_INTERVAL_SECS: u64 = 1; // Rate limiting: minimum 1 second between requests
+const MIN_SCRAPE_INTERVAL_SECS: u64 = 1;
 const MAX_RETRIES: u32 = 3;
 const INITIAL_RETRY_DELAY_SECS: u64 = 1;
+const BUILD_ID_REDISCOVERY_THRESHOLD: u32 = 3; // Consecutive failed scrapes before re-discovering build ID
+pub const MAX_SCRAPE_INTERVAL_SECS: u64 = 86_400;
+const SCRAPE_TRIGGER_CAPACITY: usize = 8; // Pending on-demand scrape requests
+const MARKET_REFRESH_CAPACITY: usize = 32; // Pending on-demand market refreshes
+pub const DEFAULT_DELIST_AFTER_MISSED_SCRAPES: u32 = 3;
+pub const DEFAULT_ENRICH_CONCURRENCY: u32 = 4;
+const MAX_LOGGED_REJECTIONS: usize = 5; // Rejected markets logged individually per scrape
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(300);
+const PIPELINE_CAPACITY: usize = 2; // Parsed batches queued before fetching waits for the writer
+const HOT_REFRESH_BUDGET_SHARE: f64 = 0.5; // Of the upstream request rate, the rest is left to scrapes
+static NEXT_SCRAPE_ID: AtomicU64 = AtomicU64::new(1);
 
-/// Run the scraper in a loop, fetching markets at specified interval
+// `scraper_state` keys of the checkpoints a restarted scraper resumes from
+const STATE_BUILD_ID: &str = "build_id";
+const STATE_LAST_SUCCESS_AT: &str = "last_success_at";
+const STATE_RESUME_OFFSET_PREFIX: &str = "resume_offset:"; // Followed by the source name
+const STATE_RESUME_CURSOR_PREFIX: &str = "resume_cursor:"; // Followed by the source name
+
+/// Upstream data source used to list markets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrapeSource {
+    /// Official Gamma API (`gamma-api.polymarket.com`)
+    Gamma,
+    /// Next.js `_next/data` endpoint of the Polymarket frontend
+    NextJs,
+    /// Polymarket's subgraph on The Graph, keyed by condition ID
+    Subgraph,
+    /// Manifold Markets API (`api.manifold.markets`)
+    #[cfg(feature = "manifold")]
+    Manifold,
+    /// Market data embedded in the server-rendered polymarket.com pages
+    #[cfg(feature = "html")]
+    Html,
+}
+
+impl ScrapeSource {
+    pub const ALL: &'static [ScrapeSource] = &[
+        ScrapeSource::Gamma,
+        ScrapeSource::NextJs,
+        ScrapeSource::Subgraph,
+        #[cfg(feature = "manifold")]
+        ScrapeSource::Manifold,
+        #[cfg(feature = "html")]
+        ScrapeSource::Html,
+    ];
+
+    /// The source to try when this one fails, if any
+    fn fallback(self) -> Option<Self> {
+        match self {
+            ScrapeSource::Gamma => Some(ScrapeSource::NextJs),
+            ScrapeSource::NextJs => Some(ScrapeSource::Gamma),
+            // Other sources key markets by Gamma ID, so falling back would duplicate them
+            ScrapeSource::Subgraph => None,
+            #[cfg(feature = "manifold")]
+            ScrapeSource::Manifold => None,
+            #[cfg(feature = "html")]
+            ScrapeSource::Html => Some(ScrapeSource::Gamma),
+        }
+    }
+
+    /// Sources tried in order until one succeeds: this one, its fallback and, for
+    /// Polymarket with the `html` feature, the server-rendered pages as a last resort
+    fn fetch_order(self) -> Vec<Self> {
+        let mut order = vec![self];
+        order.extend(self.fallback());
+        #[cfg(feature = "html")]
+        if self.market_source().venue() == source::POLYMARKET && !order.contains(&ScrapeSource::Html) {
+            order.push(ScrapeSource::Html);
+        }
+        order
+    }
+
+    /// Implementation that fetches and parses this source's markets
+    pub fn market_source(self) -> &'static dyn MarketSource {
+        match self {
+            ScrapeSource::Gamma => &GammaSource,
+            ScrapeSource::NextJs => &NextJsSource,
+            ScrapeSource::Subgraph => &SubgraphSource,
+            #[cfg(feature = "manifold")]
+            ScrapeSource::Manifold => &ManifoldSource,
+            #[cfg(feature = "html")]
+            ScrapeSource::Html => &HtmlSource,
+        }
+    }
+}
+
+impl FromStr for ScrapeSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "gamma" => Ok(ScrapeSource::Gamma),
+            "nextjs" => Ok(ScrapeSource::NextJs),
+            "subgraph" => Ok(ScrapeSource::Subgraph),
+            #[cfg(feature = "manifold")]
+            "manifold" => Ok(ScrapeSource::Manifold),
+            #[cfg(feature = "html")]
+            "html" => Ok(ScrapeSource::Html),
+            other => Err(anyhow::anyhow!(
+                "Unknown scrape source '{}', expected one of: {}",
+                other,
+                ScrapeSource::ALL
+                    .iter()
+                    .map(|source| source.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ScrapeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.market_source().name())
+    }
+}
+
+/// Scraper settings supplied from the command line
+#[derive(Debug, Clone)]
+pub struct ScraperConfig {
+    pub source: ScrapeSource,
+    /// Sources scraped after the primary one each cycle, e.g. other venues
+    pub additional_sources: Vec<ScrapeSource>,
+    /// Number of markets requested per page
+    pub page_size: u32,
+    /// Safety cap on pages fetched per scrape cycle
+    pub max_pages: u32,
+    /// Complete scrapes a market may be missing from before it is marked `delisted`; 0 disables
+    pub delist_after_missed_scrapes: u32,
+    /// Decimal places kept for stored prices and volumes
+    pub precision: Precision,
+    /// Which listed markets are stored
+    pub filter: IngestFilter,
+    /// Markets enriched with detail and orderbook requests at the same time
+    pub enrich_concurrency: u32,
+    /// How often hot and long-tail markets are refreshed
+    pub tiers: TierConfig,
+    /// Levels per side kept in orderbook snapshots of hot markets; 0 disables them
+    pub orderbook_depth: usize,
+    /// Rate limit shared by every upstream request
+    pub throttle: ThrottleConfig,
+    /// Proxy URLs upstream requests are rotated through; direct when empty
+    pub proxies: Vec<String>,
+    /// Headers sent with every upstream request
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    /// User-Agents rotated per upstream request; reqwest's default when empty
+    pub user_agents: Vec<HeaderValue>,
+    /// Fixture directory upstream responses are recorded to or replayed from
+    pub fixtures: Option<FixtureMode>,
+    /// Base URL every upstream request is sent to instead, e.g. a mock server
+    pub upstream_base_url: Option<Url>,
+    /// GraphQL endpoint queried by the subgraph source, and for trades when it is used
+    pub subgraph_url: String,
+    /// GraphQL endpoint of the positions subgraph, queried for holders when the subgraph
+    /// source is used
+    pub positions_subgraph_url: String,
+}
+
+impl ScraperConfig {
+    /// Whether markets are scraped from `source`, as the primary or an additional source
+    pub fn uses_source(&self, source: ScrapeSource) -> bool {
+        self.source == source || self.additional_sources.contains(&source)
+    }
+}
+
+/// Outcome of a single market scrape
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrapeSummary {
+    pub markets_fetched: usize,
+    pub new_markets: usize,
+    pub duration_ms: u64,
+}
+
+/// Whether scheduled scrapes run; the API stays up either way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScraperState {
+    Running,
+    Paused,
+}
+
+/// Whether the scraper task is alive, as opposed to whether it is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScraperHealth {
+    /// Scrape loop running (scheduled scrapes may still be paused)
+    #[default]
+    Running,
+    /// Scrape loop failed and is waiting to be restarted
+    Restarting,
+    /// Scraper task has stopped and nothing will restart it
+    Dead,
+}
+
+impl ScraperHealth {
+    pub const ALL: [ScraperHealth; 3] = [
+        ScraperHealth::Running,
+        ScraperHealth::Restarting,
+        ScraperHealth::Dead,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScraperHealth::Running => "running",
+            ScraperHealth::Restarting => "restarting",
+            ScraperHealth::Dead => "dead",
+        }
+    }
+}
+
+/// Current scraper settings reported by the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct ScraperStatus {
+    pub state: ScraperState,
+    pub interval_secs: u64,
+    /// Next.js build ID discovered from the homepage, if any
+    pub build_id: Option<String>,
+    /// Circuit breaker around the upstream API
+    pub circuit: CircuitState,
+    pub health: ScraperHealth,
+    /// Whether this instance runs scheduled scrapes; false on replicas that lost the
+    /// leader election
+    pub leader: bool,
+    /// Shard of the market universe this instance scrapes
+    pub shard: Shard,
+}
+
+/// Reply channel for an on-demand scrape; errors are carried as messages
+type ScrapeReply = oneshot::Sender<std::result::Result<ScrapeSummary, String>>;
+
+/// Reply channel for an on-demand market refresh; None when upstream doesn't know the market
+type RefreshReply = oneshot::Sender<std::result::Result<Option<Market>, String>>;
+
+/// Runtime controls for a running scraper, shared with the admin API
+#[derive(Clone)]
+pub struct ScraperControl {
+    interval_secs: Arc<watch::Sender<u64>>,
+    state: Arc<watch::Sender<ScraperState>>,
+    triggers: mpsc::Sender<ScrapeReply>,
+    refreshes: mpsc::Sender<(String, RefreshReply)>,
+    build_id: Arc<watch::Sender<Option<String>>>,
+    breaker: Arc<CircuitBreaker>,
+    health: Arc<watch::Sender<ScraperHealth>>,
+    /// Since when this instance has been the scraper leader, None while it follows
+    leader_since: Arc<watch::Sender<Option<chrono::DateTime<Utc>>>>,
+    shard: Arc<watch::Sender<Shard>>,
+    /// Why scrapes count as stalled, as last published by the watchdog
+    stall: Arc<watch::Sender<Option<String>>>,
+    source: ScrapeSource,
+}
+
+/// Scraper-side ends of the control channels, consumed by `run_scraper`
+pub struct ScraperSignals {
+    interval_secs: watch::Receiver<u64>,
+    state: watch::Receiver<ScraperState>,
+    triggers: mpsc::Receiver<ScrapeReply>,
+    refreshes: mpsc::Receiver<(String, RefreshReply)>,
+    build_id: Arc<watch::Sender<Option<String>>>,
+    breaker: Arc<CircuitBreaker>,
+    health: Arc<watch::Sender<ScraperHealth>>,
+    leader_since: watch::Receiver<Option<chrono::DateTime<Utc>>>,
+    shard: watch::Receiver<Shard>,
+}
+
+/// Create the control handle and the signals it drives
+pub fn control(
+    interval_secs: u64,
+    source: ScrapeSource,
+    breaker: BreakerConfig,
+) -> (ScraperControl, ScraperSignals) {
+    let (interval_tx, interval_rx) = watch::channel(interval_secs);
+    let (state_tx, state_rx) = watch::channel(ScraperState::Running);
+    let (trigger_tx, trigger_rx) = mpsc::channel(SCRAPE_TRIGGER_CAPACITY);
+    let (refresh_tx, refresh_rx) = mpsc::channel(MARKET_REFRESH_CAPACITY);
+    let build_id = Arc::new(watch::channel(None).0);
+    let breaker = Arc::new(CircuitBreaker::new(breaker));
+    let health = Arc::new(watch::channel(ScraperHealth::Running).0);
+    // A single instance leads from the start; with leader election it follows until elected
+    let (leader_tx, leader_rx) = watch::channel(Some(Utc::now()));
+    set_leader_gauge(true);
+    let (shard_tx, shard_rx) = watch::channel(Shard::ALL);
+    (
+        ScraperControl {
+            interval_secs: Arc::new(interval_tx),
+            state: Arc::new(state_tx),
+            triggers: trigger_tx,
+            refreshes: refresh_tx,
+            build_id: Arc::clone(&build_id),
+            breaker: Arc::clone(&breaker),
+            health: Arc::clone(&health),
+            leader_since: Arc::new(leader_tx),
+            shard: Arc::new(shard_tx),
+            stall: Arc::new(watch::channel(None).0),
+            source,
+        },
+        ScraperSignals {
+            interval_secs: interval_rx,
+            state: state_rx,
+            triggers: trigger_rx,
+            refreshes: refresh_rx,
+            build_id,
+            breaker,
+            health,
+            leader_since: leader_rx,
+            shard: shard_rx,
+        },
+    )
+}
+
+impl ScraperSignals {
+    /// Report the build ID in use; the hard-coded default counts as not discovered
+    fn publish_build_id(&self, build_id: &str) {
+        let discovered = (build_id != DEFAULT_BUILD_ID).then(|| build_id.to_string());
+        self.build_id.send_replace(discovered);
+    }
+
+    fn set_health(&self, health: ScraperHealth) {
+        self.health.send_replace(health);
+        set_health_gauge(health);
+    }
+
+    fn is_leader(&self) -> bool {
+        self.leader_since.borrow().is_some()
+    }
+
+    /// Hand a shard newly assigned through `ScraperControl` to the tier scheduler
+    fn sync_shard(&mut self, tiers: &TierScheduler) {
+        if self.shard.has_changed().unwrap_or(false) {
+            let shard = *self.shard.borrow_and_update();
+            info!("Scraping shard {} of the market universe", shard);
+            tiers.set_shard(shard);
+        }
+    }
+}
+
+fn set_leader_gauge(leader: bool) {
+    ::metrics::gauge!(metrics::SCRAPER_LEADER).set(if leader { 1.0 } else { 0.0 });
+}
+
+/// Set the health gauge to 1 for the current health and 0 for the others
+pub fn set_health_gauge(current: ScraperHealth) {
+    for health in ScraperHealth::ALL {
+        ::metrics::gauge!(metrics::SCRAPER_HEALTH, "state" => health.as_str())
+            .set(if health == current { 1.0 } else { 0.0 });
+    }
+}
+
+impl ScraperControl {
+    pub fn interval_secs(&self) -> u64 {
+        *self.interval_secs.borrow()
+    }
+
+    /// Primary source markets are listed from
+    pub fn source(&self) -> ScrapeSource {
+        self.source
+    }
+
+    pub fn status(&self) -> ScraperStatus {
+        ScraperStatus {
+            state: *self.state.borrow(),
+            interval_secs: self.interval_secs(),
+            build_id: self.build_id.borrow().clone(),
+            circuit: self.circuit_state(),
+            health: self.health(),
+            leader: self.is_leader(),
+            shard: self.shard(),
+        }
+    }
+
+    pub fn shard(&self) -> Shard {
+        *self.shard.borrow()
+    }
+
+    /// Scrape another shard of the market universe from the next scrape on
+    pub fn set_shard(&self, shard: Shard) {
+        self.shard.send_if_modified(|current| {
+            let changed = *current != shard;
+            *current = shard;
+            changed
+        });
+    }
+
+    /// Whether this instance runs scheduled scrapes
+    pub fn is_leader(&self) -> bool {
+        self.leader_since.borrow().is_some()
+    }
+
+    /// Whether this instance runs the work only one replica may do, such as delivering
+    /// notifications: the leader of the first shard, or the only instance without election
+    pub fn is_primary(&self) -> bool {
+        self.is_leader() && self.shard().is_first()
+    }
+
+    /// Since when this instance has been the scraper leader, None while it follows
+    pub fn leader_since(&self) -> Option<chrono::DateTime<Utc>> {
+        *self.leader_since.borrow()
+    }
+
+    /// Start or stop scheduled scrapes as the leader election is won or lost; on-demand
+    /// scrapes and refreshes run either way
+    pub fn set_leader(&self, leader: bool) {
+        self.leader_since.send_if_modified(|since| {
+            if since.is_some() == leader {
+                return false;
+            }
+            *since = leader.then(Utc::now);
+            true
+        });
+        set_leader_gauge(leader);
+    }
+
+    /// Why scrapes count as stalled, if they do; always None without a running watchdog
+    pub fn stall(&self) -> Option<String> {
+        self.stall.borrow().clone()
+    }
+
+    pub fn set_stall(&self, stall: Option<String>) {
+        self.stall.send_replace(stall);
+    }
+
+    /// Health of the scraper task; `Dead` once the task is gone, whatever it last reported
+    pub fn health(&self) -> ScraperHealth {
+        if self.triggers.is_closed() {
+            ScraperHealth::Dead
+        } else {
+            *self.health.borrow()
+        }
+    }
+
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.state()
+    }
+
+    /// Whether a discovered build ID is available; only required when Next.js is the primary source
+    pub fn build_id_valid(&self) -> bool {
+        self.source != ScrapeSource::NextJs || self.build_id.borrow().is_some()
+    }
+
+    /// Pause or resume scheduled scrapes; on-demand scrapes still run while paused
+    pub fn set_state(&self, state: ScraperState) {
+        self.state.send_replace(state);
+    }
+
+    /// Change the scrape interval; the scraper picks it up without waiting for the current tick
+    pub fn set_interval_secs(&self, secs: u64) -> anyhow::Result<()> {
+        if !(MIN_SCRAPE_INTERVAL_SECS..=MAX_SCRAPE_INTERVAL_SECS).contains(&secs) {
+            return Err(anyhow::anyhow!(
+                "Scrape interval must be between {} and {} seconds",
+                MIN_SCRAPE_INTERVAL_SECS,
+                MAX_SCRAPE_INTERVAL_SECS
+            ));
+        }
+        self.interval_secs.send_replace(secs);
+        Ok(())
+    }
+
+    /// Ask the scraper to run a cycle now and wait for its summary
+    pub async fn trigger_scrape(&self) -> anyhow::Result<ScrapeSummary> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.triggers
+            .send(reply_tx)
+            .await
+            .map_err(|_| anyhow::anyhow!("Scraper task is not running"))?;
+
+        reply_rx
+            .await
+            .map_err(|_| anyhow::anyhow!("Scraper task stopped before finishing the scrape"))?
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Ask the scraper to fetch and store one market now, through the same rate limit as
+    /// scrapes, and wait for the stored record; None when upstream doesn't know the market
+    /// Waits for a scrape in progress to finish first
+    pub async fn refresh_market(&self, id: &str) -> anyhow::Result<Option<Market>> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.refreshes
+            .send((id.to_string(), reply_tx))
+            .await
+            .map_err(|_| anyhow::anyhow!("Scraper task is not running"))?;
+
+        reply_rx
+            .await
+            .map_err(|_| anyhow::anyhow!("Scraper task stopped before refreshing the market"))?
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+}
+
+/// Ticker for the given interval whose first tick is one full period away
+fn scrape_ticker(secs: u64) -> Interval {
+    let period = Duration::from_secs(secs);
+    let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    ticker
+}
+
+/// Wait for the next tick of `ticker`, forever if there is none
+async fn tick_optional(ticker: &mut Option<Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Run the scraper in a loop at the interval set through `ScraperControl`,
+/// also running a cycle whenever one is triggered on demand
+/// Scheduled ticks are skipped while the scraper is paused, the upstream circuit is open or
+/// another replica holds the scraper lease; on-demand scrapes always run
+/// If the loop fails it is restarted with exponential backoff, reported as
+/// `ScraperHealth::Restarting` meanwhile, instead of leaving the API serving stale data
+/// Returns once `shutdown` is cancelled, after the scrape in progress (if any) has stored
+/// what it already fetched
 pub async fn run_scraper(
-    pool: Arc<Pool<Sqlite>>,
-    scrape_interval_secs: u64,
+    storage: Arc<dyn Storage>,
+    config: ScraperConfig,
     metrics: Arc<Metrics>,
+    updates: UpdateSender,
+    mut signals: ScraperSignals,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let mut restarts: u32 = 0;
+    // Kept across restarts so long-tail markets aren't all due again after one
+    let tiers = TierScheduler::new(config.tiers.clone());
+    loop {
+        signals.set_health(ScraperHealth::Running);
+        let started = Instant::now();
+        let result = scrape_loop(
+            &storage,
+            &config,
+            &metrics,
+            &updates,
+            &tiers,
+            &mut signals,
+            &shutdown,
+        )
+        .await;
+        let Err(e) = result else {
+            return Ok(());
+        };
+
+        // A loop that ran for a while before failing starts the backoff over
+        if started.elapsed() >= MAX_RESTART_DELAY {
+            restarts = 0;
+        }
+        let delay = restart_delay(restarts);
+        restarts = restarts.saturating_add(1);
+        error!("Scraper failed: {:#}, restarting in {} seconds", e, delay.as_secs());
+        ::metrics::counter!(metrics::SCRAPER_RESTARTS_TOTAL).increment(1);
+        signals.set_health(ScraperHealth::Restarting);
+
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = shutdown.cancelled() => return Ok(()),
+        }
+    }
+}
+
+/// Delay before the restart following `restarts` earlier ones, doubling up to a cap
+fn restart_delay(restarts: u32) -> Duration {
+    INITIAL_RESTART_DELAY
+        .checked_mul(2_u32.saturating_pow(restarts))
+        .map_or(MAX_RESTART_DELAY, |delay| delay.min(MAX_RESTART_DELAY))
+}
+
+/// Body of `run_scraper`: scrape on schedule and on demand until `shutdown` is cancelled,
+/// refreshing the hot tier in between
+async fn scrape_loop(
+    storage: &Arc<dyn Storage>,
+    config: &ScraperConfig,
+    metrics: &Arc<Metrics>,
+    updates: &UpdateSender,
+    tiers: &TierScheduler,
+    signals: &mut ScraperSignals,
+    shutdown: &CancellationToken,
 ) -> Result<()> {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .context("Failed to create HTTP client")?;
+    let client = build_client(config)?;
+
+    // Reuse the build ID of the previous run, or discover one at startup
+    let mut build_id = startup_build_id(&client, storage.as_ref()).await;
+    signals.publish_build_id(&build_id);
+
+    // Pick up the schedule where the previous run left it instead of scraping right away
+    let initial_interval_secs = *signals.interval_secs.borrow_and_update();
+    let period = Duration::from_secs(initial_interval_secs);
+    let first_scrape = first_scrape_delay(load_last_success(storage.as_ref()).await, period, Utc::now());
+    if !first_scrape.is_zero() {
+        info!(
+            "Last successful scrape was recent, first scrape in {} seconds",
+            first_scrape.as_secs()
+        );
+    }
+    let mut interval = tokio::time::interval_at(Instant::now() + first_scrape, period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut hot_interval = tiers.hot_interval().map(|period| scrape_ticker(period.as_secs()));
+    tiers.set_shard(*signals.shard.borrow_and_update());
+
+    // Track consecutive failures so a stale build ID can be replaced
+    let mut consecutive_failures: u32 = 0;
+
+    info!(
+        "Starting scraper with {} second interval, source: {}, using build ID: {}",
+        initial_interval_secs, config.source, build_id
+    );
+
+    loop {
+        let reply = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {
+                if *signals.state.borrow() == ScraperState::Paused {
+                    continue;
+                }
+                if !signals.is_leader() {
+                    debug!("Another instance is the scraper leader, scheduled scrape skipped");
+                    continue;
+                }
+                if !signals.breaker.allow() {
+                    debug!("Upstream circuit open, scheduled scrape skipped");
+                    continue;
+                }
+                None
+            }
+            Some(reply) = signals.triggers.recv() => {
+                info!("Immediate scrape requested");
+                // Restart the period so the next scheduled scrape isn't right behind this one
+                interval.reset();
+                Some(reply)
+            }
+            Some((id, reply)) = signals.refreshes.recv() => {
+                info!("Immediate refresh of market {} requested", id);
+                let result = if signals.breaker.state() == CircuitState::Open {
+                    Err("Upstream circuit is open".to_string())
+                } else {
+                    refresh_market(&client, storage, metrics, updates, config, &id)
+                        .await
+                        .map_err(|e| format!("{:#}", e))
+                };
+                let _ = reply.send(result);
+                continue;
+            }
+            _ = tick_optional(&mut hot_interval) => {
+                if *signals.state.borrow() == ScraperState::Running
+                    && signals.is_leader()
+                    && signals.breaker.state() != CircuitState::Open
+                {
+                    signals.sync_shard(tiers);
+                    let refreshed =
+                        refresh_hot_tier(&client, storage, metrics, updates, config, tiers).await;
+                    if let Err(e) = refreshed {
+                        warn!("Hot tier refresh failed: {}", e);
+                        metrics.record_error_from(&e);
+                    }
+                }
+                continue;
+            }
+            Ok(()) = signals.interval_secs.changed() => {
+                let secs = *signals.interval_secs.borrow_and_update();
+                info!("Scrape interval changed to {} seconds", secs);
+                interval = scrape_ticker(secs);
+                continue;
+            }
+            Ok(()) = signals.state.changed() => {
+                match *signals.state.borrow_and_update() {
+                    ScraperState::Paused => info!("Scraper paused, scheduled scrapes skipped"),
+                    ScraperState::Running => info!("Scraper resumed"),
+                }
+                continue;
+            }
+        };
+
+        signals.sync_shard(tiers);
+        let result =
+            scrape_markets(&client, storage, metrics, updates, config, tiers, &build_id, shutdown)
+                .await;
+
+        match &result {
+            Ok(_) => {
+                consecutive_failures = 0;
+                signals.breaker.record_success();
+            }
+            Err(e) => {
+                error!("Scraper error after retries: {}", e);
+                // Continue running despite errors; a database or configuration failure
+                // says nothing about upstream, so only upstream ones open the circuit or
+                // count towards replacing the build ID
+                if e.kind().is_upstream() {
+                    signals.breaker.record_failure();
+
+                    consecutive_failures += 1;
+                    if consecutive_failures >= BUILD_ID_REDISCOVERY_THRESHOLD {
+                        warn!(
+                            "{} consecutive scrape failures, re-discovering build ID...",
+                            consecutive_failures
+                        );
+                        build_id = rediscover_build_id(&client, &build_id).await;
+                        signals.publish_build_id(&build_id);
+                        save_build_id(storage.as_ref(), &build_id).await;
+                        consecutive_failures = 0;
+                    }
+                }
+            }
+        }
+
+        if let Some(reply) = reply {
+            let _ = reply.send(result.map_err(|e| format!("{:#}", e)));
+        }
+
+        if shutdown.is_cancelled() {
+            break;
+        }
+
+        // Events and resolutions hit the same API, so they wait for it to recover too
+        if signals.breaker.state() != CircuitState::Open {
+            run_secondary_passes(&client, storage, metrics, config, tiers).await;
+        }
+    }
+
+    info!("Scraper stopped");
+    Ok(())
+}
+
+/// Run a single scrape cycle (markets, events and resolutions)
+pub async fn scrape_once(
+    storage: Arc<dyn Storage>,
+    config: ScraperConfig,
+    metrics: Arc<Metrics>,
+    updates: UpdateSender,
+) -> Result<ScrapeSummary> {
+    let client = build_client(&config)?;
+    let build_id = startup_build_id(&client, storage.as_ref()).await;
+
+    let shutdown = CancellationToken::new();
+    let tiers = TierScheduler::new(config.tiers.clone());
+    let summary = scrape_markets(
+        &client, &storage, &metrics, &updates, &config, &tiers, &build_id, &shutdown,
+    )
+    .await?;
+
+    run_secondary_passes(&client, &storage, &metrics, &config, &tiers).await;
+    Ok(summary)
+}
+
+/// Fetch and store markets with retries, recording scrape metrics
+/// Each cycle gets a `scrape_id`, unique within the process, so its logs can be grouped
+#[instrument(
+    name = "scrape_cycle",
+    skip_all,
+    fields(scrape_id = NEXT_SCRAPE_ID.fetch_add(1, Ordering::Relaxed), source = %config.source)
+)]
+#[allow(clippy::too_many_arguments)]
+async fn scrape_markets(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Arc<Metrics>,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+    build_id: &str,
+    shutdown: &CancellationToken,
+) -> Result<ScrapeSummary> {
+    let scrape_started = Instant::now();
+    let result = fetch_and_store_markets_with_retry(
+        client, storage, metrics, updates, config, tiers, build_id, shutdown,
+    )
+    .await;
+    let duration = scrape_started.elapsed();
+    metrics.record_scrape_duration(duration);
+    metrics.record_scrape(result.is_ok());
+
+    let (markets_fetched, new_markets) = result?;
+    let duration_ms = duration.as_millis() as u64;
+    metrics.record_markets_discovered(new_markets as u64);
+    if new_markets > 0 {
+        info!(duration_ms, "Discovered {} new markets", new_markets);
+    } else {
+        info!(duration_ms, "Scrape completed, no new markets found");
+    }
+    if let Err(e) = storage.set_scraper_state(STATE_LAST_SUCCESS_AT, &Utc::now().to_rfc3339()).await {
+        warn!("Failed to save last successful scrape time: {}", e);
+    }
+    Ok(ScrapeSummary {
+        markets_fetched,
+        new_markets,
+        duration_ms,
+    })
+}
+
+/// HTTP client for upstream APIs, sharing one rate limit across every request
+/// With proxies configured, each gets its own client and requests rotate between them
+fn build_client(config: &ScraperConfig) -> Result<ThrottledClient> {
+    let headers: HeaderMap = config.headers.iter().cloned().collect();
+    let builder = || {
+        Client::builder()
+            .timeout(Duration::from_secs(30))
+            .default_headers(headers.clone())
+    };
+
+    let clients = if config.proxies.is_empty() {
+        let client = builder()
+            .build()
+            .context("Failed to create HTTP client")
+            .map_err(Error::Config)?;
+        vec![client]
+    } else {
+        info!("Routing upstream requests through {} proxies", config.proxies.len());
+        config
+            .proxies
+            .iter()
+            .enumerate()
+            .map(|(i, url)| {
+                let proxy = Proxy::all(url)
+                    .with_context(|| format!("Invalid proxy #{}", i + 1))
+                    .map_err(Error::Config)?;
+                builder()
+                    .proxy(proxy)
+                    .build()
+                    .with_context(|| format!("Failed to create HTTP client for proxy #{}", i + 1))
+                    .map_err(Error::Config)
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    let mut client =
+        ThrottledClient::new(clients, config.throttle).with_user_agents(config.user_agents.clone());
+    if let Some(base) = &config.upstream_base_url {
+        info!("Sending upstream requests to {}", base);
+        client = client.with_upstream_base(base.clone());
+    }
+    match &config.fixtures {
+        Some(mode) => {
+            let fixtures = Fixtures::new(mode.clone()).map_err(Error::Config)?;
+            Ok(client.with_fixtures(fixtures))
+        }
+        None => Ok(client),
+    }
+}
+
+/// Build ID saved by the previous run, or a freshly discovered one
+async fn startup_build_id(client: &ThrottledClient, storage: &dyn Storage) -> String {
+    match storage.get_scraper_state(STATE_BUILD_ID).await {
+        Ok(Some(build_id)) => {
+            info!("Using build ID saved by the previous run: {}", build_id);
+            return build_id;
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to load saved build ID: {}", e),
+    }
+
+    let build_id = initial_build_id(client).await;
+    save_build_id(storage, &build_id).await;
+    build_id
+}
+
+/// Save a discovered build ID for the next run; the default one isn't worth saving
+async fn save_build_id(storage: &dyn Storage, build_id: &str) {
+    if build_id == DEFAULT_BUILD_ID {
+        return;
+    }
+    if let Err(e) = storage.set_scraper_state(STATE_BUILD_ID, build_id).await {
+        warn!("Failed to save build ID: {}", e);
+    }
+}
+
+/// Time of the last successful scrape, also by a previous run
+async fn load_last_success(storage: &dyn Storage) -> Option<chrono::DateTime<Utc>> {
+    match storage.get_scraper_state(STATE_LAST_SUCCESS_AT).await {
+        Ok(value) => value
+            .and_then(|value| chrono::DateTime::parse_from_rfc3339(&value).ok())
+            .map(|at| at.with_timezone(&Utc)),
+        Err(e) => {
+            warn!("Failed to load last successful scrape time: {}", e);
+            None
+        }
+    }
+}
 
-    // Discover build ID once at startup
+/// Wait before the first scheduled scrape so it comes one period after the last successful one
+fn first_scrape_delay(
+    last_success: Option<chrono::DateTime<Utc>>,
+    period: Duration,
+    now: chrono::DateTime<Utc>,
+) -> Duration {
+    let Some(last_success) = last_success else {
+        return Duration::ZERO;
+    };
+    let elapsed = (now - last_success).to_std().unwrap_or(Duration::ZERO);
+    period.saturating_sub(elapsed)
+}
+
+/// Discover the Next.js build ID, falling back to the default one
+async fn initial_build_id(client: &ThrottledClient) -> String {
     info!("Discovering build ID from Polymarket homepage...");
-    let build_id = match discover_build_id(&client).await {
+    match discover_build_id(client).await {
         Ok(id) => {
             info!("Successfully discovered build ID: {}", id);
             id
@@ -40,57 +952,82 @@ pub async fn run_scraper(
             warn!("Failed to discover build ID dynamically: {}, using default build ID", e);
             DEFAULT_BUILD_ID.to_string()
         }
-    };
+    }
+}
 
-    let mut interval = tokio::time::interval(Duration::from_secs(scrape_interval_secs));
-    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+/// Event, resolution, trade, holder, comment and orderbook passes that follow each market
+/// scrape; failures are only logged
+async fn run_secondary_passes(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Arc<Metrics>,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+) {
+    // Event pass: group markets under their parent events
+    if let Err(e) = scrape_events(client, storage, metrics, config).await {
+        warn!("Event scrape failed: {}", e);
+    }
 
-    // Rate limiter: track last request time
-    let mut last_request_time = tokio::time::Instant::now();
+    // Resolution pass: re-check markets whose end date has passed
+    if let Err(e) = check_resolutions(client, storage, metrics, tiers).await {
+        warn!("Resolution check failed: {}", e);
+    }
 
-    info!("Starting scraper with {} second interval, using build ID: {}", scrape_interval_secs, build_id);
+    // Trade pass: recent trades of hot markets
+    if let Err(e) = scrape_trades(client, storage, metrics, config, tiers).await {
+        warn!("Trade scrape failed: {}", e);
+    }
 
-    loop {
-        interval.tick().await;
+    // Holder pass: top holders of hot markets
+    if let Err(e) = scrape_holders(client, storage, metrics, config, tiers).await {
+        warn!("Holder scrape failed: {}", e);
+    }
 
-        // Rate limiting: ensure minimum time between requests
-        let elapsed = last_request_time.elapsed();
-        if elapsed.as_secs() < MIN_REQUEST_INTERVAL_SECS {
-            let wait_time = Duration::from_secs(MIN_REQUEST_INTERVAL_SECS) - elapsed;
-            sleep(wait_time).await;
-        }
-        last_request_time = tokio::time::Instant::now();
+    // Comment pass: recent comments on the events of hot markets
+    if let Err(e) = scrape_comments(client, storage, metrics, tiers).await {
+        warn!("Comment scrape failed: {}", e);
+    }
 
-        match fetch_and_store_markets_with_retry(&client, &pool, &metrics, &build_id).await {
-            Ok(new_count) => {
-                metrics.record_scrape(true);
-                if new_count > 0 {
-                    info!("Discovered {} new markets", new_count);
-                } else {
-                    info!("Scrape completed, no new markets found");
-                }
-            }
-            Err(e) => {
-                metrics.record_scrape(false);
-                error!("Scraper error after retries: {}", e);
-                // Continue running despite errors
-            }
+    // Orderbook pass: snapshot the books of hot markets
+    if config.orderbook_depth > 0 {
+        if let Err(e) = capture_orderbooks(client, storage, metrics, config, tiers).await {
+            warn!("Orderbook capture failed: {}", e);
         }
     }
 }
 
 /// Fetch markets from Polymarket API with retry logic and exponential backoff
+/// Returns the number of markets fetched and how many of them were new
+/// No further attempts are made once `shutdown` is cancelled
+#[allow(clippy::too_many_arguments)]
 async fn fetch_and_store_markets_with_retry(
-    client: &Client,
-    pool: &Arc<Pool<Sqlite>>,
-    _metrics: &Arc<Metrics>,
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Arc<Metrics>,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
     build_id: &str,
-) -> Result<usize> {
+    shutdown: &CancellationToken,
+) -> Result<(usize, usize)> {
     let mut last_error = None;
     
     for attempt in 0..MAX_RETRIES {
-        match fetch_and_store_markets(client, pool, build_id).await {
-            Ok(count) => return Ok(count),
+        let result = fetch_and_store_markets(
+            client, storage, metrics, updates, config, tiers, build_id, shutdown,
+        )
+        .await;
+        match result {
+            Ok(counts) => return Ok(counts),
+            Err(e) if !e.kind().is_retryable() => {
+                warn!(
+                    "Scrape attempt {} failed with a {} error, not retrying",
+                    attempt + 1,
+                    e.kind()
+                );
+                return Err(e);
+            }
             Err(e) => {
                 last_error = Some(e);
                 if attempt < MAX_RETRIES - 1 {
@@ -100,7 +1037,10 @@ async fn fetch_and_store_markets_with_retry(
                         attempt + 1,
                         delay
                     );
-                    sleep(Duration::from_secs(delay)).await;
+                    tokio::select! {
+                        _ = sleep(Duration::from_secs(delay)) => {}
+                        _ = shutdown.cancelled() => break,
+                    }
                 }
             }
         }
@@ -108,21 +1048,21 @@ async fn fetch_and_store_markets_with_retry(
     
     // Return the last error, or create a generic error if somehow no error was captured
     Err(last_error.unwrap_or_else(|| {
-        anyhow::anyhow!("Failed after {} retry attempts", MAX_RETRIES)
+        Error::UpstreamHttp(anyhow::anyhow!("Failed after {} retry attempts", MAX_RETRIES))
     }))
 }
 
 /// Discover the Next.js build ID from Polymarket homepage
-async fn discover_build_id(client: &Client) -> Result<String> {
+/// A homepage without one is an error, so callers decide what to fall back to
+async fn discover_build_id(client: &ThrottledClient) -> Result<String> {
     
     let html = client
-        .get("https://polymarket.com")
-        .send()
+        .send(client.get("https://polymarket.com"))
         .await
-        .context("Failed to fetch Polymarket homepage")?
+        .http_context("Failed to fetch Polymarket homepage")?
         .text()
         .await
-        .context("Failed to read homepage HTML")?;
+        .http_context("Failed to read homepage HTML")?;
 
     // Look for build ID in script tags or _next/static paths
     // Pattern: /_next/static/{buildId}/ or __NEXT_DATA__ with buildId
@@ -131,8 +1071,28 @@ async fn discover_build_id(client: &Client) -> Result<String> {
         return Ok(build_id);
     }
 
-    warn!("Could not discover build ID, using default: {}", DEFAULT_BUILD_ID);
-    Ok(DEFAULT_BUILD_ID.to_string())
+    Err(Error::UpstreamSchema(anyhow::anyhow!(
+        "No build ID found on the Polymarket homepage"
+    )))
+}
+
+/// Re-run build ID discovery after repeated failures
+/// Returns the newly discovered ID, or keeps the current one if discovery fails
+async fn rediscover_build_id(client: &ThrottledClient, current_build_id: &str) -> String {
+    match discover_build_id(client).await {
+        Ok(id) if id != current_build_id => {
+            info!("Build ID changed from {} to {}", current_build_id, id);
+            id
+        }
+        Ok(_) => {
+            warn!("Re-discovered build ID is unchanged: {}", current_build_id);
+            current_build_id.to_string()
+        }
+        Err(e) => {
+            warn!("Failed to re-discover build ID: {}, keeping {}", e, current_build_id);
+            current_build_id.to_string()
+        }
+    }
 }
 
 /// Extract build ID from HTML content
@@ -177,17 +1137,18 @@ fn extract_build_id_from_html(html: &str) -> Option<String> {
 }
 
 /// Try to fetch JSON from Next.js endpoint with a given build ID
+/// Failures are logged and counted, and give None
 async fn try_fetch_with_build_id(
-    client: &Client,
+    client: &ThrottledClient,
+    metrics: &Metrics,
     build_id: &str,
-) -> Result<Option<serde_json::Value>> {
+) -> Option<serde_json::Value> {
     let nextjs_url = format!("{}/{}/index.json", POLYMARKET_BASE_URL, build_id);
     info!("Attempting to fetch from Next.js endpoint: {}", nextjs_url);
     
+    let fetch_started = Instant::now();
     let response = client
-        .get(&nextjs_url)
-        .header("Accept", "application/json")
-        .send()
+        .send(client.get(&nextjs_url).header("Accept", "application/json"))
         .await;
 
     match response {
@@ -203,21 +1164,36 @@ async fn try_fetch_with_build_id(
                     "Next.js endpoint returned non-JSON content type: {}",
                     content_type
                 );
-                return Ok(None);
+                metrics.record_error(ErrorCategory::Parse);
+                return None;
             }
 
+            let body = match resp.bytes().await {
+                Ok(body) => body,
+                Err(e) => {
+                    warn!("Failed to read Next.js endpoint response: {}", e);
+                    metrics.record_error(ErrorCategory::Network);
+                    return None;
+                }
+            };
+            metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+
             // Try to parse as JSON
-            match resp.json().await {
+            let parse_started = Instant::now();
+            let parsed = serde_json::from_slice::<serde_json::Value>(&body);
+            metrics.record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+            match parsed {
                 Ok(json_value) => {
                     info!("Successfully fetched and parsed JSON from Next.js endpoint with build ID: {}", build_id);
-                    Ok(Some(json_value))
+                    Some(json_value)
                 }
                 Err(e) => {
                     warn!(
                         "Failed to parse JSON from Next.js endpoint: {}",
                         e
                     );
-                    Ok(None)
+                    metrics.record_error(ErrorCategory::Parse);
+                    None
                 }
             }
         }
@@ -227,7 +1203,8 @@ async fn try_fetch_with_build_id(
                 resp.status(),
                 build_id
             );
-            Ok(None)
+            metrics.record_error(ErrorCategory::HttpStatus);
+            None
         }
         Err(e) => {
             warn!(
@@ -235,171 +1212,1543 @@ async fn try_fetch_with_build_id(
                 build_id,
                 e
             );
-            Ok(None)
+            metrics.record_error_from(&*e);
+            None
         }
     }
 }
 
-/// Fetch markets from Polymarket API and store new ones
-/// Uses the provided build ID (discovered once at startup)
-async fn fetch_and_store_markets(
-    client: &Client,
-    pool: &Arc<Pool<Sqlite>>,
-    build_id: &str,
-) -> Result<usize> {
-    // Fetch with the discovered build ID
-    let json = match try_fetch_with_build_id(client, build_id).await? {
-        Some(json) => json,
-        None => {
-            return Err(anyhow::anyhow!(
-                "Failed to fetch from Next.js endpoint with build ID: {}",
-                build_id
-            ));
-        }
-    };
+/// The Next.js data route of the polymarket.com frontend as a market source
+struct NextJsSource;
 
-    // Parse and store markets
-    let markets = parse_markets_from_json(&json)?;
-    info!("Parsed {} markets from API", markets.len());
+#[async_trait]
+impl MarketSource for NextJsSource {
+    fn name(&self) -> &'static str {
+        "nextjs"
+    }
 
-    let mut new_count = 0;
-    for market in markets {
-        match db::upsert_market(pool, &market).await {
-            Ok(is_new) => {
-                if is_new {
-                    new_count += 1;
-                    info!(
-                        "New market discovered: {} - {}",
-                        market.id,
-                        market.title
-                    );
-                }
+    fn venue(&self) -> &'static str {
+        source::POLYMARKET
+    }
+
+    async fn fetch_markets(
+        &self,
+        ctx: &FetchContext<'_>,
+        _pages: &PageSink,
+    ) -> Result<FetchedMarkets> {
+        // Fetch with the discovered build ID
+        let json = match try_fetch_with_build_id(ctx.client, ctx.metrics, ctx.build_id).await {
+            Some(json) => json,
+            None => {
+                return Err(Error::UpstreamHttp(anyhow::anyhow!(
+                    "Failed to fetch from Next.js endpoint with build ID: {}",
+                    ctx.build_id
+                )));
             }
-            Err(e) => {
-                warn!("Failed to upsert market {}: {}", market.id, e);
+        };
+        // The frontend only embeds a selection of markets
+        Ok(FetchedMarkets {
+            raw: extract_markets_from_json(&json)?,
+            complete: false,
+            ..Default::default()
+        })
+    }
+
+    fn parse_market(&self, raw: &serde_json::Value) -> Result<Market> {
+        parse_single_market(raw)
+    }
+}
+
+/// Fetch markets from the configured source and every additional source, and store them
+/// The primary source falls back to its alternative on failure; additional sources are
+/// best effort and their failures are only logged
+/// Fetching and storing run as two concurrent stages joined by a bounded queue of parsed
+/// batches, one per fetched page, so the next page is fetched while the previous one is
+/// written and a slow database holds fetching back instead of buffering without limit
+/// Once `shutdown` is cancelled no further sources are fetched, but batches already
+/// fetched are still stored
+#[allow(clippy::too_many_arguments)]
+async fn fetch_and_store_markets(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+    build_id: &str,
+    shutdown: &CancellationToken,
+) -> Result<(usize, usize)> {
+    let ctx = FetchContext {
+        client,
+        metrics,
+        page_size: config.page_size,
+        max_pages: config.max_pages,
+        build_id,
+        subgraph_url: &config.subgraph_url,
+        resume_offset: 0,
+        resume_cursor: None,
+    };
+
+    let (batches, queued) = mpsc::channel(PIPELINE_CAPACITY);
+    let (fetched, stored) = tokio::join!(
+        fetch_stage(storage.as_ref(), ctx, config, tiers.shard(), batches, shutdown),
+        write_stage(client, storage, metrics, updates, config, tiers, queued)
+    );
+    fetched?;
+    Ok(stored)
+}
+
+/// Markets of one page of a source, parsed and validated, on their way to the write stage
+struct ParsedBatch {
+    source: ScrapeSource,
+    /// When the listing walk the page belongs to started; markets listed on any of its pages
+    /// were seen since
+    listed_at: chrono::DateTime<Utc>,
+    /// Last batch of the walk, which alone says whether it was complete and where the next
+    /// one resumes
+    ends_walk: bool,
+    scraped: Vec<ScrapedMarket>,
+    /// IDs of markets rejected by validation or the ingest filter, which still count as seen
+    rejected_ids: Vec<String>,
+    complete: bool,
+    unchanged_ids: Vec<String>,
+    listings: Vec<ListingPage>,
+    resume_offset: u32,
+    resume_cursor: Option<String>,
+}
+
+/// Fetch and parse every source in turn, queueing one batch per fetched page
+/// Fails only when the primary source and its fallbacks all did; the queue is closed
+/// when this returns, which ends the write stage
+#[instrument(skip_all)]
+async fn fetch_stage(
+    storage: &dyn Storage,
+    ctx: FetchContext<'_>,
+    config: &ScraperConfig,
+    shard: Shard,
+    batches: mpsc::Sender<ParsedBatch>,
+    shutdown: &CancellationToken,
+) -> Result<()> {
+    fetch_with_fallbacks(storage, ctx, config, shard, &batches).await?;
+
+    for &additional in &config.additional_sources {
+        if shutdown.is_cancelled() {
+            info!("Shutting down, skipping the remaining sources");
+            break;
+        }
+        match fetch_source(storage, ctx, config, additional, shard, &batches).await {
+            Ok(()) => {}
+            Err(e) => {
+                warn!("Failed to fetch from {} source: {}", additional, e);
+                ctx.metrics.record_error_from(&e);
             }
         }
     }
 
-    Ok(new_count)
+    Ok(())
 }
 
-/// Parse markets from Polymarket JSON response
-/// Handles Next.js endpoint response structure (pageProps.markets) and direct arrays
-fn parse_markets_from_json(json: &serde_json::Value) -> Result<Vec<Market>> {
-    let mut markets = Vec::new();
-
-    // Next.js endpoint can return an array directly, or wrapped in pageProps
-    if let Some(array) = json.as_array() {
-        // Direct array response
-        for market_json in array {
-            if let Ok(market) = parse_single_market(market_json) {
-                markets.push(market);
-            }
-        }
-    } else if let Some(markets_array) = json.get("markets").and_then(|v| v.as_array()) {
-        // Wrapped in an object with "markets" key
-        for market_json in markets_array {
-            if let Ok(market) = parse_single_market(market_json) {
-                markets.push(market);
-            }
-        }
-    } else if let Some(page_props) = json.get("pageProps") {
-        // Legacy Next.js structure (fallback)
-        if let Some(markets_array) = page_props.get("markets").and_then(|v| v.as_array()) {
-            for market_json in markets_array {
-                if let Ok(market) = parse_single_market(market_json) {
-                    markets.push(market);
+/// Fetch from a source, queueing each page as it arrives and then the end of the walk
+/// Pages queued before the fetch failed are still stored
+async fn fetch_source(
+    storage: &dyn Storage,
+    ctx: FetchContext<'_>,
+    config: &ScraperConfig,
+    source: ScrapeSource,
+    shard: Shard,
+    batches: &mpsc::Sender<ParsedBatch>,
+) -> Result<()> {
+    let listed_at = Utc::now();
+    let (pages, mut received) = PageSink::channel();
+    let queue_pages = async {
+        while let Some(page) = received.recv().await {
+            let batch = parse_batch(source, page, listed_at, false, config, ctx.metrics);
+            queue_batch(batches, ctx.metrics, batch).await;
+        }
+    };
+    // The sink is dropped once the fetch returns, which ends the queueing of pages
+    let (fetched, ()) =
+        tokio::join!(fetch_resumed(storage, ctx, source, shard, pages), queue_pages);
+
+    let batch = parse_batch(source, fetched?, listed_at, true, config, ctx.metrics);
+    queue_batch(batches, ctx.metrics, batch).await;
+    Ok(())
+}
+
+/// Parse and validate markets fetched from a source
+fn parse_batch(
+    source: ScrapeSource,
+    fetched: FetchedMarkets,
+    listed_at: chrono::DateTime<Utc>,
+    ends_walk: bool,
+    config: &ScraperConfig,
+    metrics: &Metrics,
+) -> ParsedBatch {
+    let FetchedMarkets {
+        raw: raw_markets,
+        complete,
+        unchanged_ids,
+        listings,
+        resume_offset,
+        resume_cursor,
+    } = fetched;
+    let fetched = raw_markets.len();
+    if !unchanged_ids.is_empty() {
+        info!(
+            "{} markets from {} are on pages unchanged since the last scrape, skipping them",
+            unchanged_ids.len(),
+            source
+        );
+    }
+
+    let parse_started = Instant::now();
+    let (scraped, mut rejected_ids) =
+        parse_and_validate(source, raw_markets, config.precision, metrics);
+    metrics.record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+    if fetched > 0 {
+        info!("Parsed {} of {} markets from {}", scraped.len(), fetched, source);
+    }
+
+    let (scraped, filtered_ids) = config.filter.apply(scraped);
+    if !filtered_ids.is_empty() {
+        debug!("{} markets from {} filtered out", filtered_ids.len(), source);
+    }
+    // Filtered markets are still listed upstream, so they mustn't be delisted as missing
+    rejected_ids.extend(filtered_ids);
+
+    ParsedBatch {
+        source,
+        listed_at,
+        ends_walk,
+        scraped,
+        rejected_ids,
+        complete,
+        unchanged_ids,
+        listings,
+        resume_offset,
+        resume_cursor,
+    }
+}
+
+/// Queue a batch for the write stage, waiting while the queue is full
+async fn queue_batch(batches: &mpsc::Sender<ParsedBatch>, metrics: &Metrics, batch: ParsedBatch) {
+    let wait_started = Instant::now();
+    // The write stage only stops receiving once the queue is closed
+    if batches.send(batch).await.is_ok() {
+        metrics.record_pipeline_batch(PipelineStage::Fetch, wait_started.elapsed());
+    }
+}
+
+/// Store queued batches until the fetch stage closes the queue
+/// Returns the number of markets stored and how many of them were new
+#[instrument(skip_all)]
+async fn write_stage(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+    mut queued: mpsc::Receiver<ParsedBatch>,
+) -> (usize, usize) {
+    let (mut stored, mut new_count) = (0, 0);
+    loop {
+        let wait_started = Instant::now();
+        let Some(batch) = queued.recv().await else {
+            break;
+        };
+        metrics.record_pipeline_batch(PipelineStage::Write, wait_started.elapsed());
+
+        let (count, new) =
+            store_markets(client, storage, metrics, updates, config, tiers, batch).await;
+        stored += count;
+        new_count += new;
+    }
+    (stored, new_count)
+}
+
+/// Fetch from the first source in the configured source's fetch order that succeeds
+/// Returns the last error when every one of them failed
+async fn fetch_with_fallbacks(
+    storage: &dyn Storage,
+    ctx: FetchContext<'_>,
+    config: &ScraperConfig,
+    shard: Shard,
+    batches: &mpsc::Sender<ParsedBatch>,
+) -> Result<()> {
+    let source = config.source;
+    let order = source.fetch_order();
+    let mut last_error = None;
+
+    for (i, &current) in order.iter().enumerate() {
+        match fetch_source(storage, ctx, config, current, shard, batches).await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                ctx.metrics.record_error_from(&e);
+                if let Some(next) = order.get(i + 1) {
+                    warn!(
+                        "Failed to fetch from {} source: {}, falling back to {}",
+                        current, e, next
+                    );
                 }
+                last_error = Some(e);
             }
         }
     }
 
-    Ok(markets)
+    Err(last_error.unwrap_or_else(|| {
+        Error::Config(anyhow::anyhow!("No source to fetch {} markets from", source))
+    }))
 }
 
-/// Parse a single market from JSON
-/// Handles Next.js API response structure with flexible field names
-fn parse_single_market(json: &serde_json::Value) -> Result<Market> {
-    // ID can be a number or string in the API
-    let id = json
-        .get("id")
-        .and_then(|v| {
-            if let Some(s) = v.as_str() {
-                Some(s.to_string())
-            } else if let Some(n) = v.as_u64() {
-                Some(n.to_string())
-            } else { v.as_i64().map(|n| n.to_string()) }
-        })
-        .or_else(|| json.get("slug").and_then(|v| v.as_str()).map(|s| s.to_string()))
-        .or_else(|| json.get("marketId").and_then(|v| v.as_str()).map(|s| s.to_string()))
-        .or_else(|| json.get("market_slug").and_then(|v| v.as_str()).map(|s| s.to_string()))
-        .ok_or_else(|| anyhow::anyhow!("Market missing ID"))?;
-
-    let title = json
-        .get("question")
-        .or_else(|| json.get("title"))
-        .or_else(|| json.get("name"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "Untitled Market".to_string());
-
-    let description = json
-        .get("description")
-        .or_else(|| json.get("descriptionText"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    // Extract current price from tokens array (first token's price)
-    let current_price = json
-        .get("tokens")
-        .and_then(|v| v.as_array())
-        .and_then(|tokens| tokens.first())
-        .and_then(|token| token.get("price"))
-        .and_then(|v| v.as_f64())
-        .or_else(|| json.get("currentPrice").and_then(|v| v.as_f64()))
-        .or_else(|| json.get("price").and_then(|v| v.as_f64()))
-        .or_else(|| json.get("probability").and_then(|v| v.as_f64()));
-
-    // Volume can be a number or string
-    let volume = json
-        .get("volumeNum")
-        .or_else(|| json.get("volume"))
-        .or_else(|| json.get("totalVolume"))
-        .and_then(|v| {
-            if let Some(n) = v.as_f64() {
-                Some(n)
-            } else if let Some(s) = v.as_str() {
-                s.parse::<f64>().ok()
-            } else {
+/// Fetch from a source, continuing a listing walk the previous scrape of the same shard cut
+/// short
+async fn fetch_resumed(
+    storage: &dyn Storage,
+    ctx: FetchContext<'_>,
+    source: ScrapeSource,
+    shard: Shard,
+    pages: PageSink,
+) -> Result<FetchedMarkets> {
+    let load = |prefix| async move {
+        match storage.get_scraper_state(&resume_key(prefix, source, shard)).await {
+            Ok(value) => value.filter(|value| !value.is_empty()),
+            Err(e) => {
+                warn!("Failed to load resume position of {} source: {}", source, e);
                 None
             }
-        });
+        }
+    };
+    let resume_offset = load(STATE_RESUME_OFFSET_PREFIX)
+        .await
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    let resume_cursor = load(STATE_RESUME_CURSOR_PREFIX).await;
+    source
+        .market_source()
+        .fetch_markets(
+            &FetchContext {
+                resume_offset,
+                resume_cursor: resume_cursor.as_deref(),
+                ..ctx
+            },
+            &pages,
+        )
+        .await
+}
+
+/// Every shard walks the whole listing, so each resumes its own walk
+fn resume_key(prefix: &str, source: ScrapeSource, shard: Shard) -> String {
+    if shard.is_all() {
+        format!("{}{}", prefix, source)
+    } else {
+        format!("{}{}:{}", prefix, source, shard)
+    }
+}
+
+/// Save where the next scrape continues a listing walk cut short by `max_pages`
+async fn save_resume_position(
+    storage: &dyn Storage,
+    metrics: &Metrics,
+    source: ScrapeSource,
+    shard: Shard,
+    offset: u32,
+    cursor: Option<&str>,
+) {
+    let positions = [
+        (STATE_RESUME_OFFSET_PREFIX, offset.to_string()),
+        (STATE_RESUME_CURSOR_PREFIX, cursor.unwrap_or_default().to_string()),
+    ];
+    for (prefix, value) in positions {
+        let key = resume_key(prefix, source, shard);
+        if let Err(e) = storage.set_scraper_state(&key, &value).await {
+            warn!("Failed to save resume position of {} source: {}", source, e);
+            metrics.record_error_from(&e);
+        }
+    }
+}
+
+/// Store the markets of this instance's shard from a parsed batch
+/// Long-tail markets stored less than a long-tail interval ago are deferred to a later scrape
+/// Returns the number of markets stored and how many of them were new
+async fn store_markets(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+    batch: ParsedBatch,
+) -> (usize, usize) {
+    let ParsedBatch {
+        source,
+        listed_at,
+        ends_walk,
+        scraped,
+        rejected_ids,
+        complete,
+        unchanged_ids,
+        listings,
+        resume_offset,
+        resume_cursor,
+    } = batch;
+
+    let shard = tiers.shard();
+    if source == ScrapeSource::Subgraph {
+        let scraped_at = Utc::now();
+        let db_write_started = Instant::now();
+        let updated =
+            store_chain_markets(storage.as_ref(), metrics, updates, shard, scraped, scraped_at)
+                .await;
+        metrics.record_phase_duration(ScrapePhase::DbWrite, db_write_started.elapsed());
+        if ends_walk {
+            save_resume_position(
+                storage.as_ref(),
+                metrics,
+                source,
+                shard,
+                resume_offset,
+                resume_cursor.as_deref(),
+            )
+            .await;
+        }
+        return (updated, 0);
+    }
+    let (scraped, foreign): (Vec<ScrapedMarket>, Vec<ScrapedMarket>) = scraped
+        .into_iter()
+        .partition(|scraped_market| shard.owns(&scraped_market.market.id));
+    let now = Instant::now();
+    let (scraped, deferred): (Vec<ScrapedMarket>, Vec<ScrapedMarket>) = scraped
+        .into_iter()
+        .partition(|scraped_market| tiers.take_due(&scraped_market.market, now));
+    if !deferred.is_empty() {
+        debug!("Deferred {} long-tail markets stored recently", deferred.len());
+    }
+
+    tiers.prune_long_tail(now);
+
+    let scraped_at = Utc::now();
+    let db_write_started = Instant::now();
+    let mut new_count = 0;
+    // Markets whose listing page must be fetched in full again next time
+    let mut unsettled: HashSet<String> = deferred
+        .iter()
+        .map(|scraped_market| scraped_market.market.id.clone())
+        .collect();
+    for scraped_market in &scraped {
+        let stored =
+            store_market(storage.as_ref(), metrics, updates, source, scraped_market, scraped_at)
+                .await;
+        if stored.is_new {
+            new_count += 1;
+        }
+        if !stored.complete {
+            unsettled.insert(scraped_market.market.id.clone());
+        }
+    }
+    metrics.record_phase_duration(ScrapePhase::DbWrite, db_write_started.elapsed());
+
+    let markets: Vec<Market> = scraped.into_iter().map(|s| s.market).collect();
+    // Rejected, filtered, deferred and other shards' markets are still listed by the API, so
+    // they count as seen
+    let seen_ids: Vec<String> = markets
+        .iter()
+        .map(|market| market.id.clone())
+        .chain(deferred.iter().map(|scraped_market| scraped_market.market.id.clone()))
+        .chain(foreign.iter().map(|scraped_market| scraped_market.market.id.clone()))
+        .chain(rejected_ids)
+        .chain(unchanged_ids)
+        .collect();
+    let venue = source.market_source().venue();
+    // Every shard walks the whole listing, so only the first counts missing markets
+    let delist = complete && shard.is_first();
+    if let Err(e) = track_missing_markets(storage.as_ref(), venue, &seen_ids, listed_at, delist, config).await {
+        warn!("Failed to update market last-seen tracking: {}", e);
+        metrics.record_error_from(&e);
+    }
+
+    // Only now can the next scrape skip these pages when upstream reports them unchanged;
+    // pages with deferred markets or ones that failed to store are fetched in full again so
+    // their changes aren't skipped
+    let stored_listings = listings
+        .into_iter()
+        .filter(|listing| !listing.ids.iter().any(|id| unsettled.contains(id)))
+        .collect();
+    client.remember_listings(stored_listings);
+    if ends_walk {
+        save_resume_position(
+            storage.as_ref(),
+            metrics,
+            source,
+            shard,
+            resume_offset,
+            resume_cursor.as_deref(),
+        )
+        .await;
+    }
+
+    let stored = markets.len();
+    enrich_markets(client, storage, metrics, markets, config.enrich_concurrency).await;
+
+    (stored, new_count)
+}
+
+/// Update the markets stored with the condition IDs of subgraph markets with the chain's
+/// price and resolution; conditions without a stored market are skipped, as the subgraph
+/// has no title or description to store them with
+/// Returns the number of markets updated
+async fn store_chain_markets(
+    storage: &dyn Storage,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    shard: Shard,
+    scraped: Vec<ScrapedMarket>,
+    scraped_at: chrono::DateTime<Utc>,
+) -> usize {
+    let condition_ids: Vec<String> = scraped
+        .iter()
+        .filter_map(|scraped_market| scraped_market.market.condition_id.clone())
+        .collect();
+    let stored = match storage.get_markets_by_condition_ids(&condition_ids).await {
+        Ok(stored) => stored,
+        Err(e) => {
+            warn!("Failed to look up markets by condition ID: {}", e);
+            metrics.record_error_from(&e);
+            return 0;
+        }
+    };
+    // Markets stored under a Gamma ID win over ones earlier versions keyed by condition ID
+    let mut by_condition: HashMap<String, Market> = HashMap::new();
+    for market in stored {
+        let Some(condition_id) = market.condition_id.clone() else {
+            continue;
+        };
+        let keyed_by_condition = market.id == condition_id;
+        match by_condition.entry(condition_id) {
+            Entry::Occupied(_) if keyed_by_condition => {}
+            Entry::Occupied(mut entry) => {
+                entry.insert(market);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(market);
+            }
+        }
+    }
+
+    let mut updated = 0;
+    for ScrapedMarket { market: chain, .. } in &scraped {
+        let Some(market) = chain
+            .condition_id
+            .as_ref()
+            .and_then(|condition_id| by_condition.get(condition_id))
+            .filter(|market| shard.owns(&market.id))
+        else {
+            continue;
+        };
+        match store_chain_market(storage, updates, market, chain, scraped_at).await {
+            Ok(()) => updated += 1,
+            Err(e) => {
+                warn!("Failed to store chain data of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+    debug!(
+        "Updated {} of {} subgraph markets stored with their condition ID",
+        updated,
+        scraped.len()
+    );
+    updated
+}
+
+/// Store the subgraph's price of a stored market, with its change log entry, price point and
+/// update when it moved, and its resolution once the condition reported one
+async fn store_chain_market(
+    storage: &dyn Storage,
+    updates: &UpdateSender,
+    market: &Market,
+    chain: &Market,
+    scraped_at: chrono::DateTime<Utc>,
+) -> Result<()> {
+    let mut updated = market.clone();
+    updated.current_price = chain.current_price.or(market.current_price);
+    let changes = updates::field_changes(market, &updated);
+    if !changes.is_empty() {
+        let change = MarketUpdate::MarketChanged {
+            changed_fields: changes.iter().map(|change| change.field).collect(),
+            previous_price: market.current_price,
+            market: updated.clone(),
+        };
+        storage.upsert_market_with_outbox(&updated, Some(&change)).await?;
+        storage.insert_market_changes(&market.id, &changes, scraped_at).await?;
+        storage
+            .insert_price_point(&market.id, updated.current_price, updated.volume, scraped_at)
+            .await?;
+        let _ = updates.send(change);
+    }
+
+    if chain.status == Some(MarketStatus::Resolved) && market.status != chain.status {
+        // The subgraph names outcomes by index; the stored ones go by the same tokens
+        let winner = chain
+            .outcomes
+            .iter()
+            .find(|outcome| Some(&outcome.name) == chain.winning_outcome.as_ref())
+            .and_then(|outcome| outcome.token_id.clone());
+        let stored_outcomes = storage.get_outcomes(&market.id).await?;
+        updated.winning_outcome = stored_outcomes
+            .into_iter()
+            .find(|outcome| outcome.token_id.is_some() && outcome.token_id == winner)
+            .map(|outcome| outcome.name)
+            .or_else(|| chain.winning_outcome.clone());
+        updated.status = chain.status;
+        updated.resolved_at = chain.resolved_at;
+        storage.update_market_resolution(&updated).await?;
+        info!(
+            "Market resolved on chain: {} - winning outcome: {}",
+            market.id,
+            updated.winning_outcome.as_deref().unwrap_or("unknown")
+        );
+    }
+    Ok(())
+}
+
+/// Mark scraped markets as seen; with `delist`, after a complete scrape, delist active markets
+/// of the same venue that have been missing for too many consecutive scrapes
+/// Every page of a walk marks its markets seen at the time the walk started, so its last page
+/// only counts the markets none of its pages listed as missing
+async fn track_missing_markets(
+    storage: &dyn Storage,
+    venue: &str,
+    ids: &[String],
+    listed_at: chrono::DateTime<Utc>,
+    delist: bool,
+    config: &ScraperConfig,
+) -> Result<()> {
+    storage.mark_markets_seen(ids, listed_at).await?;
+
+    if delist && config.delist_after_missed_scrapes > 0 {
+        let delisted =
+            storage.delist_unseen_markets(venue, listed_at, config.delist_after_missed_scrapes)
+                .await?;
+        if delisted > 0 {
+            info!("Marked {} markets missing from the API as delisted", delisted);
+        }
+    }
+
+    Ok(())
+}
+
+/// What `store_market` managed to write
+struct StoredMarket {
+    /// The market wasn't stored before
+    is_new: bool,
+    /// Nothing failed to be written
+    complete: bool,
+}
+
+/// Store one scraped market with its outcomes, tags, price point and raw payload
+/// Failures are logged so the rest of the batch is stored
+#[instrument(skip_all, fields(market_id = %scraped.market.id))]
+async fn store_market(
+    storage: &dyn Storage,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    source: ScrapeSource,
+    scraped: &ScrapedMarket,
+    scraped_at: chrono::DateTime<Utc>,
+) -> StoredMarket {
+    let ScrapedMarket { market, raw } = scraped;
+    // The stored row is needed to record field changes in the audit log
+    let previous = storage.get_market_by_id(&market.id).await.ok().flatten();
+    // Built before the upsert so its notification is queued in the same transaction
+    let changes = previous
+        .as_ref()
+        .map(|previous| updates::field_changes(previous, market))
+        .unwrap_or_default();
+    let change = (!changes.is_empty()).then(|| MarketUpdate::MarketChanged {
+        market: market.clone(),
+        changed_fields: changes.iter().map(|change| change.field).collect(),
+        previous_price: previous.as_ref().and_then(|previous| previous.current_price),
+    });
+
+    let mut complete = true;
+    let is_new = match storage.upsert_market_with_outbox(market, change.as_ref()).await {
+        Ok(is_new) => {
+            if is_new {
+                info!(
+                    "New market discovered: {} - {}",
+                    market.id,
+                    market.title
+                );
+                let _ = updates.send(MarketUpdate::NewMarket {
+                    market: market.clone(),
+                });
+            } else if let Some(change) = change {
+                if let Err(e) =
+                    storage.insert_market_changes(&market.id, &changes, scraped_at).await
+                {
+                    warn!("Failed to record changes for market {}: {}", market.id, e);
+                    metrics.record_error_from(&e);
+                    complete = false;
+                }
+                let _ = updates.send(change);
+            }
+            is_new
+        }
+        Err(e) => {
+            warn!("Failed to upsert market {}: {}", market.id, e);
+            metrics.record_error_from(&e);
+            return StoredMarket {
+                is_new: false,
+                complete: false,
+            };
+        }
+    };
+
+    // Sources that also list closed markets report closures here instead of the resolution pass;
+    // a closure already stored isn't written again on every scrape
+    let resolution_changed = previous.as_ref().is_none_or(|previous| {
+        previous.status != market.status
+            || previous.winning_outcome != market.winning_outcome
+            || previous.resolved_at != market.resolved_at
+    });
+    if resolution_changed && market.status.is_some_and(|status| status != MarketStatus::Active) {
+        if let Err(e) = storage.update_market_resolution(market).await {
+            warn!("Failed to store status of market {}: {}", market.id, e);
+            metrics.record_error_from(&e);
+            complete = false;
+        }
+    }
+
+    if let Err(e) = storage.upsert_outcomes(&market.id, &market.outcomes).await {
+        warn!("Failed to store outcomes for market {}: {}", market.id, e);
+        metrics.record_error_from(&e);
+        complete = false;
+    }
+
+    if let Err(e) = storage.set_market_tags(&market.id, &market.tags).await {
+        warn!("Failed to store tags for market {}: {}", market.id, e);
+        metrics.record_error_from(&e);
+        complete = false;
+    }
+
+    if let Err(e) = storage.insert_price_point(
+        &market.id,
+        market.current_price,
+        market.volume,
+        scraped_at,
+    )
+    .await
+    {
+        warn!("Failed to record price history for market {}: {}", market.id, e);
+        metrics.record_error_from(&e);
+        complete = false;
+    }
+
+    if let Err(e) = archive_raw_payload(storage, &market.id, source, raw, scraped_at).await {
+        warn!("Failed to archive raw payload for market {}: {}", market.id, e);
+        metrics.record_error_from(&e);
+        complete = false;
+    }
+    StoredMarket { is_new, complete }
+}
+
+/// Compress and store a market's raw JSON unless it matches the last archived payload
+async fn archive_raw_payload(
+    storage: &dyn Storage,
+    market_id: &str,
+    source: ScrapeSource,
+    raw: &serde_json::Value,
+    scraped_at: chrono::DateTime<Utc>,
+) -> Result<()> {
+    let payload = archive::encode(raw).map_err(Error::storage)?;
+    storage.insert_raw_payload(
+        market_id,
+        &source.to_string(),
+        &payload.compressed,
+        &payload.hash,
+        scraped_at,
+    )
+    .await
+}
+
+/// Parse and validate raw markets, skipping those that fail with the reason counted in
+/// `metrics`; only the first few rejections of a scrape are logged individually
+/// Valid markets are rounded to `precision`
+/// Returns the valid markets and the IDs of rejected markets that had one
+fn parse_and_validate(
+    source: ScrapeSource,
+    raw_markets: Vec<serde_json::Value>,
+    precision: Precision,
+    metrics: &Metrics,
+) -> (Vec<ScrapedMarket>, Vec<String>) {
+    let mut scraped = Vec::with_capacity(raw_markets.len());
+    let mut rejections: Vec<Rejection> = Vec::new();
+
+    for raw in raw_markets {
+        let validated = parse_raw_market(source, &raw)
+            .map_err(|e| Rejection::from_parse_error(source::raw_market_id(&raw), &e))
+            .and_then(|market| validation::validate_market(&market).map(|()| market));
+        match validated {
+            Ok(mut market) => {
+                market.round_to(precision);
+                scraped.push(ScrapedMarket { market, raw });
+            }
+            Err(rejection) => {
+                metrics.record_rejection(rejection.reason);
+                if rejections.len() < MAX_LOGGED_REJECTIONS {
+                    warn!(
+                        "Skipping market {}: {} ({})",
+                        rejection.market_id.as_deref().unwrap_or("<unknown>"),
+                        rejection.reason,
+                        rejection.detail
+                    );
+                }
+                rejections.push(rejection);
+            }
+        }
+    }
+
+    if rejections.len() > MAX_LOGGED_REJECTIONS {
+        warn!(
+            "Skipped {} markets in total, only the first {} were logged",
+            rejections.len(),
+            MAX_LOGGED_REJECTIONS
+        );
+    }
+
+    let rejected_ids = rejections
+        .into_iter()
+        .filter_map(|rejection| rejection.market_id)
+        .collect();
+    (scraped, rejected_ids)
+}
+
+/// Parse a raw market (also an archived payload) with the current parser for the source
+/// that produced it, recording the source's venue on the market
+pub fn parse_raw_market(source: ScrapeSource, raw: &serde_json::Value) -> Result<Market> {
+    let source = source.market_source();
+    let mut market = source.parse_market(raw)?;
+    market.source = source.venue().to_string();
+    Ok(market)
+}
+
+/// What the enrichment pass added to one market
+#[derive(Debug, Default)]
+struct Enrichment {
+    detailed: bool,
+    quotes: usize,
+}
+
+/// Fill in what list payloads omit, enriching up to `concurrency` markets at a time
+/// Polymarket markets without a description or outcomes get their Gamma detail record, then
+/// every outcome token (or the market's token when outcomes are unknown) gets its CLOB
+/// orderbook quote. Failures are logged per market and never fail the scrape
+async fn enrich_markets(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    markets: Vec<Market>,
+    concurrency: u32,
+) {
+    let started = Instant::now();
+    let permits = Arc::new(Semaphore::new(concurrency.max(1) as usize));
+    let mut tasks = JoinSet::new();
+    for market in markets {
+        let client = client.clone();
+        let storage = Arc::clone(storage);
+        let metrics = metrics.clone();
+        let permits = Arc::clone(&permits);
+        // Spawned tasks don't inherit the scrape's span on their own
+        let span = info_span!("enrich_market", market_id = %market.id);
+        tasks.spawn(
+            async move {
+                // The semaphore is never closed, so acquiring only waits for a free permit
+                let _permit = permits.acquire_owned().await.ok();
+                enrich_market(&client, storage.as_ref(), &metrics, market).await
+            }
+            .instrument(span),
+        );
+    }
+
+    let mut detailed = 0;
+    let mut quotes = 0;
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok(enrichment) => {
+                detailed += usize::from(enrichment.detailed);
+                quotes += enrichment.quotes;
+            }
+            Err(e) => warn!("Market enrichment task failed: {}", e),
+        }
+    }
+    metrics.record_phase_duration(ScrapePhase::Enrich, started.elapsed());
+    info!(
+        "Enriched {} markets with details, updated CLOB quotes for {} tokens",
+        detailed, quotes
+    );
+}
+
+/// Enrich a single market, see `enrich_markets`
+async fn enrich_market(
+    client: &ThrottledClient,
+    storage: &dyn Storage,
+    metrics: &Metrics,
+    mut market: Market,
+) -> Enrichment {
+    let mut enrichment = Enrichment::default();
+
+    let incomplete = market.description.is_none() || market.outcomes.is_empty();
+    if market.source == source::POLYMARKET && incomplete && !subgraph::is_condition_id(&market.id) {
+        match store_details(client, storage, metrics, &mut market).await {
+            Ok(()) => enrichment.detailed = true,
+            Err(e) => {
+                warn!("Failed to fetch details of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    let token_ids: Vec<String> = if market.outcomes.is_empty() {
+        market.clob_token_id.iter().cloned().collect()
+    } else {
+        market
+            .outcomes
+            .iter()
+            .filter_map(|outcome| outcome.token_id.clone())
+            .collect()
+    };
+
+    for token_id in &token_ids {
+        let quote = match clob::fetch_quote(client, token_id).await {
+            Ok(quote) => quote,
+            Err(e) => {
+                warn!("Failed to fetch CLOB quote for market {}: {}", market.id, e);
+                metrics.record_error_from(&*e);
+                continue;
+            }
+        };
+
+        match store_quote(storage, &market, token_id, &quote).await {
+            Ok(()) => enrichment.quotes += 1,
+            Err(e) => {
+                warn!("Failed to store CLOB quote for market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    enrichment
+}
+
+/// Fetch the Gamma detail record of a market, fill in the fields its list payload left
+/// empty and store them
+async fn store_details(
+    client: &ThrottledClient,
+    storage: &dyn Storage,
+    metrics: &Metrics,
+    market: &mut Market,
+) -> Result<()> {
+    let detail = gamma::fetch_market(client, metrics, &market.id).await?;
+    market.description = market.description.take().or(detail.description);
+    market.clob_token_id = market.clob_token_id.take().or(detail.clob_token_id);
+    if market.outcomes.is_empty() {
+        market.outcomes = detail.outcomes;
+    }
+
+    storage.update_market_details(market).await?;
+    storage.upsert_outcomes(&market.id, &market.outcomes).await
+}
+
+/// Store a token's quote on its outcome, and on the market if it is the market's token
+async fn store_quote(
+    storage: &dyn Storage,
+    market: &Market,
+    token_id: &str,
+    quote: &clob::ClobQuote,
+) -> Result<()> {
+    if market.clob_token_id.as_deref() == Some(token_id) {
+        storage.update_market_quote(&market.id, quote).await?;
+    }
+    storage.update_outcome_quote(token_id, quote).await
+}
+
+/// Fetch events from the Gamma API, store them and link their child markets
+/// Returns the number of events stored
+async fn scrape_events(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    config: &ScraperConfig,
+) -> Result<usize> {
+    let events =
+        gamma::fetch_all_events(client, metrics, config.page_size, config.max_pages).await?;
+
+    let mut stored = 0;
+    let mut linked = 0;
+    for gamma_event in &events {
+        let event = Event::from(gamma_event);
+        if let Err(e) = storage.upsert_event(&event).await {
+            warn!("Failed to upsert event {}: {}", event.id, e);
+            metrics.record_error_from(&e);
+            continue;
+        }
+        stored += 1;
+
+        let market_ids: Vec<String> = gamma_event.markets.iter().map(|m| m.id.clone()).collect();
+        match storage.link_markets_to_event(&event.id, &market_ids).await {
+            Ok(count) => linked += count,
+            Err(e) => {
+                warn!("Failed to link markets to event {}: {}", event.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    info!("Stored {} events, linked {} markets", stored, linked);
+    Ok(stored)
+}
+
+/// Refresh the hot tier between full scrapes, as many markets at a time as
+/// `HOT_REFRESH_BUDGET_SHARE` of the upstream request budget allows
+/// Stored markets with a CLOB token get a fresh orderbook quote whose midpoint becomes their
+/// price; watchlisted IDs that aren't stored yet are fetched from Gamma instead
+/// Returns the number of markets refreshed
+async fn refresh_hot_tier(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+) -> Result<usize> {
+    // One request per market, within a share of the upstream budget over the hot interval
+    let interval = tiers.hot_interval().unwrap_or_default().as_secs_f64();
+    let budget = (client.request_rate() * interval * HOT_REFRESH_BUDGET_SHARE).floor() as usize;
+    let ids = tiers.hot_refresh_ids(storage.as_ref(), budget.max(1)).await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let stored = storage.get_markets_by_ids(&ids).await?;
+    let scraped_at = Utc::now();
+    let mut refreshed = 0;
+    for market in &stored {
+        let Some(token_id) = market.clob_token_id.as_deref() else {
+            continue;
+        };
+        let quote = match clob::fetch_quote(client, token_id).await {
+            Ok(quote) => quote,
+            Err(e) => {
+                warn!("Failed to fetch CLOB quote for hot market {}: {}", market.id, e);
+                metrics.record_error_from(&*e);
+                continue;
+            }
+        };
+        let stored_price =
+            store_clob_price(storage.as_ref(), updates, config, market, token_id, &quote, scraped_at)
+                .await;
+        match stored_price {
+            Ok(()) => refreshed += 1,
+            Err(e) => {
+                warn!("Failed to store CLOB price of hot market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    let missing: Vec<&String> = ids
+        .iter()
+        .filter(|id| !stored.iter().any(|market| &market.id == *id))
+        .collect();
+    let mut raw_markets = Vec::with_capacity(missing.len());
+    for id in missing {
+        match gamma::fetch_raw_market(client, metrics, id).await {
+            Ok(raw) => raw_markets.push(raw),
+            Err(e) => {
+                warn!("Failed to fetch watchlisted market {}: {}", id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+    let source = ScrapeSource::Gamma;
+    let (scraped, _) = parse_and_validate(source, raw_markets, config.precision, metrics);
+    for scraped_market in &scraped {
+        store_market(storage.as_ref(), metrics, updates, source, scraped_market, scraped_at).await;
+    }
+    refreshed += scraped.len();
+
+    debug!("Refreshed {} of {} hot markets", refreshed, ids.len());
+    Ok(refreshed)
+}
+
+/// Fetch one market from Gamma now, store it like a scraped one and update its CLOB quotes
+/// Returns the stored market, or None when Gamma doesn't know the ID
+async fn refresh_market(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    id: &str,
+) -> Result<Option<Market>> {
+    let raw = match gamma::fetch_raw_market(client, metrics, id).await {
+        Ok(raw) => raw,
+        Err(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => return Ok(None),
+        Err(e) => {
+            metrics.record_error_from(&e);
+            return Err(e);
+        }
+    };
+
+    let source = ScrapeSource::Gamma;
+    let (scraped, _) = parse_and_validate(source, vec![raw], config.precision, metrics);
+    let Some(scraped_market) = scraped.first() else {
+        return Err(Error::UpstreamSchema(anyhow::anyhow!(
+            "Market {} was rejected by validation",
+            id
+        )));
+    };
+    store_market(storage.as_ref(), metrics, updates, source, scraped_market, Utc::now()).await;
+    enrich_market(client, storage.as_ref(), metrics, scraped_market.market.clone()).await;
+
+    storage.get_market_by_id(id).await
+}
+
+/// Fetch the latest trades of the hot markets due for it (see `TierConfig::enrich_every`)
+/// with a condition ID and store new ones; from the subgraph's order fills when it is a
+/// configured source, otherwise from the data API
+/// Returns the number of new trades
+async fn scrape_trades(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+) -> Result<u64> {
+    let ids = tiers
+        .enrich_market_ids(EnrichPass::Trades, storage.as_ref())
+        .await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let mut stored = 0;
+    for market in storage.get_markets_by_ids(&ids).await? {
+        let Some(condition_id) = market.condition_id.as_deref() else {
+            continue;
+        };
+        // Trades since the latest one stored, which overlapping fetches store only once
+        let since = match storage.get_trades(&market.id, None, 1).await {
+            Ok(latest) => latest.first().map(|trade| trade.traded_at),
+            Err(e) => {
+                warn!("Failed to load the latest trade of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+                continue;
+            }
+        };
+        let fetched = if config.uses_source(ScrapeSource::Subgraph) {
+            match token_ids(storage.as_ref(), &market.id).await {
+                Ok(token_ids) => subgraph::fetch_trades(
+                    client,
+                    metrics,
+                    &config.subgraph_url,
+                    &market.id,
+                    &token_ids,
+                    since,
+                )
+                .await
+                .map_err(anyhow::Error::from),
+                Err(e) => Err(e.into()),
+            }
+        } else {
+            trades::fetch_trades(client, &market.id, condition_id, since).await
+        };
+        let fetched = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                warn!("Failed to fetch trades of market {}: {}", market.id, e);
+                metrics.record_error_from(&*e);
+                continue;
+            }
+        };
+        match storage.insert_trades(&fetched).await {
+            Ok(inserted) => stored += inserted,
+            Err(e) => {
+                warn!("Failed to store trades of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    ::metrics::counter!(metrics::TRADES_STORED_TOTAL).increment(stored);
+    debug!("Stored {} new trades of {} hot markets", stored, ids.len());
+    Ok(stored)
+}
+
+/// Capture the top holders of the hot markets due for it with a condition ID, when they
+/// differ from the market's latest capture; from the positions subgraph's balances when the
+/// subgraph is a configured source, otherwise from the data API
+/// Returns the number of positions stored
+async fn scrape_holders(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+) -> Result<u64> {
+    let ids = tiers
+        .enrich_market_ids(EnrichPass::Holders, storage.as_ref())
+        .await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let mut stored = 0;
+    for market in storage.get_markets_by_ids(&ids).await? {
+        let Some(condition_id) = market.condition_id.as_deref() else {
+            continue;
+        };
+        let positions = if config.uses_source(ScrapeSource::Subgraph) {
+            match token_ids(storage.as_ref(), &market.id).await {
+                Ok(token_ids) => subgraph::fetch_holders(
+                    client,
+                    metrics,
+                    &config.positions_subgraph_url,
+                    &market.id,
+                    &token_ids,
+                )
+                .await
+                .map_err(anyhow::Error::from),
+                Err(e) => Err(e.into()),
+            }
+        } else {
+            holders::fetch_holders(client, &market.id, condition_id).await
+        };
+        let positions = match positions {
+            Ok(positions) => positions,
+            Err(e) => {
+                warn!("Failed to fetch holders of market {}: {}", market.id, e);
+                metrics.record_error_from(&*e);
+                continue;
+            }
+        };
+        match storage.get_holders(&market.id, None).await {
+            Ok(latest) if holder_set(&latest) == holder_set(&positions) => continue,
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Failed to load holders of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+                continue;
+            }
+        }
+        match storage.insert_positions(&positions).await {
+            Ok(inserted) => stored += inserted,
+            Err(e) => {
+                warn!("Failed to store holders of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    ::metrics::counter!(metrics::POSITIONS_STORED_TOTAL).increment(stored);
+    debug!("Stored {} holder positions of {} hot markets", stored, ids.len());
+    Ok(stored)
+}
+
+/// CLOB token IDs of a market's stored outcomes
+async fn token_ids(storage: &dyn Storage, market_id: &str) -> Result<Vec<String>> {
+    Ok(storage
+        .get_outcomes(market_id)
+        .await?
+        .into_iter()
+        .filter_map(|outcome| outcome.token_id)
+        .collect())
+}
+
+/// Wallets holding each token in a holder capture
+fn holder_set(positions: &[Position]) -> HashSet<(&str, &str)> {
+    positions
+        .iter()
+        .map(|position| (position.token_id.as_str(), position.wallet.as_str()))
+        .collect()
+}
+
+/// Fetch the latest comments on the events of the hot markets due for it, store new ones and
+/// update the comment velocity gauge
+/// Returns the number of new comments
+async fn scrape_comments(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    tiers: &TierScheduler,
+) -> Result<u64> {
+    let ids = tiers
+        .enrich_market_ids(EnrichPass::Comments, storage.as_ref())
+        .await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    // Markets of one event share its comments
+    let mut event_ids: Vec<String> = storage
+        .get_markets_by_ids(&ids)
+        .await?
+        .into_iter()
+        .filter_map(|market| market.event_id)
+        .collect();
+    event_ids.sort();
+    event_ids.dedup();
+
+    let mut stored = 0;
+    for event_id in &event_ids {
+        let comments = match gamma::fetch_comments(client, metrics, event_id).await {
+            Ok(comments) => comments,
+            Err(e) => {
+                warn!("Failed to fetch comments of event {}: {}", event_id, e);
+                metrics.record_error_from(&e);
+                continue;
+            }
+        };
+        match storage.upsert_comments(&comments).await {
+            Ok(inserted) => stored += inserted,
+            Err(e) => {
+                warn!("Failed to store comments of event {}: {}", event_id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    ::metrics::counter!(metrics::COMMENTS_STORED_TOTAL).increment(stored);
+    let last_hour = storage
+        .count_comments_since(None, Utc::now() - chrono::Duration::hours(1))
+        .await?;
+    ::metrics::gauge!(metrics::COMMENTS_LAST_HOUR).set(last_hour as f64);
+    debug!("Stored {} new comments on {} events", stored, event_ids.len());
+    Ok(stored)
+}
+
+/// Snapshot the orderbook of the hot markets due for it with a CLOB token
+/// Returns the number of snapshots stored
+async fn capture_orderbooks(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    config: &ScraperConfig,
+    tiers: &TierScheduler,
+) -> Result<u64> {
+    let ids = tiers
+        .enrich_market_ids(EnrichPass::Orderbooks, storage.as_ref())
+        .await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let mut captured = 0;
+    for market in storage.get_markets_by_ids(&ids).await? {
+        let Some(token_id) = market.clob_token_id.as_deref() else {
+            continue;
+        };
+        let snapshot =
+            match clob::fetch_snapshot(client, &market.id, token_id, config.orderbook_depth).await {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    warn!("Failed to fetch orderbook of market {}: {}", market.id, e);
+                    metrics.record_error_from(&*e);
+                    continue;
+                }
+            };
+        match storage.insert_orderbook_snapshot(&snapshot).await {
+            Ok(()) => captured += 1,
+            Err(e) => {
+                warn!("Failed to store orderbook of market {}: {}", market.id, e);
+                metrics.record_error_from(&e);
+            }
+        }
+    }
+
+    ::metrics::counter!(metrics::ORDERBOOK_SNAPSHOTS_TOTAL).increment(captured);
+    debug!("Captured {} orderbooks of {} hot markets", captured, ids.len());
+    Ok(captured)
+}
+
+/// Store a hot market's quote and, when its midpoint moved the price, the new price with
+/// its change log entry, price point and update
+async fn store_clob_price(
+    storage: &dyn Storage,
+    updates: &UpdateSender,
+    config: &ScraperConfig,
+    market: &Market,
+    token_id: &str,
+    quote: &clob::ClobQuote,
+    scraped_at: chrono::DateTime<Utc>,
+) -> Result<()> {
+    store_quote(storage, market, token_id, quote).await?;
+
+    let mut updated = market.clone();
+    updated.current_price = quote.midpoint;
+    updated.round_to(config.precision);
+    if updated.current_price.is_none() || updated.current_price == market.current_price {
+        return Ok(());
+    }
+    // The upsert would otherwise put back the quote stored just above
+    updated.best_bid = quote.best_bid;
+    updated.best_ask = quote.best_ask;
+    updated.midpoint = quote.midpoint;
+    updated.spread = quote.spread;
+
+    let changes = updates::field_changes(market, &updated);
+    let change = MarketUpdate::MarketChanged {
+        changed_fields: changes.iter().map(|change| change.field).collect(),
+        previous_price: market.current_price,
+        market: updated.clone(),
+    };
+    storage.upsert_market_with_outbox(&updated, Some(&change)).await?;
+    storage.insert_market_changes(&market.id, &changes, scraped_at).await?;
+    storage.insert_price_point(&market.id, updated.current_price, updated.volume, scraped_at)
+        .await?;
+    let _ = updates.send(change);
+    Ok(())
+}
+
+/// Re-fetch markets past their end date and record closure/resolution
+/// Returns the number of markets newly marked as resolved
+async fn check_resolutions(
+    client: &ThrottledClient,
+    storage: &Arc<dyn Storage>,
+    metrics: &Metrics,
+    tiers: &TierScheduler,
+) -> Result<usize> {
+    let shard = tiers.shard();
+    let mut pending = storage.get_markets_pending_resolution().await?;
+    pending.retain(|id| shard.owns(id));
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let mut resolved = 0;
+    for id in &pending {
+        let market = match gamma::fetch_market(client, metrics, id).await {
+            Ok(market) => market,
+            Err(e) => {
+                warn!("Failed to fetch market {} for resolution check: {}", id, e);
+                metrics.record_error_from(&e);
+                continue;
+            }
+        };
 
-    // End date
-    let end_date = json
-        .get("end_date_iso")
-        .or_else(|| json.get("endDate"))
-        .or_else(|| json.get("end_date"))
-        .or_else(|| json.get("endTime"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+        if let Err(e) = storage.update_market_resolution(&market).await {
+            warn!("Failed to store resolution for market {}: {}", id, e);
+            metrics.record_error_from(&e);
+            continue;
+        }
+
+        if market.status == Some(MarketStatus::Resolved) {
+            resolved += 1;
+            info!(
+                "Market resolved: {} - winning outcome: {}",
+                id,
+                market.winning_outcome.as_deref().unwrap_or("unknown")
+            );
+        }
+    }
+
+    info!("Checked {} markets for resolution, {} resolved", pending.len(), resolved);
+    Ok(resolved)
+}
+
+/// Known shapes of the Next.js data route response
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NextJsPayload {
+    /// Direct array of markets
+    Markets(Vec<serde_json::Value>),
+    /// Object with a `markets` key
+    Wrapped { markets: Vec<serde_json::Value> },
+    /// Legacy Next.js page structure
+    PageProps {
+        #[serde(rename = "pageProps")]
+        page_props: NextJsPageProps,
+    },
+}
+
+#[derive(Deserialize)]
+struct NextJsPageProps {
+    markets: Vec<serde_json::Value>,
+}
+
+/// Count a payload that matched none of the known shapes
+fn record_parse_fallback(shape: &'static str) {
+    ::metrics::counter!(metrics::PARSE_FALLBACKS_TOTAL, "shape" => shape).increment(1);
+}
+
+/// Extract the raw market objects from a Next.js JSON response
+/// Handles Next.js endpoint response structure (pageProps.markets) and direct arrays
+fn extract_markets_from_json(json: &serde_json::Value) -> Result<Vec<serde_json::Value>> {
+    match NextJsPayload::deserialize(json) {
+        Ok(NextJsPayload::Markets(markets))
+        | Ok(NextJsPayload::Wrapped { markets })
+        | Ok(NextJsPayload::PageProps {
+            page_props: NextJsPageProps { markets },
+        }) => Ok(markets),
+        Err(e) => {
+            record_parse_fallback("response");
+            Err(e).schema_context("Unrecognised Next.js response shape")
+        }
+    }
+}
+
+/// Parse a single market from JSON
+/// Known Next.js shapes go through `NextJsMarket`; anything else is probed for just an ID
+/// and title so the market is still tracked
+pub fn parse_single_market(json: &serde_json::Value) -> Result<Market> {
+    match NextJsMarket::deserialize(json) {
+        Ok(market) => Ok(Market::try_from(market)?),
+        Err(e) => {
+            warn!("Unrecognised Next.js market shape ({}), using fallback parser", e);
+            record_parse_fallback("market");
+            parse_unknown_market(json)
+        }
+    }
+}
+
+/// Fallback for markets whose fields don't match `NextJsMarket`
+fn parse_unknown_market(json: &serde_json::Value) -> Result<Market> {
+    let string_field = |keys: &[&str]| {
+        keys.iter().find_map(|key| match json.get(*key)? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+    };
+
+    // Same precedence as `TryFrom<NextJsMarket>`, which stored markets are keyed by
+    let id = string_field(&["id", "slug", "marketId", "market_slug"])
+        .ok_or(validation::RejectReason::MissingId)?;
+    let slug = string_field(&["slug", "market_slug"]);
+    let title = string_field(&["question", "title", "name"])
+        .unwrap_or_else(|| "Untitled Market".to_string());
 
     Ok(Market {
         id,
+        slug,
         title,
-        description,
-        current_price,
-        volume,
-        end_date,
-        discovered_at: None,
-        updated_at: None,
+        ..Default::default()
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
+    use crate::validation::RejectReason;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn parse_nextjs(json: &serde_json::Value) -> Vec<ScrapedMarket> {
+        let raw_markets = extract_markets_from_json(json).unwrap();
+        parse_and_validate(ScrapeSource::NextJs, raw_markets, Precision::default(), &Metrics::new()).0
+    }
 
     #[test]
     fn test_parse_single_market() {
@@ -416,9 +2765,12 @@ mod tests {
         assert_eq!(market.id, "test-market-123");
         assert_eq!(market.title, "Test Market");
         assert_eq!(market.description, Some("A test market".to_string()));
-        assert_eq!(market.current_price, Some(0.65));
-        assert_eq!(market.volume, Some(1000.0));
-        assert_eq!(market.end_date, Some("2024-12-31T23:59:59Z".to_string()));
+        assert_eq!(market.current_price, Some(dec!(0.65)));
+        assert_eq!(market.volume, Some(dec!(1000.0)));
+        assert_eq!(
+            market.end_date.map(|d| d.to_rfc3339()),
+            Some("2024-12-31T23:59:59+00:00".to_string())
+        );
     }
 
     #[test]
@@ -432,9 +2784,130 @@ mod tests {
 
         let market = parse_single_market(&json).unwrap();
         assert_eq!(market.id, "alternative-id");
+        assert_eq!(market.slug.as_deref(), Some("alternative-id"));
         assert_eq!(market.title, "Alternative Title");
-        assert_eq!(market.current_price, Some(0.75));
-        assert_eq!(market.volume, Some(500.0));
+        assert_eq!(market.current_price, Some(dec!(0.75)));
+        assert_eq!(market.volume, Some(dec!(500.0)));
+    }
+
+    #[test]
+    fn test_parse_market_tokens_and_tags() {
+        let json = serde_json::json!({
+            "pageProps": {
+                "markets": [{
+                    "market_slug": "who-wins",
+                    "name": "Who wins?",
+                    "totalVolume": "250.5",
+                    "end_date_iso": "2025-01-01T00:00:00Z",
+                    "tokens": [
+                        { "outcome": "Yes", "token_id": "111", "price": 0.4 },
+                        { "outcome": "No", "tokenId": "222", "price": "0.6" }
+                    ],
+                    "category": "Sports",
+                    "tags": ["NBA", { "label": "Sports", "slug": "sports" }]
+                }]
+            }
+        });
+
+        let markets = parse_nextjs(&json);
+        let market = &markets[0].market;
+        assert_eq!(market.id, "who-wins");
+        assert_eq!(market.slug.as_deref(), Some("who-wins"));
+        assert_eq!(market.title, "Who wins?");
+        assert_eq!(market.volume, Some(dec!(250.5)));
+        assert_eq!(market.current_price, Some(dec!(0.4)));
+        assert_eq!(market.clob_token_id.as_deref(), Some("111"));
+        assert_eq!(market.outcomes[1].token_id.as_deref(), Some("222"));
+        assert_eq!(market.outcomes[1].price, Some(dec!(0.6)));
+        let slugs: Vec<&str> = market.tags.iter().map(|t| t.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["sports", "nba"]);
+    }
+
+    #[test]
+    fn test_parse_market_end_date_formats() {
+        let end_date = |json: serde_json::Value| {
+            parse_single_market(&json)
+                .unwrap()
+                .end_date
+                .map(|d| d.to_rfc3339())
+        };
+        let expected = Some("2025-01-01T00:00:00+00:00".to_string());
+
+        assert_eq!(end_date(serde_json::json!({ "id": "1", "endTime": 1735689600000u64 })), expected);
+        assert_eq!(end_date(serde_json::json!({ "id": "1", "end_date": "1735689600000" })), expected);
+        assert_eq!(end_date(serde_json::json!({ "id": "1", "endDate": "2025-01-01" })), expected);
+        assert_eq!(
+            end_date(serde_json::json!({ "id": "1", "end_date_iso": "2025-01-01T01:00:00+01:00" })),
+            expected
+        );
+
+        let error = parse_single_market(&serde_json::json!({ "id": "1", "endDate": "soon" })).unwrap_err();
+        assert_eq!(error.kind(), crate::error::ErrorKind::UpstreamSchema);
+        assert_eq!(
+            Rejection::from_parse_error(None, &error).reason,
+            validation::RejectReason::UnparsableDate
+        );
+    }
+
+    #[test]
+    fn test_parse_market_unknown_shape_fallback() {
+        let json = serde_json::json!({
+            "id": 7,
+            "question": "Odd market",
+            "tokens": "not-a-list"
+        });
+
+        let market = parse_single_market(&json).unwrap();
+        assert_eq!(market.id, "7");
+        assert_eq!(market.title, "Odd market");
+        assert!(market.outcomes.is_empty());
+
+        assert!(extract_markets_from_json(&serde_json::json!({ "data": [] })).is_err());
+    }
+
+    #[test]
+    fn test_parse_and_validate_rejections() {
+        let raw_markets = vec![
+            serde_json::json!({ "id": "ok", "price": 0.5 }),
+            serde_json::json!({ "id": "too-high", "price": 1.5 }),
+            serde_json::json!({ "id": "bad-date", "endDate": "soon" }),
+            serde_json::json!({ "title": "No ID" }),
+        ];
+        let metrics = Metrics::new();
+
+        let (markets, rejected_ids) = parse_and_validate(
+            ScrapeSource::NextJs,
+            raw_markets,
+            Precision::default(),
+            &metrics,
+        );
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets[0].market.id, "ok");
+        assert_eq!(rejected_ids, vec!["too-high", "bad-date"]);
+
+        let rejections = metrics.get_rejections();
+        for reason in [RejectReason::BadPrice, RejectReason::UnparsableDate, RejectReason::MissingId] {
+            assert!(rejections.contains(&(reason, 1)));
+        }
+    }
+
+    #[test]
+    fn test_parse_and_validate_rounds_to_precision() {
+        let raw_markets = vec![serde_json::json!({
+            "id": "1",
+            "price": "0.123456",
+            "volume": 1234.5678
+        })];
+        let precision = Precision {
+            price_decimals: 2,
+            volume_decimals: 0,
+        };
+
+        let (markets, _) =
+            parse_and_validate(ScrapeSource::NextJs, raw_markets, precision, &Metrics::new());
+        let market = &markets[0].market;
+        assert_eq!(market.current_price, Some(dec!(0.12)));
+        assert_eq!(market.volume, Some(dec!(1235)));
     }
 
     #[test]
@@ -446,6 +2919,126 @@ mod tests {
         assert!(parse_single_market(&json).is_err());
     }
 
+    fn breaker_config() -> BreakerConfig {
+        BreakerConfig {
+            failure_threshold: 2,
+            open_for: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_scraper_control_interval_bounds() {
+        let (scraper, _signals) = control(30, ScrapeSource::Gamma, breaker_config());
+        assert!(scraper.set_interval_secs(0).is_err());
+        assert!(scraper.set_interval_secs(MAX_SCRAPE_INTERVAL_SECS + 1).is_err());
+        assert_eq!(scraper.interval_secs(), 30);
+
+        scraper.set_interval_secs(120).unwrap();
+        assert_eq!(scraper.interval_secs(), 120);
+    }
+
+    #[test]
+    fn test_scraper_control_pause_resume() {
+        let (scraper, mut signals) = control(30, ScrapeSource::Gamma, breaker_config());
+        assert_eq!(scraper.status().state, ScraperState::Running);
+
+        scraper.set_state(ScraperState::Paused);
+        assert!(signals.state.has_changed().unwrap());
+        assert_eq!(*signals.state.borrow_and_update(), ScraperState::Paused);
+
+        scraper.set_state(ScraperState::Running);
+        assert_eq!(scraper.status().state, ScraperState::Running);
+    }
+
+    #[test]
+    fn test_scraper_status_reports_circuit() {
+        let (scraper, signals) = control(30, ScrapeSource::Gamma, breaker_config());
+        assert_eq!(scraper.status().circuit, CircuitState::Closed);
+
+        signals.breaker.record_failure();
+        signals.breaker.record_failure();
+        assert_eq!(scraper.status().circuit, CircuitState::Open);
+        assert!(!signals.breaker.allow());
+
+        signals.breaker.record_success();
+        assert_eq!(scraper.circuit_state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_scraper_control_leadership() {
+        let (scraper, signals) = control(30, ScrapeSource::Gamma, breaker_config());
+        assert!(scraper.status().leader);
+
+        scraper.set_leader(false);
+        assert!(!signals.is_leader());
+        assert!(scraper.leader_since().is_none());
+
+        scraper.set_leader(true);
+        let since = scraper.leader_since().unwrap();
+        scraper.set_leader(true);
+        assert_eq!(scraper.leader_since(), Some(since));
+        assert!(signals.is_leader());
+    }
+
+    #[test]
+    fn test_first_scrape_delay() {
+        let now = Utc::now();
+        let period = Duration::from_secs(60);
+        assert_eq!(first_scrape_delay(None, period, now), Duration::ZERO);
+        assert_eq!(
+            first_scrape_delay(Some(now - chrono::Duration::seconds(20)), period, now),
+            Duration::from_secs(40)
+        );
+        assert_eq!(
+            first_scrape_delay(Some(now - chrono::Duration::hours(2)), period, now),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_restart_delay_and_health() {
+        assert_eq!(restart_delay(0), Duration::from_secs(1));
+        assert_eq!(restart_delay(3), Duration::from_secs(8));
+        assert_eq!(restart_delay(20), MAX_RESTART_DELAY);
+        assert_eq!(restart_delay(u32::MAX), MAX_RESTART_DELAY);
+
+        let (scraper, signals) = control(30, ScrapeSource::Gamma, breaker_config());
+        assert_eq!(scraper.health(), ScraperHealth::Running);
+        signals.set_health(ScraperHealth::Restarting);
+        assert_eq!(scraper.status().health, ScraperHealth::Restarting);
+        drop(signals);
+        assert_eq!(scraper.health(), ScraperHealth::Dead);
+    }
+
+    #[test]
+    fn test_build_id_validity() {
+        let (gamma, _signals) = control(30, ScrapeSource::Gamma, breaker_config());
+        assert!(gamma.build_id_valid());
+
+        let (nextjs, signals) = control(30, ScrapeSource::NextJs, breaker_config());
+        assert!(!nextjs.build_id_valid());
+        signals.publish_build_id(DEFAULT_BUILD_ID);
+        assert!(!nextjs.build_id_valid());
+        signals.publish_build_id("abc123def456");
+        assert!(nextjs.build_id_valid());
+        assert_eq!(nextjs.status().build_id.as_deref(), Some("abc123def456"));
+    }
+
+    #[test]
+    fn test_scrape_source_from_str() {
+        assert_eq!("gamma".parse::<ScrapeSource>().unwrap(), ScrapeSource::Gamma);
+        assert_eq!("NextJS".parse::<ScrapeSource>().unwrap(), ScrapeSource::NextJs);
+        assert!("clob".parse::<ScrapeSource>().is_err());
+        assert_eq!(ScrapeSource::Gamma.fallback(), Some(ScrapeSource::NextJs));
+        assert_eq!(
+            ScrapeSource::Gamma.fetch_order()[..2],
+            [ScrapeSource::Gamma, ScrapeSource::NextJs]
+        );
+        assert_eq!(ScrapeSource::NextJs.to_string(), "nextjs");
+        let market = parse_raw_market(ScrapeSource::NextJs, &serde_json::json!({ "id": "1" })).unwrap();
+        assert_eq!(market.source, source::POLYMARKET);
+    }
+
     #[test]
     fn test_parse_markets_from_json() {
         // Test direct array response (Gamma API format)
@@ -462,12 +3055,33 @@ mod tests {
             }
         ]);
 
-        let markets = parse_markets_from_json(&json).unwrap();
+        let markets = parse_nextjs(&json);
         assert_eq!(markets.len(), 2);
-        assert_eq!(markets[0].id, "1");
-        assert_eq!(markets[0].title, "Market 1");
-        assert_eq!(markets[1].id, "2");
-        assert_eq!(markets[1].title, "Market 2");
+        assert_eq!(markets[0].market.id, "1");
+        assert_eq!(markets[0].market.title, "Market 1");
+        assert_eq!(markets[1].market.id, "2");
+        assert_eq!(markets[1].market.title, "Market 2");
+        assert_eq!(markets[1].raw["market_slug"], "market-2");
     }
-}
 
+    #[tokio::test]
+    async fn test_homepage_without_build_id_keeps_the_current_one() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/polymarket.com/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+            .mount(&server)
+            .await;
+        let throttle = ThrottleConfig {
+            requests_per_second: 1000.0,
+            burst: 100,
+        };
+        let client = ThrottledClient::new(vec![Client::new()], throttle)
+            .with_upstream_base(Url::parse(&server.uri()).unwrap());
+
+        assert!(discover_build_id(&client).await.is_err());
+        assert_eq!(rediscover_build_id(&client, "goodBuild").await, "goodBuild");
+        // Only a scraper that never discovered one starts from the default
+        assert_eq!(initial_build_id(&client).await, DEFAULT_BUILD_ID);
+    }
+}
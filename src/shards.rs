@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// FNV-1a parameters; a fixed hash keeps shards stable across builds, unlike `DefaultHasher`
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Slice of the market universe an instance scrapes, by hash of the market ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Shard {
+    pub index: u32,
+    pub count: u32,
+}
+
+impl Shard {
+    /// Every market, for an instance that doesn't share the work
+    pub const ALL: Shard = Shard { index: 0, count: 1 };
+
+    pub fn is_all(self) -> bool {
+        self.count <= 1
+    }
+
+    /// The shard that runs once-per-deployment work, such as delisting
+    pub fn is_first(self) -> bool {
+        self.index == 0
+    }
+
+    /// Whether the market with this ID belongs to the shard
+    pub fn owns(self, market_id: &str) -> bool {
+        self.is_all() || shard_of(market_id, self.count) == self.index
+    }
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Shard::ALL
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// Index of the shard among `count` that a market ID hashes to
+pub fn shard_of(market_id: &str, count: u32) -> u32 {
+    let hash = market_id.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+    (hash % u64::from(count.max(1))) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shards_split_markets() {
+        let ids: Vec<String> = (0..1000).map(|i| format!("{}", 500_000 + i)).collect();
+        let shards: Vec<Shard> = (0..4).map(|index| Shard { index, count: 4 }).collect();
+
+        // Every market belongs to exactly one shard, and none of them is left empty
+        for id in &ids {
+            assert_eq!(shards.iter().filter(|shard| shard.owns(id)).count(), 1);
+        }
+        for shard in &shards {
+            let owned = ids.iter().filter(|id| shard.owns(id)).count();
+            assert!(owned > 150, "shard {} owns only {} markets", shard, owned);
+        }
+
+        assert!(ids.iter().all(|id| Shard::ALL.owns(id)));
+        assert_eq!(shard_of("12345", 4), shard_of("12345", 4));
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use flate2::{write::GzEncoder, Compression};
+use object_store::{aws::AmazonS3Builder, path::Path, ObjectStore};
+use sqlx::{Pool, Sqlite};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+use crate::db;
+use crate::export;
+use crate::models::MarketFilter;
+use crate::scraper::ScraperControl;
+
+pub const DEFAULT_SNAPSHOT_PREFIX: &str = "polymarket-scraper";
+pub const DEFAULT_SNAPSHOT_INTERVAL_SECS: u64 = 3600;
+const PRICE_HISTORY_WATERMARK: &str = "price_history"; // Name of the price history delta watermark
+
+/// File format of uploaded snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SnapshotFormat {
+    /// Gzip-compressed JSON Lines
+    Jsonl,
+    /// Snappy-compressed Parquet (requires the `parquet` feature)
+    Parquet,
+}
+
+impl SnapshotFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            SnapshotFormat::Jsonl => "jsonl.gz",
+            SnapshotFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Where and how often snapshots are written
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    pub bucket: String,
+    pub prefix: String,
+    /// Custom endpoint for S3-compatible stores such as MinIO
+    pub endpoint: Option<String>,
+    pub interval_secs: u64,
+    pub format: SnapshotFormat,
+}
+
+/// Object key for a table snapshot taken at `at`
+fn object_key(prefix: &str, table: &str, at: DateTime<Utc>, format: SnapshotFormat) -> String {
+    format!(
+        "{}/{}/{}-{}.{}",
+        prefix.trim_end_matches('/'),
+        table,
+        table,
+        at.format("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    )
+}
+
+/// Encode rows as gzip-compressed JSON Lines
+fn gzip_jsonl<T: serde::Serialize>(rows: &[T]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    export::write_jsonl(rows, &mut encoder)?;
+    encoder.flush()?;
+    Ok(encoder.finish()?)
+}
+
+/// Upload the full markets table and the price history recorded since the last snapshot
+/// at a fixed interval from the primary instance; the price history watermark only advances
+/// after a successful upload
+pub async fn run_snapshots(
+    pool: Arc<Pool<Sqlite>>,
+    config: SnapshotConfig,
+    scraper: ScraperControl,
+) -> Result<()> {
+    #[cfg(not(feature = "parquet"))]
+    if config.format == SnapshotFormat::Parquet {
+        anyhow::bail!("Parquet snapshots require building with `--features parquet`");
+    }
+
+    let mut builder = AmazonS3Builder::from_env().with_bucket_name(&config.bucket);
+    if let Some(endpoint) = &config.endpoint {
+        builder = builder.with_endpoint(endpoint).with_allow_http(true);
+    }
+    let store = builder.build().context("Failed to configure S3 client")?;
+
+    info!(
+        "Snapshotting to s3://{}/{} every {} seconds",
+        config.bucket, config.prefix, config.interval_secs
+    );
+
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        if let Err(e) = snapshot(&pool, &store, &config).await {
+            error!("Snapshot failed: {:#}", e);
+        }
+    }
+}
+
+/// Take and upload one snapshot
+async fn snapshot(pool: &Pool<Sqlite>, store: &impl ObjectStore, config: &SnapshotConfig) -> Result<()> {
+    let now = Utc::now();
+
+    let filter = MarketFilter {
+        include_delisted: true,
+        ..Default::default()
+    };
+    let (markets, _) = db::get_markets(pool, u32::MAX, 0, &filter).await?;
+    let data = match config.format {
+        SnapshotFormat::Jsonl => gzip_jsonl(&markets)?,
+        #[cfg(feature = "parquet")]
+        SnapshotFormat::Parquet => {
+            let mut buf = Vec::new();
+            export::parquet::write_markets(&markets, &mut buf)?;
+            buf
+        }
+        #[cfg(not(feature = "parquet"))]
+        SnapshotFormat::Parquet => unreachable!("rejected in run_snapshots"),
+    };
+    let key = object_key(&config.prefix, "markets", now, config.format);
+    store
+        .put(&Path::from(key.as_str()), data.into())
+        .await
+        .with_context(|| format!("Failed to upload {}", key))?;
+    info!("Uploaded snapshot of {} markets to {}", markets.len(), key);
+
+    let since = db::get_snapshot_watermark(pool, PRICE_HISTORY_WATERMARK).await?;
+    let rows = db::get_price_history_between(pool, since, now).await?;
+    if !rows.is_empty() {
+        let data = match config.format {
+            SnapshotFormat::Jsonl => gzip_jsonl(&rows)?,
+            #[cfg(feature = "parquet")]
+            SnapshotFormat::Parquet => {
+                let mut buf = Vec::new();
+                export::parquet::write_price_history(&rows, &mut buf)?;
+                buf
+            }
+            #[cfg(not(feature = "parquet"))]
+            SnapshotFormat::Parquet => unreachable!("rejected in run_snapshots"),
+        };
+        let key = object_key(&config.prefix, "price_history", now, config.format);
+        store
+            .put(&Path::from(key.as_str()), data.into())
+            .await
+            .with_context(|| format!("Failed to upload {}", key))?;
+        info!("Uploaded {} price history rows to {}", rows.len(), key);
+    }
+    db::set_snapshot_watermark(pool, PRICE_HISTORY_WATERMARK, now).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_object_key() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 12, 30, 0).unwrap();
+        assert_eq!(
+            object_key("archive/", "markets", at, SnapshotFormat::Jsonl),
+            "archive/markets/markets-20250301T123000Z.jsonl.gz"
+        );
+        assert_eq!(
+            object_key("archive", "price_history", at, SnapshotFormat::Parquet),
+            "archive/price_history/price_history-20250301T123000Z.parquet"
+        );
+    }
+}
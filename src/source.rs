@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use crate::conditional::ListingPage;
+use crate::error::Result;
+use crate::metrics::Metrics;
+use crate::models::Market;
+use crate::throttle::ThrottledClient;
+
+/// Venue of the Gamma and Next.js sources, and the default of the markets `source` column
+pub const POLYMARKET: &str = "polymarket";
+
+/// Shared inputs of a single fetch
+#[derive(Clone, Copy)]
+pub struct FetchContext<'a> {
+    pub client: &'a ThrottledClient,
+    pub metrics: &'a Metrics,
+    /// Number of markets requested per page by paginated sources
+    pub page_size: u32,
+    /// Safety cap on pages fetched per scrape cycle
+    pub max_pages: u32,
+    /// Next.js build ID of the polymarket.com frontend, only used by the Next.js source
+    pub build_id: &'a str,
+    /// GraphQL endpoint of the Polymarket subgraph, only used by the subgraph source
+    pub subgraph_url: &'a str,
+    /// Offset at which a paginated source continues a listing walk cut short by `max_pages`
+    pub resume_offset: u32,
+    /// Last key listed by a source walking its listing by key instead of offset, which
+    /// continues after it
+    pub resume_cursor: Option<&'a str>,
+}
+
+/// Raw market objects returned by a source, to be parsed with `MarketSource::parse_market`
+#[derive(Default)]
+pub struct FetchedMarkets {
+    pub raw: Vec<serde_json::Value>,
+    /// Whether every active market of the venue was listed, so missing ones may be delisted
+    pub complete: bool,
+    /// Markets listed by responses unchanged since an earlier scrape (304); they count as
+    /// seen but aren't stored again
+    pub unchanged_ids: Vec<String>,
+    /// Listing responses to remember for conditional requests once their markets are stored
+    pub listings: Vec<ListingPage>,
+    /// Offset the next scrape resumes the listing walk at; 0 once it reached the end
+    pub resume_offset: u32,
+    /// Key the next scrape resumes a walk by key after; None once it reached the end
+    pub resume_cursor: Option<String>,
+}
+
+/// Where a paginated source hands over each page as soon as it is fetched, so the page is
+/// stored while the next one is fetched
+pub struct PageSink(mpsc::Sender<FetchedMarkets>);
+
+impl PageSink {
+    /// A sink and the receiver of its pages, which holds one page at a time
+    pub fn channel() -> (Self, mpsc::Receiver<FetchedMarkets>) {
+        let (pages, received) = mpsc::channel(1);
+        (Self(pages), received)
+    }
+
+    /// Hand over a page, waiting while the previous one hasn't been taken yet
+    pub async fn send(&self, page: FetchedMarkets) {
+        // The receiver only goes away once the scrape no longer wants any pages
+        let _ = self.0.send(page).await;
+    }
+}
+
+/// Best-effort ID of a raw market object, also for ones that fail to parse
+pub fn raw_market_id(raw: &serde_json::Value) -> Option<String> {
+    match raw.get("id")? {
+        serde_json::Value::String(id) if !id.is_empty() => Some(id.clone()),
+        serde_json::Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// A prediction market API that markets are scraped from
+/// New sources only need to implement this trait and be added to `ScrapeSource`
+#[async_trait]
+pub trait MarketSource: Send + Sync {
+    /// Source name, stored with archived payloads so they can be re-parsed later
+    fn name(&self) -> &'static str;
+
+    /// Venue the markets belong to, stored in the markets `source` column
+    fn venue(&self) -> &'static str;
+
+    /// Fetch the current market listing
+    /// Paginated sources send each page to `pages` as it arrives and return only where the
+    /// walk ended; others may return every market at once
+    async fn fetch_markets(
+        &self,
+        ctx: &FetchContext<'_>,
+        pages: &PageSink,
+    ) -> Result<FetchedMarkets>;
+
+    /// Parse one raw market object returned by `fetch_markets` or sent to its sink
+    fn parse_market(&self, raw: &serde_json::Value) -> Result<Market>;
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info};
+
+use crate::metrics;
+use crate::models::VolumeSpike;
+use crate::notify::Alerts;
+use crate::scraper::ScraperControl;
+use crate::storage::Storage;
+
+pub const DEFAULT_SPIKE_WINDOW_SECS: u64 = 3600;
+pub const DEFAULT_SPIKE_MULTIPLIER: f64 = 5.0;
+pub const DEFAULT_SPIKE_MIN_VOLUME: f64 = 1000.0;
+/// Hours before the spike window whose average volume per window is the baseline
+const BASELINE_HOURS: i64 = 24;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spike detection settings supplied from the command line
+#[derive(Debug, Clone)]
+pub struct SpikeConfig {
+    /// Seconds of volume compared against the baseline
+    pub window_secs: u64,
+    /// Multiple of the baseline average a window's volume must reach
+    pub multiplier: f64,
+    /// Volume a window must reach regardless of the baseline, so quiet markets don't flag
+    pub min_volume: f64,
+}
+
+/// Find markets whose volume spiked in the window ending at `now` and record them
+/// A market is flagged at most once per window
+pub async fn detect(
+    storage: &dyn Storage,
+    config: &SpikeConfig,
+    now: DateTime<Utc>,
+) -> Result<Vec<VolumeSpike>> {
+    let window_start = now - chrono::Duration::seconds(config.window_secs as i64);
+    let spikes = storage
+        .find_volume_spikes(
+            window_start - chrono::Duration::hours(BASELINE_HOURS),
+            window_start,
+            now,
+            config.multiplier,
+            config.min_volume,
+        )
+        .await?;
+    for spike in &spikes {
+        storage.insert_volume_spike(spike).await?;
+    }
+    Ok(spikes)
+}
+
+/// Run `detect` every minute on the primary instance, sending each spike to the notifiers
+/// as an alert
+pub async fn run_spike_detection(
+    storage: Arc<dyn Storage>,
+    alerts: Alerts,
+    config: SpikeConfig,
+    scraper: ScraperControl,
+) -> Result<()> {
+    info!(
+        "Flagging markets trading {}x their 24h average volume within {} seconds",
+        config.multiplier, config.window_secs
+    );
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if !scraper.is_primary() {
+            continue;
+        }
+        let spikes = match detect(storage.as_ref(), &config, Utc::now()).await {
+            Ok(spikes) => spikes,
+            Err(e) => {
+                error!("Volume spike detection failed: {:#}", e);
+                continue;
+            }
+        };
+        debug!("Detected {} volume spikes", spikes.len());
+        for spike in &spikes {
+            ::metrics::counter!(metrics::VOLUME_SPIKES_TOTAL).increment(1);
+            let message = spike_message(spike, config.window_secs);
+            info!("{}", message);
+            alerts.send(&message).await;
+        }
+    }
+}
+
+fn spike_message(spike: &VolumeSpike, window_secs: u64) -> String {
+    format!(
+        "Volume spike: \"{}\" traded {:.0} in {} ({:.1}x its 24h average of {:.0})",
+        spike.market.title,
+        spike.interval_volume,
+        format_window(window_secs),
+        spike.ratio,
+        spike.baseline_volume
+    )
+}
+
+/// Shortest of `1d`, `2h`, `30m` or `45s` describing the window
+fn format_window(secs: u64) -> String {
+    match secs {
+        s if s > 0 && s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s > 0 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s > 0 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Market;
+
+    #[test]
+    fn test_spike_message() {
+        let spike = VolumeSpike {
+            market: Market {
+                title: "Will the Fed cut rates?".to_string(),
+                ..Default::default()
+            },
+            detected_at: Utc::now(),
+            interval_volume: 60_000.0,
+            baseline_volume: 10_000.0,
+            ratio: 6.0,
+        };
+        assert_eq!(
+            spike_message(&spike, 3600),
+            "Volume spike: \"Will the Fed cut rates?\" traded 60000 in 1h (6.0x its 24h average of 10000)"
+        );
+        assert_eq!(format_window(90), "90s");
+        assert_eq!(format_window(1800), "30m");
+        assert_eq!(format_window(172_800), "2d");
+    }
+}
@@ -0,0 +1,1070 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::pin::Pin;
+use tokio_stream::Stream;
+use tracing::instrument;
+
+use crate::clob::ClobQuote;
+use crate::db::{self, DbPools};
+use crate::error::Result;
+use crate::metrics::MetricsSnapshot;
+use crate::models::{
+    AlertCondition, AlertFired, AlertRule, CalibrationResponse, Candle, CandleInterval, Comment, DailyReport,
+    Event, Market, MarketCalibration, MarketChange, MarketFilter, MarketMover, MarketVolume,
+    MoverSort, OnchainVerification, OrderbookSnapshot, OutboxEvent, Outcome, Position, PriceHistoryRow, PricePoint, ShardProgress, SortOrder, StatsResponse, Tag, TagCount,
+    Trade, VolumeSpike, Watchlist, Webhook, WebhookDeadLetter,
+};
+use crate::updates::{FieldChange, MarketUpdate};
+
+/// Rows streamed from storage one at a time, for exports too large to buffer
+pub type RowStream<T> = Pin<Box<dyn Stream<Item = Result<T>> + Send>>;
+
+/// Where scraped markets are persisted and read back by the API
+/// The scraper, REST and GraphQL APIs only go through this trait, so another backend only
+/// needs an implementation of it; `SqliteStorage` is the one shipped with the crate
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Markets
+
+    /// Insert or update a market; returns whether it was new
+    async fn upsert_market(&self, market: &Market) -> Result<bool>;
+    /// Like `upsert_market`, also queueing a notification in the outbox in the same
+    /// transaction: `NewMarket` for a new market, otherwise `change` if the row changed
+    async fn upsert_market_with_outbox(
+        &self,
+        market: &Market,
+        change: Option<&MarketUpdate>,
+    ) -> Result<bool>;
+    /// Store the description, token and other fields filled in from a market's detail record
+    async fn update_market_details(&self, market: &Market) -> Result<()>;
+    /// Store a market's status, winning outcome and resolution time
+    async fn update_market_resolution(&self, market: &Market) -> Result<()>;
+    async fn update_market_quote(&self, id: &str, quote: &ClobQuote) -> Result<()>;
+    async fn get_market_by_id(&self, id: &str) -> Result<Option<Market>>;
+    /// Market by ID or slug
+    async fn find_market(&self, key: &str) -> Result<Option<Market>>;
+    /// A page of markets matching the filter, and the total number matching it
+    async fn get_markets(
+        &self,
+        limit: u32,
+        offset: u32,
+        filter: &MarketFilter,
+    ) -> Result<(Vec<Market>, i64)>;
+    fn stream_markets(&self, limit: u32, offset: u32, filter: &MarketFilter) -> RowStream<Market>;
+    async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>>;
+    /// Polymarket markets with one of these condition IDs
+    async fn get_markets_by_condition_ids(&self, condition_ids: &[String]) -> Result<Vec<Market>>;
+    /// Markets discovered since the given time
+    async fn get_markets_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>>;
+    async fn get_markets_ending_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<Market>>;
+    /// IDs of active markets whose end date has passed
+    async fn get_markets_pending_resolution(&self) -> Result<Vec<String>>;
+    async fn get_top_movers(
+        &self,
+        since: DateTime<Utc>,
+        sort: MoverSort,
+        limit: u32,
+    ) -> Result<Vec<MarketMover>>;
+    /// Price observations of the given markets since `since`, oldest first per market
+    async fn get_price_history_since(
+        &self,
+        ids: &[String],
+        since: DateTime<Utc>,
+    ) -> Result<Vec<PriceHistoryRow>>;
+    async fn count_markets(&self) -> Result<i64>;
+    async fn count_markets_by_status(&self) -> Result<Vec<(String, i64)>>;
+    async fn get_market_stats(&self) -> Result<StatsResponse>;
+    /// Record that the markets were listed by a scrape
+    async fn mark_markets_seen(&self, ids: &[String], seen_at: DateTime<Utc>) -> Result<()>;
+    /// Delist active markets of a venue missing from more than `max_missed` scrapes; returns
+    /// how many were delisted
+    async fn delist_unseen_markets(
+        &self,
+        source: &str,
+        seen_at: DateTime<Utc>,
+        max_missed: u32,
+    ) -> Result<u64>;
+    async fn insert_market_changes(
+        &self,
+        market_id: &str,
+        changes: &[FieldChange],
+        changed_at: DateTime<Utc>,
+    ) -> Result<()>;
+    async fn get_market_changes(&self, market_id: &str, limit: u32) -> Result<Vec<MarketChange>>;
+    /// Archive a market's compressed raw payload
+    async fn insert_raw_payload(
+        &self,
+        market_id: &str,
+        source: &str,
+        payload: &[u8],
+        payload_hash: &str,
+        scraped_at: DateTime<Utc>,
+    ) -> Result<()>;
+
+    // Outcomes and tags
+
+    async fn upsert_outcomes(&self, market_id: &str, outcomes: &[Outcome]) -> Result<()>;
+    async fn update_outcome_quote(&self, token_id: &str, quote: &ClobQuote) -> Result<()>;
+    async fn get_outcomes(&self, market_id: &str) -> Result<Vec<Outcome>>;
+    /// Fill in the outcomes of each market
+    async fn attach_outcomes(&self, markets: &mut [Market]) -> Result<()>;
+    /// Outcomes of several markets at once, keyed by market ID
+    async fn get_outcomes_by_market(
+        &self,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, Vec<Outcome>>>;
+    async fn set_market_tags(&self, market_id: &str, tags: &[Tag]) -> Result<()>;
+    async fn get_market_tags(&self, market_id: &str) -> Result<Vec<Tag>>;
+    /// Tags of several markets at once, keyed by market ID
+    async fn get_tags_by_market(&self, market_ids: &[String]) -> Result<HashMap<String, Vec<Tag>>>;
+    async fn get_tag_counts(&self) -> Result<Vec<TagCount>>;
+
+    // Price history
+
+    async fn insert_price_point(
+        &self,
+        market_id: &str,
+        price: Option<Decimal>,
+        volume: Option<Decimal>,
+        scraped_at: DateTime<Utc>,
+    ) -> Result<()>;
+    async fn get_price_history(&self, market_id: &str, limit: u32) -> Result<Vec<PricePoint>>;
+    fn stream_price_history(&self, market_id: &str, limit: u32) -> RowStream<PricePoint>;
+    /// Candles rolled up from the price history, newest first
+    async fn get_candles(
+        &self,
+        market_id: &str,
+        interval: CandleInterval,
+        limit: u32,
+    ) -> Result<Vec<Candle>>;
+
+    // Trades
+
+    /// Store trades not stored yet; returns how many were new
+    async fn insert_trades(&self, trades: &[Trade]) -> Result<u64>;
+    /// A market's trades newest first, only those after `since` if given
+    async fn get_trades(
+        &self,
+        market_id: &str,
+        since: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<Trade>>;
+
+    // Orderbook snapshots
+
+    async fn insert_orderbook_snapshot(&self, snapshot: &OrderbookSnapshot) -> Result<()>;
+    /// A market's most recently captured orderbook
+    async fn get_latest_orderbook_snapshot(&self, market_id: &str) -> Result<Option<OrderbookSnapshot>>;
+
+    // Holders
+
+    /// Store a capture of market holders; returns how many positions were new
+    async fn insert_positions(&self, positions: &[Position]) -> Result<u64>;
+    /// A market's holders from its latest capture, or the latest one at or before `at`
+    async fn get_holders(&self, market_id: &str, at: Option<DateTime<Utc>>) -> Result<Vec<Position>>;
+
+    // Comments
+
+    /// Store comments, refreshing the reactions of known ones; returns how many were new
+    async fn upsert_comments(&self, comments: &[Comment]) -> Result<u64>;
+    /// An event's comments newest first
+    async fn get_comments(&self, event_id: &str, limit: u32) -> Result<Vec<Comment>>;
+    /// Comments created after `since`, on one event or on all of them
+    async fn count_comments_since(&self, event_id: Option<&str>, since: DateTime<Utc>) -> Result<i64>;
+
+    // Events
+
+    async fn upsert_event(&self, event: &Event) -> Result<()>;
+    /// Point the markets at their parent event; returns how many were linked
+    async fn link_markets_to_event(&self, event_id: &str, market_ids: &[String]) -> Result<u64>;
+    async fn get_event_by_id(&self, id: &str) -> Result<Option<Event>>;
+    /// A page of events, and the total number of events
+    async fn get_events(&self, limit: u32, offset: u32) -> Result<(Vec<Event>, i64)>;
+    async fn get_markets_by_event(&self, event_id: &str) -> Result<Vec<Market>>;
+
+    // Webhooks
+
+    async fn insert_webhook(&self, url: &str, secret: Option<&str>) -> Result<Webhook>;
+    async fn get_webhooks(&self) -> Result<Vec<Webhook>>;
+    /// Remove a webhook; returns whether it existed
+    async fn delete_webhook(&self, id: i64) -> Result<bool>;
+    async fn get_webhook_dead_letters(&self, limit: u32) -> Result<Vec<WebhookDeadLetter>>;
+
+    // Notification outbox
+
+    /// Oldest events `sink` has neither acknowledged nor dead-lettered
+    async fn get_pending_outbox(&self, sink: &str, limit: u32) -> Result<Vec<OutboxEvent>>;
+    async fn record_outbox_delivery(
+        &self,
+        outbox_id: i64,
+        sink: &str,
+        delivered_at: DateTime<Utc>,
+    ) -> Result<()>;
+    /// Count a failed delivery; returns how many times delivering to `sink` has failed
+    async fn record_outbox_failure(&self, outbox_id: i64, sink: &str, error: &str) -> Result<u32>;
+    async fn dead_letter_outbox(&self, outbox_id: i64, sink: &str, error: &str) -> Result<()>;
+
+    // Alert rules
+
+    /// Create a rule; returns None if one with the same name exists
+    async fn insert_alert_rule(
+        &self,
+        name: &str,
+        condition: &AlertCondition,
+    ) -> Result<Option<AlertRule>>;
+    /// Create a rule or replace the condition of the one with the same name
+    async fn upsert_alert_rule(&self, name: &str, condition: &AlertCondition) -> Result<()>;
+    async fn get_alert_rules(&self) -> Result<Vec<AlertRule>>;
+    /// Remove a rule; returns whether it existed
+    async fn delete_alert_rule(&self, id: i64) -> Result<bool>;
+    async fn insert_alert_fired(
+        &self,
+        rule_id: i64,
+        market_id: &str,
+        message: &str,
+    ) -> Result<AlertFired>;
+    async fn get_alerts_fired(&self, limit: u32) -> Result<Vec<AlertFired>>;
+    async fn get_last_alert_fired_at(
+        &self,
+        rule_id: i64,
+        market_id: &str,
+    ) -> Result<Option<DateTime<Utc>>>;
+    /// Oldest recorded price of a market at or after `since`
+    async fn get_first_price_since(
+        &self,
+        market_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<f64>>;
+
+    // Watchlists
+
+    /// Create a watchlist; returns None if one with the same name exists
+    async fn create_watchlist(
+        &self,
+        name: &str,
+        market_ids: &[String],
+    ) -> Result<Option<Watchlist>>;
+    async fn get_watchlists(&self) -> Result<Vec<Watchlist>>;
+    async fn get_watchlist(&self, name: &str) -> Result<Option<Watchlist>>;
+    /// Replace a watchlist's markets; returns None if it doesn't exist
+    async fn set_watchlist_markets(
+        &self,
+        name: &str,
+        market_ids: &[String],
+    ) -> Result<Option<Watchlist>>;
+    /// Remove a watchlist; returns whether it existed
+    async fn delete_watchlist(&self, name: &str) -> Result<bool>;
+    /// Watchlisted IDs of markets from `source`, including IDs not stored yet
+    async fn get_watchlisted_market_ids(&self, source: &str) -> Result<Vec<String>>;
+    /// Highest-volume active markets of `source` with at least `min_volume`
+    async fn get_top_volume_market_ids(
+        &self,
+        source: &str,
+        min_volume: f64,
+        limit: u32,
+    ) -> Result<Vec<String>>;
+
+    // Volume spikes
+
+    /// Active markets with at least `multiplier` times their average volume per window in
+    /// the window since `window_start`, not yet flagged within it
+    async fn find_volume_spikes(
+        &self,
+        baseline_start: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+        detected_at: DateTime<Utc>,
+        multiplier: f64,
+        min_volume: f64,
+    ) -> Result<Vec<VolumeSpike>>;
+    async fn insert_volume_spike(&self, spike: &VolumeSpike) -> Result<()>;
+    /// Spikes detected since `since`, newest first
+    async fn get_volume_spikes(&self, since: DateTime<Utc>, limit: u32) -> Result<Vec<VolumeSpike>>;
+
+    // Daily reports
+
+    /// Markets whose price rose (`Desc`) or fell (`Asc`) the most in `[from, to)`
+    async fn get_price_changes_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        order: SortOrder,
+        limit: u32,
+    ) -> Result<Vec<MarketMover>>;
+    async fn get_volume_leaders_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<MarketVolume>>;
+    /// Highest-volume markets discovered in `[from, to)` and how many there were
+    async fn get_markets_discovered_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<(Vec<Market>, i64)>;
+    /// Highest-volume markets resolved in `[from, to)` and how many there were
+    async fn get_markets_resolved_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<(Vec<Market>, i64)>;
+    async fn save_daily_report(&self, report: &DailyReport) -> Result<()>;
+    async fn get_daily_report(&self, date: NaiveDate) -> Result<Option<DailyReport>>;
+
+    // Calibration
+
+    /// Resolved markets with a known winner not scored yet, oldest resolution first
+    async fn get_markets_pending_calibration(&self, limit: u32) -> Result<Vec<Market>>;
+    /// Latest recorded price of a market at or before `at`
+    async fn get_last_price_at(&self, market_id: &str, at: DateTime<Utc>) -> Result<Option<f64>>;
+    async fn save_market_calibration(&self, calibration: &MarketCalibration) -> Result<()>;
+    /// Brier scores and reliability buckets over every scored market
+    async fn get_calibration(&self) -> Result<CalibrationResponse>;
+
+    // On-chain verification
+
+    /// Polymarket markets with a condition ID not yet confirmed resolved on chain,
+    /// least recently checked first
+    async fn get_markets_pending_onchain(&self, limit: u32) -> Result<Vec<Market>>;
+    /// Add USDC volumes per CLOB token and move the scanned block under `checkpoint_key`
+    /// from `scanned_from` to `scanned_to`; false, with nothing written, if the checkpoint
+    /// no longer holds `scanned_from`
+    async fn add_onchain_volumes(
+        &self,
+        volumes: &[(String, f64)],
+        checkpoint_key: &str,
+        scanned_from: Option<u64>,
+        scanned_to: u64,
+    ) -> Result<bool>;
+    /// On-chain USDC volume summed over a market's outcome tokens
+    async fn get_onchain_volume(&self, market_id: &str) -> Result<Option<f64>>;
+    async fn save_onchain_verification(&self, verification: &OnchainVerification) -> Result<()>;
+    async fn get_onchain_verification(
+        &self,
+        market_id: &str,
+    ) -> Result<Option<OnchainVerification>>;
+
+    // Scraper checkpoints and health
+
+    async fn get_scraper_state(&self, key: &str) -> Result<Option<String>>;
+    async fn set_scraper_state(&self, key: &str, value: &str) -> Result<()>;
+    /// Take or extend lease `name` for `holder` until `now + ttl`; false while another
+    /// holder's lease is unexpired
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: DateTime<Utc>,
+        ttl: chrono::Duration,
+    ) -> Result<bool>;
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<()>;
+    async fn record_shard_progress(&self, progress: &ShardProgress) -> Result<()>;
+    /// Progress of every shard of the shard count reported last
+    async fn get_shard_progress(&self) -> Result<Vec<ShardProgress>>;
+    /// Cumulative counters saved by the last metrics snapshot
+    async fn get_metrics_snapshot(&self) -> Result<MetricsSnapshot>;
+    async fn save_metrics_snapshot(&self, snapshot: &MetricsSnapshot) -> Result<()>;
+    /// Fails when storage can't be reached
+    async fn ping(&self) -> Result<()>;
+}
+
+/// SQLite storage; reads go to the read-only pool and writes to the single-writer pool
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    pools: DbPools,
+}
+
+impl SqliteStorage {
+    pub fn new(pools: DbPools) -> Self {
+        Self { pools }
+    }
+
+    pub fn pools(&self) -> &DbPools {
+        &self.pools
+    }
+}
+
+// Every query gets a span named after its method, so traces show the time spent in SQLite
+#[async_trait]
+impl Storage for SqliteStorage {
+    #[instrument(skip_all)]
+    async fn upsert_market(&self, market: &Market) -> Result<bool> {
+        db::upsert_market(&self.pools.write, market).await
+    }
+
+    #[instrument(skip_all)]
+    async fn upsert_market_with_outbox(
+        &self,
+        market: &Market,
+        change: Option<&MarketUpdate>,
+    ) -> Result<bool> {
+        db::upsert_market_with_outbox(&self.pools.write, market, change).await
+    }
+
+    #[instrument(skip_all)]
+    async fn update_market_details(&self, market: &Market) -> Result<()> {
+        db::update_market_details(&self.pools.write, market).await
+    }
+
+    #[instrument(skip_all)]
+    async fn update_market_resolution(&self, market: &Market) -> Result<()> {
+        db::update_market_resolution(&self.pools.write, market).await
+    }
+
+    #[instrument(skip_all)]
+    async fn update_market_quote(&self, id: &str, quote: &ClobQuote) -> Result<()> {
+        db::update_market_quote(&self.pools.write, id, quote).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_market_by_id(&self, id: &str) -> Result<Option<Market>> {
+        db::get_market_by_id(&self.pools.read, id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn find_market(&self, key: &str) -> Result<Option<Market>> {
+        db::find_market(&self.pools.read, key).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets(
+        &self,
+        limit: u32,
+        offset: u32,
+        filter: &MarketFilter,
+    ) -> Result<(Vec<Market>, i64)> {
+        db::get_markets(&self.pools.read, limit, offset, filter).await
+    }
+
+    fn stream_markets(&self, limit: u32, offset: u32, filter: &MarketFilter) -> RowStream<Market> {
+        Box::pin(db::stream_markets(
+            self.pools.read.clone(),
+            limit,
+            offset,
+            filter,
+        ))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_by_ids(&self, ids: &[String]) -> Result<Vec<Market>> {
+        db::get_markets_by_ids(&self.pools.read, ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_by_condition_ids(&self, condition_ids: &[String]) -> Result<Vec<Market>> {
+        db::get_markets_by_condition_ids(&self.pools.read, condition_ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_since(&self, since: DateTime<Utc>) -> Result<Vec<Market>> {
+        db::get_markets_since(&self.pools.read, since).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_ending_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<Market>> {
+        db::get_markets_ending_between(&self.pools.read, from, to, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_pending_resolution(&self) -> Result<Vec<String>> {
+        db::get_markets_pending_resolution(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_top_movers(
+        &self,
+        since: DateTime<Utc>,
+        sort: MoverSort,
+        limit: u32,
+    ) -> Result<Vec<MarketMover>> {
+        db::get_top_movers(&self.pools.read, since, sort, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_price_history_since(
+        &self,
+        ids: &[String],
+        since: DateTime<Utc>,
+    ) -> Result<Vec<PriceHistoryRow>> {
+        db::get_price_history_since(&self.pools.read, ids, since).await
+    }
+
+    #[instrument(skip_all)]
+    async fn count_markets(&self) -> Result<i64> {
+        db::count_markets(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn count_markets_by_status(&self) -> Result<Vec<(String, i64)>> {
+        db::count_markets_by_status(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_market_stats(&self) -> Result<StatsResponse> {
+        db::get_market_stats(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn mark_markets_seen(&self, ids: &[String], seen_at: DateTime<Utc>) -> Result<()> {
+        db::mark_markets_seen(&self.pools.write, ids, seen_at).await
+    }
+
+    #[instrument(skip_all)]
+    async fn delist_unseen_markets(
+        &self,
+        source: &str,
+        seen_at: DateTime<Utc>,
+        max_missed: u32,
+    ) -> Result<u64> {
+        db::delist_unseen_markets(&self.pools.write, source, seen_at, max_missed).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_market_changes(
+        &self,
+        market_id: &str,
+        changes: &[FieldChange],
+        changed_at: DateTime<Utc>,
+    ) -> Result<()> {
+        db::insert_market_changes(&self.pools.write, market_id, changes, changed_at).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_market_changes(&self, market_id: &str, limit: u32) -> Result<Vec<MarketChange>> {
+        db::get_market_changes(&self.pools.read, market_id, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_raw_payload(
+        &self,
+        market_id: &str,
+        source: &str,
+        payload: &[u8],
+        payload_hash: &str,
+        scraped_at: DateTime<Utc>,
+    ) -> Result<()> {
+        db::insert_raw_payload(
+            &self.pools.write,
+            market_id,
+            source,
+            payload,
+            payload_hash,
+            scraped_at,
+        )
+        .await
+    }
+
+    #[instrument(skip_all)]
+    async fn upsert_outcomes(&self, market_id: &str, outcomes: &[Outcome]) -> Result<()> {
+        db::upsert_outcomes(&self.pools.write, market_id, outcomes).await
+    }
+
+    #[instrument(skip_all)]
+    async fn update_outcome_quote(&self, token_id: &str, quote: &ClobQuote) -> Result<()> {
+        db::update_outcome_quote(&self.pools.write, token_id, quote).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_outcomes(&self, market_id: &str) -> Result<Vec<Outcome>> {
+        db::get_outcomes(&self.pools.read, market_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn attach_outcomes(&self, markets: &mut [Market]) -> Result<()> {
+        db::attach_outcomes(&self.pools.read, markets).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_outcomes_by_market(
+        &self,
+        market_ids: &[String],
+    ) -> Result<HashMap<String, Vec<Outcome>>> {
+        db::get_outcomes_by_market(&self.pools.read, market_ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn set_market_tags(&self, market_id: &str, tags: &[Tag]) -> Result<()> {
+        db::set_market_tags(&self.pools.write, market_id, tags).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_market_tags(&self, market_id: &str) -> Result<Vec<Tag>> {
+        db::get_market_tags(&self.pools.read, market_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_tags_by_market(&self, market_ids: &[String]) -> Result<HashMap<String, Vec<Tag>>> {
+        db::get_tags_by_market(&self.pools.read, market_ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_tag_counts(&self) -> Result<Vec<TagCount>> {
+        db::get_tag_counts(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_price_point(
+        &self,
+        market_id: &str,
+        price: Option<Decimal>,
+        volume: Option<Decimal>,
+        scraped_at: DateTime<Utc>,
+    ) -> Result<()> {
+        db::insert_price_point(&self.pools.write, market_id, price, volume, scraped_at).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_price_history(&self, market_id: &str, limit: u32) -> Result<Vec<PricePoint>> {
+        db::get_price_history(&self.pools.read, market_id, limit).await
+    }
+
+    fn stream_price_history(&self, market_id: &str, limit: u32) -> RowStream<PricePoint> {
+        Box::pin(db::stream_price_history(
+            self.pools.read.clone(),
+            market_id,
+            limit,
+        ))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_candles(
+        &self,
+        market_id: &str,
+        interval: CandleInterval,
+        limit: u32,
+    ) -> Result<Vec<Candle>> {
+        db::get_candles(&self.pools.read, market_id, interval, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_trades(&self, trades: &[Trade]) -> Result<u64> {
+        db::insert_trades(&self.pools.write, trades).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_trades(
+        &self,
+        market_id: &str,
+        since: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<Trade>> {
+        db::get_trades(&self.pools.read, market_id, since, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_orderbook_snapshot(&self, snapshot: &OrderbookSnapshot) -> Result<()> {
+        db::insert_orderbook_snapshot(&self.pools.write, snapshot).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_latest_orderbook_snapshot(&self, market_id: &str) -> Result<Option<OrderbookSnapshot>> {
+        db::get_latest_orderbook_snapshot(&self.pools.read, market_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn upsert_comments(&self, comments: &[Comment]) -> Result<u64> {
+        db::upsert_comments(&self.pools.write, comments).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_comments(&self, event_id: &str, limit: u32) -> Result<Vec<Comment>> {
+        db::get_comments(&self.pools.read, event_id, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn count_comments_since(&self, event_id: Option<&str>, since: DateTime<Utc>) -> Result<i64> {
+        db::count_comments_since(&self.pools.read, event_id, since).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_positions(&self, positions: &[Position]) -> Result<u64> {
+        db::insert_positions(&self.pools.write, positions).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_holders(&self, market_id: &str, at: Option<DateTime<Utc>>) -> Result<Vec<Position>> {
+        db::get_holders(&self.pools.read, market_id, at).await
+    }
+
+    #[instrument(skip_all)]
+    async fn upsert_event(&self, event: &Event) -> Result<()> {
+        db::upsert_event(&self.pools.write, event).await
+    }
+
+    #[instrument(skip_all)]
+    async fn link_markets_to_event(&self, event_id: &str, market_ids: &[String]) -> Result<u64> {
+        db::link_markets_to_event(&self.pools.write, event_id, market_ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_event_by_id(&self, id: &str) -> Result<Option<Event>> {
+        db::get_event_by_id(&self.pools.read, id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_events(&self, limit: u32, offset: u32) -> Result<(Vec<Event>, i64)> {
+        db::get_events(&self.pools.read, limit, offset).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_by_event(&self, event_id: &str) -> Result<Vec<Market>> {
+        db::get_markets_by_event(&self.pools.read, event_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_webhook(&self, url: &str, secret: Option<&str>) -> Result<Webhook> {
+        db::insert_webhook(&self.pools.write, url, secret).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_webhooks(&self) -> Result<Vec<Webhook>> {
+        db::get_webhooks(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_webhook(&self, id: i64) -> Result<bool> {
+        db::delete_webhook(&self.pools.write, id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_webhook_dead_letters(&self, limit: u32) -> Result<Vec<WebhookDeadLetter>> {
+        db::get_webhook_dead_letters(&self.pools.read, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_pending_outbox(&self, sink: &str, limit: u32) -> Result<Vec<OutboxEvent>> {
+        db::get_pending_outbox(&self.pools.read, sink, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn record_outbox_delivery(
+        &self,
+        outbox_id: i64,
+        sink: &str,
+        delivered_at: DateTime<Utc>,
+    ) -> Result<()> {
+        db::record_outbox_delivery(&self.pools.write, outbox_id, sink, delivered_at).await
+    }
+
+    #[instrument(skip_all)]
+    async fn record_outbox_failure(&self, outbox_id: i64, sink: &str, error: &str) -> Result<u32> {
+        db::record_outbox_failure(&self.pools.write, outbox_id, sink, error).await
+    }
+
+    #[instrument(skip_all)]
+    async fn dead_letter_outbox(&self, outbox_id: i64, sink: &str, error: &str) -> Result<()> {
+        db::dead_letter_outbox(&self.pools.write, outbox_id, sink, error, Utc::now()).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_alert_rule(
+        &self,
+        name: &str,
+        condition: &AlertCondition,
+    ) -> Result<Option<AlertRule>> {
+        db::insert_alert_rule(&self.pools.write, name, condition).await
+    }
+
+    #[instrument(skip_all)]
+    async fn upsert_alert_rule(&self, name: &str, condition: &AlertCondition) -> Result<()> {
+        db::upsert_alert_rule(&self.pools.write, name, condition).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_alert_rules(&self) -> Result<Vec<AlertRule>> {
+        db::get_alert_rules(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_alert_rule(&self, id: i64) -> Result<bool> {
+        db::delete_alert_rule(&self.pools.write, id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_alert_fired(
+        &self,
+        rule_id: i64,
+        market_id: &str,
+        message: &str,
+    ) -> Result<AlertFired> {
+        db::insert_alert_fired(&self.pools.write, rule_id, market_id, message).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_alerts_fired(&self, limit: u32) -> Result<Vec<AlertFired>> {
+        db::get_alerts_fired(&self.pools.read, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_last_alert_fired_at(
+        &self,
+        rule_id: i64,
+        market_id: &str,
+    ) -> Result<Option<DateTime<Utc>>> {
+        db::get_last_alert_fired_at(&self.pools.read, rule_id, market_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_first_price_since(
+        &self,
+        market_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<f64>> {
+        db::get_first_price_since(&self.pools.read, market_id, since).await
+    }
+
+    #[instrument(skip_all)]
+    async fn create_watchlist(
+        &self,
+        name: &str,
+        market_ids: &[String],
+    ) -> Result<Option<Watchlist>> {
+        db::create_watchlist(&self.pools.write, name, market_ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_watchlists(&self) -> Result<Vec<Watchlist>> {
+        db::get_watchlists(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_watchlist(&self, name: &str) -> Result<Option<Watchlist>> {
+        db::get_watchlist(&self.pools.read, name).await
+    }
+
+    #[instrument(skip_all)]
+    async fn set_watchlist_markets(
+        &self,
+        name: &str,
+        market_ids: &[String],
+    ) -> Result<Option<Watchlist>> {
+        db::set_watchlist_markets(&self.pools.write, name, market_ids).await
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_watchlist(&self, name: &str) -> Result<bool> {
+        db::delete_watchlist(&self.pools.write, name).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_watchlisted_market_ids(&self, source: &str) -> Result<Vec<String>> {
+        db::get_watchlisted_market_ids(&self.pools.read, source).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_top_volume_market_ids(
+        &self,
+        source: &str,
+        min_volume: f64,
+        limit: u32,
+    ) -> Result<Vec<String>> {
+        db::get_top_volume_market_ids(&self.pools.read, source, min_volume, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn find_volume_spikes(
+        &self,
+        baseline_start: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+        detected_at: DateTime<Utc>,
+        multiplier: f64,
+        min_volume: f64,
+    ) -> Result<Vec<VolumeSpike>> {
+        db::find_volume_spikes(
+            &self.pools.read,
+            baseline_start,
+            window_start,
+            detected_at,
+            multiplier,
+            min_volume,
+        )
+        .await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_volume_spike(&self, spike: &VolumeSpike) -> Result<()> {
+        db::insert_volume_spike(&self.pools.write, spike).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_volume_spikes(&self, since: DateTime<Utc>, limit: u32) -> Result<Vec<VolumeSpike>> {
+        db::get_volume_spikes(&self.pools.read, since, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_price_changes_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        order: SortOrder,
+        limit: u32,
+    ) -> Result<Vec<MarketMover>> {
+        db::get_price_changes_between(&self.pools.read, from, to, order, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_volume_leaders_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<MarketVolume>> {
+        db::get_volume_leaders_between(&self.pools.read, from, to, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_discovered_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<(Vec<Market>, i64)> {
+        db::get_markets_discovered_between(&self.pools.read, from, to, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_resolved_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<(Vec<Market>, i64)> {
+        db::get_markets_resolved_between(&self.pools.read, from, to, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn save_daily_report(&self, report: &DailyReport) -> Result<()> {
+        db::save_daily_report(&self.pools.write, report).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_daily_report(&self, date: NaiveDate) -> Result<Option<DailyReport>> {
+        db::get_daily_report(&self.pools.read, date).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_pending_calibration(&self, limit: u32) -> Result<Vec<Market>> {
+        db::get_markets_pending_calibration(&self.pools.read, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_last_price_at(&self, market_id: &str, at: DateTime<Utc>) -> Result<Option<f64>> {
+        db::get_last_price_at(&self.pools.read, market_id, at).await
+    }
+
+    #[instrument(skip_all)]
+    async fn save_market_calibration(&self, calibration: &MarketCalibration) -> Result<()> {
+        db::save_market_calibration(&self.pools.write, calibration).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_calibration(&self) -> Result<CalibrationResponse> {
+        db::get_calibration(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_markets_pending_onchain(&self, limit: u32) -> Result<Vec<Market>> {
+        db::get_markets_pending_onchain(&self.pools.read, limit).await
+    }
+
+    #[instrument(skip_all)]
+    async fn add_onchain_volumes(
+        &self,
+        volumes: &[(String, f64)],
+        checkpoint_key: &str,
+        scanned_from: Option<u64>,
+        scanned_to: u64,
+    ) -> Result<bool> {
+        db::add_onchain_volumes(
+            &self.pools.write,
+            volumes,
+            checkpoint_key,
+            scanned_from,
+            scanned_to,
+        )
+        .await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_onchain_volume(&self, market_id: &str) -> Result<Option<f64>> {
+        db::get_onchain_volume(&self.pools.read, market_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn save_onchain_verification(&self, verification: &OnchainVerification) -> Result<()> {
+        db::save_onchain_verification(&self.pools.write, verification).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_onchain_verification(
+        &self,
+        market_id: &str,
+    ) -> Result<Option<OnchainVerification>> {
+        db::get_onchain_verification(&self.pools.read, market_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_scraper_state(&self, key: &str) -> Result<Option<String>> {
+        db::get_scraper_state(&self.pools.read, key).await
+    }
+
+    #[instrument(skip_all)]
+    async fn set_scraper_state(&self, key: &str, value: &str) -> Result<()> {
+        db::set_scraper_state(&self.pools.write, key, value).await
+    }
+
+    #[instrument(skip_all)]
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: &str,
+        now: DateTime<Utc>,
+        ttl: chrono::Duration,
+    ) -> Result<bool> {
+        db::try_acquire_lease(&self.pools.write, name, holder, now, ttl).await
+    }
+
+    #[instrument(skip_all)]
+    async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+        db::release_lease(&self.pools.write, name, holder).await
+    }
+
+    #[instrument(skip_all)]
+    async fn record_shard_progress(&self, progress: &ShardProgress) -> Result<()> {
+        db::record_shard_progress(&self.pools.write, progress).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_shard_progress(&self) -> Result<Vec<ShardProgress>> {
+        db::get_shard_progress(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_metrics_snapshot(&self) -> Result<MetricsSnapshot> {
+        db::get_metrics_snapshot(&self.pools.read).await
+    }
+
+    #[instrument(skip_all)]
+    async fn save_metrics_snapshot(&self, snapshot: &MetricsSnapshot) -> Result<()> {
+        db::save_metrics_snapshot(&self.pools.write, snapshot).await
+    }
+
+    #[instrument(skip_all)]
+    async fn ping(&self) -> Result<()> {
+        db::ping(&self.pools.read).await
+    }
+}
@@ -0,0 +1,726 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::error::{Error, Result, UpstreamContext};
+use crate::metrics::{Metrics, ScrapePhase};
+use crate::models::{Market, MarketStatus, Outcome, Position, Trade, TradeSide};
+use crate::source::{self, FetchContext, FetchedMarkets, MarketSource, PageSink};
+use crate::throttle::ThrottledClient;
+use crate::validation::RejectReason;
+
+/// Polymarket's public orderbook subgraph, hosted by Goldsky
+pub const DEFAULT_SUBGRAPH_URL: &str = "https://api.goldsky.com/api/public/project_cl6mb8i9h0003e201j6li0diw/subgraphs/orderbook-subgraph/0.0.1/gn";
+/// Polymarket's public positions subgraph, hosted by Goldsky
+pub const DEFAULT_POSITIONS_SUBGRAPH_URL: &str = "https://api.goldsky.com/api/public/project_cl6mb8i9h0003e201j6li0diw/subgraphs/positions-subgraph/0.0.7/gn";
+const SUBGRAPH_SOURCE: &str = "subgraph";
+/// Conditions per page; each has two or more tokens, which must fit in one 1000-row query
+const MAX_PAGE_SIZE: u32 = 400;
+/// Largest `first` argument a subgraph accepts
+const MAX_QUERY_ROWS: usize = 1000;
+/// Fills requested per page, newest first
+const FILLS_PER_PAGE: u32 = 100;
+/// Pages of fills walked per market and scrape, well within the 5000 rows `skip` reaches
+const MAX_FILL_PAGES: u32 = 10;
+/// Holders requested per outcome token and capture, largest first
+const HOLDERS_PER_TOKEN: u32 = 20;
+/// Collateral and outcome token amounts have 6 decimals on chain
+const TOKEN_DECIMALS: u32 = 6;
+/// Asset ID the exchange uses for USDC collateral in fills
+const COLLATERAL_ASSET_ID: &str = "0";
+/// Polymarket's CTF and neg-risk CTF exchanges; a fill "taken" by one of them is the taker
+/// order's side of a match, whose maker fills are reported separately
+const EXCHANGE_ADDRESSES: [&str; 2] = [
+    "0x4bfb41d5b3570defd03c39a9a4d8de6bd8b8982e",
+    "0xc5d563a36ae78145c45a50134d48a1215220f80a",
+];
+
+// Walked by ID rather than `skip`, which subgraphs cap at 5000
+const CONDITIONS_QUERY: &str = "query Conditions($first: Int!, $lastId: String!) {
+  conditions(first: $first, where: {id_gt: $lastId}, orderBy: id, orderDirection: asc) {
+    id questionId outcomeSlotCount resolutionTimestamp payoutNumerators payoutDenominator
+  }
+}";
+const TOKENS_QUERY: &str = "query Tokens($conditions: [String!]!) {
+  marketDatas(first: 1000, where: {condition_in: $conditions}) {
+    id condition { id } outcomeIndex priceOrderbook
+  }
+}";
+const ORDERBOOKS_QUERY: &str = "query Orderbooks($tokens: [String!]!) {
+  orderbooks(first: 1000, where: {id_in: $tokens}) { id scaledCollateralVolume }
+}";
+const FILLS_QUERY: &str = "query Fills(
+  $first: Int!, $skip: Int!, $tokens: [String!]!, $since: BigInt!
+) {
+  orderFilledEvents(first: $first, skip: $skip, orderBy: timestamp, orderDirection: desc,
+    where: {timestamp_gte: $since, or: [{makerAssetId_in: $tokens}, {takerAssetId_in: $tokens}]}) {
+    id transactionHash timestamp maker taker makerAssetId takerAssetId makerAmountFilled takerAmountFilled
+  }
+}";
+const BALANCES_QUERY: &str = "query Balances($first: Int!, $token: String!) {
+  userBalances(first: $first, orderBy: balance, orderDirection: desc,
+    where: {asset: $token, balance_gt: \"0\"}) {
+    user balance asset { id outcomeIndex }
+  }
+}";
+
+/// GraphQL response envelope; errors may come with a 200 status
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConditionsData {
+    conditions: Vec<SubgraphMarket>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokensData {
+    #[serde(rename = "marketDatas")]
+    market_datas: Vec<MarketData>,
+}
+
+/// Outcome token of a condition (`MarketData` entity)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MarketData {
+    id: String,
+    condition: EntityRef,
+    outcome_index: Option<String>,
+    price_orderbook: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntityRef {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderbooksData {
+    orderbooks: Vec<Orderbook>,
+}
+
+/// Trade totals of an outcome token (`Orderbook` entity)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Orderbook {
+    id: String,
+    scaled_collateral_volume: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FillsData {
+    #[serde(rename = "orderFilledEvents")]
+    order_filled_events: Vec<OrderFilled>,
+}
+
+/// A maker order filled on the exchange (`OrderFilledEvent` entity); amounts are in the
+/// smallest units of the asset each side gave
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderFilled {
+    /// Unique per fill within its transaction
+    id: String,
+    transaction_hash: String,
+    /// Epoch seconds
+    timestamp: String,
+    maker: String,
+    taker: String,
+    maker_asset_id: String,
+    taker_asset_id: String,
+    maker_amount_filled: String,
+    taker_amount_filled: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BalancesData {
+    #[serde(rename = "userBalances")]
+    user_balances: Vec<UserBalance>,
+}
+
+/// Outcome tokens a wallet holds (`UserBalance` entity of the positions subgraph)
+#[derive(Debug, Deserialize)]
+struct UserBalance {
+    user: String,
+    balance: String,
+    asset: BalanceAsset,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BalanceAsset {
+    id: String,
+    outcome_index: Option<String>,
+}
+
+/// A condition with its outcome tokens, as assembled from the subgraph's `Condition`,
+/// `MarketData` and `Orderbook` entities; numbers are strings like the subgraph's
+/// `BigInt` and `BigDecimal` values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubgraphMarket {
+    /// Condition ID; stored under the ID of the Gamma market with this condition ID
+    pub id: String,
+    pub question_id: Option<String>,
+    pub outcome_slot_count: Option<u32>,
+    /// Epoch seconds, once resolved
+    pub resolution_timestamp: Option<String>,
+    #[serde(default)]
+    pub payout_numerators: Vec<String>,
+    pub payout_denominator: Option<String>,
+    #[serde(default)]
+    pub tokens: Vec<SubgraphToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubgraphToken {
+    /// CLOB token ID
+    pub id: String,
+    pub outcome_index: Option<u32>,
+    pub price: Option<String>,
+    /// USDC traded on the CLOB
+    pub volume: Option<String>,
+}
+
+/// Whether a market ID is a condition ID, as used by markets from the subgraph
+/// Those have no Gamma record under that ID
+pub fn is_condition_id(id: &str) -> bool {
+    id.len() == 66 && id.starts_with("0x")
+}
+
+/// Names of a condition's outcomes by index; binary conditions are `Yes`/`No` markets
+fn outcome_name(index: u32, slots: u32) -> String {
+    match (slots, index) {
+        (2, 0) => "Yes".to_string(),
+        (2, 1) => "No".to_string(),
+        _ => format!("Outcome {}", index + 1),
+    }
+}
+
+/// An on-chain amount in whole tokens
+fn token_amount(raw: &str) -> Option<Decimal> {
+    Decimal::from_str(raw)
+        .ok()
+        .map(|amount| amount / Decimal::from(10u64.pow(TOKEN_DECIMALS)))
+}
+
+fn parse_decimal(value: Option<&str>) -> Option<Decimal> {
+    value.and_then(|v| {
+        Decimal::from_str(v)
+            .or_else(|_| Decimal::from_scientific(v))
+            .ok()
+    })
+}
+
+impl TryFrom<SubgraphMarket> for Market {
+    type Error = RejectReason;
+
+    fn try_from(sm: SubgraphMarket) -> Result<Self, RejectReason> {
+        if sm.id.trim().is_empty() {
+            return Err(RejectReason::MissingId);
+        }
+        let mut tokens = sm.tokens;
+        tokens.sort_by_key(|token| token.outcome_index.unwrap_or(u32::MAX));
+        let slots = sm.outcome_slot_count.unwrap_or(tokens.len() as u32);
+
+        let outcomes: Vec<Outcome> = tokens
+            .iter()
+            .enumerate()
+            .map(|(position, token)| Outcome {
+                name: outcome_name(token.outcome_index.unwrap_or(position as u32), slots),
+                token_id: Some(token.id.clone()),
+                price: parse_decimal(token.price.as_deref()),
+                ..Default::default()
+            })
+            .collect();
+        let volumes: Vec<Decimal> = tokens
+            .iter()
+            .filter_map(|token| parse_decimal(token.volume.as_deref()))
+            .collect();
+
+        // A condition with a payout denominator has reported; the winner is paid in full
+        let denominator = sm.payout_denominator.as_deref().unwrap_or("0");
+        let resolved = !denominator.is_empty() && denominator != "0";
+        let (status, winning_outcome, resolved_at) = if resolved {
+            let winner = sm
+                .payout_numerators
+                .iter()
+                .position(|numerator| numerator == denominator)
+                .map(|index| {
+                    outcomes
+                        .get(index)
+                        .map_or_else(|| outcome_name(index as u32, slots), |o| o.name.clone())
+                });
+            let resolved_at = sm
+                .resolution_timestamp
+                .as_deref()
+                .map(|ts| {
+                    ts.parse::<i64>()
+                        .ok()
+                        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                        .ok_or(RejectReason::UnparsableDate)
+                })
+                .transpose()?;
+            (MarketStatus::Resolved, winner, resolved_at)
+        } else {
+            (MarketStatus::Active, None, None)
+        };
+
+        Ok(Market {
+            id: sm.id.clone(),
+            condition_id: Some(sm.id),
+            question_id: sm.question_id,
+            title: "Untitled Market".to_string(),
+            current_price: tokens
+                .first()
+                .and_then(|token| parse_decimal(token.price.as_deref())),
+            volume: (!volumes.is_empty()).then(|| volumes.iter().sum()),
+            clob_token_id: tokens.first().map(|token| token.id.clone()),
+            status: Some(status),
+            winning_outcome,
+            resolved_at,
+            outcomes,
+            ..Default::default()
+        })
+    }
+}
+
+/// Run a GraphQL query as a GET request, so it can be retried and recorded like other
+/// upstream requests
+async fn query<T: DeserializeOwned>(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    url: &str,
+    document: &str,
+    variables: serde_json::Value,
+) -> Result<T> {
+    let fetch_started = Instant::now();
+    let variables = variables.to_string();
+    let request = client
+        .get(url)
+        .query(&[("query", document), ("variables", variables.as_str())])
+        .header("Accept", "application/json");
+    let body = client
+        .send(request)
+        .await
+        .http_context("Failed to query subgraph")?
+        .error_for_status()
+        .http_context("Subgraph returned an error status")?
+        .bytes()
+        .await
+        .http_context("Failed to read subgraph response")?;
+    metrics.record_phase_duration(ScrapePhase::Fetch, fetch_started.elapsed());
+
+    let parse_started = Instant::now();
+    let response: GraphQlResponse<T> =
+        serde_json::from_slice(&body).schema_context("Failed to parse subgraph response")?;
+    metrics.record_phase_duration(ScrapePhase::Parse, parse_started.elapsed());
+
+    if let Some(error) = response.errors.first() {
+        return Err(Error::UpstreamSchema(anyhow::anyhow!(
+            "Subgraph query failed: {}",
+            error.message
+        )));
+    }
+    response
+        .data
+        .ok_or_else(|| Error::UpstreamSchema(anyhow::anyhow!("Subgraph response has no data")))
+}
+
+/// Fetch a page of conditions with IDs after `last_id`, with their outcome tokens and volumes
+async fn fetch_page(
+    ctx: &FetchContext<'_>,
+    page_size: u32,
+    last_id: &str,
+) -> Result<Vec<SubgraphMarket>> {
+    let ConditionsData { mut conditions } = query(
+        ctx.client,
+        ctx.metrics,
+        ctx.subgraph_url,
+        CONDITIONS_QUERY,
+        serde_json::json!({ "first": page_size, "lastId": last_id }),
+    )
+    .await?;
+    if conditions.is_empty() {
+        return Ok(conditions);
+    }
+
+    let condition_ids: Vec<&str> = conditions.iter().map(|c| c.id.as_str()).collect();
+    let TokensData { market_datas } = query(
+        ctx.client,
+        ctx.metrics,
+        ctx.subgraph_url,
+        TOKENS_QUERY,
+        serde_json::json!({ "conditions": condition_ids }),
+    )
+    .await?;
+    if market_datas.len() >= MAX_QUERY_ROWS {
+        warn!("Subgraph token query hit its row limit, some outcomes may be missing");
+    }
+
+    let token_ids: Vec<&str> = market_datas.iter().map(|t| t.id.as_str()).collect();
+    let volumes: HashMap<String, Option<String>> = if token_ids.is_empty() {
+        HashMap::new()
+    } else {
+        let OrderbooksData { orderbooks } = query(
+            ctx.client,
+            ctx.metrics,
+            ctx.subgraph_url,
+            ORDERBOOKS_QUERY,
+            serde_json::json!({ "tokens": token_ids }),
+        )
+        .await?;
+        orderbooks
+            .into_iter()
+            .map(|book| (book.id, book.scaled_collateral_volume))
+            .collect()
+    };
+
+    let mut tokens: HashMap<String, Vec<SubgraphToken>> = HashMap::new();
+    for data in market_datas {
+        let volume = volumes.get(&data.id).cloned().flatten();
+        tokens
+            .entry(data.condition.id)
+            .or_default()
+            .push(SubgraphToken {
+                outcome_index: data.outcome_index.and_then(|i| i.parse().ok()),
+                price: data.price_orderbook,
+                volume,
+                id: data.id,
+            });
+    }
+    for condition in &mut conditions {
+        condition.tokens = tokens.remove(&condition.id).unwrap_or_default();
+    }
+    Ok(conditions)
+}
+
+/// Convert an exchange fill into the trade of the outcome token it moved, from the taker's
+/// side like the data API's; None for the taker order's own summary of a match and for
+/// fills that can't be read
+fn parse_fill(market_id: &str, fill: OrderFilled) -> Option<Trade> {
+    if EXCHANGE_ADDRESSES.contains(&fill.taker.to_ascii_lowercase().as_str()) {
+        return None;
+    }
+    let maker_amount = token_amount(&fill.maker_amount_filled)?;
+    let taker_amount = token_amount(&fill.taker_amount_filled)?;
+    // The maker paying collateral bought the token from the taker, who sold it
+    let (side, token_id, size, cost) = if fill.maker_asset_id == COLLATERAL_ASSET_ID {
+        (
+            TradeSide::Sell,
+            fill.taker_asset_id,
+            taker_amount,
+            maker_amount,
+        )
+    } else {
+        (
+            TradeSide::Buy,
+            fill.maker_asset_id,
+            maker_amount,
+            taker_amount,
+        )
+    };
+    if size.is_zero() {
+        return None;
+    }
+    Some(Trade {
+        id: fill.id,
+        market_id: market_id.to_string(),
+        token_id: Some(token_id),
+        outcome: None,
+        side,
+        size,
+        price: cost / size,
+        traded_at: DateTime::from_timestamp(fill.timestamp.parse().ok()?, 0)?,
+        maker: Some(fill.maker),
+        taker: Some(fill.taker),
+        transaction_hash: Some(fill.transaction_hash),
+    })
+}
+
+/// Fetch the exchange fills of a market's outcome tokens since `since` from the orderbook
+/// subgraph; without `since` only the latest page of them
+pub async fn fetch_trades(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    url: &str,
+    market_id: &str,
+    token_ids: &[String],
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<Trade>> {
+    let mut trades = Vec::new();
+    for page in 0..MAX_FILL_PAGES {
+        let FillsData {
+            order_filled_events,
+        } = query(
+            client,
+            metrics,
+            url,
+            FILLS_QUERY,
+            serde_json::json!({
+                "first": FILLS_PER_PAGE,
+                "skip": page * FILLS_PER_PAGE,
+                "tokens": token_ids,
+                "since": since.map_or(0, |since| since.timestamp()).to_string(),
+            }),
+        )
+        .await?;
+        let is_last_page = (order_filled_events.len() as u32) < FILLS_PER_PAGE;
+        trades.extend(
+            order_filled_events
+                .into_iter()
+                .filter_map(|fill| parse_fill(market_id, fill)),
+        );
+        if is_last_page || since.is_none() {
+            break;
+        }
+    }
+    Ok(trades)
+}
+
+/// Convert the largest balances of an outcome token into positions captured at `captured_at`
+fn parse_balances(
+    market_id: &str,
+    balances: Vec<UserBalance>,
+    captured_at: DateTime<Utc>,
+) -> Vec<Position> {
+    balances
+        .into_iter()
+        .filter_map(|balance| {
+            Some(Position {
+                market_id: market_id.to_string(),
+                token_id: balance.asset.id,
+                outcome_index: balance.asset.outcome_index.and_then(|i| i.parse().ok()),
+                wallet: balance.user,
+                name: None,
+                amount: token_amount(&balance.balance)?.to_f64()?,
+                captured_at,
+            })
+        })
+        .collect()
+}
+
+/// Fetch the top holders of each of a market's outcome tokens from the positions subgraph
+pub async fn fetch_holders(
+    client: &ThrottledClient,
+    metrics: &Metrics,
+    url: &str,
+    market_id: &str,
+    token_ids: &[String],
+) -> Result<Vec<Position>> {
+    let captured_at = Utc::now();
+    let mut positions = Vec::new();
+    for token_id in token_ids {
+        let BalancesData { user_balances } = query(
+            client,
+            metrics,
+            url,
+            BALANCES_QUERY,
+            serde_json::json!({ "first": HOLDERS_PER_TOKEN, "token": token_id }),
+        )
+        .await?;
+        positions.extend(parse_balances(market_id, user_balances, captured_at));
+    }
+    Ok(positions)
+}
+
+/// Polymarket's subgraph as a market source, indexed from the chain rather than served by
+/// the web frontend
+/// Its markets carry a condition ID but no title or description, which the subgraph doesn't
+/// index, so the scraper only uses them to update the markets stored with that condition ID;
+/// the listing walks every condition ever prepared, resuming across scrapes like the Gamma one
+pub struct SubgraphSource;
+
+#[async_trait]
+impl MarketSource for SubgraphSource {
+    fn name(&self) -> &'static str {
+        SUBGRAPH_SOURCE
+    }
+
+    fn venue(&self) -> &'static str {
+        source::POLYMARKET
+    }
+
+    async fn fetch_markets(
+        &self,
+        ctx: &FetchContext<'_>,
+        pages: &PageSink,
+    ) -> Result<FetchedMarkets> {
+        let page_size = ctx.page_size.min(MAX_PAGE_SIZE);
+        let mut fetched = 0;
+        let mut last_id = ctx.resume_cursor.unwrap_or_default().to_string();
+        if !last_id.is_empty() {
+            info!("Resuming subgraph listing after condition {}", last_id);
+        }
+
+        for _ in 0..ctx.max_pages {
+            let batch = fetch_page(ctx, page_size, &last_id).await?;
+            let is_last_page = (batch.len() as u32) < page_size;
+            if let Some(last) = batch.last() {
+                last_id = last.id.clone();
+            }
+            fetched += batch.len();
+            let raw = batch
+                .into_iter()
+                .map(|market| {
+                    serde_json::to_value(market).schema_context("Failed to encode subgraph market")
+                })
+                .collect::<Result<_>>()?;
+            pages
+                .send(FetchedMarkets {
+                    raw,
+                    ..Default::default()
+                })
+                .await;
+            if is_last_page {
+                info!("Fetched {} markets from the subgraph", fetched);
+                // Markets keyed by Gamma IDs are never listed here, so none are delisted
+                return Ok(FetchedMarkets::default());
+            }
+        }
+
+        info!(
+            "Fetched {} markets from the subgraph, next scrape resumes after condition {}",
+            fetched, last_id
+        );
+        Ok(FetchedMarkets {
+            resume_cursor: Some(last_id),
+            ..Default::default()
+        })
+    }
+
+    fn parse_market(&self, raw: &serde_json::Value) -> Result<Market> {
+        let market =
+            SubgraphMarket::deserialize(raw).schema_context("Failed to parse subgraph market")?;
+        Ok(Market::try_from(market)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Rejection;
+    use rust_decimal_macros::dec;
+
+    const CONDITION_ID: &str = "0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1";
+
+    #[test]
+    fn test_parse_subgraph_market() {
+        let raw = serde_json::json!({
+            "id": CONDITION_ID,
+            "questionId": "0xabc",
+            "outcomeSlotCount": 2,
+            "payoutNumerators": [],
+            "payoutDenominator": "0",
+            "tokens": [
+                {"id": "222", "outcomeIndex": 1, "price": "0.35", "volume": "500.5"},
+                {"id": "111", "outcomeIndex": 0, "price": "0.65", "volume": "1000"}
+            ]
+        });
+
+        let market = SubgraphSource.parse_market(&raw).unwrap();
+        assert!(is_condition_id(&market.id));
+        assert_eq!(market.condition_id.as_deref(), Some(CONDITION_ID));
+        assert_eq!(market.status, Some(MarketStatus::Active));
+        assert_eq!(market.current_price, Some(dec!(0.65)));
+        assert_eq!(market.volume, Some(dec!(1500.5)));
+        assert_eq!(market.clob_token_id.as_deref(), Some("111"));
+        assert_eq!(market.outcomes[0].name, "Yes");
+        assert_eq!(market.outcomes[1].token_id.as_deref(), Some("222"));
+    }
+
+    #[test]
+    fn test_parse_resolved_subgraph_market() {
+        let raw = serde_json::json!({
+            "id": CONDITION_ID,
+            "outcomeSlotCount": 2,
+            "resolutionTimestamp": "1700000000",
+            "payoutNumerators": ["0", "1"],
+            "payoutDenominator": "1"
+        });
+
+        let market = SubgraphSource.parse_market(&raw).unwrap();
+        assert_eq!(market.status, Some(MarketStatus::Resolved));
+        assert_eq!(market.winning_outcome.as_deref(), Some("No"));
+        assert_eq!(market.resolved_at.unwrap().timestamp(), 1_700_000_000);
+
+        let missing_id = serde_json::json!({ "id": "" });
+        let error = SubgraphSource.parse_market(&missing_id).unwrap_err();
+        assert_eq!(
+            Rejection::from_parse_error(None, &error).reason,
+            RejectReason::MissingId
+        );
+        assert!(!is_condition_id("12345"));
+    }
+
+    #[test]
+    fn test_parse_fills() {
+        let fill = |taker: &str, maker_asset_id: &str, maker_amount: &str, taker_amount: &str| {
+            let taker_asset_id = if maker_asset_id == "0" { "111" } else { "0" };
+            serde_json::from_value::<OrderFilled>(serde_json::json!({
+                "id": "0xfeed_0x01",
+                "transactionHash": "0xfeed",
+                "timestamp": "1700000000",
+                "maker": "0xmaker",
+                "taker": taker,
+                "makerAssetId": maker_asset_id,
+                "takerAssetId": taker_asset_id,
+                "makerAmountFilled": maker_amount,
+                "takerAmountFilled": taker_amount
+            }))
+            .unwrap()
+        };
+
+        // The maker sells 10 tokens for 6 USDC, so the taker bought at 0.6
+        let trade = parse_fill("101", fill("0xtaker", "111", "10000000", "6000000")).unwrap();
+        assert_eq!(trade.side, TradeSide::Buy);
+        assert_eq!(trade.token_id.as_deref(), Some("111"));
+        assert_eq!(trade.size, dec!(10));
+        assert_eq!(trade.price, dec!(0.6));
+        assert_eq!(trade.traded_at.timestamp(), 1_700_000_000);
+
+        // The maker pays 4 USDC for 10 tokens, so the taker sold at 0.4
+        let trade = parse_fill("101", fill("0xtaker", "0", "4000000", "10000000")).unwrap();
+        assert_eq!(trade.side, TradeSide::Sell);
+        assert_eq!(trade.price, dec!(0.4));
+
+        // The exchange taking the maker's side summarises the taker order's fills
+        let summary = fill(EXCHANGE_ADDRESSES[0], "111", "10000000", "6000000");
+        assert!(parse_fill("101", summary).is_none());
+        assert!(parse_fill("101", fill("0xtaker", "111", "0", "0")).is_none());
+    }
+
+    #[test]
+    fn test_parse_balances() {
+        let balances: BalancesData = serde_json::from_value(serde_json::json!({
+            "userBalances": [
+                {"user": "0xabc", "balance": "2500000", "asset": {"id": "111", "outcomeIndex": "0"}},
+                {"user": "0xdef", "balance": "not a number", "asset": {"id": "111"}}
+            ]
+        }))
+        .unwrap();
+
+        let captured_at = Utc::now();
+        let positions = parse_balances("101", balances.user_balances, captured_at);
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].wallet, "0xabc");
+        assert_eq!(positions[0].outcome_index, Some(0));
+        assert_eq!(positions[0].amount, 2.5);
+        assert_eq!(positions[0].captured_at, captured_at);
+    }
+}
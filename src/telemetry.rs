@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::io;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::cli::{LogFormat, TelemetryArgs};
+
+const DEFAULT_FILTER: &str = "polymarket_scraper=info";
+#[cfg(feature = "otel")]
+pub const DEFAULT_SERVICE_NAME: &str = "polymarket-scraper";
+
+/// Keeps trace export running; dropping it flushes spans not yet exported
+#[derive(Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    exporting: bool,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if self.exporting {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Install the global tracing subscriber
+/// Logs go to stderr so `export` and `stats` output stays clean; with the `otel` feature
+/// and an OTLP endpoint configured, spans are also exported
+/// JSON logs flatten event fields to the top level and carry the fields of enclosing
+/// spans, so `scrape_id` and `market_id` can be filtered on without parsing messages
+pub fn init_tracing(args: &TelemetryArgs) -> Result<TelemetryGuard> {
+    let json = args.log_format == LogFormat::Json;
+    let registry = tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into()))
+        .with((!json).then(|| fmt::layer().with_writer(io::stderr)))
+        .with(json.then(|| {
+            fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_current_span(false)
+                .with_span_list(true)
+                .with_writer(io::stderr)
+        }));
+
+    #[cfg(feature = "otel")]
+    if let Some(endpoint) = &args.otlp_endpoint {
+        let tracer = otlp_tracer(endpoint, &args.otel_service_name)?;
+        registry
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .init();
+        tracing::info!("Exporting spans to {}", endpoint);
+        return Ok(TelemetryGuard { exporting: true });
+    }
+
+    registry.init();
+    Ok(TelemetryGuard::default())
+}
+
+/// Tracer batching spans to an OTLP collector over gRPC
+#[cfg(feature = "otel")]
+fn otlp_tracer(endpoint: &str, service_name: &str) -> Result<opentelemetry_sdk::trace::Tracer> {
+    use anyhow::Context;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+
+    let resource = Resource::new(vec![KeyValue::new(
+        "service.name",
+        service_name.to_string(),
+    )]);
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(trace::config().with_resource(resource))
+        .install_batch(runtime::Tokio)
+        .context("Failed to install the OTLP trace exporter")
+}
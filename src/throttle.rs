@@ -0,0 +1,413 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER, USER_AGENT};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tracing::{instrument, warn, Span};
+
+use crate::conditional::{Conditional, ListingCache, ListingPage};
+use crate::fixtures::Fixtures;
+use crate::metrics;
+
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 5.0;
+pub const DEFAULT_BURST: u32 = 10;
+const MAX_THROTTLED_RETRIES: u32 = 3; // Retries of a request answered with 429/503
+const MAX_PAUSE_SECS: u64 = 300; // Upper bound on honoured `Retry-After` values
+const MIN_RATE_DIVISOR: f64 = 32.0; // Throttling never slows below this fraction of the configured rate
+const RECOVERY_STEP: f64 = 0.05; // Fraction of the configured rate regained per unthrottled response
+
+/// Outbound request budget shared by every upstream API call
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleConfig {
+    /// Sustained requests per second when upstream isn't throttling
+    pub requests_per_second: f64,
+    /// Requests that may be sent back to back after an idle period
+    pub burst: u32,
+}
+
+/// Token bucket whose refill rate halves whenever upstream throttles us and recovers
+/// gradually afterwards
+struct Bucket {
+    tokens: f64,
+    /// Current refill rate in tokens per second
+    rate: f64,
+    last_refill: Instant,
+    /// No request is sent before this instant, set from `Retry-After`
+    paused_until: Option<Instant>,
+}
+
+/// Adaptive rate limiter for requests to upstream APIs
+pub struct Throttle {
+    config: ThrottleConfig,
+    bucket: Mutex<Bucket>,
+}
+
+impl Throttle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            bucket: Mutex::new(Bucket {
+                tokens: config.burst as f64,
+                rate: config.requests_per_second,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// Take a token, or return how long until one is available
+    fn check(&self, now: Instant) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(until) = bucket.paused_until {
+            if until > now {
+                return Err(until - now);
+            }
+            // Nothing refills while paused
+            bucket.last_refill = bucket.last_refill.max(until);
+            bucket.paused_until = None;
+        }
+
+        let elapsed = now
+            .saturating_duration_since(bucket.last_refill)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(self.config.burst as f64);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate))
+        }
+    }
+
+    /// Wait until a request may be sent
+    async fn acquire(&self) {
+        while let Err(wait) = self.check(Instant::now()) {
+            sleep(wait).await;
+        }
+    }
+
+    /// Halve the rate, drain the bucket and pause for `retry_after` (or one token at the
+    /// new rate); returns the pause
+    fn throttled(&self, retry_after: Option<Duration>, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.rate = (bucket.rate / 2.0).max(self.config.requests_per_second / MIN_RATE_DIVISOR);
+        bucket.tokens = 0.0;
+        bucket.last_refill = now;
+
+        let pause = retry_after
+            .unwrap_or_else(|| Duration::from_secs_f64(1.0 / bucket.rate))
+            .min(Duration::from_secs(MAX_PAUSE_SECS));
+        let until = now + pause;
+        bucket.paused_until = Some(
+            bucket
+                .paused_until
+                .map_or(until, |paused| paused.max(until)),
+        );
+        ::metrics::gauge!(metrics::UPSTREAM_REQUEST_RATE).set(bucket.rate);
+        pause
+    }
+
+    /// Move the rate back towards the configured one after an unthrottled response
+    fn recovered(&self) {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        if bucket.rate < self.config.requests_per_second {
+            bucket.rate = (bucket.rate + self.config.requests_per_second * RECOVERY_STEP)
+                .min(self.config.requests_per_second);
+            ::metrics::gauge!(metrics::UPSTREAM_REQUEST_RATE).set(bucket.rate);
+        }
+    }
+}
+
+/// Whether a status asks us to slow down
+pub(crate) fn is_throttling(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+/// Parse `Retry-After` as delay seconds or an HTTP date
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    Some((at - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// HTTP client for upstream APIs; every request waits for the shared `Throttle`, and
+/// requests answered with 429/503 are retried after the server's `Retry-After`
+/// With several clients (one per proxy) or User-Agents, requests rotate through them
+/// round-robin
+/// With fixtures, responses are also saved to disk, or served from it instead of the network
+#[derive(Clone)]
+pub struct ThrottledClient {
+    clients: Arc<[Client]>,
+    user_agents: Arc<[HeaderValue]>,
+    next_request: Arc<AtomicUsize>,
+    throttle: Arc<Throttle>,
+    listings: Arc<ListingCache>,
+    fixtures: Option<Arc<Fixtures>>,
+    upstream_base: Option<Url>,
+}
+
+impl ThrottledClient {
+    /// `clients` must not be empty
+    pub fn new(clients: Vec<Client>, config: ThrottleConfig) -> Self {
+        assert!(
+            !clients.is_empty(),
+            "ThrottledClient needs at least one client"
+        );
+        ::metrics::gauge!(metrics::UPSTREAM_REQUEST_RATE).set(config.requests_per_second);
+        Self {
+            clients: clients.into(),
+            user_agents: Arc::new([]),
+            next_request: Arc::new(AtomicUsize::new(0)),
+            throttle: Arc::new(Throttle::new(config)),
+            listings: Arc::new(ListingCache::default()),
+            fixtures: None,
+            upstream_base: None,
+        }
+    }
+
+    /// Rotate requests through these User-Agents, overriding any default one
+    pub fn with_user_agents(mut self, user_agents: Vec<HeaderValue>) -> Self {
+        self.user_agents = user_agents.into();
+        self
+    }
+
+    /// Record responses to, or replay them from, a fixture directory
+    pub fn with_fixtures(mut self, fixtures: Fixtures) -> Self {
+        self.fixtures = Some(Arc::new(fixtures));
+        self
+    }
+
+    /// Send every request to this base URL instead, with the original host as the first path
+    /// segment (`https://clob.polymarket.com/book` becomes `{base}/clob.polymarket.com/book`)
+    pub fn with_upstream_base(mut self, base: Url) -> Self {
+        self.upstream_base = Some(base);
+        self
+    }
+
+    /// Start a GET request on the next client and User-Agent in the rotation
+    pub fn get(&self, url: impl AsRef<str>) -> RequestBuilder {
+        let n = self.next_request.fetch_add(1, Ordering::Relaxed);
+        let request = self.clients[n % self.clients.len()].get(self.upstream_url(url.as_ref()));
+        match self.user_agents.len() {
+            0 => request,
+            len => request.header(USER_AGENT, self.user_agents[n % len].clone()),
+        }
+    }
+
+    fn upstream_url(&self, url: &str) -> String {
+        let (Some(base), Ok(parsed)) = (&self.upstream_base, Url::parse(url)) else {
+            return url.to_string();
+        };
+        let query = parsed.query().map(|q| format!("?{}", q));
+        format!(
+            "{}/{}{}{}",
+            base.as_str().trim_end_matches('/'),
+            parsed.host_str().unwrap_or_default(),
+            parsed.path(),
+            query.unwrap_or_default()
+        )
+    }
+
+    /// Send a request built with `get` once the rate limit allows it
+    /// After `MAX_THROTTLED_RETRIES` throttled attempts the last response is returned as is
+    /// When replaying fixtures the saved response is returned without waiting or sending
+    #[instrument(name = "upstream_request", skip_all, fields(url, status))]
+    pub async fn send(&self, request: RequestBuilder) -> anyhow::Result<Response> {
+        let url = request
+            .try_clone()
+            .context("Upstream request can't be cloned")?
+            .build()?
+            .url()
+            .clone();
+        let span = Span::current();
+        span.record("url", url.as_str());
+
+        let response = match &self.fixtures {
+            Some(fixtures) if fixtures.is_replay() => fixtures.replay(&url)?,
+            Some(fixtures) => {
+                let response = self.send_upstream(request).await?;
+                if is_throttling(response.status()) {
+                    response
+                } else {
+                    fixtures.record(&url, response).await?
+                }
+            }
+            None => self.send_upstream(request).await?,
+        };
+        span.record("status", response.status().as_u16());
+        Ok(response)
+    }
+
+    async fn send_upstream(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            // Upstream requests are bodiless GETs, so they can always be cloned for a retry
+            let retry = request.try_clone();
+            self.throttle.acquire().await;
+            let response = request.send().await?;
+
+            let status = response.status();
+            if !is_throttling(status) {
+                self.throttle.recovered();
+                return Ok(response);
+            }
+
+            ::metrics::counter!(metrics::UPSTREAM_THROTTLED_TOTAL, "status" => status.as_str().to_string())
+                .increment(1);
+            let pause = self
+                .throttle
+                .throttled(retry_after(response.headers(), Utc::now()), Instant::now());
+            attempt += 1;
+            match retry {
+                Some(next) if attempt <= MAX_THROTTLED_RETRIES => {
+                    warn!(
+                        "{} throttled with {}, retrying in {:.1}s",
+                        response.url(),
+                        status,
+                        pause.as_secs_f64()
+                    );
+                    request = next;
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    /// Send a request with the validators remembered for its URL, if any
+    /// A 304 answer returns the market IDs listed by the remembered response
+    pub async fn send_conditional(&self, request: RequestBuilder) -> anyhow::Result<Conditional> {
+        let url = match request.try_clone().map(RequestBuilder::build) {
+            Some(built) => built?.url().to_string(),
+            None => String::new(),
+        };
+        let cached = self.listings.get(&url);
+        let request = match &cached {
+            Some((validators, _)) => validators.apply(request),
+            None => request,
+        };
+
+        let response = self.send(request).await?;
+        match cached {
+            Some((_, ids)) if response.status() == StatusCode::NOT_MODIFIED => {
+                ::metrics::counter!(metrics::UPSTREAM_NOT_MODIFIED_TOTAL).increment(1);
+                Ok(Conditional::NotModified { ids })
+            }
+            _ => Ok(Conditional::Modified { response, url }),
+        }
+    }
+
+    /// Requests per second upstream currently allows us, below the configured rate while
+    /// recovering from throttling
+    pub fn request_rate(&self) -> f64 {
+        self.throttle
+            .bucket
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .rate
+    }
+
+    /// Remember listing responses once their markets are stored, for conditional requests
+    pub fn remember_listings(&self, listings: Vec<ListingPage>) {
+        self.listings.remember(listings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_backs_off_and_recovers() {
+        let throttle = Throttle::new(ThrottleConfig {
+            requests_per_second: 2.0,
+            burst: 2,
+        });
+        let start = Instant::now();
+
+        assert!(throttle.check(start).is_ok());
+        assert!(throttle.check(start).is_ok());
+        assert_eq!(
+            throttle.check(start).unwrap_err(),
+            Duration::from_millis(500)
+        );
+
+        // A throttled response pauses for Retry-After and halves the rate
+        let pause = throttle.throttled(Some(Duration::from_secs(3)), start);
+        assert_eq!(pause, Duration::from_secs(3));
+        assert_eq!(
+            throttle.check(start + Duration::from_secs(1)).unwrap_err(),
+            Duration::from_secs(2)
+        );
+        assert!(throttle.check(start + Duration::from_secs(4)).is_ok());
+        assert_eq!(
+            throttle.check(start + Duration::from_secs(4)).unwrap_err(),
+            Duration::from_secs(1)
+        );
+
+        for _ in 0..100 {
+            throttle.recovered();
+        }
+        assert_eq!(throttle.bucket.lock().unwrap().rate, 2.0);
+    }
+
+    #[test]
+    fn test_user_agent_rotation() {
+        let config = ThrottleConfig {
+            requests_per_second: 1.0,
+            burst: 1,
+        };
+        let user_agent = |client: &ThrottledClient| {
+            client
+                .get("https://gamma-api.polymarket.com/markets")
+                .build()
+                .unwrap()
+                .headers()
+                .get(USER_AGENT)
+                .cloned()
+        };
+
+        let client = ThrottledClient::new(vec![Client::new()], config);
+        assert_eq!(user_agent(&client), None);
+
+        let client = ThrottledClient::new(vec![Client::new()], config).with_user_agents(vec![
+            HeaderValue::from_static("agent-a"),
+            HeaderValue::from_static("agent-b"),
+        ]);
+        assert_eq!(user_agent(&client).unwrap(), "agent-a");
+        assert_eq!(user_agent(&client).unwrap(), "agent-b");
+        assert_eq!(user_agent(&client).unwrap(), "agent-a");
+    }
+
+    #[test]
+    fn test_retry_after_formats() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Mon, 01 Jan 2024 00:00:30 GMT"),
+        );
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(30)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers, now), None);
+    }
+}
@@ -0,0 +1,386 @@
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::error::Result;
+use crate::metrics;
+use crate::models::Market;
+use crate::shards::Shard;
+use crate::source;
+use crate::storage::Storage;
+
+pub const DEFAULT_HOT_INTERVAL_SECS: u64 = 10;
+pub const DEFAULT_HOT_MIN_VOLUME: f64 = 100_000.0;
+pub const DEFAULT_HOT_MAX_MARKETS: u32 = 100;
+pub const DEFAULT_LONG_TAIL_INTERVAL_SECS: u64 = 300;
+pub const DEFAULT_ENRICH_EVERY: u32 = 5;
+pub const DEFAULT_ENRICH_MAX_MARKETS: u32 = 20;
+/// How long the hot tier's membership is reused before it is reloaded from storage
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often a market is refreshed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrapeTier {
+    /// Watchlisted and high-volume markets, refreshed from the CLOB between full scrapes
+    Hot,
+    /// Every other market, stored from the listing at most once per long-tail interval
+    LongTail,
+}
+
+impl ScrapeTier {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScrapeTier::Hot => "hot",
+            ScrapeTier::LongTail => "long_tail",
+        }
+    }
+}
+
+/// Tier settings supplied from the command line
+#[derive(Debug, Clone)]
+pub struct TierConfig {
+    /// Seconds between CLOB refreshes of hot markets; 0 disables the hot tier
+    pub hot_interval_secs: u64,
+    /// Active markets with at least this volume are hot, as are watchlisted markets
+    pub hot_min_volume: f64,
+    /// Cap on the high-volume markets in the hot tier; watchlisted markets don't count
+    pub hot_max_markets: u32,
+    /// Seconds a long-tail market is skipped by the listing scrape after being stored;
+    /// 0 stores every market on every scrape
+    pub long_tail_interval_secs: u64,
+    /// Trade, holder, comment and orderbook passes run after every this many scrapes
+    pub enrich_every: u32,
+    /// Hot markets each of those passes fetches per run, rotating through the hot tier
+    pub enrich_max_markets: u32,
+}
+
+/// Per-market pass over the hot tier following a scrape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnrichPass {
+    Trades,
+    Holders,
+    Comments,
+    Orderbooks,
+}
+
+/// Scrapes seen by a pass and where its next run picks up in the hot tier
+#[derive(Default)]
+struct PassSchedule {
+    scrapes: u64,
+    next: usize,
+}
+
+/// Hot tier members, reloaded every `HOT_RELOAD_INTERVAL`
+#[derive(Default)]
+struct HotQueue {
+    ids: Vec<String>,
+    members: HashSet<String>,
+    loaded_at: Option<Instant>,
+}
+
+/// Per-tier queues deciding which markets are refreshed when, limited to the shard of the
+/// market universe this instance scrapes
+/// Shared by the scrape loop's tickers and the listing scrape's write stage
+pub struct TierScheduler {
+    config: TierConfig,
+    shard: Mutex<Shard>,
+    hot: Mutex<HotQueue>,
+    /// When each long-tail market was last stored from a listing
+    long_tail: Mutex<HashMap<String, Instant>>,
+    /// Where the next hot refresh picks up in the hot tier
+    hot_next: Mutex<usize>,
+    passes: Mutex<HashMap<EnrichPass, PassSchedule>>,
+}
+
+impl TierScheduler {
+    pub fn new(config: TierConfig) -> Self {
+        Self {
+            config,
+            shard: Mutex::new(Shard::ALL),
+            hot: Mutex::new(HotQueue::default()),
+            long_tail: Mutex::new(HashMap::new()),
+            hot_next: Mutex::new(0),
+            passes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Period of the hot tier's refreshes, if enabled
+    pub fn hot_interval(&self) -> Option<Duration> {
+        (self.config.hot_interval_secs > 0)
+            .then(|| Duration::from_secs(self.config.hot_interval_secs))
+    }
+
+    /// Shard of the market universe this instance scrapes
+    pub fn shard(&self) -> Shard {
+        *self.shard.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Switch to another shard; the hot tier is reloaded for it
+    pub fn set_shard(&self, shard: Shard) {
+        let mut current = self.shard.lock().unwrap_or_else(|e| e.into_inner());
+        if *current != shard {
+            *current = shard;
+            self.hot.lock().unwrap_or_else(|e| e.into_inner()).loaded_at = None;
+        }
+    }
+
+    /// Tier of a market as last seen by a scrape
+    pub fn tier(&self, market: &Market) -> ScrapeTier {
+        let volume = market.volume.and_then(|v| v.to_f64()).unwrap_or(0.0);
+        let watched = self
+            .hot
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .members
+            .contains(&market.id);
+        if watched || volume >= self.config.hot_min_volume {
+            ScrapeTier::Hot
+        } else {
+            ScrapeTier::LongTail
+        }
+    }
+
+    /// Whether the listing scrape should store this market now
+    /// Hot markets always are; long-tail markets once per long-tail interval, which is
+    /// counted from now when this returns true
+    pub fn take_due(&self, market: &Market, now: Instant) -> bool {
+        if self.config.long_tail_interval_secs == 0 || self.tier(market) == ScrapeTier::Hot {
+            return true;
+        }
+        let interval = Duration::from_secs(self.config.long_tail_interval_secs);
+        let mut stored = self.long_tail.lock().unwrap_or_else(|e| e.into_inner());
+        match stored.get(&market.id) {
+            Some(at) if now.saturating_duration_since(*at) < interval => false,
+            _ => {
+                stored.insert(market.id.clone(), now);
+                true
+            }
+        }
+    }
+
+    /// Forget long-tail markets last stored a long-tail interval or more ago, which are due
+    /// again anyway; markets that stopped being listed, like delisted ones, end up here
+    pub fn prune_long_tail(&self, now: Instant) {
+        let interval = Duration::from_secs(self.config.long_tail_interval_secs);
+        self.long_tail
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, at| now.saturating_duration_since(*at) < interval);
+    }
+
+    /// Up to `max` hot markets for the next hot refresh, continuing where the previous one
+    /// stopped so every hot market is refreshed in turn
+    pub async fn hot_refresh_ids(&self, storage: &dyn Storage, max: usize) -> Result<Vec<String>> {
+        let ids = self.hot_market_ids(storage).await?;
+        let mut next = self.hot_next.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(rotate(&ids, &mut next, max))
+    }
+
+    /// Hot markets `pass` should fetch after this scrape: none unless it is one of every
+    /// `enrich_every` scrapes, otherwise up to `enrich_max_markets` of them, continuing where
+    /// the previous run stopped so every hot market is covered in turn
+    pub async fn enrich_market_ids(
+        &self,
+        pass: EnrichPass,
+        storage: &dyn Storage,
+    ) -> Result<Vec<String>> {
+        let due = {
+            let mut passes = self.passes.lock().unwrap_or_else(|e| e.into_inner());
+            let schedule = passes.entry(pass).or_default();
+            let due = schedule
+                .scrapes
+                .is_multiple_of(u64::from(self.config.enrich_every));
+            schedule.scrapes += 1;
+            due
+        };
+        if !due {
+            return Ok(Vec::new());
+        }
+
+        let ids = self.hot_market_ids(storage).await?;
+        let mut passes = self.passes.lock().unwrap_or_else(|e| e.into_inner());
+        let schedule = passes.entry(pass).or_default();
+        Ok(rotate(
+            &ids,
+            &mut schedule.next,
+            self.config.enrich_max_markets as usize,
+        ))
+    }
+
+    /// IDs in the hot tier: watchlisted Polymarket markets (stored or not), then the
+    /// highest-volume active ones, of this instance's shard
+    pub async fn hot_market_ids(&self, storage: &dyn Storage) -> Result<Vec<String>> {
+        {
+            let hot = self.hot.lock().unwrap_or_else(|e| e.into_inner());
+            if hot
+                .loaded_at
+                .is_some_and(|at| at.elapsed() < HOT_RELOAD_INTERVAL)
+            {
+                return Ok(hot.ids.clone());
+            }
+        }
+
+        let mut ids = storage
+            .get_watchlisted_market_ids(source::POLYMARKET)
+            .await?;
+        ids.extend(
+            storage
+                .get_top_volume_market_ids(
+                    source::POLYMARKET,
+                    self.config.hot_min_volume,
+                    self.config.hot_max_markets,
+                )
+                .await?,
+        );
+        let shard = self.shard();
+        let mut members = HashSet::new();
+        ids.retain(|id| shard.owns(id) && members.insert(id.clone()));
+        let long_tail = self
+            .long_tail
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len();
+        ::metrics::gauge!(metrics::TIER_MARKETS, "tier" => ScrapeTier::Hot.as_str())
+            .set(ids.len() as f64);
+        ::metrics::gauge!(metrics::TIER_MARKETS, "tier" => ScrapeTier::LongTail.as_str())
+            .set(long_tail as f64);
+
+        let mut hot = self.hot.lock().unwrap_or_else(|e| e.into_inner());
+        *hot = HotQueue {
+            ids: ids.clone(),
+            members,
+            loaded_at: Some(Instant::now()),
+        };
+        Ok(ids)
+    }
+}
+
+/// Up to `max` of `ids` starting at `next`, wrapping around, with `next` moved past them
+fn rotate(ids: &[String], next: &mut usize, max: usize) -> Vec<String> {
+    if ids.is_empty() {
+        return Vec::new();
+    }
+    let start = *next % ids.len();
+    let taken: Vec<String> = ids
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(max.min(ids.len()))
+        .cloned()
+        .collect();
+    *next = (start + taken.len()) % ids.len();
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::storage::SqliteStorage;
+    use rust_decimal::Decimal;
+
+    fn scheduler(long_tail_interval_secs: u64) -> TierScheduler {
+        TierScheduler::new(TierConfig {
+            hot_interval_secs: DEFAULT_HOT_INTERVAL_SECS,
+            hot_min_volume: 1000.0,
+            hot_max_markets: DEFAULT_HOT_MAX_MARKETS,
+            long_tail_interval_secs,
+            enrich_every: DEFAULT_ENRICH_EVERY,
+            enrich_max_markets: DEFAULT_ENRICH_MAX_MARKETS,
+        })
+    }
+
+    fn market(id: &str, volume: i64) -> Market {
+        Market {
+            id: id.to_string(),
+            volume: Some(Decimal::from(volume)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_long_tail_markets_wait_for_their_interval() {
+        let tiers = scheduler(300);
+        let now = Instant::now();
+        let hot = market("hot", 5000);
+        let long_tail = market("tail", 10);
+        assert_eq!(tiers.tier(&hot), ScrapeTier::Hot);
+        assert_eq!(tiers.tier(&long_tail), ScrapeTier::LongTail);
+
+        assert!(tiers.take_due(&hot, now));
+        assert!(tiers.take_due(&hot, now + Duration::from_secs(1)));
+        assert!(tiers.take_due(&long_tail, now));
+        assert!(!tiers.take_due(&long_tail, now + Duration::from_secs(299)));
+        assert!(tiers.take_due(&long_tail, now + Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_long_tail_markets_are_forgotten_once_due() {
+        let tiers = scheduler(300);
+        let now = Instant::now();
+        assert!(tiers.take_due(&market("delisted", 10), now));
+        assert!(tiers.take_due(&market("listed", 10), now + Duration::from_secs(200)));
+
+        tiers.prune_long_tail(now + Duration::from_secs(300));
+        let remembered = tiers.long_tail.lock().unwrap();
+        assert_eq!(remembered.keys().collect::<Vec<_>>(), ["listed"]);
+    }
+
+    #[test]
+    fn test_long_tail_disabled_stores_everything() {
+        let tiers = scheduler(0);
+        let now = Instant::now();
+        let long_tail = market("tail", 10);
+        assert!(tiers.take_due(&long_tail, now));
+        assert!(tiers.take_due(&long_tail, now));
+    }
+
+    #[test]
+    fn test_rotate_covers_every_market_in_turn() {
+        let ids: Vec<String> = ["a", "b", "c", "d", "e"].map(String::from).to_vec();
+        let mut next = 0;
+        assert_eq!(rotate(&ids, &mut next, 2), ["a", "b"]);
+        assert_eq!(rotate(&ids, &mut next, 2), ["c", "d"]);
+        assert_eq!(rotate(&ids, &mut next, 2), ["e", "a"]);
+        assert_eq!(rotate(&ids, &mut next, 10), ["b", "c", "d", "e", "a"]);
+        // A hot tier that shrank picks up within its new bounds
+        assert_eq!(rotate(&ids[..1], &mut next, 2), ["a"]);
+        assert!(rotate(&[], &mut next, 2).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enrich_passes_run_every_few_scrapes_on_a_few_markets() {
+        let storage = SqliteStorage::new(db::test_pools().await);
+        let watched: Vec<String> = ["1", "2", "3"].map(String::from).to_vec();
+        storage.create_watchlist("hot", &watched).await.unwrap();
+        let tiers = TierScheduler::new(TierConfig {
+            enrich_every: 2,
+            enrich_max_markets: 2,
+            ..scheduler(0).config
+        });
+
+        let trades = tiers
+            .enrich_market_ids(EnrichPass::Trades, &storage)
+            .await
+            .unwrap();
+        assert_eq!(trades, ["1", "2"]);
+        // Each pass keeps its own schedule
+        let holders = tiers
+            .enrich_market_ids(EnrichPass::Holders, &storage)
+            .await
+            .unwrap();
+        assert_eq!(holders, ["1", "2"]);
+        let skipped = tiers
+            .enrich_market_ids(EnrichPass::Trades, &storage)
+            .await
+            .unwrap();
+        assert!(skipped.is_empty());
+        let trades = tiers
+            .enrich_market_ids(EnrichPass::Trades, &storage)
+            .await
+            .unwrap();
+        assert_eq!(trades, ["3", "1"]);
+    }
+}
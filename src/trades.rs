@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::models::{Trade, TradeSide};
+use crate::throttle::ThrottledClient;
+
+// Polymarket data API
+const DATA_API_TRADES_URL: &str = "https://data-api.polymarket.com/trades";
+/// Trades requested per page, newest first
+const TRADES_PER_PAGE: u32 = 100;
+/// Pages walked per listing and scrape; trades further back are left for the next scrape
+const MAX_PAGES: u32 = 10;
+
+/// Trade as returned by the data API's `GET /trades`: one wallet's side of a fill
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataApiTrade {
+    /// Wallet the trade belongs to
+    proxy_wallet: Option<String>,
+    /// `BUY` or `SELL`
+    side: String,
+    /// CLOB token ID
+    asset: Option<String>,
+    #[serde(with = "rust_decimal::serde::float")]
+    size: Decimal,
+    #[serde(with = "rust_decimal::serde::float")]
+    price: Decimal,
+    /// Epoch seconds
+    timestamp: i64,
+    outcome: Option<String>,
+    transaction_hash: Option<String>,
+}
+
+impl DataApiTrade {
+    /// What tells this side of a fill apart from the other rows of the listing
+    fn key(&self) -> (Option<&str>, Option<&str>, Option<&str>, &str, Decimal, Decimal) {
+        (
+            self.transaction_hash.as_deref(),
+            self.proxy_wallet.as_deref(),
+            self.asset.as_deref(),
+            self.side.as_str(),
+            self.size,
+            self.price,
+        )
+    }
+}
+
+/// ID of a trade that stays the same across overlapping fetches: SHA-256 over the fill's
+/// transaction, token, side, size, price and wallets, with `occurrence` numbering fills
+/// identical in all of those
+fn trade_id(trade: &Trade, occurrence: usize) -> String {
+    let key = serde_json::json!([
+        trade.transaction_hash,
+        trade.token_id,
+        trade.side,
+        trade.size.normalize().to_string(),
+        trade.price.normalize().to_string(),
+        trade.maker,
+        trade.taker,
+        occurrence,
+    ]);
+    hex::encode(Sha256::digest(key.to_string()))
+}
+
+/// Convert a taker trade into the fill it made with the maker trade `maker`, if any, in the
+/// taker's terms; None when its side or timestamp can't be read
+fn parse_fill(
+    market_id: &str,
+    taker: &DataApiTrade,
+    maker: Option<&DataApiTrade>,
+) -> Option<Trade> {
+    let side = match taker.side.to_ascii_uppercase().as_str() {
+        "BUY" => TradeSide::Buy,
+        "SELL" => TradeSide::Sell,
+        _ => return None,
+    };
+    let (size, price) = match maker {
+        // A maker trading the complementary token gets the other side of the same price
+        Some(maker) if maker.asset != taker.asset => (maker.size, Decimal::ONE - maker.price),
+        Some(maker) => (maker.size, maker.price),
+        None => (taker.size, taker.price),
+    };
+    Some(Trade {
+        id: String::new(),
+        market_id: market_id.to_string(),
+        token_id: taker.asset.clone(),
+        outcome: taker.outcome.clone(),
+        side,
+        size,
+        price,
+        traded_at: DateTime::from_timestamp(taker.timestamp, 0)?,
+        maker: maker.and_then(|maker| maker.proxy_wallet.clone()),
+        taker: taker.proxy_wallet.clone(),
+        transaction_hash: taker.transaction_hash.clone(),
+    })
+}
+
+/// Split the taker trades into fills, one per maker trade of the same transaction found among
+/// `listed`, which also lists the takers' own trades; a taker trade without any stays whole
+/// with an unknown maker
+fn attribute_fills(
+    market_id: &str,
+    takers: &[DataApiTrade],
+    listed: &[DataApiTrade],
+) -> Vec<Trade> {
+    let mut taker_keys: HashMap<_, usize> = HashMap::new();
+    for taker in takers {
+        *taker_keys.entry(taker.key()).or_default() += 1;
+    }
+    let mut makers: HashMap<&str, Vec<&DataApiTrade>> = HashMap::new();
+    for trade in listed {
+        if let Some(count) = taker_keys.get_mut(&trade.key()).filter(|count| **count > 0) {
+            *count -= 1;
+            continue;
+        }
+        if let Some(hash) = trade.transaction_hash.as_deref() {
+            makers.entry(hash).or_default().push(trade);
+        }
+    }
+
+    let mut trades = Vec::new();
+    for taker in takers {
+        // Makers go to the first taker trade of their transaction, which has only one
+        // taker order
+        let matched = taker
+            .transaction_hash
+            .as_deref()
+            .and_then(|hash| makers.remove(hash))
+            .unwrap_or_default();
+        if matched.is_empty() {
+            trades.extend(parse_fill(market_id, taker, None));
+        } else {
+            trades.extend(
+                matched
+                    .into_iter()
+                    .filter_map(|maker| parse_fill(market_id, taker, Some(maker))),
+            );
+        }
+    }
+
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for trade in &mut trades {
+        let occurrence = occurrences.entry(trade_id(trade, 0)).or_default();
+        trade.id = trade_id(trade, *occurrence);
+        *occurrence += 1;
+    }
+    trades
+}
+
+/// Fetch one page of a market's trades, newest first; `taker_only` lists only the trades of
+/// taker orders, otherwise makers' trades are listed too
+async fn fetch_page(
+    client: &ThrottledClient,
+    condition_id: &str,
+    taker_only: bool,
+    offset: u32,
+) -> Result<Vec<DataApiTrade>> {
+    let taker_only = if taker_only { "true" } else { "false" };
+    let request = client
+        .get(DATA_API_TRADES_URL)
+        .query(&[("market", condition_id), ("takerOnly", taker_only)])
+        .query(&[("limit", TRADES_PER_PAGE), ("offset", offset)])
+        .header("Accept", "application/json");
+    client
+        .send(request)
+        .await
+        .context("Failed to fetch trades from data API")?
+        .error_for_status()
+        .context("Data API returned an error status")?
+        .json()
+        .await
+        .context("Failed to parse data API trades")
+}
+
+/// Walk a trade listing newest first until it reaches trades older than `until` epoch seconds;
+/// without `until` only the first page is fetched
+async fn fetch_listing(
+    client: &ThrottledClient,
+    condition_id: &str,
+    taker_only: bool,
+    until: Option<i64>,
+) -> Result<Vec<DataApiTrade>> {
+    let mut listed = Vec::new();
+    for page in 0..MAX_PAGES {
+        let trades = fetch_page(client, condition_id, taker_only, page * TRADES_PER_PAGE).await?;
+        let is_last_page = (trades.len() as u32) < TRADES_PER_PAGE;
+        let reached_until = match (until, trades.last()) {
+            (Some(until), Some(oldest)) => oldest.timestamp < until,
+            _ => true,
+        };
+        listed.extend(trades);
+        if is_last_page || reached_until {
+            break;
+        }
+    }
+    Ok(listed)
+}
+
+/// Fetch a market's trades since `since` from the data API, which looks them up by
+/// condition ID; without `since` only the latest page of them
+/// Each taker trade is split into the fills it made with makers' orders, so both wallets are
+/// known; unreadable trades are skipped
+pub async fn fetch_trades(
+    client: &ThrottledClient,
+    market_id: &str,
+    condition_id: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<Trade>> {
+    let since = since.map(|since| since.timestamp());
+    let mut takers = fetch_listing(client, condition_id, true, since).await?;
+    if let Some(since) = since {
+        takers.retain(|taker| taker.timestamp >= since);
+    }
+    let Some(oldest) = takers.iter().map(|taker| taker.timestamp).min() else {
+        return Ok(Vec::new());
+    };
+    // The makers' trades of a fill carry the taker's timestamp
+    let listed = fetch_listing(client, condition_id, false, Some(oldest)).await?;
+
+    Ok(attribute_fills(market_id, &takers, &listed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn trade(
+        wallet: &str,
+        side: &str,
+        asset: &str,
+        size: f64,
+        price: f64,
+        hash: &str,
+    ) -> DataApiTrade {
+        serde_json::from_value(serde_json::json!({
+            "proxyWallet": wallet,
+            "side": side,
+            "asset": asset,
+            "conditionId": "0xabc",
+            "size": size,
+            "price": price,
+            "timestamp": 1700000000,
+            "outcome": "Yes",
+            "transactionHash": hash
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_attribute_fills() {
+        let takers = vec![
+            trade("0xtaker", "BUY", "111", 25.0, 0.62, "0xdef"),
+            trade("0xlone", "SELL", "111", 5.0, 0.6, "0xaaa"),
+        ];
+        let listed = vec![
+            trade("0xtaker", "BUY", "111", 25.0, 0.62, "0xdef"),
+            trade("0xseller", "SELL", "111", 15.0, 0.62, "0xdef"),
+            // Buying the complementary token at 0.38 sells the taker's at 0.62
+            trade("0xminter", "BUY", "222", 10.0, 0.38, "0xdef"),
+            trade("0xlone", "SELL", "111", 5.0, 0.6, "0xaaa"),
+        ];
+
+        let trades = attribute_fills("101", &takers, &listed);
+        assert_eq!(trades.len(), 3);
+        assert_eq!(trades[0].side, TradeSide::Buy);
+        assert_eq!(trades[0].maker.as_deref(), Some("0xseller"));
+        assert_eq!(trades[0].taker.as_deref(), Some("0xtaker"));
+        assert_eq!(trades[0].size, dec!(15));
+        assert_eq!(trades[1].maker.as_deref(), Some("0xminter"));
+        assert_eq!(trades[1].token_id.as_deref(), Some("111"));
+        assert_eq!(trades[1].price, dec!(0.62));
+        assert_eq!(trades[1].traded_at.timestamp(), 1_700_000_000);
+        // The makers of a trade weren't listed, so it is kept whole
+        assert!(trades[2].maker.is_none());
+        assert_eq!(trades[2].size, dec!(5));
+
+        let unknown_side = trade("0xtaker", "MERGE", "111", 1.0, 0.5, "0xbbb");
+        assert!(attribute_fills("101", &[unknown_side], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_identical_fills_keep_their_own_ids() {
+        let takers = vec![trade("0xtaker", "BUY", "111", 20.0, 0.5, "0xdef")];
+        let listed = vec![
+            trade("0xtaker", "BUY", "111", 20.0, 0.5, "0xdef"),
+            trade("0xmaker", "SELL", "111", 10.0, 0.5, "0xdef"),
+            trade("0xmaker", "SELL", "111", 10.0, 0.5, "0xdef"),
+        ];
+
+        let trades = attribute_fills("101", &takers, &listed);
+        assert_eq!(trades.len(), 2);
+        assert_ne!(trades[0].id, trades[1].id);
+        // A later fetch listing the same fills gives them the same IDs
+        let again = attribute_fills("101", &takers, &listed);
+        assert_eq!(again[0].id, trades[0].id);
+        assert_eq!(again[1].id, trades[1].id);
+    }
+}
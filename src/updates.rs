@@ -0,0 +1,301 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::sync::broadcast;
+
+use crate::models::Market;
+
+/// Capacity of the update channel; slow subscribers that fall further behind skip messages
+pub const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Sender side of the market update channel shared by the scraper and the API
+pub type UpdateSender = broadcast::Sender<MarketUpdate>;
+
+/// Tracked fields compared by `field_changes`
+pub const TRACKED_FIELDS: [&str; 4] = ["title", "current_price", "volume", "end_date"];
+
+/// Change notification published by the scraper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketUpdate {
+    /// A market seen for the first time
+    NewMarket { market: Market },
+    /// A tracked field of a known market changed
+    MarketChanged {
+        market: Market,
+        #[serde(deserialize_with = "deserialize_tracked_fields")]
+        changed_fields: Vec<&'static str>,
+        /// Price before this change, used to size price moves
+        #[serde(with = "crate::models::decimal_option")]
+        previous_price: Option<Decimal>,
+    },
+}
+
+impl MarketUpdate {
+    /// Event name used for SSE and WebSocket messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MarketUpdate::NewMarket { .. } => "new_market",
+            MarketUpdate::MarketChanged { .. } => "market_changed",
+        }
+    }
+
+    pub fn market(&self) -> &Market {
+        match self {
+            MarketUpdate::NewMarket { market } | MarketUpdate::MarketChanged { market, .. } => {
+                market
+            }
+        }
+    }
+
+    /// Absolute price change for price updates, if both prices are known
+    pub fn price_move(&self) -> Option<Decimal> {
+        match self {
+            MarketUpdate::MarketChanged {
+                market,
+                previous_price,
+                ..
+            } => Some(market.current_price? - (*previous_price)?),
+            MarketUpdate::NewMarket { .. } => None,
+        }
+    }
+
+    /// True if this is a price change (as opposed to a new market or other field change)
+    pub fn is_price_change(&self) -> bool {
+        match self {
+            MarketUpdate::NewMarket { .. } => false,
+            MarketUpdate::MarketChanged { changed_fields, .. } => {
+                changed_fields.contains(&"current_price")
+            }
+        }
+    }
+}
+
+/// Read changed field names back as the static names of `TRACKED_FIELDS`, dropping unknown ones
+fn deserialize_tracked_fields<'de, D>(deserializer: D) -> Result<Vec<&'static str>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let names = Vec::<String>::deserialize(deserializer)?;
+    Ok(names
+        .iter()
+        .filter_map(|name| TRACKED_FIELDS.iter().copied().find(|field| field == name))
+        .collect())
+}
+
+/// Subscriber-side filter applied before forwarding updates
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateFilter {
+    /// Only forward markets with at least this volume
+    pub min_volume: Option<f64>,
+    /// Only forward markets whose title contains this keyword (case-insensitive)
+    pub keyword: Option<String>,
+    /// Only forward new markets and price changes (skip other field changes)
+    #[serde(default)]
+    pub prices_only: bool,
+}
+
+impl UpdateFilter {
+    pub fn matches(&self, update: &MarketUpdate) -> bool {
+        let market = update.market();
+        if let Some(min_volume) = self.min_volume {
+            if market.volume.and_then(|v| v.to_f64()).unwrap_or(0.0) < min_volume {
+                return false;
+            }
+        }
+        if let Some(keyword) = &self.keyword {
+            if !market.title.to_lowercase().contains(&keyword.to_lowercase()) {
+                return false;
+            }
+        }
+        if self.prices_only && matches!(update, MarketUpdate::MarketChanged { .. }) {
+            return update.is_price_change();
+        }
+        true
+    }
+}
+
+/// Create the update channel; receivers are created via `subscribe`
+pub fn channel() -> UpdateSender {
+    broadcast::channel(UPDATE_CHANNEL_CAPACITY).0
+}
+
+/// Old and new value of a tracked field, rendered as text for the audit log
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// Tracked fields that differ between the stored and freshly scraped market
+pub fn field_changes(previous: &Market, current: &Market) -> Vec<FieldChange> {
+    fn change<T: ToString + PartialEq>(
+        field: &'static str,
+        old: Option<&T>,
+        new: Option<&T>,
+    ) -> Option<FieldChange> {
+        (old != new).then(|| FieldChange {
+            field,
+            old_value: old.map(T::to_string),
+            new_value: new.map(T::to_string),
+        })
+    }
+
+    fn rfc3339(timestamp: DateTime<Utc>) -> String {
+        timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    // Trailing zeros depend on how the value was parsed, so drop them from the audit text
+    fn normalized(value: Option<Decimal>) -> Option<Decimal> {
+        value.map(|d| d.normalize())
+    }
+
+    [
+        change("title", Some(&previous.title), Some(&current.title)),
+        change(
+            "current_price",
+            normalized(previous.current_price).as_ref(),
+            normalized(current.current_price).as_ref(),
+        ),
+        change(
+            "volume",
+            normalized(previous.volume).as_ref(),
+            normalized(current.volume).as_ref(),
+        ),
+        change(
+            "end_date",
+            previous.end_date.map(rfc3339).as_ref(),
+            current.end_date.map(rfc3339).as_ref(),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Names of tracked fields that differ between the stored and freshly scraped market
+pub fn changed_fields(previous: &Market, current: &Market) -> Vec<&'static str> {
+    field_changes(previous, current)
+        .into_iter()
+        .map(|change| change.field)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::parse_timestamp;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_changed_fields() {
+        let previous = Market {
+            id: "1".to_string(),
+            title: "Market".to_string(),
+            current_price: Some(dec!(0.5)),
+            volume: Some(dec!(100)),
+            ..Default::default()
+        };
+        let mut current = previous.clone();
+        assert!(changed_fields(&previous, &current).is_empty());
+
+        current.current_price = Some(dec!(0.50));
+        assert!(changed_fields(&previous, &current).is_empty());
+
+        current.current_price = Some(dec!(0.6));
+        current.volume = Some(dec!(150));
+        assert_eq!(
+            changed_fields(&previous, &current),
+            vec!["current_price", "volume"]
+        );
+    }
+
+    #[test]
+    fn test_field_changes_values() {
+        let previous = Market {
+            id: "1".to_string(),
+            title: "Market".to_string(),
+            end_date: parse_timestamp("2025-01-01"),
+            ..Default::default()
+        };
+        let current = Market {
+            current_price: Some(dec!(0.2500)),
+            end_date: parse_timestamp("2025-02-01"),
+            ..previous.clone()
+        };
+
+        assert_eq!(
+            field_changes(&previous, &current),
+            vec![
+                FieldChange {
+                    field: "current_price",
+                    old_value: None,
+                    new_value: Some("0.25".to_string()),
+                },
+                FieldChange {
+                    field: "end_date",
+                    old_value: Some("2025-01-01T00:00:00Z".to_string()),
+                    new_value: Some("2025-02-01T00:00:00Z".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_round_trips_through_json() {
+        let update = MarketUpdate::MarketChanged {
+            market: Market {
+                id: "1".to_string(),
+                current_price: Some(dec!(0.6)),
+                ..Default::default()
+            },
+            changed_fields: vec!["current_price", "volume"],
+            previous_price: Some(dec!(0.5)),
+        };
+
+        let json = serde_json::to_string(&update).unwrap();
+        let parsed: MarketUpdate = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.kind(), "market_changed");
+        assert_eq!(parsed.market().id, "1");
+        assert!(parsed.is_price_change());
+    }
+
+    #[test]
+    fn test_update_filter() {
+        let market = Market {
+            id: "1".to_string(),
+            title: "Will BTC hit $100k?".to_string(),
+            volume: Some(dec!(5000)),
+            ..Default::default()
+        };
+        let update = MarketUpdate::MarketChanged {
+            market: market.clone(),
+            changed_fields: vec!["volume"],
+            previous_price: None,
+        };
+
+        assert!(UpdateFilter::default().matches(&update));
+
+        let keyword = UpdateFilter {
+            keyword: Some("btc".to_string()),
+            min_volume: Some(1000.0),
+            ..Default::default()
+        };
+        assert!(keyword.matches(&update));
+
+        let high_volume = UpdateFilter {
+            min_volume: Some(10000.0),
+            ..Default::default()
+        };
+        assert!(!high_volume.matches(&update));
+
+        let prices_only = UpdateFilter {
+            prices_only: true,
+            ..Default::default()
+        };
+        assert!(!prices_only.matches(&update));
+        assert!(prices_only.matches(&MarketUpdate::NewMarket { market }));
+    }
+}
@@ -0,0 +1,151 @@
+use rust_decimal::Decimal;
+use std::fmt;
+
+use crate::error::{self, Error};
+use crate::models::Market;
+
+/// Why a scraped market was skipped instead of stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// No usable market ID
+    MissingId,
+    /// A price that is not a probability between 0 and 1
+    BadPrice,
+    /// An end date in a format we can't read (reported by the parsers)
+    UnparsableDate,
+    /// The payload could not be parsed at all
+    Malformed,
+}
+
+impl RejectReason {
+    pub const ALL: [RejectReason; 4] = [
+        RejectReason::MissingId,
+        RejectReason::BadPrice,
+        RejectReason::UnparsableDate,
+        RejectReason::Malformed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RejectReason::MissingId => "missing_id",
+            RejectReason::BadPrice => "bad_price",
+            RejectReason::UnparsableDate => "unparsable_date",
+            RejectReason::Malformed => "malformed",
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for RejectReason {}
+
+/// A skipped market with the reason and a human-readable detail
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub market_id: Option<String>,
+    pub reason: RejectReason,
+    pub detail: String,
+}
+
+impl Rejection {
+    /// Classify a parser error; parsers signal known reasons by returning a `RejectReason`
+    pub fn from_parse_error(market_id: Option<String>, error: &Error) -> Self {
+        Rejection {
+            market_id,
+            reason: error::causes(error)
+                .find_map(|cause| cause.downcast_ref::<RejectReason>())
+                .copied()
+                .unwrap_or(RejectReason::Malformed),
+            detail: error.to_string(),
+        }
+    }
+}
+
+/// Whether a price is a probability
+fn is_valid_price(price: &Decimal) -> bool {
+    (Decimal::ZERO..=Decimal::ONE).contains(price)
+}
+
+/// Check a parsed market before it is stored; dates are already checked while parsing
+pub fn validate_market(market: &Market) -> Result<(), Rejection> {
+    let reject = |reason, detail: String| Rejection {
+        market_id: (!market.id.trim().is_empty()).then(|| market.id.clone()),
+        reason,
+        detail,
+    };
+
+    if market.id.trim().is_empty() {
+        return Err(reject(
+            RejectReason::MissingId,
+            "empty market ID".to_string(),
+        ));
+    }
+
+    if let Some(price) = market.current_price.filter(|p| !is_valid_price(p)) {
+        return Err(reject(
+            RejectReason::BadPrice,
+            format!("current price {} is outside 0..=1", price),
+        ));
+    }
+    for outcome in &market.outcomes {
+        if let Some(price) = outcome.price.filter(|p| !is_valid_price(p)) {
+            return Err(reject(
+                RejectReason::BadPrice,
+                format!(
+                    "outcome {:?} price {} is outside 0..=1",
+                    outcome.name, price
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Outcome;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_validate_market_reasons() {
+        let valid = Market {
+            id: "1".to_string(),
+            current_price: Some(dec!(0.4)),
+            ..Default::default()
+        };
+        assert!(validate_market(&valid).is_ok());
+
+        let missing_id = Market {
+            id: " ".to_string(),
+            ..valid.clone()
+        };
+        assert_eq!(
+            validate_market(&missing_id).unwrap_err().reason,
+            RejectReason::MissingId
+        );
+
+        let bad_price = Market {
+            outcomes: vec![Outcome {
+                name: "Yes".to_string(),
+                price: Some(dec!(1.5)),
+                ..Default::default()
+            }],
+            ..valid
+        };
+        let rejection = validate_market(&bad_price).unwrap_err();
+        assert_eq!(rejection.reason, RejectReason::BadPrice);
+        assert_eq!(rejection.market_id.as_deref(), Some("1"));
+
+        let error = Error::from(RejectReason::UnparsableDate);
+        assert_eq!(
+            Rejection::from_parse_error(None, &error).reason,
+            RejectReason::UnparsableDate
+        );
+    }
+}
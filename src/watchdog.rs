@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::metrics::{self, Metrics};
+use crate::notify::Alerts;
+use crate::scraper::{ScraperControl, ScraperState};
+
+pub const DEFAULT_STALL_INTERVALS: u32 = 3;
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Watch for scrapes that stopped succeeding, e.g. a loop hung on a stuck connection
+/// The scraper counts as stalled once no scrape has succeeded for `stall_intervals` scrape
+/// intervals; the stall is published through `ScraperControl` for the readiness probe,
+/// and alerts are sent when it starts and when scrapes recover
+pub async fn run_watchdog(
+    metrics: Arc<Metrics>,
+    scraper: ScraperControl,
+    stall_intervals: u32,
+    alerts: Alerts,
+) {
+    info!(
+        "Watching for stalled scrapes, alerting after {} intervals without a successful scrape",
+        stall_intervals
+    );
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        let status = scraper.status();
+        // A replica that just took over the scraper lease gets the same grace period as a
+        // new process, whatever it scraped while it last led
+        let started_at = scraper
+            .leader_since()
+            .map_or(metrics.started_at(), |since| since.max(metrics.started_at()));
+        let last_success = metrics
+            .get_last_success_time()
+            .filter(|&at| at >= started_at);
+        let stall = stall_detail(
+            last_success,
+            started_at,
+            status.interval_secs,
+            stall_intervals,
+            status.state == ScraperState::Paused || !status.leader,
+            Utc::now(),
+        );
+        ::metrics::gauge!(metrics::SCRAPER_STALLED).set(if stall.is_some() { 1.0 } else { 0.0 });
+
+        let was_stalled = scraper.stall().is_some();
+        match &stall {
+            Some(detail) if !was_stalled => {
+                warn!("Scraper stalled: {}", detail);
+                alerts.send(&format!("Scraper stalled: {}", detail)).await;
+            }
+            None if was_stalled => {
+                info!("Scraper recovered, scrapes are succeeding again");
+                alerts
+                    .send("Scraper recovered, scrapes are succeeding again")
+                    .await;
+            }
+            _ => {}
+        }
+        scraper.set_stall(stall);
+    }
+}
+
+/// Why the scraper counts as stalled, or None while scrapes are recent enough
+/// Before the first success, the process start time stands in so new processes get a
+/// grace period; a paused scraper, or a replica that doesn't lead, never stalls
+pub fn stall_detail(
+    last_success: Option<DateTime<Utc>>,
+    started_at: DateTime<Utc>,
+    interval_secs: u64,
+    stall_intervals: u32,
+    idle: bool,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let max_age = chrono::Duration::seconds(interval_secs as i64 * i64::from(stall_intervals));
+    if idle || now - last_success.unwrap_or(started_at) <= max_age {
+        return None;
+    }
+
+    Some(match last_success {
+        Some(at) => format!(
+            "last successful scrape at {} is older than {} intervals",
+            at.to_rfc3339(),
+            stall_intervals
+        ),
+        None => format!(
+            "no successful scrape since startup at {}",
+            started_at.to_rfc3339()
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_detail() {
+        let started_at = Utc::now() - chrono::Duration::seconds(600);
+        let now = Utc::now();
+
+        // Grace period after startup, then stalled without a success
+        assert!(stall_detail(None, now, 60, 3, false, now).is_none());
+        let detail = stall_detail(None, started_at, 60, 3, false, now).unwrap();
+        assert!(detail.starts_with("no successful scrape"));
+
+        let recent = now - chrono::Duration::seconds(90);
+        assert!(stall_detail(Some(recent), started_at, 60, 3, false, now).is_none());
+        assert!(stall_detail(Some(recent), started_at, 60, 1, false, now).is_some());
+        assert!(stall_detail(Some(started_at), started_at, 60, 3, false, now).is_some());
+
+        // Paused scrapers don't stall
+        assert!(stall_detail(Some(started_at), started_at, 60, 3, true, now).is_none());
+    }
+}